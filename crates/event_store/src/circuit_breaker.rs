@@ -0,0 +1,270 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use metrics::{counter, gauge};
+use serde::Deserialize;
+use tracing::{info, warn};
+
+/// Errors raised by the client itself rather than by EventStore
+#[derive(Debug, thiserror::Error)]
+pub enum EventStoreError {
+    #[error("EventStore circuit breaker is open, retry in {retry_after:?}")]
+    CircuitOpen { retry_after: Duration },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct CircuitBreakerConfig {
+    /// Consecutive failed calls after which the breaker opens
+    #[serde(default = "default_failure_threshold")]
+    pub failure_threshold: u32,
+
+    /// How long an open breaker rejects calls before letting a probe through,
+    /// in milliseconds
+    #[serde(default = "default_cool_down_ms")]
+    pub cool_down_ms: u64,
+}
+
+fn default_failure_threshold() -> u32 {
+    5
+}
+
+fn default_cool_down_ms() -> u64 {
+    30_000
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: default_failure_threshold(),
+            cool_down_ms: default_cool_down_ms(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    Closed,
+    Open,
+    HalfOpen,
+}
+
+impl CircuitState {
+    /// Value reported by the `eventstore.circuit_breaker.state` gauge
+    fn as_gauge(self) -> f64 {
+        match self {
+            Self::Closed => 0.0,
+            Self::Open => 1.0,
+            Self::HalfOpen => 2.0,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Closed => "closed",
+            Self::Open => "open",
+            Self::HalfOpen => "half_open",
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Inner {
+    Closed {
+        failures: u32,
+    },
+    Open {
+        until: Instant,
+    },
+    /// A single probe is let through; a probe that never reports back (e.g.
+    /// its future was dropped) is replaced after another cool-down
+    HalfOpen {
+        probe_started: Instant,
+    },
+}
+
+impl Inner {
+    fn state(&self) -> CircuitState {
+        match self {
+            Self::Closed { .. } => CircuitState::Closed,
+            Self::Open { .. } => CircuitState::Open,
+            Self::HalfOpen { .. } => CircuitState::HalfOpen,
+        }
+    }
+}
+
+/// Fails calls fast while EventStore is unreachable.
+///
+/// After `failure_threshold` consecutive failures the breaker opens and
+/// rejects calls with `EventStoreError::CircuitOpen` until `cool_down` has
+/// passed. It then half-opens and lets one probe through: success closes the
+/// breaker, failure opens it for another cool-down.
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    cool_down: Duration,
+    inner: Mutex<Inner>,
+}
+
+impl CircuitBreaker {
+    pub fn new(config: CircuitBreakerConfig) -> Self {
+        gauge!("eventstore.circuit_breaker.state").set(CircuitState::Closed.as_gauge());
+        Self {
+            failure_threshold: config.failure_threshold.max(1),
+            cool_down: Duration::from_millis(config.cool_down_ms),
+            inner: Mutex::new(Inner::Closed { failures: 0 }),
+        }
+    }
+
+    pub fn state(&self) -> CircuitState {
+        self.lock().state()
+    }
+
+    /// Checks whether a call may go ahead. Every permitted call must report
+    /// its outcome through `record_success` or `record_failure`.
+    pub fn acquire(&self) -> Result<(), EventStoreError> {
+        let mut inner = self.lock();
+        let now = Instant::now();
+        match *inner {
+            Inner::Closed { .. } => Ok(()),
+            Inner::Open { until } if now >= until => {
+                self.transition(&mut inner, Inner::HalfOpen { probe_started: now });
+                Ok(())
+            },
+            Inner::Open { until } => Err(self.reject(until - now)),
+            Inner::HalfOpen { probe_started } if now >= probe_started + self.cool_down => {
+                *inner = Inner::HalfOpen { probe_started: now };
+                Ok(())
+            },
+            Inner::HalfOpen { probe_started } => {
+                Err(self.reject(probe_started + self.cool_down - now))
+            },
+        }
+    }
+
+    pub fn record_success(&self) {
+        let mut inner = self.lock();
+        match *inner {
+            Inner::Closed { .. } => *inner = Inner::Closed { failures: 0 },
+            _ => self.transition(&mut inner, Inner::Closed { failures: 0 }),
+        }
+    }
+
+    pub fn record_failure(&self) {
+        let mut inner = self.lock();
+        let open = Inner::Open {
+            until: Instant::now() + self.cool_down,
+        };
+        match *inner {
+            Inner::Closed { failures } if failures + 1 >= self.failure_threshold => {
+                warn!(
+                    "EventStore failed {} times in a row, opening circuit breaker",
+                    failures + 1
+                );
+                self.transition(&mut inner, open);
+            },
+            Inner::Closed { failures } => {
+                *inner = Inner::Closed {
+                    failures: failures + 1,
+                }
+            },
+            Inner::HalfOpen { .. } => {
+                warn!("EventStore probe failed, keeping circuit breaker open");
+                self.transition(&mut inner, open);
+            },
+            // A call admitted before the breaker opened
+            Inner::Open { .. } => {},
+        }
+    }
+
+    fn transition(&self, inner: &mut Inner, next: Inner) {
+        let state = next.state();
+        if state == CircuitState::Closed {
+            info!("EventStore recovered, circuit breaker closed");
+        }
+        *inner = next;
+        gauge!("eventstore.circuit_breaker.state").set(state.as_gauge());
+        counter!("eventstore.circuit_breaker.transitions_total", "state" => state.as_str())
+            .increment(1);
+    }
+
+    fn reject(&self, retry_after: Duration) -> EventStoreError {
+        counter!("eventstore.circuit_breaker.rejected_total").increment(1);
+        EventStoreError::CircuitOpen { retry_after }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        // The state stays consistent even if a holder panicked
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn breaker(cool_down_ms: u64) -> CircuitBreaker {
+        CircuitBreaker::new(CircuitBreakerConfig {
+            failure_threshold: 3,
+            cool_down_ms,
+        })
+    }
+
+    #[test]
+    fn test_breaker_opens_after_consecutive_failures() {
+        let breaker = breaker(60_000);
+
+        for _ in 0..2 {
+            assert!(breaker.acquire().is_ok());
+            breaker.record_failure();
+        }
+        assert_eq!(breaker.state(), CircuitState::Closed);
+
+        // A success in between resets the count
+        breaker.record_success();
+        for _ in 0..2 {
+            breaker.record_failure();
+        }
+        assert_eq!(breaker.state(), CircuitState::Closed);
+
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert!(matches!(
+            breaker.acquire(),
+            Err(EventStoreError::CircuitOpen { .. })
+        ));
+    }
+
+    #[test]
+    fn test_breaker_half_opens_and_closes_after_successful_probe() {
+        let breaker = breaker(10);
+        for _ in 0..3 {
+            breaker.record_failure();
+        }
+        assert_eq!(breaker.state(), CircuitState::Open);
+
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(breaker.acquire().is_ok());
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        // Only the probe goes through while half-open
+        assert!(breaker.acquire().is_err());
+
+        breaker.record_success();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert!(breaker.acquire().is_ok());
+    }
+
+    #[test]
+    fn test_failed_probe_reopens_breaker() {
+        let breaker = breaker(10);
+        for _ in 0..3 {
+            breaker.record_failure();
+        }
+
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(breaker.acquire().is_ok());
+        breaker.record_failure();
+
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert!(breaker.acquire().is_err());
+    }
+}
@@ -1,16 +1,25 @@
 use anyhow::Result;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, SecondsFormat, Utc};
+use flate2::{write::GzEncoder, Compression};
 use metrics::{counter, histogram};
-use reqwest::Client as HttpClient;
+use reqwest::header::{ACCEPT, CONTENT_ENCODING, CONTENT_TYPE, LOCATION};
+use reqwest::{Client as HttpClient, RequestBuilder, Response, StatusCode};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::future::Future;
+use std::io::Write;
+use std::sync::Arc;
 use std::time::Duration;
-use tracing::instrument;
+use tracing::{instrument, warn, Instrument};
 use url::Url;
 use uuid::Uuid;
 
-use crate::config::EventStoreConfig;
-use crate::events::{Event, EventData, TypeName};
+use crate::circuit_breaker::{CircuitBreaker, EventStoreError};
+use crate::config::{AppendOptions, CompressionConfig, EventStoreConfig, RetryPolicy};
+use crate::events::{Event, EventData, EventMetadata, TypeName};
+use crate::node_pool::NodePool;
+use crate::stream_metadata::StreamMetadata;
+use crate::WriteResult;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RecordedEvent {
@@ -20,22 +29,99 @@ pub struct RecordedEvent {
     pub event_type: String,
     pub data: Value,
     pub metadata: Value,
+    #[serde(with = "rfc3339_utc")]
     pub created: DateTime<Utc>,
 }
 
+/// Serde helpers that accept RFC 3339 timestamps with any offset or
+/// precision, normalize them to UTC and always emit RFC 3339 UTC.
+mod rfc3339_utc {
+    use super::*;
+    use serde::{de::Error, Deserializer, Serializer};
+
+    pub fn serialize<S>(value: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&value.to_rfc3339_opts(SecondsFormat::AutoSi, true))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<DateTime<Utc>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let raw = String::deserialize(deserializer)?;
+        DateTime::parse_from_rfc3339(&raw)
+            .map(|dt| dt.with_timezone(&Utc))
+            .map_err(D::Error::custom)
+    }
+}
+
 impl RecordedEvent {
+    /// Rebuilds the appended `Event`. Version, correlation and causation ids
+    /// come from the stored `EventMetadata`; events written without it, e.g.
+    /// by other clients, read back as version 1 without either id.
     pub fn into_domain_event<T>(&self) -> Result<Event<T>>
     where
         T: Serialize + for<'de> Deserialize<'de> + Clone + TypeName,
     {
         let data: T = serde_json::from_value(self.data.clone())?;
-        Ok(Event::new(data, 1, None, None, Some(self.event_id)))
+        let mut event = Event::new(data, 1, None, None, Some(self.event_id));
+        if let Ok(metadata) = EventMetadata::deserialize(&self.metadata) {
+            event.version = metadata.schema_version.into();
+            event.correlation_id = metadata.correlation_id;
+            event.causation_id = metadata.causation_id;
+            event.tenant_id = metadata.tenant_id;
+        }
+        event.created_at = self.created;
+        event.metadata = self.metadata.clone();
+        Ok(event)
     }
 }
 
+/// Returned by `read_stream` for a stream that does not exist when
+/// `EventStoreConfig::missing_stream_as_empty` is disabled
+#[derive(Debug, thiserror::Error)]
+#[error("stream '{0}' not found")]
+pub struct StreamNotFound(pub String);
+
+/// Returned by appends to a stream that was hard-deleted, whose name can't
+/// be written to again
+#[derive(Debug, thiserror::Error)]
+#[error("stream '{0}' has been deleted")]
+pub struct StreamDeleted(pub String);
+
+/// Returned when an append split into several batches fails after at least
+/// one batch was written. The first `appended` events are in the stream, so
+/// the caller can resume with the rest.
+#[derive(Debug, thiserror::Error)]
+#[error("append to '{stream_name}' failed after {appended} events were written: {source}")]
+pub struct PartialAppend {
+    pub stream_name: String,
+    /// Events written before the failed batch
+    pub appended: usize,
+    /// Number of the last written event, when EventStore reported it
+    pub position: Option<u64>,
+    #[source]
+    pub source: anyhow::Error,
+}
+
+const REQUIRE_MASTER_HEADER: &str = "ES-RequireMaster";
+const HARD_DELETE_HEADER: &str = "ES-HardDelete";
+/// Event type of the events in a `$$<stream>` metadata stream
+const METADATA_EVENT_TYPE: &str = "$metadata";
+
 pub struct EventStoreClient {
-    http_client: HttpClient,
-    base_url: Url,
+    pub(crate) nodes: Arc<NodePool>,
+    pub(crate) retry_policy: RetryPolicy,
+    missing_stream_as_empty: bool,
+    append_defaults: AppendOptions,
+    compression: CompressionConfig,
+    max_append_size: usize,
+    subscription_read_count: u64,
+    max_read_count: u64,
+    subscription_poll_interval: Duration,
+    circuit_breaker: Arc<CircuitBreaker>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -46,44 +132,253 @@ pub struct ReadStreamResponse {
 impl EventStoreClient {
     pub fn new(config: EventStoreConfig) -> Result<Self> {
         let http_client = HttpClient::builder()
-            .timeout(Duration::from_secs(30))
+            .timeout(Duration::from_secs(config.http.timeout_secs))
+            .pool_idle_timeout(Duration::from_secs(config.http.pool_idle_timeout_secs))
+            .pool_max_idle_per_host(config.http.pool_max_idle_per_host)
+            .tcp_keepalive(Duration::from_secs(config.http.tcp_keepalive_secs))
             .build()?;
 
-        let base_url = Url::parse(&config.connection_string)?;
+        let nodes = NodePool::new(
+            http_client,
+            config.node_urls(),
+            Duration::from_millis(config.node_cooldown_ms),
+        )?;
 
         Ok(Self {
-            http_client,
-            base_url,
+            nodes: Arc::new(nodes),
+            retry_policy: config.retry_policy(),
+            missing_stream_as_empty: config.missing_stream_as_empty,
+            append_defaults: config.append_defaults,
+            compression: config.compression,
+            max_append_size: config.max_append_size,
+            subscription_read_count: config.subscription_read_count,
+            max_read_count: config.max_read_count,
+            subscription_poll_interval: Duration::from_millis(config.subscription_poll_interval_ms),
+            circuit_breaker: Arc::new(CircuitBreaker::new(config.circuit_breaker)),
         })
     }
 
+    /// Per-poll read count of a subscription asking for `requested`, within
+    /// `1..=max_read_count`
+    pub(crate) fn read_count(&self, requested: Option<u64>) -> u64 {
+        requested
+            .unwrap_or(self.subscription_read_count)
+            .clamp(1, self.max_read_count.max(1))
+    }
+
+    /// Idle wait of a subscription asking for `requested` between polls
+    /// that found nothing new
+    pub(crate) fn poll_interval(&self, requested: Option<Duration>) -> Duration {
+        requested.unwrap_or(self.subscription_poll_interval)
+    }
+
+    pub fn circuit_breaker(&self) -> &CircuitBreaker {
+        &self.circuit_breaker
+    }
+
+    /// Runs `call` unless the circuit breaker is open and reports its outcome
+    /// back to the breaker
+    async fn guarded<T>(&self, call: impl Future<Output = Result<T>>) -> Result<T> {
+        self.circuit_breaker.acquire()?;
+        let result = call.await;
+        match &result {
+            Err(e) if e.downcast_ref::<reqwest::Error>().is_some_and(is_outage) => {
+                self.circuit_breaker.record_failure()
+            },
+            _ => self.circuit_breaker.record_success(),
+        }
+        result
+    }
+
+    /// Calls `call` again, with the retry policy's backoff, while it fails
+    /// with a transient error: a connection failure, a timeout or a 5xx.
+    /// Anything else, including an open circuit, fails right away. Appends
+    /// are safe to repeat since EventStore skips event ids it already wrote.
+    async fn with_retries<T, F, Fut>(&self, operation: &'static str, call: F) -> Result<T>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut attempt = 0;
+        loop {
+            match call().await {
+                Err(e) if attempt < self.retry_policy.max_retries && is_transient(&e) => {
+                    attempt += 1;
+                    let backoff = self.retry_policy.backoff(attempt);
+                    counter!("eventstore.retry_total", "operation" => operation).increment(1);
+                    warn!(
+                        "EventStore {} failed, retry {} in {:?}: {}",
+                        operation, attempt, backoff, e
+                    );
+                    tokio::time::sleep(backoff).await;
+                },
+                result => return result,
+            }
+        }
+    }
+
     #[instrument(skip(self, events), fields(stream_name))]
-    pub async fn append_to_stream<T>(&self, stream_name: &str, events: Vec<Event<T>>) -> Result<()>
+    pub async fn append_to_stream<T>(
+        &self,
+        stream_name: &str,
+        events: Vec<Event<T>>,
+    ) -> Result<WriteResult>
     where
         T: Serialize + for<'de> Deserialize<'de> + Clone + TypeName,
     {
-        let url = self.base_url.join(&format!("/streams/{}", stream_name))?;
+        self.append_to_stream_with_options(stream_name, events, self.append_defaults)
+            .await
+    }
 
+    /// Appends events with explicit consistency options instead of the
+    /// configured `append_defaults`
+    #[instrument(skip(self, events), fields(stream_name))]
+    pub async fn append_to_stream_with_options<T>(
+        &self,
+        stream_name: &str,
+        events: Vec<Event<T>>,
+        options: AppendOptions,
+    ) -> Result<WriteResult>
+    where
+        T: Serialize + for<'de> Deserialize<'de> + Clone + TypeName,
+    {
         let events: Vec<EventData> = events
             .into_iter()
             .map(|e| e.to_event_data())
             .collect::<Result<_>>()?;
 
-        let start = std::time::Instant::now();
-        let _response = self
-            .http_client
-            .post(url)
-            .json(&events)
-            .send()
-            .await?
-            .error_for_status()?;
+        self.post_events(stream_name, events, options).await
+    }
 
-        histogram!(
-            "eventstore.append.duration_ms",
-            start.elapsed().as_millis() as f64
-        );
-        counter!("eventstore.append.success_total", 1);
-        Ok(())
+    /// Appends events together with caller supplied metadata (e.g. user id,
+    /// source system), merged with the metadata derived from each event
+    #[instrument(skip(self, events), fields(stream_name))]
+    pub async fn append_to_stream_with_metadata<T>(
+        &self,
+        stream_name: &str,
+        events: Vec<(Event<T>, Value)>,
+    ) -> Result<WriteResult>
+    where
+        T: Serialize + for<'de> Deserialize<'de> + Clone + TypeName,
+    {
+        let events: Vec<EventData> = events
+            .into_iter()
+            .map(|(event, metadata)| event.to_event_data_with_metadata(metadata))
+            .collect::<Result<_>>()?;
+
+        self.post_events(stream_name, events, self.append_defaults)
+            .await
+    }
+
+    /// Appends events already converted with `Event::to_event_data`, e.g.
+    /// after being queued without their concrete type
+    #[instrument(skip(self, events), fields(stream_name))]
+    pub async fn append_event_data(
+        &self,
+        stream_name: &str,
+        events: Vec<EventData>,
+    ) -> Result<WriteResult> {
+        self.post_events(stream_name, events, self.append_defaults)
+            .await
+    }
+
+    /// Posts the events in batches of at most `max_append_size`, one after
+    /// another. When a batch after the first fails the error is a
+    /// `PartialAppend` telling how many events were written before it.
+    async fn post_events(
+        &self,
+        stream_name: &str,
+        events: Vec<EventData>,
+        options: AppendOptions,
+    ) -> Result<WriteResult> {
+        let path = format!("/streams/{}", stream_name);
+        let batches = events
+            .chunks(self.max_append_size.max(1))
+            .map(|batch| {
+                let (body, gzipped) = encode_body(&self.compression, serde_json::to_vec(batch)?)?;
+                Ok((batch.len(), append_request(options, body, gzipped)))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        if !options.await_commit {
+            self.circuit_breaker.acquire()?;
+            let circuit_breaker = Arc::clone(&self.circuit_breaker);
+            let nodes = Arc::clone(&self.nodes);
+            let stream_name = stream_name.to_string();
+            let append = async move {
+                for (_, build) in batches {
+                    let result = nodes
+                        .send(&path, build)
+                        .await
+                        .and_then(|r| Ok(r.error_for_status()?));
+                    match result {
+                        Ok(_) => {
+                            circuit_breaker.record_success();
+                            counter!("eventstore.append.success_total").increment(1)
+                        },
+                        Err(e) => {
+                            warn!("Unacknowledged append to {} failed: {}", stream_name, e);
+                            if e.downcast_ref::<reqwest::Error>().is_some_and(is_outage) {
+                                circuit_breaker.record_failure();
+                            } else {
+                                circuit_breaker.record_success();
+                            }
+                            record_failure("eventstore.append.failure_total", &e);
+                            // Later batches would leave a gap in the stream
+                            break;
+                        },
+                    }
+                }
+            };
+            // Runs in the caller's span so a failure is logged against its request
+            tokio::spawn(append.in_current_span());
+            return Ok(WriteResult {
+                position: None,
+                events_written: 0,
+            });
+        }
+
+        let mut appended = 0;
+        let mut position = None;
+        for (size, build) in batches {
+            let start = std::time::Instant::now();
+            let result = self
+                .with_retries("append", || {
+                    self.guarded(async {
+                        let response = self.nodes.send(&path, &build).await?;
+                        if response.status() == StatusCode::GONE {
+                            return Err(StreamDeleted(stream_name.to_string()).into());
+                        }
+                        let response = response.error_for_status()?;
+                        Ok(first_event_number(&response))
+                    })
+                })
+                .await
+                .inspect_err(|e| record_failure("eventstore.append.failure_total", e));
+
+            let first = match result {
+                Ok(first) => first,
+                Err(e) if appended == 0 => return Err(e),
+                Err(e) => {
+                    return Err(PartialAppend {
+                        stream_name: stream_name.to_string(),
+                        appended,
+                        position,
+                        source: e,
+                    }
+                    .into())
+                },
+            };
+            appended += size;
+            position = first.map(|first| first + size as u64 - 1);
+
+            histogram!("eventstore.append.duration_ms").record(start.elapsed().as_millis() as f64);
+            counter!("eventstore.append.success_total").increment(1);
+        }
+        Ok(WriteResult {
+            position,
+            events_written: appended,
+        })
     }
 
     #[instrument(skip(self), fields(stream_name, start, count))]
@@ -96,34 +391,267 @@ impl EventStoreClient {
     where
         T: Serialize + for<'de> Deserialize<'de> + Clone + TypeName,
     {
-        let url = self.base_url.join(&format!(
-            "/streams/{}/{}?count={}",
-            stream_name, start, count
-        ))?;
+        self.read_recorded(stream_name, start, count)
+            .await?
+            .into_iter()
+            .map(|e| e.into_domain_event())
+            .collect()
+    }
+
+    /// Reads events as stored, without decoding their payload into a domain
+    /// type
+    #[instrument(skip(self), fields(stream_name, start, count))]
+    pub async fn read_recorded(
+        &self,
+        stream_name: &str,
+        start: u64,
+        count: u64,
+    ) -> Result<Vec<RecordedEvent>> {
+        let path = format!("/streams/{}/{}?count={}", stream_name, start, count);
 
         let start = std::time::Instant::now();
-        let response = self.http_client.get(url).send().await?.error_for_status()?;
+        let events = self
+            .with_retries("read", || {
+                self.guarded(async {
+                    let response = self
+                        .nodes
+                        .send(&path, |http_client, url| http_client.get(url))
+                        .await?;
+                    if response.status() == StatusCode::NOT_FOUND {
+                        // Nothing has been appended to the stream yet
+                        if self.missing_stream_as_empty {
+                            counter!("eventstore.read.not_found_total").increment(1);
+                            return Ok(Vec::new());
+                        }
+                        return Err(StreamNotFound(stream_name.to_string()).into());
+                    }
+                    let response = response.error_for_status()?;
 
-        let events: Vec<RecordedEvent> = response.json().await?;
+                    Ok(response.json::<Vec<RecordedEvent>>().await?)
+                })
+            })
+            .await
+            .inspect_err(|e| record_failure("eventstore.read.failure_total", e))?;
 
-        let domain_events = events
-            .into_iter()
-            .map(|e| e.into_domain_event())
-            .collect::<Result<_>>()?;
+        histogram!("eventstore.read.duration_ms").record(start.elapsed().as_millis() as f64);
+        counter!("eventstore.read.success_total").increment(1);
+        Ok(events)
+    }
 
-        histogram!(
-            "eventstore.read.duration_ms",
-            start.elapsed().as_millis() as f64
-        );
-        counter!("eventstore.read.success_total", 1);
-        Ok(domain_events)
+    /// Deletes a stream. A soft delete hides its events but the stream is
+    /// recreated by the next append; a hard delete leaves a tombstone, so
+    /// later appends fail with `StreamDeleted`. Missing or already deleted
+    /// streams are not an error.
+    #[instrument(skip(self), fields(stream_name, hard_delete))]
+    pub async fn delete_stream(&self, stream_name: &str, hard_delete: bool) -> Result<()> {
+        let path = format!("/streams/{}", stream_name);
+        self.guarded(async {
+            let response = self
+                .nodes
+                .send(&path, |http_client, url| {
+                    http_client
+                        .delete(url)
+                        .header(HARD_DELETE_HEADER, hard_delete.to_string())
+                })
+                .await?;
+
+            match response.status() {
+                StatusCode::NOT_FOUND | StatusCode::GONE => Ok(()),
+                _ => {
+                    response.error_for_status()?;
+                    counter!("eventstore.delete.success_total", "hard_delete" => hard_delete.to_string()).increment(1);
+                    Ok(())
+                },
+            }
+        })
+        .await
+    }
+
+    /// Hard-deletes a stream, leaving a tombstone so its name can never be
+    /// written to again
+    pub async fn tombstone_stream(&self, stream_name: &str) -> Result<()> {
+        self.delete_stream(stream_name, true).await
+    }
+
+    /// Replaces the stream's metadata, e.g. its retention, by appending to
+    /// its `$$<stream>` metadata stream. Always awaits the commit, so the
+    /// new settings are in place once this returns.
+    #[instrument(skip(self, metadata), fields(stream_name))]
+    pub async fn set_stream_metadata(
+        &self,
+        stream_name: &str,
+        metadata: StreamMetadata,
+    ) -> Result<()> {
+        let event = EventData::new(METADATA_EVENT_TYPE.to_string(), metadata, Value::Null)?;
+        let options = AppendOptions {
+            await_commit: true,
+            ..self.append_defaults
+        };
+        // `$` percent-encoded, as it is reserved in URL paths
+        self.post_events(&format!("%24%24{}", stream_name), vec![event], options)
+            .await?;
+        Ok(())
+    }
+
+    /// Current metadata of the stream; a stream without any reads as the
+    /// default, i.e. no retention limits
+    #[instrument(skip(self), fields(stream_name))]
+    pub async fn get_stream_metadata(&self, stream_name: &str) -> Result<StreamMetadata> {
+        let path = format!("/streams/{}/metadata", stream_name);
+        self.with_retries("read", || {
+            self.guarded(async {
+                let response = self
+                    .nodes
+                    .send(&path, |http_client, url| {
+                        http_client.get(url).header(ACCEPT, "application/json")
+                    })
+                    .await?;
+                if response.status() == StatusCode::NOT_FOUND {
+                    return Ok(StreamMetadata::default());
+                }
+                let response = response.error_for_status()?;
+
+                Ok(response.json::<StreamMetadata>().await?)
+            })
+        })
+        .await
+        .inspect_err(|e| record_failure("eventstore.read.failure_total", e))
+    }
+}
+
+/// Builds the POST of one encoded batch; called again for every node tried
+fn append_request(
+    options: AppendOptions,
+    body: Vec<u8>,
+    gzipped: bool,
+) -> impl Fn(&HttpClient, Url) -> RequestBuilder {
+    move |http_client, url| {
+        let request = http_client
+            .post(url)
+            .header(
+                REQUIRE_MASTER_HEADER,
+                if options.require_master {
+                    "True"
+                } else {
+                    "False"
+                },
+            )
+            .header(CONTENT_TYPE, "application/json");
+        let request = if gzipped {
+            request.header(CONTENT_ENCODING, "gzip")
+        } else {
+            request
+        };
+        request.body(body.clone())
     }
 }
 
+/// Number of the first written event, from the `Location` EventStore sends
+/// back for an append (`.../streams/{stream}/{number}`)
+fn first_event_number(response: &Response) -> Option<u64> {
+    response
+        .headers()
+        .get(LOCATION)?
+        .to_str()
+        .ok()?
+        .rsplit('/')
+        .next()?
+        .parse()
+        .ok()
+}
+
+/// Gzips an append body when compression is enabled and the body reaches the
+/// size threshold, returning the body to send and whether it was compressed
+fn encode_body(config: &CompressionConfig, body: Vec<u8>) -> Result<(Vec<u8>, bool)> {
+    if !config.enabled || body.len() < config.min_size_bytes {
+        return Ok((body, false));
+    }
+
+    let mut encoder = GzEncoder::new(Vec::with_capacity(body.len() / 4), Compression::default());
+    encoder.write_all(&body)?;
+    let compressed = encoder.finish()?;
+    histogram!("eventstore.append.compression_ratio")
+        .record(compressed.len() as f64 / body.len() as f64);
+    Ok((compressed, true))
+}
+
+/// Cause of a failed EventStore call, reported as the `error_class` label of
+/// the failure counters. The fixed set of values keeps the label bounded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FailureClass {
+    CircuitOpen,
+    Connection,
+    Timeout,
+    ServerError,
+    ClientError,
+    NotFound,
+    Decode,
+    Other,
+}
+
+impl FailureClass {
+    fn of(error: &anyhow::Error) -> Self {
+        if error.is::<EventStoreError>() {
+            return Self::CircuitOpen;
+        }
+        if error.is::<StreamNotFound>() {
+            return Self::NotFound;
+        }
+        if error.is::<serde_json::Error>() {
+            return Self::Decode;
+        }
+        let Some(error) = error.downcast_ref::<reqwest::Error>() else {
+            return Self::Other;
+        };
+        match error.status() {
+            Some(status) if status.is_server_error() => Self::ServerError,
+            Some(status) if status == StatusCode::NOT_FOUND => Self::NotFound,
+            Some(status) if status.is_client_error() => Self::ClientError,
+            _ if error.is_timeout() => Self::Timeout,
+            _ if error.is_connect() => Self::Connection,
+            _ if error.is_decode() => Self::Decode,
+            _ => Self::Other,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::CircuitOpen => "circuit_open",
+            Self::Connection => "connection",
+            Self::Timeout => "timeout",
+            Self::ServerError => "server_error",
+            Self::ClientError => "client_error",
+            Self::NotFound => "not_found",
+            Self::Decode => "decode",
+            Self::Other => "other",
+        }
+    }
+}
+
+/// Counts a failed call in `counter` under its `FailureClass`
+fn record_failure(counter: &'static str, error: &anyhow::Error) {
+    counter!(counter, "error_class" => FailureClass::of(error).as_str()).increment(1);
+}
+
+/// Whether a failed call may succeed when repeated, i.e. it failed on an
+/// outage rather than on the request itself
+fn is_transient(error: &anyhow::Error) -> bool {
+    error
+        .downcast_ref::<reqwest::Error>()
+        .is_some_and(is_outage)
+}
+
+/// Whether a failed request means EventStore is unavailable: connection
+/// errors, timeouts and 5xx responses. A 4xx or an undecodable body comes
+/// from a live server and does not count against the circuit breaker.
+fn is_outage(error: &reqwest::Error) -> bool {
+    !error.is_decode() && error.status().is_none_or(|status| status.is_server_error())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use wiremock::matchers::{method, path};
+    use wiremock::matchers::{header, method, path};
     use wiremock::{Mock, MockServer, ResponseTemplate};
 
     #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -137,6 +665,18 @@ mod tests {
         }
     }
 
+    fn assert_timestamps_close(left: DateTime<Utc>, right: DateTime<Utc>, tolerance_ms: i64) {
+        let diff = (left - right).num_milliseconds().abs();
+        assert!(
+            diff <= tolerance_ms,
+            "timestamps differ by {}ms (tolerance {}ms): {} vs {}",
+            diff,
+            tolerance_ms,
+            left,
+            right
+        );
+    }
+
     #[tokio::test]
     async fn test_append_to_stream() -> Result<()> {
         let mock_server = MockServer::start().await;
@@ -162,6 +702,229 @@ mod tests {
         Ok(())
     }
 
+    /// Value of a counter the snapshotter's recorder got with the given
+    /// label
+    fn labelled_count(
+        snapshotter: &metrics_util::debugging::Snapshotter,
+        name: &str,
+        label: &str,
+        label_value: &str,
+    ) -> u64 {
+        use metrics_util::debugging::DebugValue;
+
+        snapshotter
+            .snapshot()
+            .into_vec()
+            .into_iter()
+            .find_map(|(key, _, _, value)| {
+                let key = key.key();
+                let labelled = key
+                    .labels()
+                    .any(|l| l.key() == label && l.value() == label_value);
+                match value {
+                    DebugValue::Counter(count) if key.name() == name && labelled => Some(count),
+                    _ => None,
+                }
+            })
+            .unwrap_or_default()
+    }
+
+    #[tokio::test]
+    async fn test_append_failure_is_counted_by_error_class() -> Result<()> {
+        // Local to this test's thread, so tests running in parallel don't
+        // see each other's metrics
+        let recorder = metrics_util::debugging::DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        let _recorder = metrics::set_default_local_recorder(&recorder);
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/streams/test-stream"))
+            .respond_with(ResponseTemplate::new(500))
+            .expect(3)
+            .mount(&mock_server)
+            .await;
+
+        let client = EventStoreClient::new(EventStoreConfig {
+            connection_string: mock_server.uri(),
+            max_retries: 2,
+            retry_delay: 10,
+            ..Default::default()
+        })?;
+        let event = Event::new(
+            TestEvent {
+                message: "Hello".to_string(),
+            },
+            1,
+            None,
+            None,
+            None,
+        );
+
+        assert!(client
+            .append_to_stream("test-stream", vec![event])
+            .await
+            .is_err());
+        // Counted once, after the retries ran out
+        assert_eq!(
+            labelled_count(
+                &snapshotter,
+                "eventstore.append.failure_total",
+                "error_class",
+                "server_error"
+            ),
+            1
+        );
+        assert_eq!(
+            labelled_count(
+                &snapshotter,
+                "eventstore.retry_total",
+                "operation",
+                "append"
+            ),
+            2
+        );
+        mock_server.verify().await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_read_is_retried_until_event_store_recovers() -> Result<()> {
+        let recorder = metrics_util::debugging::DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        let _recorder = metrics::set_default_local_recorder(&recorder);
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/streams/tenant-1/0"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(2)
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/streams/tenant-1/0"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(Vec::<RecordedEvent>::new()))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = EventStoreClient::new(EventStoreConfig {
+            connection_string: mock_server.uri(),
+            retry_delay: 10,
+            ..Default::default()
+        })?;
+
+        let events = client.read_recorded("tenant-1", 0, 10).await?;
+        assert!(events.is_empty());
+        assert_eq!(
+            labelled_count(&snapshotter, "eventstore.retry_total", "operation", "read"),
+            2
+        );
+        mock_server.verify().await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_failed_second_batch_reports_what_was_appended() -> Result<()> {
+        let mock_server = MockServer::start().await;
+        let location = format!("{}/streams/test-stream/0", mock_server.uri());
+
+        Mock::given(method("POST"))
+            .and(path("/streams/test-stream"))
+            .respond_with(ResponseTemplate::new(201).insert_header("Location", location.as_str()))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        // e.g. a concurrent writer made the expected version wrong
+        Mock::given(method("POST"))
+            .and(path("/streams/test-stream"))
+            .respond_with(ResponseTemplate::new(400))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = EventStoreClient::new(EventStoreConfig {
+            connection_string: mock_server.uri(),
+            max_append_size: 2,
+            ..Default::default()
+        })?;
+        let events = ["first", "second", "third"]
+            .into_iter()
+            .map(|message| {
+                Event::new(
+                    TestEvent {
+                        message: message.to_string(),
+                    },
+                    1,
+                    None,
+                    None,
+                    None,
+                )
+            })
+            .collect();
+
+        let error = client
+            .append_to_stream("test-stream", events)
+            .await
+            .expect_err("second batch is rejected");
+        let partial = error
+            .downcast_ref::<PartialAppend>()
+            .expect("error should be PartialAppend");
+        assert_eq!(partial.appended, 2);
+        assert_eq!(partial.position, Some(1));
+        mock_server.verify().await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_large_append_is_posted_in_batches() -> Result<()> {
+        let mock_server = MockServer::start().await;
+        for first in [0, 1000, 2000] {
+            let location = format!("{}/streams/test-stream/{}", mock_server.uri(), first);
+            Mock::given(method("POST"))
+                .and(path("/streams/test-stream"))
+                .respond_with(
+                    ResponseTemplate::new(201).insert_header("Location", location.as_str()),
+                )
+                .up_to_n_times(1)
+                .expect(1)
+                .mount(&mock_server)
+                .await;
+        }
+
+        let client = EventStoreClient::new(EventStoreConfig {
+            connection_string: mock_server.uri(),
+            max_append_size: 1000,
+            ..Default::default()
+        })?;
+        let events = (0..2500)
+            .map(|n| {
+                Event::new(
+                    TestEvent {
+                        message: n.to_string(),
+                    },
+                    1,
+                    None,
+                    None,
+                    None,
+                )
+            })
+            .collect();
+
+        let result = client.append_to_stream("test-stream", events).await?;
+        assert_eq!(
+            result,
+            WriteResult {
+                position: Some(2499),
+                events_written: 2500,
+            }
+        );
+        mock_server.verify().await;
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_read_stream() -> Result<()> {
         let mock_server = MockServer::start().await;
@@ -196,9 +959,577 @@ mod tests {
 
         let event = &events[0];
         assert_eq!(event.event_id, event_id);
-        assert_eq!(event.created_at, created);
+        assert_timestamps_close(event.created_at, created, 1);
         assert_eq!(event.data.message, "Hello");
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_append_options_set_headers() -> Result<()> {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/streams/test-stream"))
+            .and(header(REQUIRE_MASTER_HEADER, "True"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = EventStoreClient::new(EventStoreConfig {
+            connection_string: mock_server.uri(),
+            ..Default::default()
+        })?;
+        let event = Event::new(
+            TestEvent {
+                message: "Hello".to_string(),
+            },
+            1,
+            None,
+            None,
+            None,
+        );
+        client.append_to_stream("test-stream", vec![event]).await?;
+
+        mock_server.verify().await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_fire_and_forget_append_does_not_wait_for_ack() -> Result<()> {
+        let mock_server = MockServer::start().await;
+
+        // A failing server must not fail an unacknowledged append
+        Mock::given(method("POST"))
+            .and(path("/streams/test-stream"))
+            .and(header(REQUIRE_MASTER_HEADER, "False"))
+            .respond_with(ResponseTemplate::new(500))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = EventStoreClient::new(EventStoreConfig {
+            connection_string: mock_server.uri(),
+            ..Default::default()
+        })?;
+        let event = Event::new(
+            TestEvent {
+                message: "Hello".to_string(),
+            },
+            1,
+            None,
+            None,
+            None,
+        );
+        let options = AppendOptions {
+            require_master: false,
+            await_commit: false,
+        };
+        client
+            .append_to_stream_with_options("test-stream", vec![event], options)
+            .await?;
+
+        // The request is sent in the background
+        for _ in 0..50 {
+            if !mock_server
+                .received_requests()
+                .await
+                .unwrap_or_default()
+                .is_empty()
+            {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        mock_server.verify().await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_large_appends_are_gzipped() -> Result<()> {
+        use flate2::read::GzDecoder;
+        use std::io::Read;
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(201))
+            .mount(&mock_server)
+            .await;
+
+        let client = EventStoreClient::new(EventStoreConfig {
+            connection_string: mock_server.uri(),
+            compression: CompressionConfig {
+                enabled: true,
+                min_size_bytes: 1024,
+            },
+            ..Default::default()
+        })?;
+        let event = |message: String| Event::new(TestEvent { message }, 1, None, None, None);
+
+        client
+            .append_to_stream("large", vec![event("x".repeat(4096))])
+            .await?;
+        client
+            .append_to_stream("small", vec![event("hi".to_string())])
+            .await?;
+
+        let requests = mock_server
+            .received_requests()
+            .await
+            .expect("request recording enabled");
+        assert_eq!(requests.len(), 2);
+
+        let large = &requests[0];
+        let content_encoding = |request: &wiremock::Request| {
+            request
+                .headers
+                .iter()
+                .find(|(name, _)| name.as_str() == "content-encoding")
+                .map(|(_, value)| value.as_str().to_string())
+        };
+        assert_eq!(content_encoding(large).as_deref(), Some("gzip"));
+        assert!(large.body.len() < 4096);
+        let mut json = String::new();
+        GzDecoder::new(large.body.as_slice()).read_to_string(&mut json)?;
+        let sent: Vec<Value> = serde_json::from_str(&json)?;
+        assert_eq!(sent[0]["data"]["message"], "x".repeat(4096));
+
+        let small = &requests[1];
+        assert_eq!(content_encoding(small), None);
+        let sent: Vec<Value> = serde_json::from_slice(&small.body)?;
+        assert_eq!(sent[0]["data"]["message"], "hi");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_read_missing_stream() -> Result<()> {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/streams/missing-stream/0"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&mock_server)
+            .await;
+
+        let client = EventStoreClient::new(EventStoreConfig {
+            connection_string: mock_server.uri(),
+            ..Default::default()
+        })?;
+        let events = client
+            .read_stream::<TestEvent>("missing-stream", 0, 1)
+            .await?;
+        assert!(events.is_empty());
+
+        let strict_client = EventStoreClient::new(EventStoreConfig {
+            connection_string: mock_server.uri(),
+            missing_stream_as_empty: false,
+            ..Default::default()
+        })?;
+        let err = strict_client
+            .read_stream::<TestEvent>("missing-stream", 0, 1)
+            .await
+            .expect_err("missing stream should fail");
+        let not_found = err
+            .downcast_ref::<StreamNotFound>()
+            .expect("error should be StreamNotFound");
+        assert_eq!(not_found.0, "missing-stream");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_tombstone_stream_hard_deletes() -> Result<()> {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("DELETE"))
+            .and(path("/streams/tenant-1"))
+            .and(header(HARD_DELETE_HEADER, "true"))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        // Already tombstoned
+        Mock::given(method("DELETE"))
+            .and(path("/streams/tenant-2"))
+            .respond_with(ResponseTemplate::new(410))
+            .mount(&mock_server)
+            .await;
+
+        let client = EventStoreClient::new(EventStoreConfig {
+            connection_string: mock_server.uri(),
+            ..Default::default()
+        })?;
+        client.tombstone_stream("tenant-1").await?;
+        client.tombstone_stream("tenant-2").await?;
+
+        mock_server.verify().await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_soft_deleted_stream_can_be_recreated() -> Result<()> {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("DELETE"))
+            .and(path("/streams/orders"))
+            .and(header(HARD_DELETE_HEADER, "false"))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/streams/orders"))
+            .respond_with(ResponseTemplate::new(201))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = EventStoreClient::new(EventStoreConfig {
+            connection_string: mock_server.uri(),
+            ..Default::default()
+        })?;
+        client.delete_stream("orders", false).await?;
+        let event = Event::new(
+            TestEvent {
+                message: "Hello again".to_string(),
+            },
+            1,
+            None,
+            None,
+            None,
+        );
+        client.append_to_stream("orders", vec![event]).await?;
+
+        mock_server.verify().await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_append_to_hard_deleted_stream_fails_with_stream_deleted() -> Result<()> {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("DELETE"))
+            .and(path("/streams/orders"))
+            .and(header(HARD_DELETE_HEADER, "true"))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        // Not retried, a tombstone is permanent
+        Mock::given(method("POST"))
+            .and(path("/streams/orders"))
+            .respond_with(ResponseTemplate::new(410))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = EventStoreClient::new(EventStoreConfig {
+            connection_string: mock_server.uri(),
+            ..Default::default()
+        })?;
+        client.delete_stream("orders", true).await?;
+        let event = Event::new(
+            TestEvent {
+                message: "Hello again".to_string(),
+            },
+            1,
+            None,
+            None,
+            None,
+        );
+        let error = client
+            .append_to_stream("orders", vec![event])
+            .await
+            .expect_err("the stream is tombstoned");
+
+        let deleted = error
+            .downcast_ref::<StreamDeleted>()
+            .expect("a StreamDeleted error");
+        assert_eq!(deleted.0, "orders");
+        mock_server.verify().await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_set_stream_metadata_posts_to_metadata_stream() -> Result<()> {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/streams/%24%24test-stream"))
+            .respond_with(ResponseTemplate::new(201))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = EventStoreClient::new(EventStoreConfig {
+            connection_string: mock_server.uri(),
+            ..Default::default()
+        })?;
+        client
+            .set_stream_metadata(
+                "test-stream",
+                StreamMetadata {
+                    max_age: Some(Duration::from_secs(3600)),
+                    max_count: Some(500),
+                    ..Default::default()
+                },
+            )
+            .await?;
+
+        let requests = mock_server
+            .received_requests()
+            .await
+            .expect("request recording enabled");
+        let posted: Vec<Value> = serde_json::from_slice(&requests[0].body)?;
+        assert_eq!(posted.len(), 1);
+        assert_eq!(posted[0]["eventType"], "$metadata");
+        let expected: Value = serde_json::from_str(r#"{"$maxAge": 3600, "$maxCount": 500}"#)?;
+        assert_eq!(posted[0]["data"], expected);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_stream_metadata() -> Result<()> {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/streams/test-stream/metadata"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(
+                r#"{"$maxCount": 10, "$acl": {"$r": "$admins"}}"#,
+                "application/json",
+            ))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/streams/new-stream/metadata"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&mock_server)
+            .await;
+
+        let client = EventStoreClient::new(EventStoreConfig {
+            connection_string: mock_server.uri(),
+            ..Default::default()
+        })?;
+        let metadata = client.get_stream_metadata("test-stream").await?;
+        assert_eq!(metadata.max_count, Some(10));
+        assert_eq!(
+            metadata.acl.and_then(|acl| acl.read),
+            Some(vec!["$admins".to_string()])
+        );
+        assert_eq!(
+            client.get_stream_metadata("new-stream").await?,
+            StreamMetadata::default()
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_read_fails_over_to_next_node() -> Result<()> {
+        let failing_node = MockServer::start().await;
+        let healthy_node = MockServer::start().await;
+
+        // Skipped after the first failure instead of being retried
+        Mock::given(method("GET"))
+            .and(path("/streams/tenant-1/0"))
+            .respond_with(ResponseTemplate::new(503))
+            .expect(1)
+            .mount(&failing_node)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/streams/tenant-1/0"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(Vec::<RecordedEvent>::new()))
+            .expect(2)
+            .mount(&healthy_node)
+            .await;
+
+        let client = EventStoreClient::new(EventStoreConfig {
+            nodes: vec![failing_node.uri(), healthy_node.uri()],
+            ..Default::default()
+        })?;
+
+        client.read_recorded("tenant-1", 0, 10).await?;
+        client.read_recorded("tenant-1", 0, 10).await?;
+
+        failing_node.verify().await;
+        healthy_node.verify().await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_circuit_breaker_short_circuits_unavailable_event_store() -> Result<()> {
+        use crate::circuit_breaker::{CircuitBreakerConfig, CircuitState, EventStoreError};
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/streams/tenant-1/0"))
+            .respond_with(ResponseTemplate::new(503))
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+
+        let client = EventStoreClient::new(EventStoreConfig {
+            connection_string: mock_server.uri(),
+            max_retries: 0,
+            circuit_breaker: CircuitBreakerConfig {
+                failure_threshold: 2,
+                cool_down_ms: 50,
+            },
+            ..Default::default()
+        })?;
+
+        for _ in 0..2 {
+            assert!(client.read_recorded("tenant-1", 0, 10).await.is_err());
+        }
+        assert_eq!(client.circuit_breaker().state(), CircuitState::Open);
+
+        // Rejected without reaching the server
+        let error = client
+            .read_recorded("tenant-1", 0, 10)
+            .await
+            .expect_err("circuit is open");
+        assert!(matches!(
+            error.downcast_ref::<EventStoreError>(),
+            Some(EventStoreError::CircuitOpen { .. })
+        ));
+        mock_server.verify().await;
+
+        // EventStore recovers; the probe after the cool-down closes the circuit
+        mock_server.reset().await;
+        Mock::given(method("GET"))
+            .and(path("/streams/tenant-1/0"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(Vec::<RecordedEvent>::new()))
+            .mount(&mock_server)
+            .await;
+        tokio::time::sleep(Duration::from_millis(60)).await;
+
+        client.read_recorded("tenant-1", 0, 10).await?;
+        assert_eq!(client.circuit_breaker().state(), CircuitState::Closed);
+        Ok(())
+    }
+
+    #[test]
+    fn test_created_with_offset_normalizes_to_utc() -> Result<()> {
+        let json = format!(
+            r#"{{
+                "eventId": "{}",
+                "eventType": "TestEvent",
+                "data": {{ "message": "Hello" }},
+                "metadata": null,
+                "created": "2024-03-10T14:30:00.1234567+02:00"
+            }}"#,
+            Uuid::new_v4()
+        );
+
+        let recorded: RecordedEvent = serde_json::from_str(&json)?;
+        let expected = DateTime::parse_from_rfc3339("2024-03-10T12:30:00.1234567Z")?;
+        assert_eq!(recorded.created, expected);
+
+        let serialized = serde_json::to_value(&recorded)?;
+        assert_eq!(serialized["created"], "2024-03-10T12:30:00.123456700Z");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_custom_metadata_round_trips() -> Result<()> {
+        let mock_server = MockServer::start().await;
+
+        let config = EventStoreConfig {
+            connection_string: mock_server.uri(),
+            ..Default::default()
+        };
+        let client = EventStoreClient::new(config)?;
+
+        Mock::given(method("POST"))
+            .and(path("/streams/test-stream"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&mock_server)
+            .await;
+
+        let event = Event::new(
+            TestEvent {
+                message: "Hello".to_string(),
+            },
+            1,
+            None,
+            None,
+            None,
+        );
+        client
+            .append_to_stream_with_metadata(
+                "test-stream",
+                vec![(event, serde_json::from_str(r#"{ "user_id": "user-42" }"#)?)],
+            )
+            .await?;
+
+        // Serve back exactly what was appended
+        let requests = mock_server
+            .received_requests()
+            .await
+            .expect("request recording enabled");
+        let mut appended: Vec<Value> = serde_json::from_slice(&requests[0].body)?;
+        appended[0]["created"] = serde_json::to_value(Utc::now())?;
+
+        Mock::given(method("GET"))
+            .and(path("/streams/test-stream/0"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(appended))
+            .mount(&mock_server)
+            .await;
+
+        let events = client.read_stream::<TestEvent>("test-stream", 0, 1).await?;
+        assert_eq!(events[0].metadata["user_id"], "user-42");
+        assert_eq!(events[0].metadata["schema_version"], 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_correlation_and_causation_ids_round_trip() -> Result<()> {
+        let mock_server = MockServer::start().await;
+
+        let config = EventStoreConfig {
+            connection_string: mock_server.uri(),
+            ..Default::default()
+        };
+        let client = EventStoreClient::new(config)?;
+
+        Mock::given(method("POST"))
+            .and(path("/streams/test-stream"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&mock_server)
+            .await;
+
+        let correlation_id = Uuid::new_v4();
+        let causation_id = Uuid::new_v4();
+        let event = Event::builder(TestEvent {
+            message: "Hello".to_string(),
+        })
+        .version(3)
+        .correlation_id(correlation_id)
+        .causation_id(causation_id)
+        .build();
+        client.append_to_stream("test-stream", vec![event]).await?;
+
+        // Serve back exactly what was appended
+        let requests = mock_server
+            .received_requests()
+            .await
+            .expect("request recording enabled");
+        let mut appended: Vec<Value> = serde_json::from_slice(&requests[0].body)?;
+        appended[0]["created"] = Value::String(Utc::now().to_rfc3339());
+
+        Mock::given(method("GET"))
+            .and(path("/streams/test-stream/0"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(appended))
+            .mount(&mock_server)
+            .await;
+
+        let events = client.read_stream::<TestEvent>("test-stream", 0, 1).await?;
+        assert_eq!(events[0].version, 3);
+        assert_eq!(events[0].correlation_id, Some(correlation_id));
+        assert_eq!(events[0].causation_id, Some(causation_id));
+
+        Ok(())
+    }
 }
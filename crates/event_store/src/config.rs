@@ -2,6 +2,7 @@ use anyhow::Result;
 use serde::Deserialize;
 use std::time::Duration;
 
+use crate::circuit_breaker::CircuitBreakerConfig;
 use crate::client::EventStoreClient;
 
 #[derive(Debug, Clone, Deserialize)]
@@ -9,6 +10,16 @@ pub struct EventStoreConfig {
     /// The connection string to the EventStore cluster
     pub connection_string: String,
 
+    /// URLs of the cluster nodes in failover order. When empty,
+    /// `connection_string` is the only node.
+    #[serde(default)]
+    pub nodes: Vec<String>,
+
+    /// How long a node that failed is skipped before it is tried again, in
+    /// milliseconds
+    #[serde(default = "default_node_cooldown_ms")]
+    pub node_cooldown_ms: u64,
+
     /// Maximum number of retry attempts for operations
     pub max_retries: u32,
 
@@ -17,15 +28,161 @@ pub struct EventStoreConfig {
 
     /// Maximum number of events to append in a single batch
     pub max_append_size: usize,
+
+    /// Events a subscription reads per poll unless its options ask for
+    /// another count
+    #[serde(default = "default_subscription_read_count")]
+    pub subscription_read_count: u64,
+
+    /// Upper bound of any per-poll read count of a subscription
+    #[serde(default = "default_max_read_count")]
+    pub max_read_count: u64,
+
+    /// How long a subscription that caught up with its stream waits before
+    /// polling again, in milliseconds, unless its options ask for another
+    /// interval
+    #[serde(default = "default_subscription_poll_interval_ms")]
+    pub subscription_poll_interval_ms: u64,
+
+    /// Treat reading a stream that does not exist as reading an empty stream
+    /// instead of failing with `StreamNotFound`
+    #[serde(default = "default_missing_stream_as_empty")]
+    pub missing_stream_as_empty: bool,
+
+    /// Write options used by appends that don't pass their own
+    #[serde(default)]
+    pub append_defaults: AppendOptions,
+
+    /// Gzip compression of append request bodies
+    #[serde(default)]
+    pub compression: CompressionConfig,
+
+    /// When to stop calling an unreachable EventStore and fail fast instead
+    #[serde(default)]
+    pub circuit_breaker: CircuitBreakerConfig,
+
+    /// Connection pool of the HTTP client
+    #[serde(default)]
+    pub http: HttpPoolConfig,
+}
+
+/// Pooling of the connections to the EventStore nodes. Fields missing from
+/// the configuration keep their `Default` value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct HttpPoolConfig {
+    /// Idle pooled connections are closed after this many seconds
+    pub pool_idle_timeout_secs: u64,
+
+    /// Idle connections kept open per node
+    pub pool_max_idle_per_host: usize,
+
+    /// Interval of TCP keep-alive probes on open connections, in seconds
+    pub tcp_keepalive_secs: u64,
+
+    /// Timeout of a whole request, in seconds
+    pub timeout_secs: u64,
+}
+
+impl Default for HttpPoolConfig {
+    fn default() -> Self {
+        Self {
+            pool_idle_timeout_secs: 90,
+            pool_max_idle_per_host: 16,
+            tcp_keepalive_secs: 60,
+            timeout_secs: 30,
+        }
+    }
+}
+
+/// Consistency options for a single append
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct AppendOptions {
+    /// Only accept the write on the cluster master (`ES-RequireMaster`)
+    #[serde(default = "default_true")]
+    pub require_master: bool,
+
+    /// Wait for EventStore to acknowledge the write before returning. When
+    /// disabled the append is fire-and-forget and failures are only logged.
+    #[serde(default = "default_true")]
+    pub await_commit: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct CompressionConfig {
+    /// Send append bodies gzip-compressed (`Content-Encoding: gzip`)
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Bodies smaller than this many bytes are sent uncompressed, as gzip
+    /// gains nothing on them
+    #[serde(default = "default_compression_min_size")]
+    pub min_size_bytes: usize,
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min_size_bytes: default_compression_min_size(),
+        }
+    }
+}
+
+impl Default for AppendOptions {
+    fn default() -> Self {
+        Self {
+            require_master: true,
+            await_commit: true,
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_missing_stream_as_empty() -> bool {
+    true
+}
+
+fn default_subscription_read_count() -> u64 {
+    100
+}
+
+fn default_max_read_count() -> u64 {
+    4096
+}
+
+fn default_subscription_poll_interval_ms() -> u64 {
+    1000
+}
+
+fn default_node_cooldown_ms() -> u64 {
+    5000
+}
+
+fn default_compression_min_size() -> usize {
+    4096
 }
 
 impl Default for EventStoreConfig {
     fn default() -> Self {
         Self {
             connection_string: "http://localhost:2113".to_string(),
+            nodes: Vec::new(),
+            node_cooldown_ms: default_node_cooldown_ms(),
             max_retries: 3,
             retry_delay: 1000,
             max_append_size: 1000,
+            subscription_read_count: default_subscription_read_count(),
+            max_read_count: default_max_read_count(),
+            subscription_poll_interval_ms: default_subscription_poll_interval_ms(),
+            missing_stream_as_empty: default_missing_stream_as_empty(),
+            append_defaults: AppendOptions::default(),
+            compression: CompressionConfig::default(),
+            circuit_breaker: CircuitBreakerConfig::default(),
+            http: HttpPoolConfig::default(),
         }
     }
 }
@@ -34,6 +191,15 @@ impl EventStoreConfig {
     pub fn create_client(&self) -> Result<EventStoreClient> {
         EventStoreClient::new(self.clone())
     }
+
+    /// The nodes to connect to, in failover order
+    pub fn node_urls(&self) -> &[String] {
+        if self.nodes.is_empty() {
+            std::slice::from_ref(&self.connection_string)
+        } else {
+            &self.nodes
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -49,6 +215,16 @@ impl RetryPolicy {
             delay: Duration::from_millis(delay_ms),
         }
     }
+
+    /// Wait before retry number `attempt`, counting from 1: `delay` doubled
+    /// for every earlier retry, plus up to half of that as jitter so clients
+    /// that failed together don't retry in lockstep
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let base = self
+            .delay
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)));
+        base.saturating_add(base.mul_f64(fastrand::f64() / 2.0))
+    }
 }
 
 impl EventStoreConfig {
@@ -68,6 +244,26 @@ mod tests {
         assert_eq!(config.max_retries, 3);
         assert_eq!(config.retry_delay, 1000);
         assert_eq!(config.max_append_size, 1000);
+        assert!(config.nodes.is_empty());
+        assert_eq!(config.node_urls(), ["http://localhost:2113"]);
+        assert!(config.missing_stream_as_empty);
+        assert_eq!(config.append_defaults, AppendOptions::default());
+        assert!(!config.compression.enabled);
+        assert_eq!(config.circuit_breaker, CircuitBreakerConfig::default());
+        assert_eq!(config.http.timeout_secs, 30);
+    }
+
+    #[test]
+    fn test_pool_keys_left_out_keep_their_defaults() -> Result<()> {
+        let http: HttpPoolConfig = serde_json::from_str(r#"{ "timeout_secs": 5 }"#)?;
+        assert_eq!(
+            http,
+            HttpPoolConfig {
+                timeout_secs: 5,
+                ..HttpPoolConfig::default()
+            }
+        );
+        Ok(())
     }
 
     #[test]
@@ -76,4 +272,15 @@ mod tests {
         let _client = config.create_client()?;
         Ok(())
     }
+
+    #[test]
+    fn test_backoff_doubles_with_jitter() {
+        let policy = RetryPolicy::new(3, 100);
+        for _ in 0..20 {
+            let first = policy.backoff(1);
+            assert!(first >= Duration::from_millis(100) && first <= Duration::from_millis(150));
+            let third = policy.backoff(3);
+            assert!(third >= Duration::from_millis(400) && third <= Duration::from_millis(600));
+        }
+    }
 }
@@ -7,7 +7,7 @@ use uuid::Uuid;
 /// Base trait for all domain events
 pub trait DomainEvent: Send + Sync {
     /// Returns the type of the event
-    fn event_type(&self) -> &str;
+    fn event_type(&self) -> String;
 
     /// Returns the aggregate ID this event belongs to
     fn aggregate_id(&self) -> Uuid;
@@ -54,6 +54,10 @@ where
     pub version: u64,
     pub correlation_id: Option<Uuid>,
     pub causation_id: Option<Uuid>,
+    /// Tenant the event belongs to, stored as `tenant_id` in its metadata
+    pub tenant_id: Option<Uuid>,
+    /// Metadata stored alongside the event (`Null` until appended or read)
+    pub metadata: Value,
 }
 
 impl<T> Event<T>
@@ -74,19 +78,190 @@ where
             causation_id,
             created_at: Utc::now(),
             event_id: event_id.unwrap_or_else(Uuid::new_v4),
+            tenant_id: None,
+            metadata: Value::Null,
         }
     }
 
+    /// Starts an event for `data` with its optional fields named, as an
+    /// alternative to the positional arguments of `new`. Without further
+    /// calls it builds the same event as `Event::new(data, 1, None, None, None)`.
+    pub fn builder(data: T) -> EventBuilder<T> {
+        EventBuilder {
+            data,
+            version: 1,
+            correlation_id: None,
+            causation_id: None,
+            event_id: None,
+            tenant_id: None,
+        }
+    }
+
+    /// Event derived from `source`, e.g. by a handler reacting to it: it is
+    /// caused by the source event, shares its correlation id and belongs to
+    /// its tenant. A source without a correlation id starts the chain, so
+    /// its own id is used.
+    pub fn caused_by<S>(source: &Event<S>, data: T) -> Self
+    where
+        S: Serialize + for<'de> Deserialize<'de> + Clone + TypeName,
+    {
+        let mut event = Self::builder(data)
+            .correlation_id(source.correlation_id.unwrap_or(source.event_id))
+            .causation_id(source.event_id)
+            .build();
+        event.tenant_id = source.tenant_id;
+        event
+    }
+
+    /// Event as written to the store, with the metadata derived from the
+    /// event (schema version, timestamp, correlation, causation and tenant
+    /// ids)
     pub fn to_event_data(&self) -> Result<EventData> {
+        self.to_event_data_with_metadata(Value::Null)
+    }
+
+    /// Like `to_event_data`, but the derived metadata is merged with caller
+    /// supplied metadata. Caller keys take precedence.
+    pub fn to_event_data_with_metadata(&self, metadata: Value) -> Result<EventData> {
+        let derived = EventMetadata {
+            schema_version: self.version as u32,
+            timestamp: self.created_at,
+            correlation_id: self.correlation_id,
+            causation_id: self.causation_id,
+            tenant_id: self.tenant_id,
+        };
+
+        let mut merged = metadata_fields(serde_json::to_value(derived)?)?;
+        if !metadata.is_null() {
+            merged.extend(metadata_fields(metadata)?);
+        }
+
         Ok(EventData {
             event_type: self.data.type_name(),
             data: serde_json::to_value(&self.data)?,
-            metadata: Value::Null,
+            metadata: Value::Object(merged),
             event_id: self.event_id,
         })
     }
 }
 
+/// Fields of event metadata, which must be a JSON object
+fn metadata_fields(metadata: Value) -> Result<serde_json::Map<String, Value>> {
+    match metadata {
+        Value::Object(fields) => Ok(fields),
+        other => anyhow::bail!("event metadata must be a JSON object, got {}", other),
+    }
+}
+
+impl<T> Event<T>
+where
+    T: Serialize + for<'de> Deserialize<'de> + Clone + TypeName,
+{
+    /// Id stored under `key` in the event metadata
+    fn metadata_id(&self, key: &str) -> Option<Uuid> {
+        self.metadata.get(key)?.as_str()?.parse().ok()
+    }
+}
+
+/// Ids that aren't fields of `Event` come from its metadata, as written by
+/// `to_event_data_with_metadata` or read back from the store. An event
+/// without an `aggregate_id` there reports the nil UUID.
+impl<T> DomainEvent for Event<T>
+where
+    T: Serialize + for<'de> Deserialize<'de> + Clone + TypeName + Send + Sync,
+{
+    fn event_type(&self) -> String {
+        self.data.type_name()
+    }
+
+    fn aggregate_id(&self) -> Uuid {
+        self.metadata_id("aggregate_id").unwrap_or_else(Uuid::nil)
+    }
+
+    fn schema_version(&self) -> u32 {
+        self.version as u32
+    }
+
+    fn timestamp(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+
+    fn correlation_id(&self) -> Option<Uuid> {
+        self.correlation_id
+    }
+
+    fn causation_id(&self) -> Option<Uuid> {
+        self.causation_id
+    }
+
+    fn tenant_id(&self) -> Option<Uuid> {
+        self.tenant_id.or_else(|| self.metadata_id("tenant_id"))
+    }
+}
+
+/// Builder returned by `Event::builder`
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct EventBuilder<T>
+where
+    T: Serialize + for<'de> Deserialize<'de> + Clone + TypeName,
+{
+    data: T,
+    version: u64,
+    correlation_id: Option<Uuid>,
+    causation_id: Option<Uuid>,
+    event_id: Option<Uuid>,
+    tenant_id: Option<Uuid>,
+}
+
+impl<T> EventBuilder<T>
+where
+    T: Serialize + for<'de> Deserialize<'de> + Clone + TypeName,
+{
+    /// Schema version of the event data, 1 unless set
+    pub fn version(mut self, version: u64) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Id shared by all events of one request or process
+    pub fn correlation_id(mut self, correlation_id: Uuid) -> Self {
+        self.correlation_id = Some(correlation_id);
+        self
+    }
+
+    /// Id of the event or command that caused this event
+    pub fn causation_id(mut self, causation_id: Uuid) -> Self {
+        self.causation_id = Some(causation_id);
+        self
+    }
+
+    /// Fixed event id, e.g. for idempotent appends; a random one unless set
+    pub fn event_id(mut self, event_id: Uuid) -> Self {
+        self.event_id = Some(event_id);
+        self
+    }
+
+    /// Tenant the event belongs to, which routes it to the tenant's live
+    /// event sockets and webhooks
+    pub fn tenant_id(mut self, tenant_id: Uuid) -> Self {
+        self.tenant_id = Some(tenant_id);
+        self
+    }
+
+    pub fn build(self) -> Event<T> {
+        let mut event = Event::new(
+            self.data,
+            self.version,
+            self.correlation_id,
+            self.causation_id,
+            self.event_id,
+        );
+        event.tenant_id = self.tenant_id;
+        event
+    }
+}
+
 /// Stream naming conventions
 pub struct StreamName;
 
@@ -187,6 +362,9 @@ mod tests {
 
         assert_eq!(event_data.event_type, "TestEvent");
         assert_eq!(event_data.event_id, event.event_id);
+        let metadata: EventMetadata = serde_json::from_value(event_data.metadata)?;
+        assert_eq!(metadata.schema_version, 1);
+        assert_eq!(metadata.timestamp, event.created_at);
 
         let data: TestEvent = serde_json::from_value(event_data.data)?;
         assert_eq!(data.message, "Hello");
@@ -194,6 +372,180 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_builder_matches_new() {
+        let correlation_id = Uuid::new_v4();
+        let causation_id = Uuid::new_v4();
+        let event_id = Uuid::new_v4();
+
+        let built = Event::builder(TestEvent {
+            message: "Hello".to_string(),
+        })
+        .version(3)
+        .causation_id(causation_id)
+        .correlation_id(correlation_id)
+        .event_id(event_id)
+        .build();
+        let new = Event::new(
+            TestEvent {
+                message: "Hello".to_string(),
+            },
+            3,
+            Some(correlation_id),
+            Some(causation_id),
+            Some(event_id),
+        );
+
+        assert_eq!(built.data.message, new.data.message);
+        assert_eq!(built.version, new.version);
+        assert_eq!(built.correlation_id, new.correlation_id);
+        assert_eq!(built.causation_id, new.causation_id);
+        assert_eq!(built.event_id, new.event_id);
+        assert_eq!(built.metadata, new.metadata);
+    }
+
+    #[test]
+    fn test_builder_defaults_match_new() {
+        let built = Event::builder(TestEvent {
+            message: "Hello".to_string(),
+        })
+        .build();
+        let new = Event::new(
+            TestEvent {
+                message: "Hello".to_string(),
+            },
+            1,
+            None,
+            None,
+            None,
+        );
+
+        assert_eq!(built.version, new.version);
+        assert_eq!(built.correlation_id, None);
+        assert_eq!(built.causation_id, None);
+        assert_eq!(built.metadata, Value::Null);
+        // Like `new`, every built event gets its own id
+        assert_ne!(built.event_id, new.event_id);
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    struct DerivedEvent {
+        reply: String,
+    }
+
+    impl TypeName for DerivedEvent {
+        fn type_name(&self) -> String {
+            "DerivedEvent".to_string()
+        }
+    }
+
+    #[test]
+    fn test_caused_by_chains_ids() {
+        let correlation_id = Uuid::new_v4();
+        let source = Event::builder(TestEvent {
+            message: "Hello".to_string(),
+        })
+        .correlation_id(correlation_id)
+        .build();
+
+        let derived = Event::caused_by(
+            &source,
+            DerivedEvent {
+                reply: "Hi".to_string(),
+            },
+        );
+        assert_eq!(derived.causation_id, Some(source.event_id));
+        assert_eq!(derived.correlation_id, Some(correlation_id));
+        assert_ne!(derived.event_id, source.event_id);
+
+        // Further down the chain the correlation id stays the same
+        let next = Event::caused_by(&derived, derived.data.clone());
+        assert_eq!(next.causation_id, Some(derived.event_id));
+        assert_eq!(next.correlation_id, Some(correlation_id));
+    }
+
+    #[test]
+    fn test_caused_by_uncorrelated_source_starts_the_chain() {
+        let source = Event::builder(TestEvent {
+            message: "Hello".to_string(),
+        })
+        .build();
+
+        let derived = Event::caused_by(
+            &source,
+            DerivedEvent {
+                reply: "Hi".to_string(),
+            },
+        );
+        assert_eq!(derived.causation_id, Some(source.event_id));
+        assert_eq!(derived.correlation_id, Some(source.event_id));
+    }
+
+    #[test]
+    fn test_event_implements_domain_event() {
+        let (aggregate_id, tenant_id) = (Uuid::new_v4(), Uuid::new_v4());
+        let (correlation_id, causation_id) = (Uuid::new_v4(), Uuid::new_v4());
+        let mut event = Event::builder(TestEvent {
+            message: "Hello".to_string(),
+        })
+        .version(3)
+        .correlation_id(correlation_id)
+        .causation_id(causation_id)
+        .build();
+        let mut metadata = serde_json::Map::new();
+        metadata.insert("aggregate_id".to_string(), aggregate_id.to_string().into());
+        metadata.insert("tenant_id".to_string(), tenant_id.to_string().into());
+        event.metadata = metadata.into();
+
+        let event: &dyn DomainEvent = &event;
+        assert_eq!(event.event_type(), "TestEvent");
+        assert_eq!(event.aggregate_id(), aggregate_id);
+        assert_eq!(event.tenant_id(), Some(tenant_id));
+        assert_eq!(event.schema_version(), 3);
+        assert_eq!(event.correlation_id(), Some(correlation_id));
+        assert_eq!(event.causation_id(), Some(causation_id));
+        assert!(event.timestamp() <= Utc::now());
+    }
+
+    #[test]
+    fn test_domain_event_ids_missing_from_metadata() {
+        let event = Event::new(
+            TestEvent {
+                message: "Hello".to_string(),
+            },
+            1,
+            None,
+            None,
+            None,
+        );
+
+        assert_eq!(DomainEvent::aggregate_id(&event), Uuid::nil());
+        assert_eq!(DomainEvent::tenant_id(&event), None);
+    }
+
+    #[test]
+    fn test_tenant_id_is_written_to_metadata() -> Result<()> {
+        let tenant_id = Uuid::new_v4();
+        let event = Event::builder(TestEvent {
+            message: "Hello".to_string(),
+        })
+        .tenant_id(tenant_id)
+        .build();
+
+        let event_data = event.to_event_data()?;
+        assert_eq!(event_data.metadata["tenant_id"], tenant_id.to_string());
+        assert_eq!(DomainEvent::tenant_id(&event), Some(tenant_id));
+
+        let derived = Event::caused_by(
+            &event,
+            TestEvent {
+                message: "Derived".to_string(),
+            },
+        );
+        assert_eq!(derived.tenant_id, Some(tenant_id));
+        Ok(())
+    }
+
     #[test]
     fn test_stream_naming() {
         let tenant_id = Uuid::new_v4();
@@ -217,4 +569,35 @@ mod tests {
         assert_eq!(EventCategory::User.as_str(), "user");
         assert_eq!(EventCategory::System.as_str(), "system");
     }
+
+    #[test]
+    fn test_event_data_with_metadata_merges_derived_fields() -> Result<()> {
+        let correlation_id = Uuid::new_v4();
+        let event = Event::new(
+            TestEvent {
+                message: "Hello".to_string(),
+            },
+            2,
+            Some(correlation_id),
+            None,
+            None,
+        );
+
+        let event_data = event.to_event_data_with_metadata(serde_json::from_str(
+            r#"{ "user_id": "user-42", "source": "billing" }"#,
+        )?)?;
+
+        assert_eq!(event_data.metadata["user_id"], "user-42");
+        assert_eq!(event_data.metadata["source"], "billing");
+        assert_eq!(event_data.metadata["schema_version"], 2);
+        assert_eq!(
+            event_data.metadata["correlation_id"],
+            correlation_id.to_string()
+        );
+
+        assert!(event
+            .to_event_data_with_metadata(Value::from("not an object"))
+            .is_err());
+        Ok(())
+    }
 }
@@ -1,12 +1,22 @@
+pub mod circuit_breaker;
 pub mod client;
 pub mod config;
 pub mod events;
-
-pub use client::{EventStoreClient, RecordedEvent};
-pub use config::{EventStoreConfig, RetryPolicy};
-pub use events::{DomainEvent, Event, EventCategory, EventMetadata, StreamName, TypeName};
+mod node_pool;
+pub mod stream_metadata;
+pub mod subscription;
+
+pub use circuit_breaker::{CircuitBreaker, CircuitBreakerConfig, CircuitState, EventStoreError};
+pub use client::{EventStoreClient, PartialAppend, RecordedEvent, StreamDeleted, StreamNotFound};
+pub use config::{AppendOptions, CompressionConfig, EventStoreConfig, HttpPoolConfig, RetryPolicy};
+pub use events::{
+    DomainEvent, Event, EventBuilder, EventCategory, EventData, EventMetadata, StreamName, TypeName,
+};
+pub use stream_metadata::{StreamAcl, StreamMetadata};
+pub use subscription::SubscriptionError;
 
 use std::fmt::Debug;
+use std::time::Duration;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct StreamPosition(pub u64);
@@ -15,19 +25,47 @@ impl StreamPosition {
     pub const START: StreamPosition = StreamPosition(0);
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct SubscribeToAllOptions {
     pub from_position: Option<StreamPosition>,
+    /// Events read per poll, in place of the client's
+    /// `subscription_read_count`; clamped to its `max_read_count`
+    pub read_count: Option<u64>,
+    /// Wait between polls once caught up, in place of the client's
+    /// `subscription_poll_interval_ms`
+    pub poll_interval: Option<Duration>,
 }
 
+/// Allow-list of event types a subscription consumer wants to receive
 #[derive(Debug, Clone)]
 pub struct SubscriptionFilter {
     pub event_types: Vec<String>,
 }
 
-#[derive(Debug, Clone)]
+impl SubscriptionFilter {
+    pub fn event_types<I, S>(event_types: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            event_types: event_types.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    pub fn matches(&self, event_type: &str) -> bool {
+        self.event_types.iter().any(|allowed| allowed == event_type)
+    }
+}
+
+/// Outcome of an append that EventStore acknowledged
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct WriteResult {
-    pub position: u64,
+    /// Number of the last written event, when EventStore reported it
+    pub position: Option<u64>,
+    /// Events written, over all batches of the append. Appends that don't
+    /// await the commit return before anything is written and report 0.
+    pub events_written: usize,
 }
 
 pub fn add(left: u64, right: u64) -> u64 {
@@ -37,6 +75,8 @@ pub fn add(left: u64, right: u64) -> u64 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use serde::{Deserialize, Serialize};
+    use uuid::Uuid;
 
     #[derive(Debug, Clone, Serialize, Deserialize)]
     struct TestEvent {
@@ -0,0 +1,150 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use metrics::counter;
+use reqwest::{Client as HttpClient, RequestBuilder, Response};
+use tracing::warn;
+use url::Url;
+
+struct Node {
+    url: Url,
+    unhealthy_until: Mutex<Option<Instant>>,
+}
+
+impl Node {
+    fn is_healthy(&self, now: Instant) -> bool {
+        self.lock().is_none_or(|until| now >= until)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<Instant>> {
+        self.unhealthy_until
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// The EventStore nodes of a cluster, tried in configured order.
+///
+/// A node that can't be reached or answers with a 5xx is marked unhealthy and
+/// skipped for `unhealthy_for`, so requests go straight to the next node
+/// instead of waiting on the broken one. When every node is marked unhealthy
+/// they are all tried anyway rather than failing without a request.
+pub(crate) struct NodePool {
+    http_client: HttpClient,
+    nodes: Vec<Node>,
+    unhealthy_for: Duration,
+}
+
+impl NodePool {
+    pub(crate) fn new(
+        http_client: HttpClient,
+        urls: &[String],
+        unhealthy_for: Duration,
+    ) -> Result<Self> {
+        anyhow::ensure!(!urls.is_empty(), "no EventStore nodes configured");
+        let nodes = urls
+            .iter()
+            .map(|url| {
+                Ok(Node {
+                    url: Url::parse(url)
+                        .with_context(|| format!("invalid EventStore node URL '{}'", url))?,
+                    unhealthy_until: Mutex::new(None),
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            http_client,
+            nodes,
+            unhealthy_for,
+        })
+    }
+
+    /// Sends the request built by `build` for `path`, failing over to the next
+    /// node on connection errors and 5xx responses. The last node's response
+    /// or error is returned when no node succeeds.
+    pub(crate) async fn send<F>(&self, path: &str, build: F) -> Result<Response>
+    where
+        F: Fn(&HttpClient, Url) -> RequestBuilder,
+    {
+        let now = Instant::now();
+        let (healthy, unhealthy): (Vec<&Node>, Vec<&Node>) =
+            self.nodes.iter().partition(|node| node.is_healthy(now));
+        let candidates = if healthy.is_empty() {
+            unhealthy
+        } else {
+            healthy
+        };
+
+        let mut last = None;
+        for node in candidates {
+            let url = node.url.join(path)?;
+            match build(&self.http_client, url).send().await {
+                Ok(response) if !response.status().is_server_error() => {
+                    *node.lock() = None;
+                    return Ok(response);
+                },
+                result => {
+                    let reason = match &result {
+                        Ok(response) => response.status().to_string(),
+                        Err(e) => e.to_string(),
+                    };
+                    warn!(
+                        "EventStore node {} failed, marking it unhealthy: {}",
+                        node.url, reason
+                    );
+                    counter!("eventstore.node.failover_total").increment(1);
+                    *node.lock() = Some(Instant::now() + self.unhealthy_for);
+                    last = Some(result);
+                },
+            }
+        }
+
+        match last {
+            Some(result) => Ok(result?),
+            None => anyhow::bail!("no EventStore nodes configured"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::method;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_unhealthy_nodes_are_retried_when_none_is_healthy() -> Result<()> {
+        let node = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(1)
+            .mount(&node)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&node)
+            .await;
+
+        let pool = NodePool::new(HttpClient::new(), &[node.uri()], Duration::from_secs(60))?;
+
+        let first = pool.send("/streams/a", |http, url| http.get(url)).await?;
+        assert_eq!(first.status(), 503);
+        // Still marked unhealthy, but it's the only node left to try
+        let second = pool.send("/streams/a", |http, url| http.get(url)).await?;
+        assert_eq!(second.status(), 200);
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_node_url_is_rejected() {
+        assert!(NodePool::new(
+            HttpClient::new(),
+            &["not a url".to_string()],
+            Duration::ZERO
+        )
+        .is_err());
+        assert!(NodePool::new(HttpClient::new(), &[], Duration::ZERO).is_err());
+    }
+}
@@ -0,0 +1,130 @@
+use serde::{Deserialize, Deserializer, Serialize};
+use std::time::Duration;
+
+/// Per-stream settings EventStore keeps in the `$$<stream>` metadata stream.
+/// Retention is enforced by EventStore: events past `max_age` or beyond the
+/// newest `max_count` are no longer returned and get scavenged.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StreamMetadata {
+    /// Oldest events are dropped once older than this, in whole seconds
+    #[serde(
+        rename = "$maxAge",
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "seconds"
+    )]
+    pub max_age: Option<Duration>,
+    /// Only the newest this many events are kept
+    #[serde(rename = "$maxCount", default, skip_serializing_if = "Option::is_none")]
+    pub max_count: Option<u64>,
+    /// How long HTTP clients may cache reads of the stream, in whole seconds
+    #[serde(
+        rename = "$cacheControl",
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "seconds"
+    )]
+    pub cache_control: Option<Duration>,
+    #[serde(rename = "$acl", default, skip_serializing_if = "Option::is_none")]
+    pub acl: Option<StreamAcl>,
+}
+
+/// Users and groups allowed each kind of access to a stream. A role left
+/// `None` falls back to EventStore's default ACL.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StreamAcl {
+    #[serde(rename = "$r", default, skip_serializing_if = "Option::is_none")]
+    #[serde(deserialize_with = "one_or_many")]
+    pub read: Option<Vec<String>>,
+    #[serde(rename = "$w", default, skip_serializing_if = "Option::is_none")]
+    #[serde(deserialize_with = "one_or_many")]
+    pub write: Option<Vec<String>>,
+    #[serde(rename = "$d", default, skip_serializing_if = "Option::is_none")]
+    #[serde(deserialize_with = "one_or_many")]
+    pub delete: Option<Vec<String>>,
+    #[serde(rename = "$mr", default, skip_serializing_if = "Option::is_none")]
+    #[serde(deserialize_with = "one_or_many")]
+    pub metadata_read: Option<Vec<String>>,
+    #[serde(rename = "$mw", default, skip_serializing_if = "Option::is_none")]
+    #[serde(deserialize_with = "one_or_many")]
+    pub metadata_write: Option<Vec<String>>,
+}
+
+/// EventStore writes an ACL entry with a single principal as a plain string
+fn one_or_many<'de, D>(deserializer: D) -> Result<Option<Vec<String>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    Ok(match Option::<OneOrMany>::deserialize(deserializer)? {
+        None => None,
+        Some(OneOrMany::One(principal)) => Some(vec![principal]),
+        Some(OneOrMany::Many(principals)) => Some(principals),
+    })
+}
+
+/// Optional durations as whole seconds, the unit EventStore uses
+mod seconds {
+    use super::*;
+    use serde::Serializer;
+
+    pub fn serialize<S>(value: &Option<Duration>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        value
+            .map(|duration| duration.as_secs())
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(Option::<u64>::deserialize(deserializer)?.map(Duration::from_secs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serializes_to_event_store_names() -> anyhow::Result<()> {
+        let metadata = StreamMetadata {
+            max_age: Some(Duration::from_secs(86_400)),
+            max_count: Some(1000),
+            cache_control: None,
+            acl: Some(StreamAcl {
+                read: Some(vec!["$admins".to_string(), "auditor".to_string()]),
+                ..Default::default()
+            }),
+        };
+
+        let expected: serde_json::Value = serde_json::from_str(
+            r#"{"$maxAge": 86400, "$maxCount": 1000, "$acl": {"$r": ["$admins", "auditor"]}}"#,
+        )?;
+        assert_eq!(serde_json::to_value(&metadata)?, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_single_principal_acl_is_read_as_list() -> anyhow::Result<()> {
+        let metadata: StreamMetadata =
+            serde_json::from_str(r#"{"$cacheControl": 60, "$acl": {"$w": "$admins"}}"#)?;
+
+        assert_eq!(metadata.cache_control, Some(Duration::from_secs(60)));
+        assert_eq!(
+            metadata.acl.and_then(|acl| acl.write),
+            Some(vec!["$admins".to_string()])
+        );
+        assert_eq!(metadata.max_age, None);
+        Ok(())
+    }
+}
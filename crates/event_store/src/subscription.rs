@@ -0,0 +1,633 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use futures::future;
+use futures::stream::{self, Stream, StreamExt};
+use metrics::counter;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::client::{EventStoreClient, RecordedEvent};
+use crate::events::{Event, TypeName};
+use crate::{StreamName, StreamPosition, SubscribeToAllOptions, SubscriptionFilter};
+
+/// Errors produced while following a stream.
+///
+/// Transient errors (network failures, 5xx, throttling) are retried inside the
+/// subscription using the client's retry policy and never reach the caller
+/// unless the retries run out. Everything else is fatal and ends the stream.
+#[derive(Debug, thiserror::Error)]
+pub enum SubscriptionError {
+    #[error("not authorized to read stream '{0}'")]
+    Unauthorized(String),
+    #[error("stream '{0}' has been deleted")]
+    StreamDeleted(String),
+    #[error("EventStore rejected the read with status {0}")]
+    Rejected(StatusCode),
+    #[error("failed to decode events: {0}")]
+    Decode(String),
+    #[error("giving up after {attempts} failed attempts: {last_error}")]
+    RetriesExhausted { attempts: u32, last_error: String },
+    #[error("transient error: {0}")]
+    Transient(String),
+}
+
+impl SubscriptionError {
+    /// Whether the error ends the subscription
+    pub fn is_fatal(&self) -> bool {
+        !matches!(self, Self::Transient(_))
+    }
+
+    fn from_status(status: StatusCode, stream_name: &str) -> Self {
+        match status {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+                Self::Unauthorized(stream_name.to_string())
+            },
+            StatusCode::GONE => Self::StreamDeleted(stream_name.to_string()),
+            StatusCode::TOO_MANY_REQUESTS | StatusCode::REQUEST_TIMEOUT => {
+                Self::Transient(format!("EventStore responded with {}", status))
+            },
+            status if status.is_server_error() => {
+                Self::Transient(format!("EventStore responded with {}", status))
+            },
+            status => Self::Rejected(status),
+        }
+    }
+}
+
+struct SubscriptionState<'a> {
+    client: &'a EventStoreClient,
+    stream_name: String,
+    position: u64,
+    read_count: u64,
+    poll_interval: Duration,
+    buffer: VecDeque<RecordedEvent>,
+    finished: bool,
+}
+
+impl EventStoreClient {
+    /// Follows `stream_name` from `from`, yielding events as they are appended.
+    ///
+    /// The stream only yields `Err` for fatal errors and ends right after it.
+    pub fn follow_stream(
+        &self,
+        stream_name: &str,
+        from: StreamPosition,
+    ) -> impl Stream<Item = Result<RecordedEvent, SubscriptionError>> + '_ {
+        self.follow(
+            stream_name,
+            SubscribeToAllOptions {
+                from_position: Some(from),
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Follows `$all` like `follow_stream`, from the start unless the
+    /// options say otherwise and with their per-poll read count
+    pub fn subscribe_to_all(
+        &self,
+        options: SubscribeToAllOptions,
+    ) -> impl Stream<Item = Result<RecordedEvent, SubscriptionError>> + '_ {
+        self.follow(StreamName::all_stream(), options)
+    }
+
+    /// Catch-up subscription to `stream_name`: reads the stream from the
+    /// options' position (the start by default) and keeps polling for new
+    /// events, decoded into `Event<T>`.
+    ///
+    /// The first page is read before the stream is returned, so an invalid
+    /// stream name or an EventStore that can't be reached (once transient
+    /// errors have been retried) fails the call itself. Every poll resumes
+    /// after the last event yielded. Events that can't be decoded end the
+    /// stream with `SubscriptionError::Decode`.
+    pub async fn subscribe_to_stream<T>(
+        &self,
+        stream_name: &str,
+        options: SubscribeToAllOptions,
+    ) -> anyhow::Result<impl Stream<Item = Result<Event<T>, SubscriptionError>> + '_>
+    where
+        T: Serialize + for<'de> Deserialize<'de> + Clone + TypeName + 'static,
+    {
+        if stream_name.trim().is_empty() || stream_name.contains('/') {
+            anyhow::bail!("Invalid stream name '{}'", stream_name);
+        }
+
+        let mut state = self.subscription_state(stream_name, options);
+        let first_page = state.next_page().await?;
+        state.buffer.extend(first_page);
+        Ok(decoded(poll(state)))
+    }
+
+    fn follow(
+        &self,
+        stream_name: &str,
+        options: SubscribeToAllOptions,
+    ) -> impl Stream<Item = Result<RecordedEvent, SubscriptionError>> + '_ {
+        poll(self.subscription_state(stream_name, options))
+    }
+
+    fn subscription_state(
+        &self,
+        stream_name: &str,
+        options: SubscribeToAllOptions,
+    ) -> SubscriptionState<'_> {
+        SubscriptionState {
+            client: self,
+            stream_name: stream_name.to_string(),
+            position: options.from_position.unwrap_or(StreamPosition::START).0,
+            read_count: self.read_count(options.read_count),
+            poll_interval: self.poll_interval(options.poll_interval),
+            buffer: VecDeque::new(),
+            finished: false,
+        }
+    }
+
+    /// Follows `stream_name` like `follow_stream`, decoding only the
+    /// events whose type is in `filter`.
+    ///
+    /// Other events are skipped on their `event_type` alone, so their payload
+    /// is never deserialized into `T`. Events that match but can't be decoded
+    /// end the stream with `SubscriptionError::Decode`.
+    pub fn subscribe_to_events<T>(
+        &self,
+        stream_name: &str,
+        from: StreamPosition,
+        filter: SubscriptionFilter,
+    ) -> impl Stream<Item = Result<Event<T>, SubscriptionError>> + '_
+    where
+        T: Serialize + for<'de> Deserialize<'de> + Clone + TypeName + 'static,
+    {
+        decoded(self.follow_stream(stream_name, from).filter(move |result| {
+            future::ready(match result {
+                Ok(event) => filter.matches(&event.event_type),
+                Err(_) => true,
+            })
+        }))
+    }
+
+    async fn read_page(
+        &self,
+        stream_name: &str,
+        start: u64,
+        count: u64,
+    ) -> Result<Vec<RecordedEvent>, SubscriptionError> {
+        let path = format!("/streams/{}/{}?count={}", stream_name, start, count);
+        let response = self
+            .nodes
+            .send(&path, |http_client, url| http_client.get(url))
+            .await
+            .map_err(|e| match e.downcast_ref::<reqwest::Error>() {
+                Some(_) => SubscriptionError::Transient(e.to_string()),
+                None => SubscriptionError::Decode(e.to_string()),
+            })?;
+
+        match response.status() {
+            // Nothing has been appended to the stream yet
+            StatusCode::NOT_FOUND => Ok(Vec::new()),
+            status if status.is_success() => response
+                .json()
+                .await
+                .map_err(|e| SubscriptionError::Decode(e.to_string())),
+            status => Err(SubscriptionError::from_status(status, stream_name)),
+        }
+    }
+}
+
+/// Yields the buffered events, then the pages read from the state's
+/// position on, waiting the poll interval whenever a read comes back empty
+fn poll(
+    state: SubscriptionState<'_>,
+) -> impl Stream<Item = Result<RecordedEvent, SubscriptionError>> + '_ {
+    stream::unfold(state, |mut state| async move {
+        if state.finished {
+            return None;
+        }
+
+        loop {
+            if let Some(event) = state.buffer.pop_front() {
+                state.position += 1;
+                return Some((Ok(event), state));
+            }
+
+            match state.next_page().await {
+                Ok(events) if events.is_empty() => {
+                    tokio::time::sleep(state.poll_interval).await;
+                },
+                Ok(events) => state.buffer.extend(events),
+                Err(error) => {
+                    state.finished = true;
+                    return Some((Err(error), state));
+                },
+            }
+        }
+    })
+}
+
+/// Decodes the events of a subscription into `Event<T>`
+fn decoded<'a, T>(
+    events: impl Stream<Item = Result<RecordedEvent, SubscriptionError>> + 'a,
+) -> impl Stream<Item = Result<Event<T>, SubscriptionError>> + 'a
+where
+    T: Serialize + for<'de> Deserialize<'de> + Clone + TypeName + 'static,
+{
+    events
+        .map(|result| {
+            result.and_then(|event| {
+                event
+                    .into_domain_event()
+                    .map_err(|e| SubscriptionError::Decode(e.to_string()))
+            })
+        })
+        .scan(false, |failed, result| {
+            // Like the raw subscription, end right after a fatal error
+            if *failed {
+                return future::ready(None);
+            }
+            *failed = result.is_err();
+            future::ready(Some(result))
+        })
+}
+
+impl SubscriptionState<'_> {
+    /// Reads the next page, retrying transient errors with the client's
+    /// retry policy
+    async fn next_page(&self) -> Result<Vec<RecordedEvent>, SubscriptionError> {
+        let policy = &self.client.retry_policy;
+        let mut attempts = 0;
+
+        loop {
+            match self
+                .client
+                .read_page(&self.stream_name, self.position, self.read_count)
+                .await
+            {
+                Err(error) if !error.is_fatal() => {
+                    attempts += 1;
+                    counter!("eventstore.subscription.transient_errors_total").increment(1);
+                    if attempts > policy.max_retries {
+                        return Err(SubscriptionError::RetriesExhausted {
+                            attempts,
+                            last_error: error.to_string(),
+                        });
+                    }
+                    warn!(
+                        "Transient error following {} (attempt {}): {}",
+                        self.stream_name, attempts, error
+                    );
+                    tokio::time::sleep(policy.backoff(attempts)).await;
+                },
+                result => return result,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EventStoreConfig;
+    use chrono::Utc;
+    use futures::StreamExt;
+    use serde_json::Value;
+    use uuid::Uuid;
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn recorded_event() -> RecordedEvent {
+        RecordedEvent {
+            event_id: Uuid::new_v4(),
+            event_type: "TestEvent".to_string(),
+            data: Value::Null,
+            metadata: Value::Null,
+            created: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transient_error_is_retried_and_fatal_error_ends_stream() -> anyhow::Result<()> {
+        let mock_server = MockServer::start().await;
+        let event = recorded_event();
+
+        // The first poll hits a transient failure, the retry succeeds
+        Mock::given(method("GET"))
+            .and(path("/streams/orders/0"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/streams/orders/0"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(vec![event.clone()]))
+            .mount(&mock_server)
+            .await;
+        // The next poll is rejected for good
+        Mock::given(method("GET"))
+            .and(path("/streams/orders/1"))
+            .respond_with(ResponseTemplate::new(401))
+            .mount(&mock_server)
+            .await;
+
+        let client = EventStoreClient::new(EventStoreConfig {
+            connection_string: mock_server.uri(),
+            retry_delay: 10,
+            ..Default::default()
+        })?;
+        let mut subscription = Box::pin(client.follow_stream("orders", StreamPosition::START));
+
+        let first = subscription
+            .next()
+            .await
+            .expect("stream ended early")
+            .expect("transient error should be retried");
+        assert_eq!(first.event_id, event.event_id);
+
+        let error = subscription
+            .next()
+            .await
+            .expect("stream ended early")
+            .expect_err("401 should end the stream");
+        assert!(matches!(error, SubscriptionError::Unauthorized(_)));
+        assert!(subscription.next().await.is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_persistent_transient_errors_exhaust_retries() -> anyhow::Result<()> {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/streams/orders/0"))
+            .respond_with(ResponseTemplate::new(503))
+            .mount(&mock_server)
+            .await;
+
+        let client = EventStoreClient::new(EventStoreConfig {
+            connection_string: mock_server.uri(),
+            max_retries: 2,
+            retry_delay: 10,
+            ..Default::default()
+        })?;
+        let mut subscription = Box::pin(client.follow_stream("orders", StreamPosition::START));
+
+        let error = subscription
+            .next()
+            .await
+            .expect("stream ended early")
+            .expect_err("retries should run out");
+        assert!(matches!(
+            error,
+            SubscriptionError::RetriesExhausted { attempts: 3, .. }
+        ));
+        assert!(subscription.next().await.is_none());
+
+        Ok(())
+    }
+
+    #[derive(Debug, Clone, Serialize)]
+    struct Wanted {
+        id: u32,
+    }
+
+    impl TypeName for Wanted {
+        fn type_name(&self) -> String {
+            "Wanted".to_string()
+        }
+    }
+
+    /// Panics on payloads of any other event type, so decoding one of them
+    /// fails the test
+    impl<'de> Deserialize<'de> for Wanted {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: serde::Deserializer<'de>,
+        {
+            let value = Value::deserialize(deserializer)?;
+            assert!(value.get("poison").is_none(), "decoded a filtered event");
+            let id = value["id"].as_u64().unwrap_or_default() as u32;
+            Ok(Self { id })
+        }
+    }
+
+    fn typed_event(event_type: &str, data: Value) -> RecordedEvent {
+        RecordedEvent {
+            event_type: event_type.to_string(),
+            data,
+            ..recorded_event()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_filtered_event_types_are_never_deserialized() -> anyhow::Result<()> {
+        let mock_server = MockServer::start().await;
+        let poison = || serde_json::from_str::<Value>(r#"{"poison": true}"#);
+
+        Mock::given(method("GET"))
+            .and(path("/streams/$all/0"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(vec![
+                typed_event("Other", poison()?),
+                typed_event("Wanted", serde_json::from_str(r#"{"id": 7}"#)?),
+                typed_event("Other", poison()?),
+            ]))
+            .mount(&mock_server)
+            .await;
+        // Ends the stream once the page has been consumed
+        Mock::given(method("GET"))
+            .and(path("/streams/$all/3"))
+            .respond_with(ResponseTemplate::new(401))
+            .mount(&mock_server)
+            .await;
+
+        let client = EventStoreClient::new(EventStoreConfig {
+            connection_string: mock_server.uri(),
+            retry_delay: 10,
+            ..Default::default()
+        })?;
+        let mut subscription = Box::pin(client.subscribe_to_events::<Wanted>(
+            "$all",
+            StreamPosition::START,
+            SubscriptionFilter::event_types(["Wanted"]),
+        ));
+
+        let event = subscription
+            .next()
+            .await
+            .expect("stream ended early")
+            .expect("matching event decodes");
+        assert_eq!(event.data.id, 7);
+
+        // The trailing event of the other type was skipped as well
+        let error = subscription
+            .next()
+            .await
+            .expect("stream ended early")
+            .expect_err("401 should end the stream");
+        assert!(matches!(error, SubscriptionError::Unauthorized(_)));
+        assert!(subscription.next().await.is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_to_stream_resumes_after_last_event() -> anyhow::Result<()> {
+        let mock_server = MockServer::start().await;
+        let wanted = |id: u32| typed_event("Wanted", Value::from_iter([("id", id)]));
+
+        // Catching up reads the first page once
+        Mock::given(method("GET"))
+            .and(path("/streams/orders/5"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(vec![wanted(5), wanted(6)]))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        // The next poll finds nothing new, the one after it a new event
+        Mock::given(method("GET"))
+            .and(path("/streams/orders/7"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(Vec::<RecordedEvent>::new()))
+            .up_to_n_times(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/streams/orders/7"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(vec![wanted(7)]))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/streams/orders/8"))
+            .respond_with(ResponseTemplate::new(401))
+            .mount(&mock_server)
+            .await;
+
+        let client = EventStoreClient::new(EventStoreConfig {
+            connection_string: mock_server.uri(),
+            ..Default::default()
+        })?;
+        let started = tokio::time::Instant::now();
+        let events: Vec<_> = client
+            .subscribe_to_stream::<Wanted>(
+                "orders",
+                SubscribeToAllOptions {
+                    from_position: Some(StreamPosition(5)),
+                    poll_interval: Some(Duration::from_millis(10)),
+                    ..Default::default()
+                },
+            )
+            .await?
+            .collect()
+            .await;
+
+        let ids: Vec<_> = events
+            .iter()
+            .filter_map(|event| event.as_ref().ok())
+            .map(|event| event.data.id)
+            .collect();
+        assert_eq!(ids, [5, 6, 7]);
+        assert!(matches!(
+            events.last(),
+            Some(Err(SubscriptionError::Unauthorized(_)))
+        ));
+        // Polled with the options' interval, not the default second
+        assert!(started.elapsed() < Duration::from_secs(1));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_to_stream_fails_up_front() -> anyhow::Result<()> {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/streams/orders/0"))
+            .respond_with(ResponseTemplate::new(401))
+            .mount(&mock_server)
+            .await;
+        let client = EventStoreClient::new(EventStoreConfig {
+            connection_string: mock_server.uri(),
+            ..Default::default()
+        })?;
+
+        let unauthorized = client
+            .subscribe_to_stream::<Wanted>("orders", SubscribeToAllOptions::default())
+            .await;
+        let error = unauthorized
+            .err()
+            .ok_or_else(|| anyhow::anyhow!("read was rejected"))?;
+        assert!(matches!(
+            error.downcast_ref::<SubscriptionError>(),
+            Some(SubscriptionError::Unauthorized(_))
+        ));
+
+        let invalid = client
+            .subscribe_to_stream::<Wanted>("", SubscribeToAllOptions::default())
+            .await;
+        assert!(invalid.is_err());
+        Ok(())
+    }
+
+    /// Answers the first poll of `$all` only when it asks for `count` events
+    /// and rejects the next one, so the subscription yields exactly one event
+    /// when the count was right
+    async fn mock_all_with_count(mock_server: &MockServer, count: &str) -> RecordedEvent {
+        let event = recorded_event();
+        Mock::given(method("GET"))
+            .and(path("/streams/$all/0"))
+            .and(query_param("count", count))
+            .respond_with(ResponseTemplate::new(200).set_body_json(vec![event.clone()]))
+            .expect(1)
+            .mount(mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/streams/$all/1"))
+            .and(query_param("count", count))
+            .respond_with(ResponseTemplate::new(401))
+            .mount(mock_server)
+            .await;
+        event
+    }
+
+    #[tokio::test]
+    async fn test_polls_read_the_configured_count() -> anyhow::Result<()> {
+        let mock_server = MockServer::start().await;
+        let event = mock_all_with_count(&mock_server, "25").await;
+
+        let client = EventStoreClient::new(EventStoreConfig {
+            connection_string: mock_server.uri(),
+            retry_delay: 10,
+            ..Default::default()
+        })?;
+        let subscription = client.subscribe_to_all(SubscribeToAllOptions {
+            read_count: Some(25),
+            ..Default::default()
+        });
+        let events: Vec<_> = subscription.collect().await;
+
+        assert_eq!(events.len(), 2);
+        assert_eq!(
+            events[0].as_ref().expect("first poll answered").event_id,
+            event.event_id
+        );
+        assert!(matches!(events[1], Err(SubscriptionError::Unauthorized(_))));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_read_count_is_clamped_to_max_read_count() -> anyhow::Result<()> {
+        let mock_server = MockServer::start().await;
+        mock_all_with_count(&mock_server, "50").await;
+
+        let client = EventStoreClient::new(EventStoreConfig {
+            connection_string: mock_server.uri(),
+            retry_delay: 10,
+            max_read_count: 50,
+            ..Default::default()
+        })?;
+        let subscription = client.subscribe_to_all(SubscribeToAllOptions {
+            read_count: Some(10_000),
+            ..Default::default()
+        });
+        let events: Vec<_> = subscription.collect().await;
+
+        assert!(events[0].is_ok(), "poll asked for more than max_read_count");
+        Ok(())
+    }
+}
@@ -14,6 +14,7 @@ error-not-found = Ressource nicht gefunden
 error-unauthorized = Nicht autorisierter Zugriff
 error-internal = Interner Serverfehler
 error-validation = Validierungsfehler: { $message }
+error-unsupported-media-type = Der Request-Body muss als application/json gesendet werden
 
 # Navigation
 nav-home = Startseite
@@ -24,7 +25,7 @@ nav-logout = Abmelden
 # User Messages
 user-greeting = Hallo, { $name }!
 user-role = Rolle: { $role }
-user-last-login = Letzte Anmeldung: { $datetime }
+user-last-login = Letzte Anmeldung: { DATETIME($datetime) }
 
 # Tenant Messages
 tenant-name = Mandantenname
@@ -14,6 +14,7 @@ error-not-found = Resource not found
 error-unauthorized = Unauthorized access
 error-internal = Internal server error
 error-validation = Validation error: { $message }
+error-unsupported-media-type = Request body must be sent as application/json
 
 # Navigation
 nav-home = Home
@@ -24,7 +25,7 @@ nav-logout = Logout
 # User Messages
 user-greeting = Hello, { $name }!
 user-role = Role: { $role }
-user-last-login = Last login: { $datetime }
+user-last-login = Last login: { DATETIME($datetime) }
 
 # Tenant Messages
 tenant-name = Tenant Name
@@ -14,6 +14,7 @@ error-not-found = Recurso no encontrado
 error-unauthorized = Acceso no autorizado
 error-internal = Error interno del servidor
 error-validation = Error de validación: { $message }
+error-unsupported-media-type = El cuerpo de la solicitud debe enviarse como application/json
 
 # Navigation
 nav-home = Inicio
@@ -24,7 +25,7 @@ nav-logout = Cerrar sesión
 # User Messages
 user-greeting = ¡Hola, { $name }!
 user-role = Rol: { $role }
-user-last-login = Último acceso: { $datetime }
+user-last-login = Último acceso: { DATETIME($datetime) }
 
 # Tenant Messages
 tenant-name = Nombre del inquilino
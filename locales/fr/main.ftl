@@ -14,6 +14,7 @@ error-not-found = Ressource non trouvée
 error-unauthorized = Accès non autorisé
 error-internal = Erreur interne du serveur
 error-validation = Erreur de validation : { $message }
+error-unsupported-media-type = Le corps de la requête doit être envoyé en application/json
 
 # Navigation
 nav-home = Accueil
@@ -24,7 +25,7 @@ nav-logout = Déconnexion
 # User Messages
 user-greeting = Bonjour, { $name } !
 user-role = Rôle : { $role }
-user-last-login = Dernière connexion : { $datetime }
+user-last-login = Dernière connexion : { DATETIME($datetime) }
 
 # Tenant Messages
 tenant-name = Nom du locataire
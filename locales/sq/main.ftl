@@ -14,6 +14,7 @@ error-not-found = Burimi nuk u gjet
 error-unauthorized = Qasje e paautorizuar
 error-internal = Gabim i brendshëm i serverit
 error-validation = Gabim validimi: { $message }
+error-unsupported-media-type = Trupi i kërkesës duhet të dërgohet si application/json
 
 # Navigation
 nav-home = Ballina
@@ -24,7 +25,7 @@ nav-logout = Dilni
 # User Messages
 user-greeting = Përshëndetje, { $name }!
 user-role = Roli: { $role }
-user-last-login = Hyrja e fundit: { $datetime }
+user-last-login = Hyrja e fundit: { DATETIME($datetime) }
 
 # Tenant Messages
 tenant-name = Emri i Qiramarrësit
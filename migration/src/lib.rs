@@ -1,12 +1,92 @@
 pub use sea_orm_migration::prelude::*;
 
 mod m20240301_000001_create_tenant_table;
+mod m20240318_000001_create_base_schema;
+mod m20240319_000001_create_users_table;
+mod m20250201_000001_add_tenant_deleted_at;
+mod m20250301_000001_create_api_keys_table;
+mod m20250401_000001_create_audit_log_table;
+mod m20250501_000001_create_webhooks_table;
+mod m20250601_000001_create_subscription_checkpoints_table;
 
 pub struct Migrator;
 
 #[async_trait::async_trait]
 impl MigratorTrait for Migrator {
     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
-        vec![Box::new(m20240301_000001_create_tenant_table::Migration)]
+        vec![
+            Box::new(m20240301_000001_create_tenant_table::Migration),
+            Box::new(m20240318_000001_create_base_schema::Migration),
+            Box::new(m20240319_000001_create_users_table::Migration),
+            Box::new(m20250201_000001_add_tenant_deleted_at::Migration),
+            Box::new(m20250301_000001_create_api_keys_table::Migration),
+            Box::new(m20250401_000001_create_audit_log_table::Migration),
+            Box::new(m20250501_000001_create_webhooks_table::Migration),
+            Box::new(m20250601_000001_create_subscription_checkpoints_table::Migration),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult};
+
+    /// SQL of every migration's `up`, in order
+    async fn up_statements() -> Vec<String> {
+        let results = (0..100).map(|_| MockExecResult {
+            last_insert_id: 0,
+            rows_affected: 0,
+        });
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_exec_results(results)
+            .into_connection();
+        let manager = SchemaManager::new(&db);
+        for migration in Migrator::migrations() {
+            migration.up(&manager).await.expect("migration runs");
+        }
+        db.into_transaction_log()
+            .iter()
+            .flat_map(|transaction| transaction.statements())
+            .map(|statement| statement.sql.clone())
+            .collect()
+    }
+
+    /// Quoted names following each occurrence of `keyword`
+    fn names_after<'a>(sql: &'a str, keyword: &str) -> Vec<&'a str> {
+        sql.match_indices(keyword)
+            .filter_map(|(at, _)| sql[at + keyword.len()..].split('"').nth(1))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_foreign_keys_reference_created_tables() {
+        let statements = up_statements().await;
+        let created: Vec<&str> = statements
+            .iter()
+            .flat_map(|sql| names_after(sql, "CREATE TABLE IF NOT EXISTS "))
+            .collect();
+
+        assert!(created.contains(&"tenants"));
+        assert!(!created.contains(&"tenant"));
+        for sql in &statements {
+            for table in names_after(sql, "REFERENCES ") {
+                assert!(
+                    created.contains(&table),
+                    "{} is never created: {}",
+                    table,
+                    sql
+                );
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tenant_deleted_at_is_added_to_tenants() {
+        let statements = up_statements().await;
+
+        assert!(statements.iter().any(|sql| sql.starts_with(
+            r#"ALTER TABLE "tenants" ADD COLUMN "deleted_at" timestamp with time zone"#
+        )));
     }
 }
@@ -10,26 +10,26 @@ impl MigrationTrait for Migration {
         manager
             .create_table(
                 Table::create()
-                    .table(Tenant::Table)
+                    .table(Tenants::Table)
                     .if_not_exists()
-                    .col(ColumnDef::new(Tenant::Id).uuid().not_null().primary_key())
-                    .col(ColumnDef::new(Tenant::Name).string().not_null())
-                    .col(ColumnDef::new(Tenant::Domain).string().not_null())
+                    .col(ColumnDef::new(Tenants::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(Tenants::Name).string().not_null())
+                    .col(ColumnDef::new(Tenants::Domain).string().not_null())
                     .col(
-                        ColumnDef::new(Tenant::IsActive)
+                        ColumnDef::new(Tenants::IsActive)
                             .boolean()
                             .not_null()
                             .default(true),
                     )
-                    .col(ColumnDef::new(Tenant::Settings).json().not_null())
+                    .col(ColumnDef::new(Tenants::Settings).json().not_null())
                     .col(
-                        ColumnDef::new(Tenant::CreatedAt)
+                        ColumnDef::new(Tenants::CreatedAt)
                             .timestamp_with_time_zone()
                             .not_null()
                             .default(Expr::current_timestamp()),
                     )
                     .col(
-                        ColumnDef::new(Tenant::UpdatedAt)
+                        ColumnDef::new(Tenants::UpdatedAt)
                             .timestamp_with_time_zone()
                             .not_null()
                             .default(Expr::current_timestamp()),
@@ -41,13 +41,13 @@ impl MigrationTrait for Migration {
 
     async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
         manager
-            .drop_table(Table::drop().table(Tenant::Table).to_owned())
+            .drop_table(Table::drop().table(Tenants::Table).to_owned())
             .await
     }
 }
 
 #[derive(DeriveIden)]
-enum Tenant {
+enum Tenants {
     Table,
     Id,
     Name,
@@ -1,3 +1,5 @@
+#![allow(clippy::disallowed_methods)]
+
 use sea_orm_migration::prelude::*;
 
 #[derive(DeriveMigrationName)]
@@ -10,31 +12,31 @@ impl MigrationTrait for Migration {
         manager
             .create_table(
                 Table::create()
-                    .table(Tenant::Table)
+                    .table(Tenants::Table)
                     .if_not_exists()
-                    .col(ColumnDef::new(Tenant::Id).uuid().not_null().primary_key())
-                    .col(ColumnDef::new(Tenant::Name).string().not_null())
+                    .col(ColumnDef::new(Tenants::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(Tenants::Name).string().not_null())
                     .col(
-                        ColumnDef::new(Tenant::Domain)
+                        ColumnDef::new(Tenants::Domain)
                             .string()
                             .not_null()
                             .unique_key(),
                     )
                     .col(
-                        ColumnDef::new(Tenant::IsActive)
+                        ColumnDef::new(Tenants::IsActive)
                             .boolean()
                             .not_null()
                             .default(true),
                     )
-                    .col(ColumnDef::new(Tenant::Settings).json().not_null())
+                    .col(ColumnDef::new(Tenants::Settings).json().not_null())
                     .col(
-                        ColumnDef::new(Tenant::CreatedAt)
+                        ColumnDef::new(Tenants::CreatedAt)
                             .timestamp_with_time_zone()
                             .not_null()
                             .default(Expr::current_timestamp()),
                     )
                     .col(
-                        ColumnDef::new(Tenant::UpdatedAt)
+                        ColumnDef::new(Tenants::UpdatedAt)
                             .timestamp_with_time_zone()
                             .not_null()
                             .default(Expr::current_timestamp()),
@@ -48,8 +50,8 @@ impl MigrationTrait for Migration {
             .create_index(
                 Index::create()
                     .name("idx_tenant_name")
-                    .table(Tenant::Table)
-                    .col(Tenant::Name)
+                    .table(Tenants::Table)
+                    .col(Tenants::Name)
                     .to_owned(),
             )
             .await?;
@@ -58,8 +60,8 @@ impl MigrationTrait for Migration {
             .create_index(
                 Index::create()
                     .name("idx_tenant_domain")
-                    .table(Tenant::Table)
-                    .col(Tenant::Domain)
+                    .table(Tenants::Table)
+                    .col(Tenants::Domain)
                     .unique()
                     .to_owned(),
             )
@@ -70,13 +72,13 @@ impl MigrationTrait for Migration {
 
     async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
         manager
-            .drop_table(Table::drop().table(Tenant::Table).to_owned())
+            .drop_table(Table::drop().table(Tenants::Table).to_owned())
             .await
     }
 }
 
 #[derive(Iden)]
-enum Tenant {
+enum Tenants {
     Table,
     Id,
     Name,
@@ -1,3 +1,5 @@
+#![allow(clippy::disallowed_methods)]
+
 use sea_orm_migration::prelude::*;
 use sea_orm_migration::sea_query::extension::postgres::Type;
 
@@ -0,0 +1,274 @@
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Extension, Query, State},
+    response::Json,
+    routing::get,
+    Router,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::api::tenant_data::require_permission;
+use crate::common::{
+    error::AppError, i18n::SupportedLanguage, json::LimitedJson, middleware::auth::UserInfo,
+};
+use crate::infrastructure::{state::AppState, subscriptions::SubscriptionStatus};
+
+/// Permission required to inspect the running EventStore subscriptions
+const SUBSCRIPTIONS_READ_PERMISSION: &str = "subscriptions:read";
+/// Permission required to see and switch maintenance mode
+const MAINTENANCE_PERMISSION: &str = "maintenance:manage";
+/// Permission required to render messages through `/admin/i18n/render`
+const I18N_RENDER_PERMISSION: &str = "i18n:render";
+
+pub fn admin_routes() -> Router<AppState> {
+    Router::new()
+        .route("/admin/subscriptions", get(list_subscriptions))
+        .route(
+            "/admin/maintenance",
+            get(get_maintenance).put(set_maintenance),
+        )
+}
+
+/// Message rendering for translators and support, only mounted where
+/// `routes.i18n_debug` allows it
+pub fn i18n_debug_routes() -> Router<AppState> {
+    Router::new().route("/admin/i18n/render", get(render_message))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RenderParams {
+    pub lang: String,
+    pub id: String,
+    /// Message arguments as a JSON object of strings, e.g. `{"name":"Acme"}`
+    pub args: Option<String>,
+}
+
+/// Either the rendered message or why it couldn't be rendered
+#[derive(Debug, Serialize)]
+pub struct RenderResponse {
+    pub lang: String,
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rendered: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MaintenanceDto {
+    pub enabled: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MaintenanceResponse {
+    pub enabled: bool,
+    pub retry_after_secs: u64,
+}
+
+async fn list_subscriptions(
+    State(state): State<AppState>,
+    user: Option<Extension<UserInfo>>,
+) -> Result<Json<Vec<SubscriptionStatus>>, AppError> {
+    require_permission(user, SUBSCRIPTIONS_READ_PERMISSION)?;
+    Ok(Json(state.subscriptions.snapshot()))
+}
+
+fn maintenance_response(state: &AppState) -> Json<MaintenanceResponse> {
+    Json(MaintenanceResponse {
+        enabled: state.maintenance.is_enabled(),
+        retry_after_secs: state.maintenance.retry_after_secs(),
+    })
+}
+
+async fn get_maintenance(
+    State(state): State<AppState>,
+    user: Option<Extension<UserInfo>>,
+) -> Result<Json<MaintenanceResponse>, AppError> {
+    require_permission(user, MAINTENANCE_PERMISSION)?;
+    Ok(maintenance_response(&state))
+}
+
+async fn set_maintenance(
+    State(state): State<AppState>,
+    user: Option<Extension<UserInfo>>,
+    LimitedJson(payload): LimitedJson<MaintenanceDto>,
+) -> Result<Json<MaintenanceResponse>, AppError> {
+    require_permission(user, MAINTENANCE_PERMISSION)?;
+    state.maintenance.set_enabled(payload.enabled);
+    Ok(maintenance_response(&state))
+}
+
+/// Renders a message the way responses would. A message that is missing
+/// or fails to format is reported in the body rather than as a 500, since
+/// finding those is what the endpoint is for.
+async fn render_message(
+    State(state): State<AppState>,
+    user: Option<Extension<UserInfo>>,
+    Query(params): Query<RenderParams>,
+) -> Result<Json<RenderResponse>, AppError> {
+    require_permission(user, I18N_RENDER_PERMISSION)?;
+    let lang = params
+        .lang
+        .parse::<SupportedLanguage>()
+        .map_err(|e| AppError::validation(e.to_string()))?;
+    let args = params
+        .args
+        .map(|args| serde_json::from_str::<HashMap<String, String>>(&args))
+        .transpose()
+        .map_err(|e| {
+            AppError::validation(format!("args must be a JSON object of strings: {}", e))
+        })?;
+
+    let (rendered, error) = match state.i18n.format_message(lang, &params.id, args).await {
+        Ok(rendered) => (Some(rendered), None),
+        Err(e) => (None, Some(e.kind.to_string())),
+    };
+    Ok(Json(RenderResponse {
+        lang: lang.as_str().to_string(),
+        id: params.id,
+        rendered,
+        error,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::i18n::{I18nManager, SupportedLanguage, TestResourceProvider};
+    use crate::infrastructure::services::tenant_service::TenantServiceImpl;
+    use axum::{
+        body::Body,
+        http::{header, Request, StatusCode},
+    };
+    use sea_orm::{DatabaseBackend, MockDatabase};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    fn operator(permissions: &[&str]) -> UserInfo {
+        UserInfo {
+            sub: "operator".to_string(),
+            preferred_username: "operator".to_string(),
+            email: None,
+            roles: vec![],
+            tenant_id: None,
+            permissions: permissions.iter().map(|p| p.to_string()).collect(),
+            auth_methods: vec![],
+        }
+    }
+
+    async fn put_maintenance(state: &AppState, caller: UserInfo, enabled: bool) -> StatusCode {
+        let request = Request::builder()
+            .method("PUT")
+            .uri("/admin/maintenance")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(format!(r#"{{"enabled": {}}}"#, enabled)))
+            .expect("valid request");
+        admin_routes()
+            .with_state(state.clone())
+            .layer(Extension(caller))
+            .oneshot(request)
+            .await
+            .expect("infallible router")
+            .status()
+    }
+
+    #[tokio::test]
+    async fn test_maintenance_is_switched_by_admins_only() {
+        let i18n = I18nManager::new(SupportedLanguage::En, Arc::new(TestResourceProvider::new()))
+            .await
+            .expect("i18n manager");
+        let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+        let state = AppState::for_test(
+            Arc::new(TenantServiceImpl::new(Arc::new(db))),
+            Arc::new(i18n),
+        );
+
+        assert_eq!(
+            put_maintenance(&state, operator(&[]), true).await,
+            StatusCode::FORBIDDEN
+        );
+        assert!(!state.maintenance.is_enabled());
+
+        let admin = operator(&[MAINTENANCE_PERMISSION]);
+        assert_eq!(
+            put_maintenance(&state, admin.clone(), true).await,
+            StatusCode::OK
+        );
+        assert!(state.maintenance.is_enabled());
+        assert_eq!(put_maintenance(&state, admin, false).await, StatusCode::OK);
+        assert!(!state.maintenance.is_enabled());
+    }
+
+    async fn render(query: &str, caller: UserInfo) -> (StatusCode, serde_json::Value) {
+        let i18n = I18nManager::new(
+            SupportedLanguage::En,
+            Arc::new(TestResourceProvider::new().with_resource(
+                SupportedLanguage::De,
+                "tenant-created = Mandant { $name } wurde angelegt",
+            )),
+        )
+        .await
+        .expect("i18n manager");
+        let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+        let state = AppState::for_test(
+            Arc::new(TenantServiceImpl::new(Arc::new(db))),
+            Arc::new(i18n),
+        );
+
+        let request = Request::builder()
+            .uri(format!("/admin/i18n/render?{}", query))
+            .body(Body::empty())
+            .expect("valid request");
+        let response = i18n_debug_routes()
+            .with_state(state)
+            .layer(Extension(caller))
+            .oneshot(request)
+            .await
+            .expect("infallible router");
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("response body");
+        (status, serde_json::from_slice(&body).expect("JSON body"))
+    }
+
+    #[tokio::test]
+    async fn test_render_known_message_with_args() {
+        let translator = operator(&[I18N_RENDER_PERMISSION]);
+        // args={"name":"Acme"}
+        let (status, body) = render(
+            "lang=de&id=tenant-created&args=%7B%22name%22%3A%22Acme%22%7D",
+            translator,
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["lang"], "de");
+        // Fluent isolates placeables with Unicode bidi marks
+        assert_eq!(
+            body["rendered"],
+            "Mandant \u{2068}Acme\u{2069} wurde angelegt"
+        );
+        assert!(body.get("error").is_none());
+
+        let (status, _) = render("lang=de&id=tenant-created", operator(&[])).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_render_missing_message_reports_the_error() {
+        let translator = operator(&[I18N_RENDER_PERMISSION]);
+        let (status, body) = render("lang=de&id=no-such-message", translator.clone()).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.get("rendered").is_none());
+        assert_eq!(
+            body["error"],
+            "I18n error: Message no-such-message not found"
+        );
+
+        let (status, _) = render("lang=xx&id=tenant-created", translator).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,344 @@
+use axum::{
+    extract::{Extension, Path, Query, State},
+    http::HeaderMap,
+    response::Json,
+    routing::get,
+    Router,
+};
+use oauth2::url::form_urlencoded;
+use serde::Deserialize;
+use tracing::error;
+use uuid::Uuid;
+
+use crate::api::tenant_data::require_tenant_permission;
+use crate::common::{error::AppError, middleware::auth::UserInfo, pagination::PaginationParams};
+use crate::domain::audit::{AuditEntry, AuditFilter};
+use crate::infrastructure::state::AppState;
+
+/// Permission required to read a tenant's audit log
+const AUDIT_READ_PERMISSION: &str = "audit:read";
+
+pub fn audit_routes() -> Router<AppState> {
+    Router::new().route("/tenants/{id}/audit", get(list_audit_entries))
+}
+
+/// `?action=&entity_type=&page=&page_size=`; the pagination fields aren't
+/// flattened from `PaginationParams`, as flattening breaks parsing numbers
+/// from the query string
+#[derive(Debug, Default, Deserialize)]
+pub struct AuditParams {
+    pub action: Option<String>,
+    pub entity_type: Option<String>,
+    pub page: Option<u64>,
+    pub page_size: Option<u64>,
+}
+
+/// Admins of the tenant and platform admins only, and only while the tenant
+/// has audit logging
+async fn list_audit_entries(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(params): Query<AuditParams>,
+    user: Option<Extension<UserInfo>>,
+) -> Result<(HeaderMap, Json<Vec<AuditEntry>>), AppError> {
+    require_tenant_permission(user, id, AUDIT_READ_PERMISSION)?;
+
+    let tenant = state.tenant_service.find_by_id(&id.to_string()).await?;
+    if !tenant.settings.features.audit_logging {
+        return Err(AppError::authorization(
+            "Audit logging is not enabled for this tenant",
+        ));
+    }
+
+    let pagination = PaginationParams {
+        page: params.page,
+        page_size: params.page_size,
+    }
+    .resolve(&state.config.pagination);
+    let filter = AuditFilter {
+        action: params.action,
+        entity_type: params.entity_type,
+    };
+    let entries = state.audit_log.list(&id, &filter, pagination).await?;
+
+    let links = pagination.links(
+        &state.config.server,
+        &filtered_path(id, &filter),
+        entries.len(),
+    );
+    Ok((links, Json(entries)))
+}
+
+/// Records a change the caller made to the tenant's data. The change has
+/// already been committed, so failing to record it is logged instead of
+/// failing the request.
+pub(super) async fn record_change(
+    state: &AppState,
+    user: Option<&UserInfo>,
+    tenant_id: Uuid,
+    action: &str,
+    entity_type: &str,
+    entity_id: impl ToString,
+    details: Option<serde_json::Value>,
+) {
+    let actor = user.map(|user| user.sub.clone());
+    let entry = AuditEntry {
+        details,
+        ..AuditEntry::new(tenant_id, actor, action, entity_type, entity_id)
+    };
+    if let Err(e) = state.audit_log.record(entry).await {
+        error!(
+            "Failed to record {} of tenant {} in the audit log: {}",
+            action, tenant_id, e
+        );
+    }
+}
+
+/// Path of the audit log with the filter as query string, so the page links
+/// keep it
+fn filtered_path(id: Uuid, filter: &AuditFilter) -> String {
+    let mut query = form_urlencoded::Serializer::new(String::new());
+    if let Some(action) = &filter.action {
+        query.append_pair("action", action);
+    }
+    if let Some(entity_type) = &filter.entity_type {
+        query.append_pair("entity_type", entity_type);
+    }
+    let query = query.finish();
+
+    let path = format!("/tenants/{}/audit", id);
+    if query.is_empty() {
+        path
+    } else {
+        format!("{}?{}", path, query)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::tenant_data::PLATFORM_ADMIN_PERMISSION;
+    use crate::common::i18n::{I18nManager, SupportedLanguage, TestResourceProvider};
+    use crate::domain::tenant::TenantSettings;
+    use crate::infrastructure::database::entities::{audit_log, tenant};
+    use crate::infrastructure::services::{
+        audit_log_service::AuditLogServiceImpl, tenant_service::TenantServiceImpl,
+    };
+    use axum::{
+        body::Body,
+        http::{header, Request, StatusCode},
+    };
+    use chrono::{Duration, Utc};
+    use sea_orm::{DatabaseBackend, MockDatabase};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    fn stored_tenant(id: Uuid, audit_logging: bool) -> tenant::Model {
+        let mut settings = TenantSettings::default();
+        settings.features.audit_logging = audit_logging;
+        tenant::Model {
+            id,
+            name: "Acme".to_string(),
+            domain: "acme.example.com".to_string(),
+            is_active: true,
+            settings: serde_json::to_value(settings).expect("serializable settings"),
+            created_at: Utc::now().naive_utc(),
+            updated_at: Utc::now().naive_utc(),
+            deleted_at: None,
+        }
+    }
+
+    fn entry(tenant_id: Uuid, action: &str, minutes_ago: i64) -> audit_log::Model {
+        audit_log::Model {
+            id: Uuid::new_v4(),
+            tenant_id,
+            actor: Some("admin".to_string()),
+            action: action.to_string(),
+            entity_type: "tenant".to_string(),
+            entity_id: Some(tenant_id.to_string()),
+            details: None,
+            created_at: (Utc::now() - Duration::minutes(minutes_ago)).into(),
+        }
+    }
+
+    fn admin_of(tenant_id: Uuid, permissions: &[&str]) -> UserInfo {
+        UserInfo {
+            sub: "admin".to_string(),
+            preferred_username: "admin".to_string(),
+            email: None,
+            roles: vec!["tenant_admin".to_string()],
+            tenant_id: Some(tenant_id.to_string()),
+            permissions: permissions.iter().map(|p| p.to_string()).collect(),
+            auth_methods: vec![],
+        }
+    }
+
+    async fn get_audit(
+        tenant: tenant::Model,
+        entries: Vec<audit_log::Model>,
+        caller: UserInfo,
+        query: &str,
+    ) -> (StatusCode, HeaderMap, serde_json::Value) {
+        let i18n = I18nManager::new(SupportedLanguage::En, Arc::new(TestResourceProvider::new()))
+            .await
+            .expect("i18n manager");
+        let uri = format!("/tenants/{}/audit{}", tenant.id, query);
+        let tenants =
+            MockDatabase::new(DatabaseBackend::Postgres).append_query_results(vec![vec![tenant]]);
+        let audit =
+            MockDatabase::new(DatabaseBackend::Postgres).append_query_results(vec![entries]);
+        let mut state = AppState::for_test(
+            Arc::new(TenantServiceImpl::new(Arc::new(tenants.into_connection()))),
+            Arc::new(i18n),
+        );
+        state.audit_log = Arc::new(AuditLogServiceImpl::new(Arc::new(audit.into_connection())));
+
+        let request = Request::builder()
+            .uri(uri)
+            .body(Body::empty())
+            .expect("valid request");
+        let response = audit_routes()
+            .with_state(state)
+            .layer(Extension(caller))
+            .oneshot(request)
+            .await
+            .expect("response");
+        let (status, headers) = (response.status(), response.headers().clone());
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("response body");
+        let body = serde_json::from_slice(&body).unwrap_or_default();
+        (status, headers, body)
+    }
+
+    #[tokio::test]
+    async fn test_filtered_page_keeps_order_and_filter() {
+        let id = Uuid::new_v4();
+        let newest = entry(id, "tenant.update", 1);
+        let older = entry(id, "tenant.update", 30);
+        let (status, headers, body) = get_audit(
+            stored_tenant(id, true),
+            vec![newest.clone(), older.clone()],
+            admin_of(id, &[AUDIT_READ_PERMISSION]),
+            "?action=tenant.update&page_size=2",
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body[0]["id"], newest.id.to_string());
+        assert_eq!(body[1]["id"], older.id.to_string());
+        assert_eq!(body[0]["action"], "tenant.update");
+
+        // A full page links to the next one with the same filter
+        let link = headers
+            .get(header::LINK)
+            .and_then(|value| value.to_str().ok())
+            .expect("Link header");
+        assert!(link.contains(&format!(
+            "/tenants/{}/audit?action=tenant.update&page=2&page_size=2>; rel=\"next\"",
+            id
+        )));
+    }
+
+    #[tokio::test]
+    async fn test_requires_admin_permission_and_audit_logging() {
+        let id = Uuid::new_v4();
+
+        let (status, _, _) =
+            get_audit(stored_tenant(id, true), vec![], admin_of(id, &[]), "").await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        let (status, _, _) = get_audit(
+            stored_tenant(id, true),
+            vec![],
+            admin_of(Uuid::new_v4(), &[AUDIT_READ_PERMISSION]),
+            "",
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        // Platform admins read any tenant's log
+        let (status, _, _) = get_audit(
+            stored_tenant(id, true),
+            vec![],
+            admin_of(
+                Uuid::new_v4(),
+                &[AUDIT_READ_PERMISSION, PLATFORM_ADMIN_PERMISSION],
+            ),
+            "",
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, _, body) = get_audit(
+            stored_tenant(id, false),
+            vec![],
+            admin_of(id, &[AUDIT_READ_PERMISSION]),
+            "",
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(
+            body["message"],
+            "Authorization error: Audit logging is not enabled for this tenant"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_changes_are_recorded_with_the_caller_as_actor() {
+        let i18n = I18nManager::new(SupportedLanguage::En, Arc::new(TestResourceProvider::new()))
+            .await
+            .expect("i18n manager");
+        let id = Uuid::new_v4();
+        let audit = Arc::new(
+            MockDatabase::new(DatabaseBackend::Postgres)
+                .append_query_results(vec![vec![entry(id, "tenant.reactivate", 0)]])
+                .into_connection(),
+        );
+        let tenants = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+        let mut state = AppState::for_test(
+            Arc::new(TenantServiceImpl::new(Arc::new(tenants))),
+            Arc::new(i18n),
+        );
+        state.audit_log = Arc::new(AuditLogServiceImpl::new(Arc::clone(&audit)));
+
+        let caller = admin_of(id, &[]);
+        record_change(
+            &state,
+            Some(&caller),
+            id,
+            "tenant.reactivate",
+            "tenant",
+            id,
+            None,
+        )
+        .await;
+
+        drop(state);
+        let log = Arc::try_unwrap(audit)
+            .expect("state dropped")
+            .into_transaction_log();
+        let statement = &log[0].statements()[0];
+        assert!(statement.sql.starts_with(r#"INSERT INTO "audit_log""#));
+        let values = format!("{:?}", statement.values);
+        assert!(values.contains(r#"String(Some("admin"))"#));
+        assert!(values.contains(r#"String(Some("tenant.reactivate"))"#));
+    }
+
+    #[test]
+    fn test_filtered_path_encodes_values() {
+        let id = Uuid::nil();
+        let filter = AuditFilter {
+            action: None,
+            entity_type: Some("api key".to_string()),
+        };
+        assert_eq!(
+            filtered_path(id, &filter),
+            format!("/tenants/{}/audit?entity_type=api+key", id)
+        );
+        assert_eq!(
+            filtered_path(id, &AuditFilter::default()),
+            format!("/tenants/{}/audit", id)
+        );
+    }
+}
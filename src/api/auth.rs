@@ -1,31 +1,49 @@
 use axum::{
     extract::State,
     http::{header, StatusCode},
-    response::{IntoResponse, Redirect, Response},
-    Json,
+    response::{AppendHeaders, IntoResponse, Redirect, Response},
+    routing::get,
+    Json, Router,
 };
 use headers::{Cookie, HeaderMapExt};
 use oauth2::{
-    AuthorizationCode, CsrfToken, PkceCodeChallenge, PkceCodeVerifier, Scope, TokenResponse,
+    basic::{BasicErrorResponse, BasicErrorResponseType, BasicTokenResponse},
+    http::StatusCode as OAuthStatusCode,
+    AuthorizationCode, CsrfToken, HttpRequest, HttpResponse, PkceCodeChallenge, PkceCodeVerifier,
+    RequestTokenError, Scope, TokenResponse,
 };
 use serde::{Deserialize, Serialize};
-use tracing::{debug, instrument};
+use std::time::Duration;
+use tracing::{debug, instrument, warn};
 
 use crate::common::error::AppError;
 use crate::common::middleware::auth::AuthState;
 
-#[allow(dead_code)]
 const CSRF_COOKIE_NAME: &str = "csrf_state";
-#[allow(dead_code)]
 const PKCE_VERIFIER_COOKIE_NAME: &str = "pkce_verifier";
 
+/// The Keycloak login flow; its handlers run on `state` instead of the
+/// router's state
+pub fn auth_routes<S>(state: AuthState) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/auth/login", get(login))
+        .route("/auth/callback", get(oauth_callback))
+        .route("/auth/logout", get(logout))
+        .with_state(state)
+}
+
 #[derive(Debug, Serialize)]
 pub struct LoginResponse {
     auth_url: String,
+    /// Seconds until the CSRF/PKCE cookies expire and the login has to be
+    /// restarted
+    state_expires_in: u64,
 }
 
 #[derive(Debug, Deserialize)]
-#[allow(dead_code)]
 pub struct CallbackQuery {
     code: String,
     state: String,
@@ -39,6 +57,115 @@ pub struct TokenInfo {
     token_type: String,
 }
 
+/// Why exchanging an authorization code for tokens failed
+#[derive(Debug, thiserror::Error)]
+pub enum TokenExchangeError {
+    /// The token endpoint was unreachable or failed with a server error;
+    /// the exchange may succeed when retried
+    #[error("Token endpoint unavailable: {0}")]
+    Transient(String),
+    /// The code is invalid, expired or was already redeemed
+    #[error("Authorization code rejected: {0}")]
+    InvalidGrant(String),
+    /// Any other error response from the token endpoint
+    #[error("Token exchange rejected: {0}")]
+    Rejected(String),
+}
+
+impl TokenExchangeError {
+    pub fn is_transient(&self) -> bool {
+        matches!(self, Self::Transient(_))
+    }
+}
+
+impl From<RequestTokenError<TokenHttpError, BasicErrorResponse>> for TokenExchangeError {
+    fn from(err: RequestTokenError<TokenHttpError, BasicErrorResponse>) -> Self {
+        match err {
+            RequestTokenError::Request(e) => Self::Transient(e.to_string()),
+            RequestTokenError::ServerResponse(response)
+                if *response.error() == BasicErrorResponseType::InvalidGrant =>
+            {
+                Self::InvalidGrant(response.to_string())
+            },
+            RequestTokenError::ServerResponse(response) => Self::Rejected(response.to_string()),
+            other => Self::Rejected(other.to_string()),
+        }
+    }
+}
+
+impl From<TokenExchangeError> for AppError {
+    fn from(err: TokenExchangeError) -> Self {
+        match err {
+            TokenExchangeError::Transient(_) => Self::upstream(err.to_string()),
+            TokenExchangeError::InvalidGrant(_) => {
+                Self::authentication(format!("{}; the login has to be restarted", err))
+            },
+            TokenExchangeError::Rejected(_) => Self::authentication(err.to_string()),
+        }
+    }
+}
+
+/// Failed round trip to the token endpoint
+#[derive(Debug, thiserror::Error)]
+pub enum TokenHttpError {
+    #[error(transparent)]
+    Request(#[from] oauth2::reqwest::AsyncHttpClientError),
+    #[error("Token endpoint returned {0}")]
+    Status(OAuthStatusCode),
+}
+
+/// `async_http_client`, but server errors and throttling are reported as
+/// request failures, so they're retried rather than parsed as an OAuth error
+/// response
+async fn token_http_client(request: HttpRequest) -> Result<HttpResponse, TokenHttpError> {
+    let response = oauth2::reqwest::async_http_client(request).await?;
+    if response.status_code.is_server_error()
+        || response.status_code == OAuthStatusCode::TOO_MANY_REQUESTS
+    {
+        return Err(TokenHttpError::Status(response.status_code));
+    }
+    Ok(response)
+}
+
+/// Exchanges the authorization code for tokens, retrying with exponential
+/// backoff while the token endpoint is unavailable. Rejected codes are not
+/// retried.
+async fn exchange_code(
+    state: &AuthState,
+    code: &str,
+    pkce_verifier: &str,
+) -> Result<BasicTokenResponse, TokenExchangeError> {
+    let keycloak = &state.config.keycloak;
+    let mut delay = Duration::from_millis(keycloak.token_exchange_retry_delay_ms);
+    let mut retry = 0;
+
+    loop {
+        let result = state
+            .oauth_client
+            .exchange_code(AuthorizationCode::new(code.to_string()))
+            .set_pkce_verifier(PkceCodeVerifier::new(pkce_verifier.to_string()))
+            .request_async(token_http_client)
+            .await
+            .map_err(TokenExchangeError::from);
+
+        match result {
+            Err(e) if e.is_transient() && retry < keycloak.token_exchange_max_retries => {
+                warn!(
+                    "Token exchange failed (retry {}/{} in {:?}): {}",
+                    retry + 1,
+                    keycloak.token_exchange_max_retries,
+                    delay,
+                    e
+                );
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+                retry += 1;
+            },
+            result => return result,
+        }
+    }
+}
+
 #[instrument(skip(state))]
 pub async fn login(State(state): State<AuthState>) -> Result<impl IntoResponse, AppError> {
     let (pkce_challenge, pkce_verifier) = PkceCodeChallenge::new_random_sha256();
@@ -56,34 +183,75 @@ pub async fn login(State(state): State<AuthState>) -> Result<impl IntoResponse,
     debug!("CSRF Token: {}", csrf_token.secret());
     debug!("PKCE Verifier: {}", pkce_verifier.secret());
 
+    // Flow cookies expire on their own so abandoned logins don't pile up
+    let max_age = state.config.keycloak.auth_flow_cookie_max_age;
     let response = LoginResponse {
         auth_url: auth_url.to_string(),
+        state_expires_in: max_age,
     };
 
+    // AppendHeaders, since both cookies share the Set-Cookie header name
     Ok((
         StatusCode::OK,
-        [
+        AppendHeaders([
             (
                 header::SET_COOKIE,
-                format!(
-                    "{}={}; HttpOnly; Secure; SameSite=Lax",
-                    CSRF_COOKIE_NAME,
-                    csrf_token.secret()
-                ),
+                flow_cookie(CSRF_COOKIE_NAME, csrf_token.secret(), max_age),
             ),
             (
                 header::SET_COOKIE,
-                format!(
-                    "{}={}; HttpOnly; Secure; SameSite=Lax",
-                    PKCE_VERIFIER_COOKIE_NAME,
-                    pkce_verifier.secret()
-                ),
+                flow_cookie(PKCE_VERIFIER_COOKIE_NAME, pkce_verifier.secret(), max_age),
             ),
-        ],
+        ]),
         Json(response),
     ))
 }
 
+fn flow_cookie(name: &str, value: &str, max_age: u64) -> String {
+    format!(
+        "{}={}; HttpOnly; Secure; SameSite=Lax; Max-Age={}",
+        name, value, max_age
+    )
+}
+
+/// Value of the login flow cookie `name`, described as `label` in errors.
+///
+/// A missing cookie fails authentication. A cookie sent more than once,
+/// longer than `max_size` or holding anything but the URL-safe characters
+/// `login` generates is rejected as a bad request.
+fn flow_cookie_value<'a>(
+    cookies: &'a Cookie,
+    name: &str,
+    label: &str,
+    max_size: usize,
+) -> Result<&'a str, AppError> {
+    let mut values = cookies
+        .iter()
+        .filter(|(key, _)| *key == name)
+        .map(|(_, value)| value);
+    let value = values
+        .next()
+        .ok_or_else(|| AppError::authentication(format!("Missing {}", label)))?;
+
+    if values.next().is_some() {
+        return Err(AppError::validation(format!(
+            "{} cookie sent more than once",
+            label
+        )));
+    }
+    if value.len() > max_size {
+        return Err(AppError::validation(format!(
+            "{} cookie exceeds {} bytes",
+            label, max_size
+        )));
+    }
+    let url_safe = |byte: u8| byte.is_ascii_alphanumeric() || b"-._~".contains(&byte);
+    if value.is_empty() || !value.bytes().all(url_safe) {
+        return Err(AppError::validation(format!("Malformed {} cookie", label)));
+    }
+    Ok(value)
+}
+
 #[instrument(skip(state))]
 pub async fn oauth_callback(
     State(state): State<AuthState>,
@@ -94,25 +262,38 @@ pub async fn oauth_callback(
         .typed_get::<Cookie>()
         .ok_or_else(|| AppError::authentication("No cookies found".to_string()))?;
 
-    let stored_csrf_token = cookies
-        .get(CSRF_COOKIE_NAME)
-        .ok_or_else(|| AppError::authentication("Missing CSRF token".to_string()))?;
+    // Other cookies on the domain are none of the login flow's business
+    let keycloak = &state.config.keycloak;
+    let cookie_count = cookies
+        .iter()
+        .filter(|(name, _)| [CSRF_COOKIE_NAME, PKCE_VERIFIER_COOKIE_NAME].contains(name))
+        .count();
+    if cookie_count > keycloak.auth_flow_max_cookies {
+        return Err(AppError::validation(format!(
+            "Too many login flow cookies: {} sent, at most {} accepted",
+            cookie_count, keycloak.auth_flow_max_cookies
+        )));
+    }
+
+    let stored_csrf_token = flow_cookie_value(
+        &cookies,
+        CSRF_COOKIE_NAME,
+        "CSRF token",
+        keycloak.auth_flow_cookie_max_size,
+    )?;
 
     if stored_csrf_token != query.state {
         return Err(AppError::authentication("Invalid CSRF token".to_string()));
     }
 
-    let pkce_verifier = cookies
-        .get(PKCE_VERIFIER_COOKIE_NAME)
-        .ok_or_else(|| AppError::authentication("Missing PKCE verifier".to_string()))?;
+    let pkce_verifier = flow_cookie_value(
+        &cookies,
+        PKCE_VERIFIER_COOKIE_NAME,
+        "PKCE verifier",
+        keycloak.auth_flow_cookie_max_size,
+    )?;
 
-    let token_result = state
-        .oauth_client
-        .exchange_code(AuthorizationCode::new(query.code.clone()))
-        .set_pkce_verifier(PkceCodeVerifier::new(pkce_verifier.to_string()))
-        .request_async(oauth2::reqwest::async_http_client)
-        .await
-        .map_err(|e| AppError::authentication(format!("Token exchange failed: {}", e)))?;
+    let token_result = exchange_code(&state, &query.code, pkce_verifier).await?;
 
     let token_info = TokenInfo {
         access_token: token_result.access_token().secret().clone(),
@@ -158,3 +339,261 @@ pub async fn logout(State(state): State<AuthState>) -> impl IntoResponse {
 
     Redirect::to(&logout_url)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use wiremock::{
+        matchers::{method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use crate::common::config::AppConfig;
+
+    const TOKEN_PATH: &str = "/realms/acci/protocol/openid-connect/token";
+
+    async fn state_for(server: &MockServer) -> AuthState {
+        let mut config = AppConfig::default();
+        config.keycloak.url = server.uri();
+        config.keycloak.token_exchange_max_retries = 2;
+        config.keycloak.token_exchange_retry_delay_ms = 1;
+        let redis_client =
+            redis::Client::open("redis://dummy").expect("Failed to create dummy Redis client");
+        AuthState::new(Arc::new(config), Arc::new(redis_client))
+            .await
+            .expect("Failed to create auth state")
+    }
+
+    async fn callback_with_cookies(cookie: &str) -> Result<Response, AppError> {
+        let server = MockServer::start().await;
+        let state = state_for(&server).await;
+        let mut headers = axum::http::HeaderMap::new();
+        headers.insert(header::COOKIE, cookie.parse().expect("valid cookie header"));
+        let query = CallbackQuery {
+            code: "code".to_string(),
+            state: "csrf".to_string(),
+        };
+
+        oauth_callback(State(state), axum::extract::Query(query), headers).await
+    }
+
+    fn rejection_status(result: Result<Response, AppError>) -> StatusCode {
+        result
+            .expect_err("flow cookies are rejected")
+            .into_response()
+            .status()
+    }
+
+    #[tokio::test]
+    async fn test_oversized_pkce_cookie_is_rejected() {
+        let cookie = format!(
+            "{}=csrf; {}={}",
+            CSRF_COOKIE_NAME,
+            PKCE_VERIFIER_COOKIE_NAME,
+            "a".repeat(257)
+        );
+
+        let status = rejection_status(callback_with_cookies(&cookie).await);
+
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_garbled_pkce_cookie_is_rejected() {
+        for verifier in ["\"not-base64\"", "abc%20def", ""] {
+            let cookie = format!(
+                "{}=csrf; {}={}",
+                CSRF_COOKIE_NAME, PKCE_VERIFIER_COOKIE_NAME, verifier
+            );
+
+            let status = rejection_status(callback_with_cookies(&cookie).await);
+
+            assert_eq!(status, StatusCode::BAD_REQUEST, "verifier {:?}", verifier);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_or_excess_flow_cookies_are_rejected() {
+        let duplicate = format!(
+            "{}=csrf; {}=verifier; {}=other",
+            CSRF_COOKIE_NAME, PKCE_VERIFIER_COOKIE_NAME, PKCE_VERIFIER_COOKIE_NAME
+        );
+        assert_eq!(
+            rejection_status(callback_with_cookies(&duplicate).await),
+            StatusCode::BAD_REQUEST
+        );
+
+        let repeated: Vec<_> = (0..64)
+            .map(|_| format!("{}=verifier", PKCE_VERIFIER_COOKIE_NAME))
+            .collect();
+        let excess = format!("{}=csrf; {}", CSRF_COOKIE_NAME, repeated.join("; "));
+        assert_eq!(
+            rejection_status(callback_with_cookies(&excess).await),
+            StatusCode::BAD_REQUEST
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unrelated_cookies_do_not_count_against_the_limit() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path(TOKEN_PATH))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(
+                r#"{"access_token": "access", "token_type": "Bearer", "expires_in": 300}"#,
+                "application/json",
+            ))
+            .mount(&server)
+            .await;
+        let state = state_for(&server).await;
+        let filler: Vec<_> = (0..64).map(|i| format!("c{}=v", i)).collect();
+        let cookie = format!(
+            "{}; {}=csrf; {}=verifier",
+            filler.join("; "),
+            CSRF_COOKIE_NAME,
+            PKCE_VERIFIER_COOKIE_NAME
+        );
+        let mut headers = axum::http::HeaderMap::new();
+        headers.insert(header::COOKIE, cookie.parse().expect("valid cookie header"));
+        let query = CallbackQuery {
+            code: "code".to_string(),
+            state: "csrf".to_string(),
+        };
+
+        let response = oauth_callback(State(state), axum::extract::Query(query), headers)
+            .await
+            .expect("the login completes");
+
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    async fn token_requests(server: &MockServer) -> usize {
+        server
+            .received_requests()
+            .await
+            .expect("request recording enabled")
+            .len()
+    }
+
+    #[tokio::test]
+    async fn test_token_exchange_retries_transient_failures() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path(TOKEN_PATH))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path(TOKEN_PATH))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(
+                r#"{"access_token": "access", "token_type": "Bearer", "expires_in": 300}"#,
+                "application/json",
+            ))
+            .mount(&server)
+            .await;
+        let state = state_for(&server).await;
+
+        let token = exchange_code(&state, "code", "verifier")
+            .await
+            .expect("exchange succeeds once the endpoint recovers");
+
+        assert_eq!(token.access_token().secret(), "access");
+        assert_eq!(token_requests(&server).await, 2);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_grant_is_not_retried() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path(TOKEN_PATH))
+            .respond_with(ResponseTemplate::new(400).set_body_raw(
+                r#"{"error": "invalid_grant", "error_description": "Code not valid"}"#,
+                "application/json",
+            ))
+            .mount(&server)
+            .await;
+        let state = state_for(&server).await;
+
+        let error = exchange_code(&state, "code", "verifier")
+            .await
+            .expect_err("a rejected code fails the exchange");
+
+        assert!(matches!(error, TokenExchangeError::InvalidGrant(_)));
+        assert_eq!(token_requests(&server).await, 1);
+        let response = AppError::from(error).into_response();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_exhausted_retries_report_upstream_error() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path(TOKEN_PATH))
+            .respond_with(ResponseTemplate::new(502))
+            .mount(&server)
+            .await;
+        let state = state_for(&server).await;
+
+        let error = exchange_code(&state, "code", "verifier")
+            .await
+            .expect_err("the endpoint never recovers");
+
+        assert!(error.is_transient());
+        assert_eq!(token_requests(&server).await, 3);
+        let response = AppError::from(error).into_response();
+        assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+    }
+
+    #[tokio::test]
+    async fn test_login_flow_is_routed() {
+        use tower::ServiceExt;
+
+        let server = MockServer::start().await;
+        let app: Router = auth_routes(state_for(&server).await);
+
+        let request = axum::http::Request::builder()
+            .uri("/auth/login")
+            .body(axum::body::Body::empty())
+            .expect("valid request");
+        let response = app.clone().oneshot(request).await.expect("response");
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // Reaches the handler, which wants the flow cookies
+        let request = axum::http::Request::builder()
+            .uri("/auth/callback?code=code&state=csrf")
+            .body(axum::body::Body::empty())
+            .expect("valid request");
+        let response = app.oneshot(request).await.expect("response");
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_login_cookies_carry_configured_max_age() -> Result<(), AppError> {
+        let mut config = AppConfig::default();
+        config.keycloak.auth_flow_cookie_max_age = 300;
+        let redis_client =
+            redis::Client::open("redis://dummy").expect("Failed to create dummy Redis client");
+        let state = AuthState::new(Arc::new(config), Arc::new(redis_client)).await?;
+
+        let response = login(State(state)).await?.into_response();
+
+        let cookies: Vec<_> = response
+            .headers()
+            .get_all(header::SET_COOKIE)
+            .iter()
+            .map(|value| value.to_str().expect("ascii cookie").to_string())
+            .collect();
+        assert_eq!(cookies.len(), 2);
+        assert!(cookies
+            .iter()
+            .all(|cookie| cookie.ends_with("; Max-Age=300")));
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("readable body");
+        let body: serde_json::Value = serde_json::from_slice(&body)?;
+        assert_eq!(body["state_expires_in"], 300);
+        Ok(())
+    }
+}
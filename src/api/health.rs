@@ -1,14 +1,28 @@
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
 use axum::{
-    extract::State,
+    extract::{Extension, Query, State},
     http::StatusCode,
     response::{IntoResponse, Json},
 };
 use chrono::Utc;
-use serde::Serialize;
-use sysinfo::System as SysInfo;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use crate::common::{
+    config::{HealthSettings, KeycloakConfig, ResourceThresholds},
+    error::AppResult,
+    i18n::SupportedLanguage,
+    middleware::auth::UserInfo,
+    pagination::{Pagination, Sort},
+};
+use crate::domain::tenant::TenantSortField;
+use crate::infrastructure::{state::AppState, subscriptions::SubscriptionStatus};
 
-use crate::common::{error::AppResult, i18n::SupportedLanguage};
-use crate::infrastructure::state::AppState;
+/// Permission that unlocks full health details when they are not exposed
+const HEALTH_DETAILS_PERMISSION: &str = "health:details";
 
 pub fn health_routes() -> axum::Router<AppState> {
     axum::Router::new()
@@ -22,27 +36,49 @@ pub struct HealthResponse {
     message: String,
     timestamp: String,
     #[serde(skip_serializing_if = "Option::is_none")]
+    ready_for: Option<FunctionalReadiness>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     details: Option<HealthDetails>,
 }
 
-#[derive(Debug, Serialize)]
+/// Which parts of the service can take traffic, so a caller can tell an
+/// outage of event processing apart from one of the whole service
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+pub struct FunctionalReadiness {
+    tenant_crud: bool,
+    event_processing: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HealthParams {
+    /// Run the dependency checks even if a cached result is still fresh
+    #[serde(default)]
+    refresh: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct HealthDetails {
     tenant_service: ComponentHealth,
     cache: ComponentHealth,
     event_store: ComponentHealth,
+    /// Whether the subscription workers are connected, as opposed to the
+    /// `event_store` ping, which only shows that EventStore is reachable
+    event_processing: ComponentHealth,
     message_broker: ComponentHealth,
+    /// Whether the realm answers, as tokens can't be verified without it
+    keycloak: ComponentHealth,
     external_services: Vec<ServiceHealth>,
     system: SystemHealth,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ComponentHealth {
     status: HealthStatus,
     latency_ms: u64,
     message: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ServiceHealth {
     name: String,
     status: HealthStatus,
@@ -50,14 +86,14 @@ pub struct ServiceHealth {
     message: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct SystemHealth {
     cpu_usage: f64,
     memory_usage: f64,
     disk_usage: f64,
 }
 
-#[derive(Debug, Serialize, PartialEq)]
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, PartialOrd, Ord)]
 #[serde(rename_all = "lowercase")]
 #[allow(dead_code)]
 pub enum HealthStatus {
@@ -66,29 +102,76 @@ pub enum HealthStatus {
     Unhealthy,
 }
 
-pub async fn health_check(State(state): State<AppState>) -> impl IntoResponse {
-    let mut sys = SysInfo::new();
-    sys.refresh_all();
+/// Reuses the last computed `HealthDetails` for a short window, so rapid
+/// probes don't each run the full dependency checks. Concurrent probes wait
+/// for the check in flight instead of starting their own.
+#[derive(Clone)]
+pub struct HealthCache {
+    ttl: Duration,
+    last: Arc<Mutex<Option<(Instant, HealthDetails)>>>,
+}
+
+impl HealthCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            last: Arc::new(Mutex::new(None)),
+        }
+    }
 
-    let health_details = check_system_health(&state, &sys).await;
+    /// Returns the cached details while they are younger than the TTL,
+    /// otherwise runs `check` and caches a successful result
+    async fn get_or_check<F, Fut>(&self, refresh: bool, check: F) -> AppResult<HealthDetails>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = AppResult<HealthDetails>>,
+    {
+        let mut last = self.last.lock().await;
+        if let Some((checked_at, details)) = last.as_ref() {
+            if !refresh && checked_at.elapsed() < self.ttl {
+                return Ok(details.clone());
+            }
+        }
+
+        let details = check().await?;
+        *last = Some((Instant::now(), details.clone()));
+        Ok(details)
+    }
+}
+
+async fn cached_system_health(state: &AppState, params: &HealthParams) -> AppResult<HealthDetails> {
+    state
+        .health_cache
+        .get_or_check(params.refresh, || check_system_health(state))
+        .await
+}
+
+pub async fn health_check(
+    State(state): State<AppState>,
+    Query(params): Query<HealthParams>,
+    user: Option<Extension<UserInfo>>,
+) -> impl IntoResponse {
+    let health_details = cached_system_health(&state, &params).await;
     let (status, status_code) = match &health_details {
         Ok(details) => {
-            let is_healthy = details.tenant_service.status == HealthStatus::Healthy
+            let components_healthy = details.tenant_service.status == HealthStatus::Healthy
                 && details.cache.status == HealthStatus::Healthy
                 && details.event_store.status == HealthStatus::Healthy
+                && details.event_processing.status == HealthStatus::Healthy
                 && details.message_broker.status == HealthStatus::Healthy
                 && details
                     .external_services
                     .iter()
-                    .all(|s| s.status == HealthStatus::Healthy)
-                && details.system.cpu_usage < 90.0
-                && details.system.memory_usage < 90.0
-                && details.system.disk_usage < 90.0;
-
-            if is_healthy {
-                ("healthy".to_string(), StatusCode::OK)
-            } else {
-                ("degraded".to_string(), StatusCode::OK)
+                    .all(|s| s.status == HealthStatus::Healthy);
+
+            match system_status(&details.system, &state.config.health) {
+                HealthStatus::Unhealthy => {
+                    ("unhealthy".to_string(), StatusCode::SERVICE_UNAVAILABLE)
+                },
+                HealthStatus::Healthy if components_healthy => {
+                    ("healthy".to_string(), StatusCode::OK)
+                },
+                _ => ("degraded".to_string(), StatusCode::OK),
             }
         },
         Err(_) => ("unhealthy".to_string(), StatusCode::SERVICE_UNAVAILABLE),
@@ -98,68 +181,54 @@ pub async fn health_check(State(state): State<AppState>) -> impl IntoResponse {
         status,
         message: "Health check completed".to_string(),
         timestamp: Utc::now().to_rfc3339(),
-        details: health_details.ok(),
+        ready_for: None,
+        details: health_details
+            .ok()
+            .filter(|_| details_visible(&state.config.health, user.as_deref())),
     });
 
     (status_code, body).into_response()
 }
 
-async fn readiness_check(State(state): State<AppState>) -> impl IntoResponse {
-    let mut sys = SysInfo::new();
-    sys.refresh_all();
-
-    let health_details = check_system_health(&state, &sys).await;
+async fn readiness_check(
+    State(state): State<AppState>,
+    Query(params): Query<HealthParams>,
+    user: Option<Extension<UserInfo>>,
+) -> impl IntoResponse {
+    let health_details = cached_system_health(&state, &params).await;
     let (status, status_code, message) = match &health_details {
-        Ok(details) => {
-            let has_unhealthy = details.tenant_service.status == HealthStatus::Unhealthy
-                || details.cache.status == HealthStatus::Unhealthy
-                || details.event_store.status == HealthStatus::Unhealthy
-                || details.message_broker.status == HealthStatus::Unhealthy;
-
-            let has_degraded = details.tenant_service.status == HealthStatus::Degraded
-                || details.cache.status == HealthStatus::Degraded
-                || details.event_store.status == HealthStatus::Degraded
-                || details.message_broker.status == HealthStatus::Degraded;
-
-            let system_overloaded = details.system.cpu_usage >= 90.0
-                || details.system.memory_usage >= 90.0
-                || details.system.disk_usage >= 90.0;
-
-            if has_unhealthy {
-                (
-                    "not_ready".to_string(),
-                    StatusCode::SERVICE_UNAVAILABLE,
-                    state
-                        .i18n
-                        .format_message(SupportedLanguage::En, "system-not-ready-message", None)
-                        .await
-                        .unwrap_or_else(|_| {
-                            "System is not ready - critical services unavailable".to_string()
-                        }),
-                )
-            } else if has_degraded || system_overloaded {
-                (
-                    "partially_ready".to_string(),
-                    StatusCode::OK,
-                    state
-                        .i18n
-                        .format_message(SupportedLanguage::En, "system-degraded-message", None)
-                        .await
-                        .unwrap_or_else(|_| {
-                            "System is partially ready - some services degraded".to_string()
-                        }),
-                )
-            } else {
-                (
-                    "ready".to_string(),
-                    StatusCode::OK,
-                    state
-                        .i18n
-                        .format_message(SupportedLanguage::En, "system-ready-message", None)
-                        .await
-                        .unwrap_or_else(|_| "System is ready".to_string()),
-                )
-            }
+        Ok(details) => match readiness_status(details, &state.config.health) {
+            HealthStatus::Unhealthy => (
+                "not_ready".to_string(),
+                StatusCode::SERVICE_UNAVAILABLE,
+                state
+                    .i18n
+                    .format_message(SupportedLanguage::En, "system-not-ready-message", None)
+                    .await
+                    .unwrap_or_else(|_| {
+                        "System is not ready - critical services unavailable".to_string()
+                    }),
+            ),
+            HealthStatus::Degraded => (
+                "partially_ready".to_string(),
+                StatusCode::OK,
+                state
+                    .i18n
+                    .format_message(SupportedLanguage::En, "system-degraded-message", None)
+                    .await
+                    .unwrap_or_else(|_| {
+                        "System is partially ready - some services degraded".to_string()
+                    }),
+            ),
+            HealthStatus::Healthy => (
+                "ready".to_string(),
+                StatusCode::OK,
+                state
+                    .i18n
+                    .format_message(SupportedLanguage::En, "system-ready-message", None)
+                    .await
+                    .unwrap_or_else(|_| "System is ready".to_string()),
+            ),
         },
         Err(_) => (
             "not_ready".to_string(),
@@ -172,20 +241,35 @@ async fn readiness_check(State(state): State<AppState>) -> impl IntoResponse {
         ),
     };
 
+    let ready_for = health_details
+        .as_ref()
+        .ok()
+        .map(|details| functional_readiness(details, &state.config.health));
     let body = Json(HealthResponse {
         status,
         message,
         timestamp: Utc::now().to_rfc3339(),
-        details: health_details.ok(),
+        ready_for,
+        details: health_details
+            .ok()
+            .filter(|_| details_visible(&state.config.health, user.as_deref())),
     });
 
     (status_code, body).into_response()
 }
 
-async fn check_system_health(state: &AppState, sys: &SysInfo) -> AppResult<HealthDetails> {
+async fn check_system_health(state: &AppState) -> AppResult<HealthDetails> {
     // Check tenant service (which includes database health)
     let tenant_start = std::time::Instant::now();
-    let tenant_health = match state.tenant_service.list().await {
+    let probe = Pagination {
+        page: 1,
+        page_size: 1,
+    };
+    let sort = Sort {
+        field: TenantSortField::Name,
+        descending: false,
+    };
+    let tenant_health = match state.tenant_service.list(probe, sort).await {
         Ok(_) => ComponentHealth {
             status: HealthStatus::Healthy,
             latency_ms: tenant_start.elapsed().as_millis() as u64,
@@ -242,6 +326,8 @@ async fn check_system_health(state: &AppState, sys: &SysInfo) -> AppResult<Healt
         },
     };
 
+    let event_processing_health = event_processing_health(&state.subscriptions.snapshot());
+
     // Check RabbitMQ health
     let mq_start = std::time::Instant::now();
     let message_broker_health = match &state.message_broker {
@@ -264,17 +350,27 @@ async fn check_system_health(state: &AppState, sys: &SysInfo) -> AppResult<Healt
         },
     };
 
-    // Calculate system metrics
-    let total_memory = sys.total_memory() as f64;
-    let used_memory = sys.used_memory() as f64;
-    let memory_usage = if total_memory > 0.0 {
-        (used_memory / total_memory) * 100.0
-    } else {
-        0.0
-    };
+    let keycloak_health = keycloak_health(
+        &state.http_client,
+        &state.config.keycloak,
+        state.config.health.keycloak_probe_timeout(),
+    )
+    .await;
+
+    // Read system metrics from the background-refreshed snapshot
+    let (cpu_usage, memory_usage) = state.system.with_snapshot(|sys| {
+        let total_memory = sys.total_memory() as f64;
+        let used_memory = sys.used_memory() as f64;
+        let memory_usage = if total_memory > 0.0 {
+            (used_memory / total_memory) * 100.0
+        } else {
+            0.0
+        };
+        (sys.global_cpu_usage() as f64, memory_usage)
+    });
 
     let system_health = SystemHealth {
-        cpu_usage: sys.global_cpu_usage() as f64,
+        cpu_usage,
         memory_usage,
         disk_usage: calculate_disk_usage(),
     };
@@ -283,12 +379,136 @@ async fn check_system_health(state: &AppState, sys: &SysInfo) -> AppResult<Healt
         tenant_service: tenant_health,
         cache: cache_health,
         event_store: event_store_health,
+        event_processing: event_processing_health,
         message_broker: message_broker_health,
+        keycloak: keycloak_health,
         external_services: Vec::new(),
         system: system_health,
     })
 }
 
+/// Fetches the realm's OpenID configuration from Keycloak, which also
+/// serves the keys tokens are verified with
+async fn keycloak_health(
+    client: &reqwest::Client,
+    keycloak: &KeycloakConfig,
+    timeout: Duration,
+) -> ComponentHealth {
+    let url = format!(
+        "{}/realms/{}/.well-known/openid-configuration",
+        keycloak.url, keycloak.realm
+    );
+    let start = Instant::now();
+    let result = client
+        .get(&url)
+        .timeout(timeout)
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status);
+
+    ComponentHealth {
+        status: if result.is_ok() {
+            HealthStatus::Healthy
+        } else {
+            HealthStatus::Unhealthy
+        },
+        latency_ms: start.elapsed().as_millis() as u64,
+        message: result.err().map(|e| e.to_string()),
+    }
+}
+
+/// Unhealthy while any subscription worker has lost EventStore. Without
+/// running subscriptions there is nothing to report.
+fn event_processing_health(subscriptions: &[SubscriptionStatus]) -> ComponentHealth {
+    let disconnected: Vec<&str> = subscriptions
+        .iter()
+        .filter(|subscription| !subscription.connected)
+        .map(|subscription| subscription.name.as_str())
+        .collect();
+
+    ComponentHealth {
+        status: if disconnected.is_empty() {
+            HealthStatus::Healthy
+        } else {
+            HealthStatus::Unhealthy
+        },
+        latency_ms: 0,
+        message: (!disconnected.is_empty())
+            .then(|| format!("Subscriptions not connected: {}", disconnected.join(", "))),
+    }
+}
+
+/// Readiness fails on an unhealthy critical component (by default only the
+/// database) or exhausted system resources. Any other component that is
+/// down or was unreachable at startup leaves the service taking traffic,
+/// degraded.
+fn readiness_status(details: &HealthDetails, settings: &HealthSettings) -> HealthStatus {
+    let critical = &settings.critical;
+    let components = [
+        (&details.tenant_service, critical.database),
+        (&details.cache, critical.cache),
+        (&details.event_store, critical.event_store),
+        (&details.event_processing, critical.event_processing),
+        (&details.message_broker, critical.message_broker),
+        (&details.keycloak, critical.keycloak),
+    ];
+
+    let system = system_status(&details.system, settings);
+    let critical_down = components
+        .iter()
+        .any(|(component, critical)| *critical && component.status == HealthStatus::Unhealthy);
+    if critical_down || system == HealthStatus::Unhealthy {
+        return HealthStatus::Unhealthy;
+    }
+
+    let any_down = components
+        .iter()
+        .any(|(component, _)| component.status != HealthStatus::Healthy);
+    if any_down || system == HealthStatus::Degraded {
+        HealthStatus::Degraded
+    } else {
+        HealthStatus::Healthy
+    }
+}
+
+/// Tenant CRUD only needs the service to be ready at all, while anything
+/// driven by events also needs the subscription workers
+fn functional_readiness(details: &HealthDetails, settings: &HealthSettings) -> FunctionalReadiness {
+    let serving = readiness_status(details, settings) != HealthStatus::Unhealthy;
+    FunctionalReadiness {
+        tenant_crud: serving,
+        event_processing: serving && details.event_processing.status == HealthStatus::Healthy,
+    }
+}
+
+/// Anonymous callers only get the top-level status unless details are exposed
+fn details_visible(settings: &HealthSettings, user: Option<&UserInfo>) -> bool {
+    settings.expose_details || user.is_some_and(|u| u.has_permission(HEALTH_DETAILS_PERMISSION))
+}
+
+/// Classifies a resource usage percentage against its configured thresholds
+fn resource_status(usage: f64, thresholds: &ResourceThresholds) -> HealthStatus {
+    if usage >= thresholds.unhealthy {
+        HealthStatus::Unhealthy
+    } else if usage >= thresholds.degraded {
+        HealthStatus::Degraded
+    } else {
+        HealthStatus::Healthy
+    }
+}
+
+/// Returns the worst status across CPU, memory and disk usage
+fn system_status(system: &SystemHealth, settings: &HealthSettings) -> HealthStatus {
+    [
+        resource_status(system.cpu_usage, &settings.cpu),
+        resource_status(system.memory_usage, &settings.memory),
+        resource_status(system.disk_usage, &settings.disk),
+    ]
+    .into_iter()
+    .max()
+    .unwrap_or(HealthStatus::Healthy)
+}
+
 fn calculate_disk_usage() -> f64 {
     let disks = sysinfo::Disks::new_with_refreshed_list();
     if let Some(disk) = disks.iter().next() {
@@ -300,3 +520,343 @@ fn calculate_disk_usage() -> f64 {
     }
     0.0
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn system(cpu_usage: f64, memory_usage: f64, disk_usage: f64) -> SystemHealth {
+        SystemHealth {
+            cpu_usage,
+            memory_usage,
+            disk_usage,
+        }
+    }
+
+    #[test]
+    fn test_system_status_with_default_thresholds() {
+        let settings = HealthSettings::default();
+
+        assert_eq!(
+            system_status(&system(50.0, 60.0, 70.0), &settings),
+            HealthStatus::Healthy
+        );
+        assert_eq!(
+            system_status(&system(92.0, 60.0, 70.0), &settings),
+            HealthStatus::Degraded
+        );
+        assert_eq!(
+            system_status(&system(50.0, 60.0, 99.0), &settings),
+            HealthStatus::Unhealthy
+        );
+    }
+
+    #[test]
+    fn test_lower_threshold_flips_status_sooner() {
+        let usage = system(50.0, 75.0, 40.0);
+        assert_eq!(
+            system_status(&usage, &HealthSettings::default()),
+            HealthStatus::Healthy
+        );
+
+        let mut settings = HealthSettings {
+            memory: ResourceThresholds {
+                degraded: 70.0,
+                unhealthy: 80.0,
+            },
+            ..Default::default()
+        };
+        assert_eq!(system_status(&usage, &settings), HealthStatus::Degraded);
+
+        settings.memory.unhealthy = 75.0;
+        assert_eq!(system_status(&usage, &settings), HealthStatus::Unhealthy);
+    }
+
+    fn response(details: Option<HealthDetails>) -> serde_json::Value {
+        serde_json::to_value(HealthResponse {
+            status: "healthy".to_string(),
+            message: "Health check completed".to_string(),
+            timestamp: Utc::now().to_rfc3339(),
+            ready_for: None,
+            details,
+        })
+        .expect("response serializes")
+    }
+
+    fn details() -> HealthDetails {
+        let component = || ComponentHealth {
+            status: HealthStatus::Healthy,
+            latency_ms: 1,
+            message: None,
+        };
+        HealthDetails {
+            tenant_service: component(),
+            cache: component(),
+            event_store: component(),
+            event_processing: component(),
+            message_broker: component(),
+            keycloak: component(),
+            external_services: vec![],
+            system: system(10.0, 20.0, 30.0),
+        }
+    }
+
+    fn user(permissions: &[&str]) -> UserInfo {
+        UserInfo {
+            sub: "admin".to_string(),
+            preferred_username: "admin".to_string(),
+            email: None,
+            roles: vec![],
+            tenant_id: None,
+            permissions: permissions.iter().map(|p| p.to_string()).collect(),
+            auth_methods: vec![],
+        }
+    }
+
+    #[test]
+    fn test_minimal_response_when_details_hidden() {
+        let settings = HealthSettings::default();
+        assert!(!details_visible(&settings, None));
+        assert!(!details_visible(&settings, Some(&user(&["tenant:read"]))));
+
+        let body = response(details_visible(&settings, None).then(details));
+        assert_eq!(body["status"], "healthy");
+        assert!(body.get("details").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_cached_health_is_reused_within_ttl() {
+        let cache = HealthCache::new(Duration::from_secs(60));
+        let checks = AtomicU32::new(0);
+        let check = || async {
+            checks.fetch_add(1, Ordering::SeqCst);
+            Ok(details())
+        };
+
+        cache.get_or_check(false, check).await.expect("first check");
+        cache
+            .get_or_check(false, check)
+            .await
+            .expect("cached result");
+        assert_eq!(checks.load(Ordering::SeqCst), 1);
+
+        cache.get_or_check(true, check).await.expect("forced check");
+        assert_eq!(checks.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_zero_ttl_checks_every_time() {
+        let cache = HealthCache::new(Duration::ZERO);
+        let checks = AtomicU32::new(0);
+        let check = || async {
+            checks.fetch_add(1, Ordering::SeqCst);
+            Ok(details())
+        };
+
+        cache.get_or_check(false, check).await.expect("first check");
+        cache
+            .get_or_check(false, check)
+            .await
+            .expect("second check");
+        assert_eq!(checks.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_full_response_when_details_exposed_or_permitted() {
+        let exposed = HealthSettings {
+            expose_details: true,
+            ..Default::default()
+        };
+        assert!(details_visible(&exposed, None));
+        assert!(details_visible(
+            &HealthSettings::default(),
+            Some(&user(&[HEALTH_DETAILS_PERMISSION]))
+        ));
+
+        let body = response(details_visible(&exposed, None).then(details));
+        assert_eq!(body["details"]["system"]["cpu_usage"], 10.0);
+    }
+
+    #[test]
+    fn test_unreachable_optional_dependency_degrades_readiness() {
+        let settings = HealthSettings::default();
+        assert_eq!(
+            readiness_status(&details(), &settings),
+            HealthStatus::Healthy
+        );
+
+        // RabbitMQ never connected at startup, so it is absent from the state
+        let mut without_broker = details();
+        without_broker.message_broker = ComponentHealth {
+            status: HealthStatus::Unhealthy,
+            latency_ms: 0,
+            message: Some("MessageBroker not configured".to_string()),
+        };
+        assert_eq!(
+            readiness_status(&without_broker, &settings),
+            HealthStatus::Degraded
+        );
+
+        let mut without_database = details();
+        without_database.tenant_service.status = HealthStatus::Unhealthy;
+        assert_eq!(
+            readiness_status(&without_database, &settings),
+            HealthStatus::Unhealthy
+        );
+    }
+
+    #[test]
+    fn test_only_critical_failures_fail_readiness() {
+        let event_store_down = || {
+            let mut details = details();
+            details.event_store = ComponentHealth {
+                status: HealthStatus::Unhealthy,
+                latency_ms: 0,
+                message: Some("connection refused".to_string()),
+            };
+            details
+        };
+
+        let settings = HealthSettings::default();
+        assert_eq!(
+            readiness_status(&event_store_down(), &settings),
+            HealthStatus::Degraded
+        );
+        assert!(functional_readiness(&event_store_down(), &settings).tenant_crud);
+
+        let mut database_down = details();
+        database_down.tenant_service.status = HealthStatus::Unhealthy;
+        assert_eq!(
+            readiness_status(&database_down, &settings),
+            HealthStatus::Unhealthy
+        );
+
+        // Deployments that can't work without EventStore mark it critical
+        let mut strict = HealthSettings::default();
+        strict.critical.event_store = true;
+        assert_eq!(
+            readiness_status(&event_store_down(), &strict),
+            HealthStatus::Unhealthy
+        );
+    }
+
+    #[test]
+    fn test_disconnected_worker_keeps_crud_ready() {
+        let settings = HealthSettings::default();
+        let subscriptions = [
+            SubscriptionStatus {
+                name: "audit-log".to_string(),
+                position: 7,
+                lag: 0,
+                last_error: None,
+                connected: true,
+            },
+            SubscriptionStatus {
+                name: "tenant-projection".to_string(),
+                position: 40,
+                lag: 2,
+                last_error: Some("connection refused".to_string()),
+                connected: false,
+            },
+        ];
+
+        let mut worker_down = details();
+        worker_down.event_processing = event_processing_health(&subscriptions);
+        assert_eq!(worker_down.event_processing.status, HealthStatus::Unhealthy);
+        assert_eq!(
+            worker_down.event_processing.message.as_deref(),
+            Some("Subscriptions not connected: tenant-projection")
+        );
+
+        // EventStore itself still answers the ping
+        assert_eq!(worker_down.event_store.status, HealthStatus::Healthy);
+        assert_eq!(
+            readiness_status(&worker_down, &settings),
+            HealthStatus::Degraded
+        );
+        assert_eq!(
+            functional_readiness(&worker_down, &settings),
+            FunctionalReadiness {
+                tenant_crud: true,
+                event_processing: false,
+            }
+        );
+        assert_eq!(
+            functional_readiness(&details(), &settings),
+            FunctionalReadiness {
+                tenant_crud: true,
+                event_processing: true,
+            }
+        );
+    }
+
+    /// Keycloak settings of the `acci` realm at `url`
+    fn keycloak(url: String) -> KeycloakConfig {
+        KeycloakConfig {
+            url,
+            ..crate::common::config::AppConfig::default().keycloak
+        }
+    }
+
+    #[tokio::test]
+    async fn test_keycloak_probe_healthy() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/realms/acci/.well-known/openid-configuration"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(
+                r#"{"issuer":"http://localhost/realms/acci"}"#,
+                "application/json",
+            ))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let health = keycloak_health(
+            &reqwest::Client::new(),
+            &keycloak(server.uri()),
+            Duration::from_secs(1),
+        )
+        .await;
+        assert_eq!(health.status, HealthStatus::Healthy);
+        assert_eq!(health.message, None);
+    }
+
+    #[tokio::test]
+    async fn test_keycloak_probe_unreachable() {
+        let client = reqwest::Client::new();
+
+        // Answers, but too slowly
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(5)))
+            .mount(&server)
+            .await;
+        let health =
+            keycloak_health(&client, &keycloak(server.uri()), Duration::from_millis(50)).await;
+        assert_eq!(health.status, HealthStatus::Unhealthy);
+        assert!(health.message.is_some());
+
+        // Gone altogether
+        let uri = server.uri();
+        drop(server);
+        let health = keycloak_health(&client, &keycloak(uri), Duration::from_secs(1)).await;
+        assert_eq!(health.status, HealthStatus::Unhealthy);
+
+        // A Keycloak outage only degrades readiness unless marked critical
+        let mut keycloak_down = details();
+        keycloak_down.keycloak = health;
+        let mut settings = HealthSettings::default();
+        assert_eq!(
+            readiness_status(&keycloak_down, &settings),
+            HealthStatus::Degraded
+        );
+        settings.critical.keycloak = true;
+        assert_eq!(
+            readiness_status(&keycloak_down, &settings),
+            HealthStatus::Unhealthy
+        );
+    }
+}
@@ -1,17 +1,59 @@
+pub mod admin;
+pub mod audit;
 pub mod auth;
 pub mod health;
 pub mod metrics;
 pub mod not_found;
+pub mod openapi;
 pub mod tenant;
+pub mod tenant_data;
+pub mod user;
+pub mod webhook;
 
 use axum::Router;
 
+use crate::common::config::{get_run_mode, RouteSettings};
 use crate::infrastructure::state::AppState;
 
 #[allow(dead_code)]
-pub fn api_routes() -> Router<AppState> {
+pub fn api_routes(routes: &RouteSettings) -> Router<AppState> {
     Router::new()
         .merge(health::health_routes())
-        .merge(tenant::tenant_routes())
+        .merge(service_routes(routes))
+}
+
+/// Every route but the health probes, without the groups disabled in
+/// `routes`
+pub fn service_routes(routes: &RouteSettings) -> Router<AppState> {
+    let mut router = Router::new()
         .merge(metrics::metrics_routes())
+        .merge(openapi::openapi_routes())
+        .merge(user::user_routes())
+        .merge(audit::audit_routes());
+
+    router = if routes.tenant_write {
+        router
+            .merge(tenant::tenant_routes())
+            .merge(tenant::tenant_write_routes())
+    } else {
+        // `POST /tenants` would otherwise answer 405 from the read route
+        // and give away that writes exist
+        router.merge(tenant::tenant_routes().method_not_allowed_fallback(not_found::not_found))
+    };
+    if routes.tenant_write {
+        router = router.merge(webhook::webhook_routes());
+    }
+    if routes.tenant_data {
+        router = router.merge(tenant_data::tenant_data_routes());
+    }
+    if routes.user_write {
+        router = router.merge(user::user_write_routes());
+    }
+    if routes.admin {
+        router = router.merge(admin::admin_routes());
+    }
+    if routes.i18n_debug_enabled(&get_run_mode()) {
+        router = router.merge(admin::i18n_debug_routes());
+    }
+    router
 }
@@ -0,0 +1,211 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+};
+
+use axum::{
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use once_cell::sync::Lazy;
+use serde_json::{json, Value};
+
+use crate::infrastructure::state::AppState;
+
+/// The spec only changes between builds, so it is rendered once and reused
+static OPENAPI_SPEC: Lazy<CachedDocument> = Lazy::new(|| CachedDocument::new(&openapi_document()));
+
+pub fn openapi_routes() -> Router<AppState> {
+    Router::new().route("/openapi.json", get(openapi_spec))
+}
+
+/// A pre-rendered JSON document served with a strong ETag
+pub struct CachedDocument {
+    body: String,
+    etag: String,
+}
+
+impl CachedDocument {
+    pub fn new(document: &Value) -> Self {
+        let body = document.to_string();
+        let mut hasher = DefaultHasher::new();
+        body.hash(&mut hasher);
+
+        Self {
+            etag: format!("\"{:016x}\"", hasher.finish()),
+            body,
+        }
+    }
+
+    pub fn respond(&self, headers: &HeaderMap) -> Response {
+        let not_modified = headers
+            .get(header::IF_NONE_MATCH)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| {
+                value
+                    .split(',')
+                    .map(str::trim)
+                    .any(|tag| tag == "*" || tag == self.etag)
+            });
+
+        if not_modified {
+            return (
+                StatusCode::NOT_MODIFIED,
+                [(header::ETAG, self.etag.clone())],
+            )
+                .into_response();
+        }
+
+        (
+            [
+                (header::CONTENT_TYPE, "application/json".to_string()),
+                (header::ETAG, self.etag.clone()),
+            ],
+            self.body.clone(),
+        )
+            .into_response()
+    }
+}
+
+async fn openapi_spec(headers: HeaderMap) -> Response {
+    OPENAPI_SPEC.respond(&headers)
+}
+
+#[allow(clippy::disallowed_methods)]
+fn openapi_document() -> Value {
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "ACCI API",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "paths": {
+            "/health": {
+                "get": { "summary": "Service and dependency health", "responses": { "200": { "description": "Healthy or degraded" }, "503": { "description": "Unhealthy" } } }
+            },
+            "/ready": {
+                "get": { "summary": "Readiness to accept traffic", "responses": { "200": { "description": "Ready" }, "503": { "description": "Not ready" } } }
+            },
+            "/metrics": {
+                "get": { "summary": "Prometheus metrics", "responses": { "200": { "description": "Metrics in text format" } } }
+            },
+            "/tenants": {
+                "get": {
+                    "summary": "List tenants",
+                    "parameters": [
+                        { "name": "sort", "in": "query", "schema": { "type": "string", "enum": ["name", "-name", "domain", "-domain", "created_at", "-created_at", "updated_at", "-updated_at"] }, "description": "Field to sort by, `-` prefix for descending; defaults to pagination.default_tenant_sort" },
+                        { "name": "page", "in": "query", "schema": { "type": "integer", "minimum": 1 } },
+                        { "name": "page_size", "in": "query", "schema": { "type": "integer", "minimum": 1 }, "description": "Capped at pagination.max_page_size" }
+                    ],
+                    "responses": { "200": { "description": "Tenant list" }, "400": { "description": "Field can't be sorted by" } }
+                },
+                "post": { "summary": "Create a tenant", "responses": { "201": { "description": "Tenant created" }, "400": { "description": "Validation error" } } }
+            },
+            "/tenants/{id}": {
+                "parameters": [{ "name": "id", "in": "path", "required": true, "schema": { "type": "string", "format": "uuid" } }],
+                "get": { "summary": "Get a tenant", "responses": { "200": { "description": "Tenant" }, "404": { "description": "Not found" } } },
+                "put": {
+                    "summary": "Update a tenant",
+                    "parameters": [{ "name": "If-Unmodified-Since", "in": "header", "schema": { "type": "string" }, "description": "HTTP date; the update is rejected if the tenant changed after it" }],
+                    "responses": { "200": { "description": "Tenant updated" }, "400": { "description": "Validation error, or `is_active: true` for a deactivated tenant, which has to be reactivated" }, "404": { "description": "Not found" }, "412": { "description": "Tenant modified since If-Unmodified-Since" } }
+                },
+                "delete": { "summary": "Delete a tenant", "responses": { "204": { "description": "Tenant deleted" }, "404": { "description": "Not found" } } }
+            },
+            "/tenants/{id}/users": {
+                "parameters": [{ "name": "id", "in": "path", "required": true, "schema": { "type": "string", "format": "uuid" } }],
+                "get": {
+                    "summary": "List the users of a tenant",
+                    "parameters": [
+                        { "name": "sort", "in": "query", "schema": { "type": "string", "enum": ["username", "-username", "email", "-email", "full_name", "-full_name", "created_at", "-created_at", "updated_at", "-updated_at"] }, "description": "Field to sort by, `-` prefix for descending; defaults to pagination.default_user_sort" },
+                        { "name": "page", "in": "query", "schema": { "type": "integer", "minimum": 1 } },
+                        { "name": "page_size", "in": "query", "schema": { "type": "integer", "minimum": 1 }, "description": "Capped at pagination.max_page_size" }
+                    ],
+                    "responses": { "200": { "description": "User list" }, "400": { "description": "Field can't be sorted by" }, "403": { "description": "Missing user:read in the tenant" } }
+                }
+            },
+            "/tenants/{id}/reactivate": {
+                "parameters": [{ "name": "id", "in": "path", "required": true, "schema": { "type": "string", "format": "uuid" } }],
+                "post": { "summary": "Reactivate a deactivated or deleted tenant", "responses": { "200": { "description": "Tenant reactivated" }, "400": { "description": "Validation error, e.g. domain taken" }, "403": { "description": "Missing tenant:reactivate permission" }, "404": { "description": "Not found" } } }
+            },
+            "/tenants/{id}/usage": {
+                "parameters": [{ "name": "id", "in": "path", "required": true, "schema": { "type": "string", "format": "uuid" } }],
+                "get": { "summary": "Active users, storage and the current rate-limit windows against the tenant's limits; storage isn't accounted yet and rate-limit windows are those of the answering instance", "responses": { "200": { "description": "Usage per limit" }, "403": { "description": "Caller isn't a member of the tenant" }, "404": { "description": "Not found" } } }
+            },
+            "/tenants/{id}/audit": {
+                "parameters": [
+                    { "name": "id", "in": "path", "required": true, "schema": { "type": "string", "format": "uuid" } },
+                    { "name": "action", "in": "query", "schema": { "type": "string" } },
+                    { "name": "entity_type", "in": "query", "schema": { "type": "string" } },
+                    { "name": "page", "in": "query", "schema": { "type": "integer", "minimum": 1 } },
+                    { "name": "page_size", "in": "query", "schema": { "type": "integer", "minimum": 1 }, "description": "Capped at pagination.max_page_size" }
+                ],
+                "get": { "summary": "Page through the tenant's audit log, newest entries first", "responses": { "200": { "description": "Audit entries" }, "403": { "description": "Caller isn't a tenant member or platform admin with audit:read, or the tenant has no audit logging" }, "404": { "description": "Not found" } } }
+            },
+            "/tenants/{id}/events": {
+                "parameters": [
+                    { "name": "id", "in": "path", "required": true, "schema": { "type": "string", "format": "uuid" } },
+                    { "name": "from", "in": "query", "schema": { "type": "integer", "minimum": 0 } },
+                    { "name": "count", "in": "query", "schema": { "type": "integer", "minimum": 1 }, "description": "Capped at pagination.max_event_count" }
+                ],
+                "get": { "summary": "Read a window of the tenant's events; `next` is the position of the following window", "responses": { "200": { "description": "Events and next position" }, "403": { "description": "Missing tenant:events permission" } } }
+            },
+            "/tenants/{id}/events/live": {
+                "parameters": [{ "name": "id", "in": "path", "required": true, "schema": { "type": "string", "format": "uuid" } }],
+                "get": { "summary": "WebSocket pushing the tenant's events as they are appended, one JSON message each", "responses": { "101": { "description": "Switched to WebSocket" }, "403": { "description": "Missing tenant:events permission" }, "503": { "description": "live_events.max_subscriptions sockets already open" } } }
+            },
+            "/auth/login": {
+                "get": { "summary": "Start the Keycloak login; sets the CSRF and PKCE cookies", "responses": { "200": { "description": "Authorization URL to redirect to" } } }
+            },
+            "/auth/callback": {
+                "parameters": [
+                    { "name": "code", "in": "query", "required": true, "schema": { "type": "string" } },
+                    { "name": "state", "in": "query", "required": true, "schema": { "type": "string" } }
+                ],
+                "get": { "summary": "Exchange the authorization code for tokens", "responses": { "200": { "description": "Tokens" }, "400": { "description": "Malformed, duplicate or too many flow cookies" }, "401": { "description": "Missing flow cookies, CSRF mismatch or rejected code" }, "502": { "description": "Token endpoint unavailable" } } }
+            },
+            "/auth/logout": {
+                "get": { "summary": "Redirect to the Keycloak logout", "responses": { "303": { "description": "Redirect" } } }
+            },
+            "/openapi.json": {
+                "get": { "summary": "This document", "responses": { "200": { "description": "OpenAPI spec" }, "304": { "description": "Not modified" } } }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[tokio::test]
+    async fn test_etag_is_stable_across_requests() {
+        let first = openapi_spec(HeaderMap::new()).await;
+        let second = openapi_spec(HeaderMap::new()).await;
+
+        assert_eq!(first.status(), StatusCode::OK);
+        assert_eq!(
+            first.headers().get(header::ETAG),
+            second.headers().get(header::ETAG)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_matching_if_none_match_returns_not_modified() {
+        let response = openapi_spec(HeaderMap::new()).await;
+        let etag = response.headers()[header::ETAG].clone();
+
+        let mut headers = HeaderMap::new();
+        headers.insert(header::IF_NONE_MATCH, etag.clone());
+        let response = openapi_spec(headers).await;
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers()[header::ETAG], etag);
+
+        let mut headers = HeaderMap::new();
+        headers.insert(header::IF_NONE_MATCH, HeaderValue::from_static("\"stale\""));
+        let response = openapi_spec(headers).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
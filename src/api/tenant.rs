@@ -1,19 +1,45 @@
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    extract::{Extension, Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
     response::Json,
-    routing::get,
+    routing::{get, post, put},
     Router,
 };
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::Instant;
+use tracing::debug;
 use uuid::Uuid;
 
 use crate::{
-    common::error::AppError,
-    domain::tenant::{Tenant, TenantFeatures, TenantSettings},
+    api::{audit::record_change, tenant_data::require_tenant_permission},
+    common::{
+        error::AppError,
+        json::LimitedJson,
+        middleware::auth::UserInfo,
+        pagination::{PaginationParams, Sort},
+    },
+    domain::tenant::{
+        RateLimitBucket, Tenant, TenantFeatures, TenantSettings, TenantSort,
+        TENANT_SETTINGS_VERSION,
+    },
     infrastructure::state::AppState,
 };
 
+/// Permission required to bring back a deactivated or deleted tenant
+const TENANT_REACTIVATE_PERMISSION: &str = "tenant:reactivate";
+
+/// `?sort=&page=&page_size=`; the pagination fields aren't flattened from
+/// `PaginationParams`, as flattening breaks parsing numbers from the query
+/// string
+#[derive(Debug, Default, Deserialize)]
+pub struct TenantListParams {
+    pub sort: Option<String>,
+    pub page: Option<u64>,
+    pub page_size: Option<u64>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct CreateTenantDto {
     pub name: String,
@@ -36,6 +62,7 @@ pub struct TenantResponse {
     pub domain: String,
     pub is_active: bool,
     pub settings: TenantSettings,
+    pub updated_at: Option<DateTime<Utc>>,
 }
 
 impl From<Tenant> for TenantResponse {
@@ -46,25 +73,120 @@ impl From<Tenant> for TenantResponse {
             domain: tenant.domain,
             is_active: tenant.is_active,
             settings: tenant.settings,
+            updated_at: tenant.updated_at,
         }
     }
 }
 
+/// Consumption of one limit
+#[derive(Debug, Serialize)]
+pub struct Quota {
+    pub used: i64,
+    pub limit: i64,
+}
+
+/// Storage consumption. Storage isn't accounted yet, so `used` is `null`
+/// and `accounted` is `false` until a usage source exists.
+#[derive(Debug, Serialize)]
+pub struct StorageQuota {
+    pub used: Option<i64>,
+    pub accounted: bool,
+    pub limit: i64,
+}
+
+/// A tenant's current usage against the limits in its settings
+#[derive(Debug, Serialize)]
+pub struct TenantUsageResponse {
+    /// Active users against `max_users`
+    pub users: Quota,
+    /// Bytes stored against `storage_limit`
+    pub storage: StorageQuota,
+    /// Requests in the current one-minute window of each bucket
+    pub rate_limits: BTreeMap<RateLimitBucket, Quota>,
+}
+
+/// Rejects callers that don't belong to the tenant
+fn require_membership(user: Option<Extension<UserInfo>>, tenant_id: Uuid) -> Result<(), AppError> {
+    let member = user.is_some_and(|user| {
+        user.tenant_id
+            .as_deref()
+            .and_then(|id| Uuid::parse_str(id).ok())
+            == Some(tenant_id)
+    });
+    if member {
+        Ok(())
+    } else {
+        Err(AppError::authorization(
+            "Only members of the tenant can see its usage",
+        ))
+    }
+}
+
+/// Parses `If-Unmodified-Since`. A value that isn't a valid HTTP date is
+/// ignored, as RFC 9110 requires.
+fn if_unmodified_since(headers: &HeaderMap) -> Option<DateTime<Utc>> {
+    let value = headers.get(header::IF_UNMODIFIED_SINCE)?.to_str().ok()?;
+    match DateTime::parse_from_rfc2822(value) {
+        Ok(since) => Some(since.with_timezone(&Utc)),
+        Err(e) => {
+            debug!("Ignoring invalid If-Unmodified-Since {:?}: {}", value, e);
+            None
+        },
+    }
+}
+
+/// Rejects the update when the tenant changed after `since`. HTTP dates only
+/// have whole seconds, so a change within the same second still passes.
+fn check_unmodified_since(tenant: &Tenant, since: DateTime<Utc>) -> Result<(), AppError> {
+    match tenant.updated_at {
+        Some(updated_at) if updated_at.timestamp() > since.timestamp() => {
+            Err(AppError::precondition_failed(format!(
+                "Tenant was modified at {}",
+                updated_at.to_rfc2822()
+            )))
+        },
+        _ => Ok(()),
+    }
+}
+
 pub fn tenant_routes() -> Router<AppState> {
     Router::new()
-        .route("/tenants", get(list_tenants).post(create_tenant))
-        .route(
-            "/tenants/{id}",
-            get(get_tenant).put(update_tenant).delete(delete_tenant),
-        )
+        .route("/tenants", get(list_tenants))
+        .route("/tenants/{id}", get(get_tenant))
+        .route("/tenants/{id}/usage", get(tenant_usage))
+}
+
+/// Routes changing tenants, mounted unless `routes.tenant_write` is off
+pub fn tenant_write_routes() -> Router<AppState> {
+    Router::new()
+        .route("/tenants", post(create_tenant))
+        .route("/tenants/{id}", put(update_tenant).delete(delete_tenant))
+        .route("/tenants/{id}/reactivate", post(reactivate_tenant))
 }
 
 #[axum::debug_handler]
 async fn list_tenants(
     State(state): State<AppState>,
-) -> Result<Json<Vec<TenantResponse>>, AppError> {
-    let tenants = state.tenant_service.list().await?;
-    Ok(Json(tenants.into_iter().map(Into::into).collect()))
+    Query(params): Query<TenantListParams>,
+) -> Result<(HeaderMap, Json<Vec<TenantResponse>>), AppError> {
+    let pagination = PaginationParams {
+        page: params.page,
+        page_size: params.page_size,
+    }
+    .resolve(&state.config.pagination);
+    let sort: TenantSort = match &params.sort {
+        Some(sort) => Sort::parse(sort)?,
+        None => Sort::parse(&state.config.pagination.default_tenant_sort)?,
+    };
+    let tenants = state.tenant_service.list(pagination, sort).await?;
+
+    // Only a validated field name ends up in the links
+    let path = match &params.sort {
+        Some(sort) => format!("/tenants?sort={}", sort),
+        None => "/tenants".to_string(),
+    };
+    let links = pagination.links(&state.config.server, &path, tenants.len());
+    Ok((links, Json(tenants.into_iter().map(Into::into).collect())))
 }
 
 #[axum::debug_handler]
@@ -79,7 +201,8 @@ async fn get_tenant(
 #[axum::debug_handler]
 async fn create_tenant(
     State(state): State<AppState>,
-    Json(payload): Json<CreateTenantDto>,
+    user: Option<Extension<UserInfo>>,
+    LimitedJson(payload): LimitedJson<CreateTenantDto>,
 ) -> Result<(StatusCode, Json<TenantResponse>), AppError> {
     let settings = payload.settings.unwrap_or(TenantSettings {
         max_users: 10,
@@ -91,18 +214,34 @@ async fn create_tenant(
             api_access: true,
             audit_logging: false,
         },
+        read_only: false,
+        allowed_auth_methods: Default::default(),
+        rate_limits: Default::default(),
+        settings_version: TENANT_SETTINGS_VERSION,
     });
 
-    let tenant = Tenant {
+    let mut tenant = Tenant {
         id: Uuid::new_v4(),
         name: payload.name,
         domain: payload.domain,
         is_active: true,
         settings,
+        updated_at: None,
     };
 
+    tenant.normalize();
     tenant.validate()?;
     let created_tenant = state.tenant_service.create(tenant).await?;
+    record_change(
+        &state,
+        user.as_deref(),
+        created_tenant.id,
+        "tenant.create",
+        "tenant",
+        created_tenant.id,
+        None,
+    )
+    .await;
     Ok((StatusCode::CREATED, Json(created_tenant.into())))
 }
 
@@ -110,10 +249,36 @@ async fn create_tenant(
 async fn update_tenant(
     State(state): State<AppState>,
     Path(id): Path<Uuid>,
-    Json(payload): Json<UpdateTenantDto>,
+    headers: HeaderMap,
+    user: Option<Extension<UserInfo>>,
+    LimitedJson(payload): LimitedJson<UpdateTenantDto>,
 ) -> Result<Json<TenantResponse>, AppError> {
     let mut tenant = state.tenant_service.find_by_id(&id.to_string()).await?;
+    // Reactivation needs `tenant:reactivate` and checks the domain is still
+    // free, so it only goes through its own route
+    if payload.is_active == Some(true) && !tenant.is_active {
+        return Err(AppError::validation(format!(
+            "Tenant {} is deactivated; reactivate it with POST /tenants/{}/reactivate",
+            id, id
+        )));
+    }
+    let conditional = match if_unmodified_since(&headers) {
+        Some(since) => {
+            check_unmodified_since(&tenant, since)?;
+            true
+        },
+        None => false,
+    };
 
+    let changed: Vec<&str> = [
+        ("name", payload.name.is_some()),
+        ("domain", payload.domain.is_some()),
+        ("is_active", payload.is_active.is_some()),
+        ("settings", payload.settings.is_some()),
+    ]
+    .into_iter()
+    .filter_map(|(field, set)| set.then_some(field))
+    .collect();
     if let Some(name) = payload.name {
         tenant.name = name;
     }
@@ -127,8 +292,27 @@ async fn update_tenant(
         tenant.settings = settings;
     }
 
+    tenant.normalize();
     tenant.validate()?;
-    let updated_tenant = state.tenant_service.update(tenant).await?;
+    // The check above only saw the tenant as read; the conditional update
+    // also catches a change made since
+    let updated_tenant = if conditional {
+        state.tenant_service.update_unmodified(tenant).await?
+    } else {
+        state.tenant_service.update(tenant).await?
+    };
+    let mut details = serde_json::Map::new();
+    details.insert("fields".to_string(), changed.into());
+    record_change(
+        &state,
+        user.as_deref(),
+        id,
+        "tenant.update",
+        "tenant",
+        id,
+        Some(details.into()),
+    )
+    .await;
     Ok(Json(updated_tenant.into()))
 }
 
@@ -137,14 +321,87 @@ async fn delete_tenant(
     State(state): State<AppState>,
     Path(id): Path<Uuid>,
 ) -> Result<StatusCode, AppError> {
+    // Not audited: the tenant's audit log is deleted along with it
     state.tenant_service.delete(&id.to_string()).await?;
     Ok(StatusCode::NO_CONTENT)
 }
 
+#[axum::debug_handler]
+async fn reactivate_tenant(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    user: Option<Extension<UserInfo>>,
+) -> Result<Json<TenantResponse>, AppError> {
+    require_tenant_permission(user.clone(), id, TENANT_REACTIVATE_PERMISSION)?;
+    let tenant = state.tenant_service.reactivate(&id.to_string()).await?;
+    record_change(
+        &state,
+        user.as_deref(),
+        id,
+        "tenant.reactivate",
+        "tenant",
+        id,
+        None,
+    )
+    .await;
+    Ok(Json(tenant.into()))
+}
+
+/// Usage of a tenant against its limits. Rate-limit windows are counted in
+/// each instance's in-memory cache, so behind several replicas the reported
+/// windows are those of the replica that answers.
+#[axum::debug_handler]
+async fn tenant_usage(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    user: Option<Extension<UserInfo>>,
+) -> Result<Json<TenantUsageResponse>, AppError> {
+    require_membership(user, id)?;
+    let tenant = state.tenant_service.find_by_id(&id.to_string()).await?;
+    let users = state.user_service.list_by_tenant(&id).await?;
+    let active_users = users.iter().filter(|user| user.is_active).count();
+
+    let now = Instant::now();
+    let mut rate_limits = BTreeMap::new();
+    for bucket in [RateLimitBucket::Read, RateLimitBucket::Write] {
+        let used = state.rate_limiter.usage(&id.to_string(), bucket, now).await;
+        let quota = Quota {
+            used: used.into(),
+            limit: tenant.settings.rate_limit(bucket).into(),
+        };
+        rate_limits.insert(bucket, quota);
+    }
+
+    Ok(Json(TenantUsageResponse {
+        users: Quota {
+            used: active_users as i64,
+            limit: tenant.settings.max_users.into(),
+        },
+        storage: StorageQuota {
+            used: None,
+            accounted: false,
+            limit: tenant.settings.storage_limit,
+        },
+        rate_limits,
+    }))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::common::i18n::{I18nManager, SupportedLanguage, TestResourceProvider};
+    use crate::common::middleware::rate_limit::TenantRateLimiter;
     use crate::domain::tenant::TenantFeatures;
+    use crate::domain::user::UserSettings;
+    use crate::infrastructure::{
+        database::entities::{tenant, user},
+        services::{tenant_service::TenantServiceImpl, user_service::UserServiceImpl},
+    };
+    use axum::{body::Body, http::Request};
+    use chrono::Duration;
+    use sea_orm::{DatabaseBackend, MockDatabase};
+    use std::sync::Arc;
+    use tower::ServiceExt;
 
     fn create_test_tenant() -> Tenant {
         Tenant {
@@ -162,7 +419,12 @@ mod tests {
                     api_access: true,
                     audit_logging: true,
                 },
+                read_only: false,
+                allowed_auth_methods: Default::default(),
+                rate_limits: Default::default(),
+                settings_version: TENANT_SETTINGS_VERSION,
             },
+            updated_at: None,
         }
     }
 
@@ -177,4 +439,246 @@ mod tests {
         assert_eq!(response.is_active, tenant.is_active);
         assert_eq!(response.settings.max_users, tenant.settings.max_users);
     }
+
+    fn stored_model(tenant: &Tenant, updated_at: DateTime<Utc>) -> tenant::Model {
+        tenant::Model {
+            id: tenant.id,
+            name: tenant.name.clone(),
+            domain: tenant.domain.clone(),
+            is_active: tenant.is_active,
+            settings: serde_json::to_value(&tenant.settings).expect("serializable settings"),
+            created_at: updated_at.naive_utc(),
+            updated_at: updated_at.naive_utc(),
+            deleted_at: None,
+        }
+    }
+
+    async fn put_tenant(db: MockDatabase, id: Uuid, since: DateTime<Utc>) -> StatusCode {
+        let i18n = I18nManager::new(SupportedLanguage::En, Arc::new(TestResourceProvider::new()))
+            .await
+            .expect("i18n manager");
+        let state = AppState::for_test(
+            Arc::new(TenantServiceImpl::new(Arc::new(db.into_connection()))),
+            Arc::new(i18n),
+        );
+
+        let request = Request::builder()
+            .method("PUT")
+            .uri(format!("/tenants/{}", id))
+            .header(header::CONTENT_TYPE, "application/json")
+            .header(header::IF_UNMODIFIED_SINCE, since.to_rfc2822())
+            .body(Body::from(r#"{"name":"Renamed Tenant"}"#))
+            .expect("valid request");
+        tenant_write_routes()
+            .with_state(state)
+            .oneshot(request)
+            .await
+            .expect("response")
+            .status()
+    }
+
+    #[tokio::test]
+    async fn test_update_if_unmodified_since_passes() {
+        let tenant = create_test_tenant();
+        let updated_at = Utc::now() - Duration::hours(1);
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![stored_model(&tenant, updated_at)]])
+            // No other tenant uses the domain
+            .append_query_results::<tenant::Model, _, _>(vec![vec![]])
+            .append_query_results(vec![vec![stored_model(&tenant, Utc::now())]]);
+
+        // The header is sent with the stored time, so it matches exactly
+        assert_eq!(put_tenant(db, tenant.id, updated_at).await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_update_racing_another_change_fails_precondition() {
+        let tenant = create_test_tenant();
+        let updated_at = Utc::now() - Duration::hours(1);
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![stored_model(&tenant, updated_at)]])
+            // No other tenant uses the domain
+            .append_query_results::<tenant::Model, _, _>(vec![vec![]])
+            // Another request changed the tenant after it was read, so the
+            // conditional update matches no row
+            .append_query_results::<tenant::Model, _, _>(vec![vec![]]);
+
+        assert_eq!(
+            put_tenant(db, tenant.id, updated_at).await,
+            StatusCode::PRECONDITION_FAILED
+        );
+    }
+
+    #[tokio::test]
+    async fn test_update_cannot_reactivate() {
+        let mut tenant = create_test_tenant();
+        tenant.is_active = false;
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![stored_model(&tenant, Utc::now())]]);
+        let i18n = I18nManager::new(SupportedLanguage::En, Arc::new(TestResourceProvider::new()))
+            .await
+            .expect("i18n manager");
+        let state = AppState::for_test(
+            Arc::new(TenantServiceImpl::new(Arc::new(db.into_connection()))),
+            Arc::new(i18n),
+        );
+
+        let request = Request::builder()
+            .method("PUT")
+            .uri(format!("/tenants/{}", tenant.id))
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(r#"{"is_active":true}"#))
+            .expect("valid request");
+        let response = tenant_write_routes()
+            .with_state(state)
+            .oneshot(request)
+            .await
+            .expect("response");
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_list_rejects_unsortable_field() {
+        let i18n = I18nManager::new(SupportedLanguage::En, Arc::new(TestResourceProvider::new()))
+            .await
+            .expect("i18n manager");
+        let db = MockDatabase::new(DatabaseBackend::Postgres);
+        let state = AppState::for_test(
+            Arc::new(TenantServiceImpl::new(Arc::new(db.into_connection()))),
+            Arc::new(i18n),
+        );
+
+        let request = Request::builder()
+            .uri("/tenants?sort=-settings")
+            .body(Body::empty())
+            .expect("valid request");
+        let response = tenant_routes()
+            .with_state(state)
+            .oneshot(request)
+            .await
+            .expect("response");
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_update_modified_since_fails_precondition() {
+        let tenant = create_test_tenant();
+        let updated_at = Utc::now();
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![stored_model(&tenant, updated_at)]]);
+
+        assert_eq!(
+            put_tenant(db, tenant.id, updated_at - Duration::minutes(5)).await,
+            StatusCode::PRECONDITION_FAILED
+        );
+    }
+
+    fn member_of(tenant_id: Uuid) -> UserInfo {
+        UserInfo {
+            sub: "member".to_string(),
+            preferred_username: "member".to_string(),
+            email: None,
+            roles: vec![],
+            tenant_id: Some(tenant_id.to_string()),
+            permissions: Default::default(),
+            auth_methods: vec![],
+        }
+    }
+
+    fn stored_user(tenant_id: Uuid, is_active: bool) -> user::Model {
+        user::Model {
+            id: Uuid::new_v4(),
+            tenant_id,
+            email: format!("{}@example.com", Uuid::new_v4()),
+            username: "user".to_string(),
+            full_name: "Some User".to_string(),
+            is_active,
+            role: user::Role::User,
+            settings: serde_json::to_value(UserSettings::default()).expect("serializable settings"),
+            created_at: Utc::now().into(),
+            updated_at: Utc::now().into(),
+            last_login_at: None,
+        }
+    }
+
+    async fn get_usage(
+        tenant: &Tenant,
+        caller: UserInfo,
+        rate_limiter: TenantRateLimiter,
+    ) -> (StatusCode, serde_json::Value) {
+        let i18n = I18nManager::new(SupportedLanguage::En, Arc::new(TestResourceProvider::new()))
+            .await
+            .expect("i18n manager");
+        let tenants = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![stored_model(tenant, Utc::now())]]);
+        let users = MockDatabase::new(DatabaseBackend::Postgres).append_query_results(vec![vec![
+            stored_user(tenant.id, true),
+            stored_user(tenant.id, true),
+            stored_user(tenant.id, false),
+        ]]);
+        let mut state = AppState::for_test(
+            Arc::new(TenantServiceImpl::new(Arc::new(tenants.into_connection()))),
+            Arc::new(i18n),
+        );
+        state.user_service = Arc::new(UserServiceImpl::new(Arc::new(users.into_connection())));
+        state.rate_limiter = rate_limiter;
+
+        let request = Request::builder()
+            .uri(format!("/tenants/{}/usage", tenant.id))
+            .body(Body::empty())
+            .expect("valid request");
+        let response = tenant_routes()
+            .with_state(state)
+            .layer(Extension(caller))
+            .oneshot(request)
+            .await
+            .expect("response");
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("response body");
+        (status, serde_json::from_slice(&body).expect("JSON body"))
+    }
+
+    #[tokio::test]
+    async fn test_usage_reports_seeded_state() {
+        let mut tenant = create_test_tenant();
+        tenant.settings.rate_limits = BTreeMap::from([(RateLimitBucket::Write, 10)]);
+        let rate_limiter = TenantRateLimiter::default();
+        let (id, now) = (tenant.id.to_string(), Instant::now());
+        for _ in 0..3 {
+            let _ = rate_limiter
+                .acquire(&id, RateLimitBucket::Read, 1000, now)
+                .await;
+        }
+        let _ = rate_limiter
+            .acquire(&id, RateLimitBucket::Write, 10, now)
+            .await;
+        let (status, usage) = get_usage(&tenant, member_of(tenant.id), rate_limiter).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(usage["users"]["used"], 2);
+        assert_eq!(usage["users"]["limit"], 100);
+        assert_eq!(usage["storage"]["used"], serde_json::Value::Null);
+        assert_eq!(usage["storage"]["accounted"], false);
+        assert_eq!(usage["storage"]["limit"], 1024 * 1024 * 1024);
+        assert_eq!(usage["rate_limits"]["read"]["used"], 3);
+        assert_eq!(usage["rate_limits"]["read"]["limit"], 1000);
+        assert_eq!(usage["rate_limits"]["write"]["used"], 1);
+        assert_eq!(usage["rate_limits"]["write"]["limit"], 10);
+    }
+
+    #[tokio::test]
+    async fn test_usage_is_only_shown_to_members() {
+        let tenant = create_test_tenant();
+        let (status, _) = get_usage(
+            &tenant,
+            member_of(Uuid::new_v4()),
+            TenantRateLimiter::default(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+    }
 }
@@ -0,0 +1,822 @@
+use axum::{
+    body::{Body, Bytes},
+    extract::{
+        ws::{rejection::WebSocketUpgradeRejection, Message, WebSocket, WebSocketUpgrade},
+        Extension, Path, Query, State,
+    },
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
+    routing::{get, post},
+    Router,
+};
+use chrono::{DateTime, Utc};
+use event_store::{RecordedEvent, StreamName};
+use futures::{future, stream, Stream, StreamExt};
+use metrics::counter;
+use serde::Serialize;
+use tracing::{info, instrument, warn};
+use uuid::Uuid;
+
+use crate::api::audit::record_change;
+use crate::common::{
+    error::{AppError, AppResult},
+    middleware::auth::UserInfo,
+    pagination::EventWindowParams,
+};
+use crate::domain::{tenant::Tenant, user::User};
+use crate::infrastructure::{live_events::LiveSubscription, state::AppState};
+
+/// Permission required for data-portability exports
+const TENANT_EXPORT_PERMISSION: &str = "tenant:export";
+/// Permission required to erase a tenant's data
+const TENANT_ERASE_PERMISSION: &str = "tenant:erase";
+/// Permission required to read a tenant's event stream
+const TENANT_EVENTS_PERMISSION: &str = "tenant:events";
+/// Permission that lets platform operators act on tenants they don't belong to
+pub(super) const PLATFORM_ADMIN_PERMISSION: &str = "platform:admin";
+/// Media type of the line-delimited export, one JSON document per line
+const NDJSON_CONTENT_TYPE: &str = "application/x-ndjson";
+
+pub fn tenant_data_routes() -> Router<AppState> {
+    Router::new()
+        .route("/tenants/{id}/export-data", get(export_tenant_data))
+        .route("/tenants/{id}/erase", post(erase_tenant_data))
+        .route("/tenants/{id}/events", get(list_tenant_events))
+        .route("/tenants/{id}/events/live", get(live_tenant_events))
+}
+
+/// One window of a tenant's events; `next` is the `from` of the following
+/// window and absent once the end of the stream was reached
+#[derive(Debug, Serialize)]
+struct EventPage {
+    events: Vec<RecordedEvent>,
+    next: Option<u64>,
+}
+
+/// Everything in the export except the events, which are streamed after it
+#[derive(Debug, Serialize)]
+struct ExportHeader<'a> {
+    exported_at: DateTime<Utc>,
+    tenant: &'a Tenant,
+    users: &'a [User],
+}
+
+pub(super) fn require_permission(
+    user: Option<Extension<UserInfo>>,
+    permission: &str,
+) -> AppResult<()> {
+    if user.is_some_and(|user| user.has_permission(permission)) {
+        Ok(())
+    } else {
+        Err(AppError::authorization(format!(
+            "This operation requires the {} permission",
+            permission
+        )))
+    }
+}
+
+/// Like `require_permission`, but also rejects callers of another tenant
+/// unless they have the `platform:admin` permission, so a tenant's token or
+/// API key can't reach other tenants' data
+pub(super) fn require_tenant_permission(
+    user: Option<Extension<UserInfo>>,
+    tenant_id: Uuid,
+    permission: &str,
+) -> AppResult<()> {
+    let in_tenant = user.as_ref().is_some_and(|user| {
+        user.has_permission(PLATFORM_ADMIN_PERMISSION)
+            || user
+                .tenant_id
+                .as_deref()
+                .and_then(|id| Uuid::parse_str(id).ok())
+                == Some(tenant_id)
+    });
+    if !in_tenant {
+        return Err(AppError::authorization(
+            "Only members of the tenant can perform this operation",
+        ));
+    }
+    require_permission(user, permission)
+}
+
+#[instrument(skip(state, user))]
+async fn export_tenant_data(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    headers: HeaderMap,
+    user: Option<Extension<UserInfo>>,
+) -> Result<Response, AppError> {
+    require_tenant_permission(user, id, TENANT_EXPORT_PERMISSION)?;
+
+    // Fail before anything is sent rather than emit an export without events
+    let event_store = state
+        .event_store
+        .clone()
+        .ok_or_else(|| AppError::internal("EventStore is unavailable"))?;
+
+    let tenant = state.tenant_service.find_by_id(&id.to_string()).await?;
+    let users = state.user_service.list_by_tenant(&id).await?;
+    let events = event_store.read_pages(StreamName::tenant_stream(id));
+
+    let (content_type, extension, body) = if accepts_ndjson(&headers) {
+        (
+            NDJSON_CONTENT_TYPE,
+            "ndjson",
+            export_ndjson_body(&tenant, &users, events)?,
+        )
+    } else {
+        (
+            "application/json",
+            "json",
+            export_body(&tenant, &users, events)?,
+        )
+    };
+
+    info!("Exporting data of tenant {}", id);
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!(
+                    "attachment; filename=\"tenant-{}-export.{}\"",
+                    id, extension
+                ),
+            ),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+/// Whether the client asked for the NDJSON export with
+/// `Accept: application/x-ndjson`
+fn accepts_ndjson(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(header::ACCEPT)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|media_type| {
+            media_type
+                .split(';')
+                .next()
+                .is_some_and(|t| t.trim().eq_ignore_ascii_case(NDJSON_CONTENT_TYPE))
+        })
+}
+
+#[instrument(skip(state, user))]
+async fn erase_tenant_data(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    user: Option<Extension<UserInfo>>,
+) -> Result<StatusCode, AppError> {
+    require_tenant_permission(user.clone(), id, TENANT_ERASE_PERMISSION)?;
+
+    let event_store = state
+        .event_store
+        .clone()
+        .ok_or_else(|| AppError::internal("EventStore is unavailable"))?;
+
+    let erased_users = state.tenant_service.erase(&id.to_string()).await?;
+    for stream_name in erased_streams(id, &erased_users) {
+        event_store.tombstone(&stream_name).await?;
+    }
+    record_change(
+        &state,
+        user.as_deref(),
+        id,
+        "tenant.erase",
+        "tenant",
+        id,
+        None,
+    )
+    .await;
+
+    info!("Erased data of tenant {}", id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// The tenant's stream and the streams of its users, which all hold the
+/// tenant's personal data
+fn erased_streams(tenant_id: Uuid, user_ids: &[Uuid]) -> Vec<String> {
+    std::iter::once(StreamName::tenant_stream(tenant_id))
+        .chain(
+            user_ids
+                .iter()
+                .map(|user_id| StreamName::user_stream(tenant_id, *user_id)),
+        )
+        .collect()
+}
+
+/// Reads `?count=` events from position `?from=`, with `count` clamped to
+/// `pagination.max_event_count` so a client can't ask for the whole stream
+#[instrument(skip(state, user))]
+async fn list_tenant_events(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(params): Query<EventWindowParams>,
+    user: Option<Extension<UserInfo>>,
+) -> Result<(HeaderMap, Json<EventPage>), AppError> {
+    require_tenant_permission(user, id, TENANT_EVENTS_PERMISSION)?;
+
+    let event_store = state
+        .event_store
+        .clone()
+        .ok_or_else(|| AppError::internal("EventStore is unavailable"))?;
+
+    let window = params.resolve(&state.config.pagination);
+    let events = event_store
+        .read_window(&StreamName::tenant_stream(id), window.from, window.count)
+        .await?;
+    let links = window.links(
+        &state.config.server,
+        &format!("/tenants/{}/events", id),
+        events.len(),
+    );
+    let next = window.next(events.len());
+    Ok((links, Json(EventPage { events, next })))
+}
+
+/// Pushes the tenant's events over a WebSocket as they are appended, one JSON
+/// text message per event. Upgrades beyond `live_events.max_subscriptions`
+/// open sockets are refused with 503.
+#[instrument(skip(state, user, ws))]
+async fn live_tenant_events(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    user: Option<Extension<UserInfo>>,
+    ws: Result<WebSocketUpgrade, WebSocketUpgradeRejection>,
+) -> Result<Response, AppError> {
+    require_tenant_permission(user, id, TENANT_EVENTS_PERMISSION)?;
+
+    let Some(subscription) = state.live_events.subscribe(id) else {
+        counter!("live_event_upgrades_rejected_total").increment(1);
+        warn!(
+            "Refusing live events of tenant {}: too many open sockets",
+            id
+        );
+        return Ok((
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Too many live event subscriptions",
+        )
+            .into_response());
+    };
+    let ws = match ws {
+        Ok(ws) => ws,
+        Err(rejection) => return Ok(rejection.into_response()),
+    };
+
+    Ok(ws.on_upgrade(move |socket| forward_live_events(socket, subscription)))
+}
+
+/// Sends events until either side goes away
+async fn forward_live_events(mut socket: WebSocket, mut subscription: LiveSubscription) {
+    loop {
+        tokio::select! {
+            event = subscription.recv() => {
+                let Some(event) = event else { break };
+                let text = match serde_json::to_string(&event) {
+                    Ok(text) => text,
+                    Err(e) => {
+                        warn!("Failed to serialize live event {}: {}", event.event_id, e);
+                        continue;
+                    },
+                };
+                if socket.send(Message::Text(text.into())).await.is_err() {
+                    break;
+                }
+            },
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {},
+            },
+        }
+    }
+}
+
+/// Streams `{"exported_at", "tenant", "users", "events": [...]}`, writing the
+/// events page by page as they are read instead of buffering the stream
+fn export_body<S>(tenant: &Tenant, users: &[User], pages: S) -> AppResult<Body>
+where
+    S: Stream<Item = anyhow::Result<Vec<RecordedEvent>>> + Send + 'static,
+{
+    let mut head = serde_json::to_vec(&ExportHeader {
+        exported_at: Utc::now(),
+        tenant,
+        users,
+    })?;
+    // Reopen the object to append the events array
+    head.pop();
+    head.extend_from_slice(br#","events":["#);
+
+    let mut first = true;
+    let events = pages.map(move |page| -> AppResult<Bytes> {
+        let mut chunk = Vec::new();
+        for event in page? {
+            if !first {
+                chunk.push(b',');
+            }
+            first = false;
+            serde_json::to_writer(&mut chunk, &event)?;
+        }
+        Ok(Bytes::from(chunk))
+    });
+
+    let body = stream::once(future::ready(Ok(Bytes::from(head))))
+        .chain(events)
+        .chain(stream::once(future::ready(Ok(Bytes::from_static(b"]}")))));
+    Ok(Body::from_stream(body))
+}
+
+/// Streams the export as NDJSON: a first line with the tenant and its users,
+/// then one line per event. Each page becomes one chunk once it is read, so
+/// at most one page of events is held in memory however long the stream is.
+fn export_ndjson_body<S>(tenant: &Tenant, users: &[User], pages: S) -> AppResult<Body>
+where
+    S: Stream<Item = anyhow::Result<Vec<RecordedEvent>>> + Send + 'static,
+{
+    let mut head = serde_json::to_vec(&ExportHeader {
+        exported_at: Utc::now(),
+        tenant,
+        users,
+    })?;
+    head.push(b'\n');
+
+    let events = pages.map(|page| -> AppResult<Bytes> {
+        let mut chunk = Vec::new();
+        for event in page? {
+            serde_json::to_writer(&mut chunk, &event)?;
+            chunk.push(b'\n');
+        }
+        Ok(Bytes::from(chunk))
+    });
+
+    let body = stream::once(future::ready(Ok(Bytes::from(head)))).chain(events);
+    Ok(Body::from_stream(body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{
+        tenant::{TenantSettings, TENANT_SETTINGS_VERSION},
+        user::{UserRole, UserSettings},
+    };
+    use crate::infrastructure::live_events::LiveEvents;
+
+    fn tenant() -> Tenant {
+        Tenant {
+            id: Uuid::new_v4(),
+            name: "Acme".to_string(),
+            domain: "acme.example.com".to_string(),
+            is_active: true,
+            settings: TenantSettings {
+                settings_version: TENANT_SETTINGS_VERSION,
+                ..Default::default()
+            },
+            updated_at: None,
+        }
+    }
+
+    fn user(tenant_id: Uuid) -> User {
+        User {
+            id: Uuid::new_v4(),
+            tenant_id,
+            email: "jane@example.com".to_string(),
+            username: "jane".to_string(),
+            full_name: "Jane Doe".to_string(),
+            is_active: true,
+            role: UserRole::User,
+            settings: UserSettings::default(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            last_login_at: None,
+        }
+    }
+
+    fn event(event_type: &str) -> RecordedEvent {
+        RecordedEvent {
+            event_id: Uuid::new_v4(),
+            event_type: event_type.to_string(),
+            data: serde_json::Value::Null,
+            metadata: serde_json::Value::Null,
+            created: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_export_document_shape() {
+        let tenant = tenant();
+        let users = vec![user(tenant.id), user(tenant.id)];
+        let pages = stream::iter(vec![
+            Ok(vec![event("TenantCreated"), event("TenantUpdated")]),
+            Ok(vec![event("UserAdded")]),
+        ]);
+
+        let body = export_body(&tenant, &users, pages).expect("export body");
+        let bytes = axum::body::to_bytes(body, usize::MAX)
+            .await
+            .expect("complete body");
+        let document: serde_json::Value =
+            serde_json::from_slice(&bytes).expect("export is valid JSON");
+
+        assert!(document["exported_at"].is_string());
+        assert_eq!(document["tenant"]["id"], tenant.id.to_string());
+        assert_eq!(document["users"].as_array().map(Vec::len), Some(2));
+        let events = document["events"].as_array().expect("events array");
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[2]["eventType"], "UserAdded");
+    }
+
+    #[tokio::test]
+    async fn test_export_of_empty_stream_is_valid_json() {
+        let tenant = tenant();
+        let body = export_body(&tenant, &[], stream::iter(Vec::new())).expect("export body");
+        let bytes = axum::body::to_bytes(body, usize::MAX)
+            .await
+            .expect("complete body");
+        let document: serde_json::Value =
+            serde_json::from_slice(&bytes).expect("export is valid JSON");
+
+        assert_eq!(document["events"], serde_json::Value::Array(vec![]));
+    }
+
+    #[tokio::test]
+    async fn test_ndjson_export_reads_pages_as_it_streams() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        const PAGES: usize = 500;
+        const PAGE_SIZE: usize = 100;
+
+        let tenant = tenant();
+        let pages_read = Arc::new(AtomicUsize::new(0));
+        let pages = stream::iter(0..PAGES).map({
+            let pages_read = Arc::clone(&pages_read);
+            move |_| {
+                pages_read.fetch_add(1, Ordering::SeqCst);
+                Ok((0..PAGE_SIZE).map(|_| event("TenantUpdated")).collect())
+            }
+        });
+
+        let mut chunks = export_ndjson_body(&tenant, &[user(tenant.id)], pages)
+            .expect("export body")
+            .into_data_stream();
+
+        let head = chunks.next().await.expect("head").expect("head chunk");
+        let head: serde_json::Value = serde_json::from_slice(&head).expect("head is JSON");
+        assert_eq!(head["tenant"]["id"], tenant.id.to_string());
+        assert!(head.get("events").is_none());
+        assert_eq!(pages_read.load(Ordering::SeqCst), 0);
+
+        let mut events = 0;
+        while let Some(chunk) = chunks.next().await {
+            let chunk = chunk.expect("event chunk");
+            events += chunk
+                .split(|b| *b == b'\n')
+                .filter(|line| !line.is_empty())
+                .map(|line| {
+                    let event: serde_json::Value =
+                        serde_json::from_slice(line).expect("each line is JSON");
+                    assert_eq!(event["eventType"], "TenantUpdated");
+                })
+                .count();
+            // Only the page being sent has been read, none ahead of it
+            assert_eq!(pages_read.load(Ordering::SeqCst) * PAGE_SIZE, events);
+        }
+        assert_eq!(events, PAGES * PAGE_SIZE);
+    }
+
+    #[test]
+    fn test_ndjson_is_chosen_by_accept_header() {
+        let mut headers = HeaderMap::new();
+        assert!(!accepts_ndjson(&headers));
+
+        headers.insert(
+            header::ACCEPT,
+            "application/json;q=0.5, application/x-ndjson"
+                .parse()
+                .expect("valid header"),
+        );
+        assert!(accepts_ndjson(&headers));
+    }
+
+    #[tokio::test]
+    async fn test_live_event_upgrades_beyond_the_cap_get_503() {
+        use crate::common::i18n::{I18nManager, SupportedLanguage, TestResourceProvider};
+        use crate::infrastructure::services::tenant_service::TenantServiceImpl;
+        use axum::http::Request;
+        use sea_orm::{DatabaseBackend, MockDatabase};
+        use std::sync::Arc;
+        use tower::ServiceExt;
+
+        let i18n = I18nManager::new(SupportedLanguage::En, Arc::new(TestResourceProvider::new()))
+            .await
+            .expect("i18n manager");
+        let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+        let mut state = AppState::for_test(
+            Arc::new(TenantServiceImpl::new(Arc::new(db))),
+            Arc::new(i18n),
+        );
+        let mut config = (*state.config).clone();
+        config.live_events.max_subscriptions = 1;
+        state.live_events = LiveEvents::new(&config.live_events);
+        let tenant_id = Uuid::new_v4();
+
+        let app = tenant_data_routes()
+            .with_state(state.clone())
+            .layer(axum::middleware::from_fn(
+                move |mut req: axum::extract::Request, next: axum::middleware::Next| async move {
+                    req.extensions_mut().insert(UserInfo {
+                        sub: "admin".to_string(),
+                        preferred_username: "admin".to_string(),
+                        email: None,
+                        roles: vec![],
+                        tenant_id: Some(tenant_id.to_string()),
+                        permissions: [TENANT_EVENTS_PERMISSION.to_string()].into(),
+                        auth_methods: vec![],
+                    });
+                    next.run(req).await
+                },
+            ));
+        let upgrade = |tenant_id: Uuid| {
+            Request::builder()
+                .uri(format!("/tenants/{}/events/live", tenant_id))
+                .header(header::CONNECTION, "upgrade")
+                .header(header::UPGRADE, "websocket")
+                .header(header::SEC_WEBSOCKET_VERSION, "13")
+                .header(header::SEC_WEBSOCKET_KEY, "dGhlIHNhbXBsZSBub25jZQ==")
+                .body(Body::empty())
+                .expect("valid request")
+        };
+
+        // The only place is taken by an open socket
+        let _open = state
+            .live_events
+            .subscribe(Uuid::new_v4())
+            .expect("first socket");
+        let response = app
+            .oneshot(upgrade(tenant_id))
+            .await
+            .expect("infallible router");
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn test_api_key_of_another_tenant_gets_403() {
+        use crate::common::i18n::{I18nManager, SupportedLanguage, TestResourceProvider};
+        use crate::domain::api_key::ApiKey;
+        use crate::infrastructure::services::tenant_service::TenantServiceImpl;
+        use axum::http::Request;
+        use sea_orm::{DatabaseBackend, MockDatabase};
+        use std::sync::Arc;
+        use tower::ServiceExt;
+
+        let i18n = I18nManager::new(SupportedLanguage::En, Arc::new(TestResourceProvider::new()))
+            .await
+            .expect("i18n manager");
+        let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+        let state = AppState::for_test(
+            Arc::new(TenantServiceImpl::new(Arc::new(db))),
+            Arc::new(i18n),
+        );
+        let key = ApiKey {
+            id: Uuid::new_v4(),
+            tenant_id: Uuid::new_v4(),
+            name: "ci".to_string(),
+            scopes: vec![
+                TENANT_EXPORT_PERMISSION.to_string(),
+                TENANT_ERASE_PERMISSION.to_string(),
+                TENANT_EVENTS_PERMISSION.to_string(),
+            ],
+            created_at: Utc::now(),
+            expires_at: None,
+            revoked_at: None,
+        };
+        let app = tenant_data_routes()
+            .with_state(state)
+            .layer(axum::middleware::from_fn(
+                move |mut req: axum::extract::Request, next: axum::middleware::Next| {
+                    let key = key.clone();
+                    async move {
+                        req.extensions_mut().insert(UserInfo::from(key));
+                        next.run(req).await
+                    }
+                },
+            ));
+
+        let other_tenant = Uuid::new_v4();
+        for (method, path) in [
+            ("GET", format!("/tenants/{}/export-data", other_tenant)),
+            ("POST", format!("/tenants/{}/erase", other_tenant)),
+            ("GET", format!("/tenants/{}/events", other_tenant)),
+            ("GET", format!("/tenants/{}/events/live", other_tenant)),
+        ] {
+            let request = Request::builder()
+                .method(method)
+                .uri(&path)
+                .body(Body::empty())
+                .expect("valid request");
+            let response = app.clone().oneshot(request).await.expect("response");
+            assert_eq!(
+                response.status(),
+                StatusCode::FORBIDDEN,
+                "{} {}",
+                method,
+                path
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_erase_tombstones_every_stream_of_the_tenant() {
+        use crate::common::config::EventStoreSettings;
+        use crate::common::i18n::{I18nManager, SupportedLanguage, TestResourceProvider};
+        use crate::infrastructure::config::EventStoreConfig;
+        use crate::infrastructure::database::entities::{
+            tenant as tenant_entity, user as user_entity,
+        };
+        use crate::infrastructure::event_store::EventStoreClient;
+        use crate::infrastructure::services::tenant_service::TenantServiceImpl;
+        use axum::http::Request;
+        use sea_orm::{DatabaseBackend, MockDatabase};
+        use std::collections::HashSet;
+        use std::sync::Arc;
+        use tower::ServiceExt;
+        use wiremock::{matchers::any, Mock, MockServer, ResponseTemplate};
+
+        let event_store = MockServer::start().await;
+        Mock::given(any())
+            .respond_with(ResponseTemplate::new(204))
+            .mount(&event_store)
+            .await;
+
+        let tenant_id = Uuid::new_v4();
+        let users: Vec<user_entity::Model> = (0..2)
+            .map(|_| user_entity::Model {
+                id: Uuid::new_v4(),
+                tenant_id,
+                email: "jane@example.com".to_string(),
+                username: "jane".to_string(),
+                full_name: "Jane Doe".to_string(),
+                is_active: true,
+                role: user_entity::Role::User,
+                settings: serde_json::Value::Object(Default::default()),
+                created_at: Utc::now().into(),
+                updated_at: Utc::now().into(),
+                last_login_at: None,
+            })
+            .collect();
+        let erased = tenant_entity::Model {
+            id: tenant_id,
+            name: "Acme".to_string(),
+            domain: "acme.example.com".to_string(),
+            is_active: false,
+            settings: serde_json::Value::Object(Default::default()),
+            created_at: Utc::now().naive_utc(),
+            updated_at: Utc::now().naive_utc(),
+            deleted_at: Some(Utc::now().naive_utc()),
+        };
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![users.clone()])
+            .append_query_results(vec![vec![erased]])
+            .into_connection();
+
+        let i18n = I18nManager::new(SupportedLanguage::En, Arc::new(TestResourceProvider::new()))
+            .await
+            .expect("i18n manager");
+        let mut state = AppState::for_test(
+            Arc::new(TenantServiceImpl::new(Arc::new(db))),
+            Arc::new(i18n),
+        );
+        state.event_store = Some(Arc::new(
+            EventStoreClient::new(
+                EventStoreConfig {
+                    url: event_store.uri(),
+                    nodes: vec![],
+                },
+                &EventStoreSettings::default(),
+                event_store::HttpPoolConfig::default(),
+            )
+            .expect("event store client"),
+        ));
+
+        let app = tenant_data_routes()
+            .with_state(state)
+            .layer(axum::middleware::from_fn(
+                move |mut req: axum::extract::Request, next: axum::middleware::Next| async move {
+                    req.extensions_mut().insert(UserInfo {
+                        sub: "admin".to_string(),
+                        preferred_username: "admin".to_string(),
+                        email: None,
+                        roles: vec![],
+                        tenant_id: Some(tenant_id.to_string()),
+                        permissions: [TENANT_ERASE_PERMISSION.to_string()].into(),
+                        auth_methods: vec![],
+                    });
+                    next.run(req).await
+                },
+            ));
+        let request = Request::builder()
+            .method("POST")
+            .uri(format!("/tenants/{}/erase", tenant_id))
+            .body(Body::empty())
+            .expect("valid request");
+        let response = app.oneshot(request).await.expect("response");
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+        let requests = event_store
+            .received_requests()
+            .await
+            .expect("request recording enabled");
+        // Nothing is written to the streams on the way out
+        assert!(requests
+            .iter()
+            .all(|request| request.method == wiremock::http::Method::Delete));
+        let tombstoned: HashSet<String> = requests
+            .iter()
+            .map(|request| {
+                request
+                    .url
+                    .path()
+                    .trim_start_matches("/streams/")
+                    .to_string()
+            })
+            .collect();
+        let streams: HashSet<String> = std::iter::once(StreamName::tenant_stream(tenant_id))
+            .chain(
+                users
+                    .iter()
+                    .map(|user| StreamName::user_stream(tenant_id, user.id)),
+            )
+            .collect();
+        assert_eq!(tombstoned, streams);
+    }
+
+    #[test]
+    fn test_tenant_permission_requires_membership_or_platform_admin() {
+        let tenant_id = Uuid::new_v4();
+        let caller = |tenant: Option<Uuid>, permissions: &[&str]| UserInfo {
+            sub: "caller".to_string(),
+            preferred_username: "caller".to_string(),
+            email: None,
+            roles: vec![],
+            tenant_id: tenant.map(|id| id.to_string()),
+            permissions: permissions.iter().map(|p| p.to_string()).collect(),
+            auth_methods: vec![],
+        };
+
+        let member = caller(Some(tenant_id), &[TENANT_EXPORT_PERMISSION]);
+        assert!(require_tenant_permission(
+            Some(Extension(member)),
+            tenant_id,
+            TENANT_EXPORT_PERMISSION
+        )
+        .is_ok());
+
+        let outsider = caller(Some(Uuid::new_v4()), &[TENANT_EXPORT_PERMISSION]);
+        assert!(require_tenant_permission(
+            Some(Extension(outsider)),
+            tenant_id,
+            TENANT_EXPORT_PERMISSION
+        )
+        .is_err());
+
+        let operator = caller(None, &[TENANT_EXPORT_PERMISSION, PLATFORM_ADMIN_PERMISSION]);
+        assert!(require_tenant_permission(
+            Some(Extension(operator)),
+            tenant_id,
+            TENANT_EXPORT_PERMISSION
+        )
+        .is_ok());
+
+        // Membership alone doesn't grant the permission
+        let member = caller(Some(tenant_id), &[]);
+        assert!(require_tenant_permission(
+            Some(Extension(member)),
+            tenant_id,
+            TENANT_EXPORT_PERMISSION
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_export_and_erase_require_permissions() {
+        let admin = UserInfo {
+            sub: "admin".to_string(),
+            preferred_username: "admin".to_string(),
+            email: None,
+            roles: vec![],
+            tenant_id: None,
+            permissions: [TENANT_EXPORT_PERMISSION.to_string()].into(),
+            auth_methods: vec![],
+        };
+
+        assert!(require_permission(None, TENANT_EXPORT_PERMISSION).is_err());
+        assert!(
+            require_permission(Some(Extension(admin.clone())), TENANT_EXPORT_PERMISSION).is_ok()
+        );
+        assert!(require_permission(Some(Extension(admin)), TENANT_ERASE_PERMISSION).is_err());
+    }
+}
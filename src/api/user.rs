@@ -0,0 +1,427 @@
+use axum::{
+    extract::{Extension, Path, Query, State},
+    http::HeaderMap,
+    response::Json,
+    routing::{get, patch},
+    Router,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::api::{audit::record_change, tenant_data::require_tenant_permission};
+use crate::common::{
+    error::{AppError, AppResult},
+    json::LimitedJson,
+    middleware::{api_key::API_KEY_AUTH_METHOD, auth::UserInfo},
+    pagination::{PaginationParams, Sort},
+};
+use crate::domain::user::{User, UserService, UserSettingsPatch, UserSort};
+use crate::infrastructure::state::AppState;
+
+/// Permission to change other users of the caller's tenant
+const USER_WRITE_PERMISSION: &str = "user:write";
+
+/// Permission to list the users of the caller's tenant
+const USER_READ_PERMISSION: &str = "user:read";
+
+pub fn user_routes() -> Router<AppState> {
+    Router::new()
+        .route("/me", get(current_user))
+        .route("/tenants/{id}/users", get(list_users))
+}
+
+/// Routes changing users, mounted unless `routes.user_write` is off
+pub fn user_write_routes() -> Router<AppState> {
+    Router::new().route(
+        "/tenants/{id}/users/{uid}/settings",
+        patch(update_user_settings),
+    )
+}
+
+/// `?sort=&page=&page_size=` of the user list, kept flat like
+/// `TenantListParams`
+#[derive(Debug, Default, Deserialize)]
+pub struct UserListParams {
+    pub sort: Option<String>,
+    pub page: Option<u64>,
+    pub page_size: Option<u64>,
+}
+
+/// The caller's token identity together with their stored user record
+#[derive(Debug, Serialize)]
+pub struct CurrentUserResponse {
+    pub sub: String,
+    pub preferred_username: String,
+    pub email: Option<String>,
+    pub roles: Vec<String>,
+    pub permissions: Vec<String>,
+    pub tenant_id: Option<String>,
+    /// `None` for API keys, which act for their tenant rather than a user
+    pub user: Option<User>,
+}
+
+async fn current_user(
+    State(state): State<AppState>,
+    user: Option<Extension<UserInfo>>,
+) -> Result<Json<CurrentUserResponse>, AppError> {
+    let Some(Extension(identity)) = user else {
+        return Err(AppError::authentication("Authentication required"));
+    };
+
+    load_current_user(state.user_service.as_ref(), identity)
+        .await
+        .map(Json)
+}
+
+#[axum::debug_handler]
+async fn list_users(
+    State(state): State<AppState>,
+    Path(tenant_id): Path<Uuid>,
+    user: Option<Extension<UserInfo>>,
+    Query(params): Query<UserListParams>,
+) -> Result<(HeaderMap, Json<Vec<User>>), AppError> {
+    require_tenant_permission(user, tenant_id, USER_READ_PERMISSION)?;
+    let pagination = PaginationParams {
+        page: params.page,
+        page_size: params.page_size,
+    }
+    .resolve(&state.config.pagination);
+    let sort: UserSort = match &params.sort {
+        Some(sort) => Sort::parse(sort)?,
+        None => Sort::parse(&state.config.pagination.default_user_sort)?,
+    };
+    let users = state
+        .user_service
+        .list(&tenant_id, pagination, sort)
+        .await?;
+
+    // Only a validated field name ends up in the links
+    let path = match &params.sort {
+        Some(sort) => format!("/tenants/{}/users?sort={}", tenant_id, sort),
+        None => format!("/tenants/{}/users", tenant_id),
+    };
+    let links = pagination.links(&state.config.server, &path, users.len());
+    Ok((links, Json(users)))
+}
+
+/// Users may change their own settings; anyone else needs `user:write` in
+/// the user's tenant
+fn authorize_settings_update(
+    user: Option<Extension<UserInfo>>,
+    tenant_id: Uuid,
+    user_id: Uuid,
+) -> AppResult<()> {
+    let Some(caller) = user else {
+        return Err(AppError::authentication("Authentication required"));
+    };
+    if Uuid::parse_str(&caller.sub).ok() == Some(user_id) {
+        return Ok(());
+    }
+    require_tenant_permission(Some(caller), tenant_id, USER_WRITE_PERMISSION)
+}
+
+#[axum::debug_handler]
+async fn update_user_settings(
+    State(state): State<AppState>,
+    Path((tenant_id, user_id)): Path<(Uuid, Uuid)>,
+    user: Option<Extension<UserInfo>>,
+    LimitedJson(patch): LimitedJson<UserSettingsPatch>,
+) -> Result<Json<User>, AppError> {
+    authorize_settings_update(user.clone(), tenant_id, user_id)?;
+
+    let updated = state
+        .user_service
+        .update_settings(&tenant_id, &user_id, patch)
+        .await?;
+    record_change(
+        &state,
+        user.as_deref(),
+        tenant_id,
+        "user.settings_update",
+        "user",
+        user_id,
+        None,
+    )
+    .await;
+    Ok(Json(updated))
+}
+
+async fn load_current_user(
+    users: &dyn UserService,
+    identity: UserInfo,
+) -> AppResult<CurrentUserResponse> {
+    let user = if identity
+        .auth_methods
+        .iter()
+        .any(|method| method == API_KEY_AUTH_METHOD)
+    {
+        None
+    } else {
+        Some(users.find_by_subject(&identity.sub).await?)
+    };
+
+    let mut permissions: Vec<String> = identity.permissions.into_iter().collect();
+    permissions.sort();
+
+    Ok(CurrentUserResponse {
+        sub: identity.sub,
+        preferred_username: identity.preferred_username,
+        email: identity.email,
+        roles: identity.roles,
+        permissions,
+        tenant_id: identity.tenant_id,
+        user,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use std::sync::Arc;
+
+    use chrono::Utc;
+    use sea_orm::{DatabaseBackend, MockDatabase};
+
+    use crate::common::error::ErrorKind;
+    use crate::domain::user::{UserRole, UserSettings};
+    use crate::infrastructure::database::entities::user;
+    use crate::infrastructure::services::user_service::UserServiceImpl;
+
+    fn identity(sub: &str) -> UserInfo {
+        UserInfo {
+            sub: sub.to_string(),
+            preferred_username: "jane".to_string(),
+            email: Some("jane@example.com".to_string()),
+            roles: vec!["user".to_string()],
+            tenant_id: None,
+            permissions: HashSet::from(["user:read".to_string(), "tenant:read".to_string()]),
+            auth_methods: vec![],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_current_user_is_enriched_with_stored_record() {
+        let id = Uuid::new_v4();
+        let tenant_id = Uuid::new_v4();
+        let settings = UserSettings {
+            language: "de".to_string(),
+            ..Default::default()
+        };
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![user::Model {
+                id,
+                tenant_id,
+                email: "jane@example.com".to_string(),
+                username: "jane".to_string(),
+                full_name: "Jane Doe".to_string(),
+                is_active: true,
+                role: user::Role::TenantAdmin,
+                settings: serde_json::to_value(&settings).expect("serializable settings"),
+                created_at: Utc::now().into(),
+                updated_at: Utc::now().into(),
+                last_login_at: None,
+            }]])
+            .into_connection();
+        let users = UserServiceImpl::new(Arc::new(db));
+
+        let response = load_current_user(&users, identity(&id.to_string()))
+            .await
+            .expect("current user");
+
+        assert_eq!(response.sub, id.to_string());
+        assert_eq!(response.permissions, vec!["tenant:read", "user:read"]);
+        let user = response.user.expect("stored user");
+        assert_eq!(user.id, id);
+        assert_eq!(user.tenant_id, tenant_id);
+        assert_eq!(user.role, UserRole::TenantAdmin);
+        assert_eq!(user.settings.language, "de");
+    }
+
+    #[test]
+    fn test_settings_update_needs_the_user_or_user_write_in_the_tenant() {
+        let tenant_id = Uuid::new_v4();
+        let user_id = Uuid::new_v4();
+        let caller = |sub: Uuid, tenant: Uuid, permissions: &[&str]| {
+            Some(Extension(UserInfo {
+                tenant_id: Some(tenant.to_string()),
+                permissions: permissions.iter().map(|p| p.to_string()).collect(),
+                ..identity(&sub.to_string())
+            }))
+        };
+
+        assert!(
+            authorize_settings_update(caller(user_id, tenant_id, &[]), tenant_id, user_id).is_ok()
+        );
+
+        let admin = caller(Uuid::new_v4(), tenant_id, &[USER_WRITE_PERMISSION]);
+        assert!(authorize_settings_update(admin, tenant_id, user_id).is_ok());
+
+        let error =
+            authorize_settings_update(caller(Uuid::new_v4(), tenant_id, &[]), tenant_id, user_id)
+                .expect_err("other users need user:write");
+        assert!(matches!(*error.kind, ErrorKind::AuthorizationError(_)));
+
+        let outsider = caller(Uuid::new_v4(), Uuid::new_v4(), &[USER_WRITE_PERMISSION]);
+        let error = authorize_settings_update(outsider, tenant_id, user_id)
+            .expect_err("user:write of another tenant");
+        assert!(matches!(*error.kind, ErrorKind::AuthorizationError(_)));
+    }
+
+    #[tokio::test]
+    async fn test_settings_update_status_codes() {
+        use crate::common::i18n::{I18nManager, SupportedLanguage, TestResourceProvider};
+        use crate::infrastructure::services::tenant_service::TenantServiceImpl;
+        use axum::{
+            body::Body,
+            http::{header, Request, StatusCode},
+        };
+        use tower::ServiceExt;
+
+        let i18n = I18nManager::new(SupportedLanguage::En, Arc::new(TestResourceProvider::new()))
+            .await
+            .expect("i18n manager");
+        let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+        let state = AppState::for_test(
+            Arc::new(TenantServiceImpl::new(Arc::new(db))),
+            Arc::new(i18n),
+        );
+        let patch = |caller: Option<UserInfo>| {
+            let mut request = Request::builder()
+                .method("PATCH")
+                .uri(format!(
+                    "/tenants/{}/users/{}/settings",
+                    Uuid::new_v4(),
+                    Uuid::new_v4()
+                ))
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(r#"{"language":"de"}"#))
+                .expect("valid request");
+            if let Some(caller) = caller {
+                request.extensions_mut().insert(caller);
+            }
+            request
+        };
+        let app = user_write_routes().with_state(state);
+
+        let response = app.clone().oneshot(patch(None)).await.expect("response");
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let stranger = identity(&Uuid::new_v4().to_string());
+        let response = app.oneshot(patch(Some(stranger))).await.expect("response");
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_list_users_needs_user_read_and_a_sortable_field() {
+        use crate::common::i18n::{I18nManager, SupportedLanguage, TestResourceProvider};
+        use crate::infrastructure::services::tenant_service::TenantServiceImpl;
+        use axum::{
+            body::Body,
+            http::{Request, StatusCode},
+        };
+        use tower::ServiceExt;
+
+        let i18n = I18nManager::new(SupportedLanguage::En, Arc::new(TestResourceProvider::new()))
+            .await
+            .expect("i18n manager");
+        let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+        let state = AppState::for_test(
+            Arc::new(TenantServiceImpl::new(Arc::new(db))),
+            Arc::new(i18n),
+        );
+        let tenant_id = Uuid::new_v4();
+        let list = |query: &str, tenant: Uuid| {
+            let mut request = Request::builder()
+                .uri(format!("/tenants/{}/users{}", tenant_id, query))
+                .body(Body::empty())
+                .expect("valid request");
+            request.extensions_mut().insert(UserInfo {
+                tenant_id: Some(tenant.to_string()),
+                ..identity(&Uuid::new_v4().to_string())
+            });
+            request
+        };
+        let app = user_routes().with_state(state);
+
+        let response = app
+            .clone()
+            .oneshot(list("?sort=-settings", tenant_id))
+            .await
+            .expect("response");
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = app
+            .oneshot(list("?sort=email", Uuid::new_v4()))
+            .await
+            .expect("response");
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_current_user_without_user_row_is_not_found() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results::<user::Model, _, _>(vec![vec![]])
+            .into_connection();
+        let users = UserServiceImpl::new(Arc::new(db));
+
+        let error = load_current_user(&users, identity(&Uuid::new_v4().to_string()))
+            .await
+            .expect_err("no user row for the subject");
+
+        assert!(matches!(*error.kind, ErrorKind::NotFoundError(_)));
+    }
+
+    #[tokio::test]
+    async fn test_current_user_of_an_api_key() {
+        use crate::common::i18n::{I18nManager, SupportedLanguage, TestResourceProvider};
+        use crate::domain::api_key::ApiKey;
+        use crate::infrastructure::services::tenant_service::TenantServiceImpl;
+        use axum::{
+            body::Body,
+            http::{Request, StatusCode},
+        };
+        use tower::ServiceExt;
+
+        let i18n = I18nManager::new(SupportedLanguage::En, Arc::new(TestResourceProvider::new()))
+            .await
+            .expect("i18n manager");
+        // No query results: looking up a user would fail the request
+        let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+        let state = AppState::for_test(
+            Arc::new(TenantServiceImpl::new(Arc::new(db))),
+            Arc::new(i18n),
+        );
+        let api_key = ApiKey {
+            id: Uuid::new_v4(),
+            tenant_id: Uuid::new_v4(),
+            name: "billing-sync".to_string(),
+            scopes: vec!["tenant:read".to_string()],
+            created_at: Utc::now(),
+            expires_at: None,
+            revoked_at: None,
+        };
+        let (key_id, tenant_id) = (api_key.id, api_key.tenant_id);
+        let mut request = Request::builder()
+            .uri("/me")
+            .body(Body::empty())
+            .expect("valid request");
+        request.extensions_mut().insert(UserInfo::from(api_key));
+
+        let response = user_routes()
+            .with_state(state)
+            .oneshot(request)
+            .await
+            .expect("response");
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("response body");
+        let me: serde_json::Value = serde_json::from_slice(&body).expect("JSON body");
+        assert_eq!(me["sub"], format!("api-key:{}", key_id));
+        assert_eq!(me["tenant_id"], tenant_id.to_string());
+        assert_eq!(me["permissions"][0], "tenant:read");
+        assert!(me["user"].is_null());
+    }
+}
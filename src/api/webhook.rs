@@ -0,0 +1,247 @@
+use axum::{
+    extract::{Extension, Path, State},
+    http::StatusCode,
+    response::Json,
+    routing::post,
+    Router,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::api::{audit::record_change, tenant_data::require_tenant_permission};
+use crate::common::{error::AppError, json::LimitedJson, middleware::auth::UserInfo};
+use crate::domain::webhook::{ensure_public_target, validate_registration, Webhook};
+use crate::infrastructure::state::AppState;
+
+/// Permission required to register webhooks for a tenant
+const WEBHOOK_WRITE_PERMISSION: &str = "webhook:write";
+
+/// Routes registering webhooks, mounted unless `routes.tenant_write` is off
+pub fn webhook_routes() -> Router<AppState> {
+    Router::new().route("/tenants/{id}/webhooks", post(create_webhook))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateWebhookDto {
+    pub url: String,
+    pub event_types: Vec<String>,
+}
+
+/// The registered webhook with its secret, which is only returned here
+#[derive(Debug, Serialize)]
+pub struct CreatedWebhookResponse {
+    #[serde(flatten)]
+    pub webhook: Webhook,
+    pub secret: String,
+}
+
+/// Members of the tenant with `webhook:write` only
+async fn create_webhook(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    user: Option<Extension<UserInfo>>,
+    LimitedJson(payload): LimitedJson<CreateWebhookDto>,
+) -> Result<(StatusCode, Json<CreatedWebhookResponse>), AppError> {
+    require_tenant_permission(user.clone(), id, WEBHOOK_WRITE_PERMISSION)?;
+    validate_registration(&payload.url, &payload.event_types)?;
+    if !state.config.webhooks.allow_private_targets {
+        ensure_public_target(&payload.url).await?;
+    }
+
+    // Fails with 404 for unknown tenants instead of a foreign key error
+    state.tenant_service.find_by_id(&id.to_string()).await?;
+    let webhook = state
+        .webhooks
+        .create(&id, payload.url, payload.event_types)
+        .await?;
+    let mut details = serde_json::Map::new();
+    details.insert("url".to_string(), webhook.url.clone().into());
+    details.insert(
+        "event_types".to_string(),
+        webhook.event_types.clone().into(),
+    );
+    record_change(
+        &state,
+        user.as_deref(),
+        id,
+        "webhook.create",
+        "webhook",
+        webhook.id,
+        Some(details.into()),
+    )
+    .await;
+    let secret = webhook.secret.clone();
+    Ok((
+        StatusCode::CREATED,
+        Json(CreatedWebhookResponse { webhook, secret }),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use std::sync::Arc;
+
+    use axum::{
+        body::Body,
+        http::{header, Request},
+    };
+    use chrono::Utc;
+    use sea_orm::{DatabaseBackend, MockDatabase};
+    use tower::ServiceExt;
+
+    use crate::common::i18n::{I18nManager, SupportedLanguage, TestResourceProvider};
+    use crate::domain::tenant::{TenantSettings, TENANT_SETTINGS_VERSION};
+    use crate::infrastructure::database::entities::{tenant, webhook};
+    use crate::infrastructure::services::{
+        tenant_service::TenantServiceImpl, webhook_service::WebhookServiceImpl,
+    };
+
+    fn caller(tenant_id: Uuid, permissions: &[&str]) -> UserInfo {
+        UserInfo {
+            sub: Uuid::new_v4().to_string(),
+            preferred_username: "jane".to_string(),
+            email: None,
+            roles: vec!["tenant_admin".to_string()],
+            tenant_id: Some(tenant_id.to_string()),
+            permissions: permissions
+                .iter()
+                .map(|p| p.to_string())
+                .collect::<HashSet<_>>(),
+            auth_methods: vec![],
+        }
+    }
+
+    fn register(tenant_id: Uuid, caller: UserInfo) -> Request<Body> {
+        register_url(tenant_id, caller, "https://93.184.216.34/hooks")
+    }
+
+    fn register_url(tenant_id: Uuid, caller: UserInfo, url: &str) -> Request<Body> {
+        let body = format!(r#"{{"url":"{}","event_types":["TenantUpdated"]}}"#, url);
+        let mut request = Request::builder()
+            .method("POST")
+            .uri(format!("/tenants/{}/webhooks", tenant_id))
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body))
+            .expect("valid request");
+        request.extensions_mut().insert(caller);
+        request
+    }
+
+    async fn state(tenants: MockDatabase, webhooks: MockDatabase) -> AppState {
+        let i18n = I18nManager::new(SupportedLanguage::En, Arc::new(TestResourceProvider::new()))
+            .await
+            .expect("i18n manager");
+        let mut state = AppState::for_test(
+            Arc::new(TenantServiceImpl::new(Arc::new(tenants.into_connection()))),
+            Arc::new(i18n),
+        );
+        state.webhooks = Arc::new(WebhookServiceImpl::new(Arc::new(
+            webhooks.into_connection(),
+        )));
+        state
+    }
+
+    #[tokio::test]
+    async fn test_tenant_admin_registers_webhook_and_gets_its_secret() {
+        let tenant_id = Uuid::new_v4();
+        let settings = TenantSettings {
+            max_users: 10,
+            storage_limit: 1024,
+            api_rate_limit: 100,
+            settings_version: TENANT_SETTINGS_VERSION,
+            ..Default::default()
+        };
+        let tenants = MockDatabase::new(DatabaseBackend::Postgres).append_query_results(vec![
+            vec![tenant::Model {
+                id: tenant_id,
+                name: "Acme".to_string(),
+                domain: "acme.example.com".to_string(),
+                is_active: true,
+                settings: serde_json::to_value(&settings).expect("serializable settings"),
+                created_at: Utc::now().naive_utc(),
+                updated_at: Utc::now().naive_utc(),
+                deleted_at: None,
+            }],
+        ]);
+        let webhooks = MockDatabase::new(DatabaseBackend::Postgres).append_query_results(vec![
+            vec![webhook::Model {
+                id: Uuid::new_v4(),
+                tenant_id,
+                url: "https://93.184.216.34/hooks".to_string(),
+                event_types: serde_json::Value::from(vec!["TenantUpdated"]),
+                secret: "generated".to_string(),
+                created_at: Utc::now().into(),
+            }],
+        ]);
+        let app = webhook_routes().with_state(state(tenants, webhooks).await);
+
+        let response = app
+            .oneshot(register(
+                tenant_id,
+                caller(tenant_id, &[WEBHOOK_WRITE_PERMISSION]),
+            ))
+            .await
+            .expect("response");
+
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("body");
+        let created: serde_json::Value = serde_json::from_slice(&body).expect("JSON body");
+        assert_eq!(created["secret"], "generated");
+        assert_eq!(created["tenant_id"], tenant_id.to_string());
+    }
+
+    #[tokio::test]
+    async fn test_webhooks_to_internal_addresses_are_rejected() {
+        let tenant_id = Uuid::new_v4();
+        let app = webhook_routes().with_state(
+            state(
+                MockDatabase::new(DatabaseBackend::Postgres),
+                MockDatabase::new(DatabaseBackend::Postgres),
+            )
+            .await,
+        );
+
+        for url in [
+            "http://169.254.169.254/latest/meta-data",
+            "http://127.0.0.1:6379/",
+            "http://localhost/hooks",
+            "http://10.0.0.5/hooks",
+        ] {
+            let response = app
+                .clone()
+                .oneshot(register_url(
+                    tenant_id,
+                    caller(tenant_id, &[WEBHOOK_WRITE_PERMISSION]),
+                    url,
+                ))
+                .await
+                .expect("response");
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", url);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_webhooks_of_another_tenant_cannot_be_registered() {
+        let app = webhook_routes().with_state(
+            state(
+                MockDatabase::new(DatabaseBackend::Postgres),
+                MockDatabase::new(DatabaseBackend::Postgres),
+            )
+            .await,
+        );
+
+        let response = app
+            .oneshot(register(
+                Uuid::new_v4(),
+                caller(Uuid::new_v4(), &[WEBHOOK_WRITE_PERMISSION]),
+            ))
+            .await
+            .expect("response");
+
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+}
@@ -2,11 +2,13 @@ use config::{Config, ConfigError, Environment, File};
 use once_cell::sync::Lazy;
 use sea_orm::ConnectOptions;
 use serde::{Deserialize, Serialize};
-use std::{env, fs, path::Path};
+use std::{
+    collections::{HashMap, HashSet},
+    env, fs,
+    path::Path,
+};
 use tracing::Level;
 
-#[cfg(test)]
-use std::collections::HashMap;
 #[cfg(test)]
 use std::sync::Mutex;
 
@@ -49,11 +51,55 @@ static MOCK_FS: Lazy<Mutex<MockFs>> = Lazy::new(|| Mutex::new(MockFs::new()));
 pub struct ServerSettings {
     pub backend_port: u16,
     pub default_language: String,
+    /// Origin clients reach the API under, e.g. `https://api.example.com`,
+    /// used to build absolute URLs such as `Link` headers
+    #[serde(default = "default_public_base_url")]
+    pub public_base_url: String,
+}
+
+impl ServerSettings {
+    /// Joins `path` (with any query) onto the public base URL
+    pub fn absolute_url(&self, path: &str) -> String {
+        format!(
+            "{}/{}",
+            self.public_base_url.trim_end_matches('/'),
+            path.trim_start_matches('/')
+        )
+    }
+}
+
+fn default_public_base_url() -> String {
+    "http://localhost:3333".to_string()
 }
 
 #[derive(Debug, Deserialize, Clone, Serialize)]
 pub struct LoggingSettings {
     pub level: String,
+    /// Emit one access log line per request
+    #[serde(default = "default_request_logging")]
+    pub request_logging: bool,
+    /// Query parameters and headers whose values are redacted in the access log
+    #[serde(default = "default_sensitive_fields")]
+    pub sensitive_fields: Vec<String>,
+}
+
+fn default_request_logging() -> bool {
+    true
+}
+
+fn default_sensitive_fields() -> Vec<String> {
+    [
+        "token",
+        "access_token",
+        "code",
+        "state",
+        "password",
+        "authorization",
+        "cookie",
+    ]
+    .iter()
+    .map(|field| field.to_string())
+    .collect()
 }
 
 #[derive(Debug, Deserialize, Clone, Serialize)]
@@ -70,6 +116,42 @@ pub struct AppConfig {
     pub redis: RedisSettings,
     pub logging: LoggingSettings,
     pub keycloak: KeycloakConfig,
+    #[serde(default)]
+    pub permissions: Permissions,
+    #[serde(default)]
+    pub health: HealthSettings,
+    #[serde(default)]
+    pub runtime: RuntimeSettings,
+    #[serde(default)]
+    pub pagination: PaginationSettings,
+    #[serde(default)]
+    pub cache: CacheSettings,
+    #[serde(default)]
+    pub json_limits: JsonLimitSettings,
+    #[serde(default)]
+    pub concurrency: ConcurrencySettings,
+    #[serde(default)]
+    pub default_user_settings: DefaultUserSettings,
+    #[serde(default)]
+    pub event_publisher: EventPublisherSettings,
+    #[serde(default)]
+    pub http_client: HttpClientSettings,
+    #[serde(default)]
+    pub eventstore: EventStoreSettings,
+    #[serde(default)]
+    pub live_events: LiveEventsSettings,
+    #[serde(default)]
+    pub webhooks: WebhookSettings,
+    #[serde(default)]
+    pub seed: SeedSettings,
+    #[serde(default)]
+    pub response_envelope: ResponseEnvelopeSettings,
+    #[serde(default)]
+    pub request_timeouts: RequestTimeoutSettings,
+    #[serde(default)]
+    pub maintenance: MaintenanceSettings,
+    #[serde(default)]
+    pub routes: RouteSettings,
 }
 
 impl Default for AppConfig {
@@ -78,6 +160,7 @@ impl Default for AppConfig {
             server: ServerSettings {
                 backend_port: 3333,
                 default_language: "en".to_string(),
+                public_base_url: default_public_base_url(),
             },
             database: DatabaseSettings {
                 host: "localhost".to_string(),
@@ -91,12 +174,17 @@ impl Default for AppConfig {
                 acquire_timeout: default_acquire_timeout(),
                 idle_timeout: default_idle_timeout(),
                 max_lifetime: default_max_lifetime(),
+                warm_up: false,
+                slow_query_threshold_ms: default_slow_query_threshold_ms(),
             },
             redis: RedisSettings {
                 url: "redis://localhost:6379".to_string(),
+                key_prefix: default_redis_key_prefix(),
             },
             logging: LoggingSettings {
                 level: "debug".to_string(),
+                request_logging: default_request_logging(),
+                sensitive_fields: default_sensitive_fields(),
             },
             keycloak: KeycloakConfig {
                 url: "http://localhost:8080".to_string(),
@@ -105,7 +193,34 @@ impl Default for AppConfig {
                 client_secret: "test_secret".to_string(),
                 verify_token: true,
                 public_key_cache_ttl: 3600,
+                require_roles: false,
+                role_sources: default_role_sources(),
+                tenant_claim: default_tenant_claim(),
+                max_token_age: None,
+                auth_flow_cookie_max_age: default_auth_flow_cookie_max_age(),
+                auth_flow_cookie_max_size: default_auth_flow_cookie_max_size(),
+                auth_flow_max_cookies: default_auth_flow_max_cookies(),
+                token_exchange_max_retries: default_token_exchange_max_retries(),
+                token_exchange_retry_delay_ms: default_token_exchange_retry_delay_ms(),
             },
+            permissions: Permissions::default(),
+            health: HealthSettings::default(),
+            runtime: RuntimeSettings::default(),
+            pagination: PaginationSettings::default(),
+            cache: CacheSettings::default(),
+            json_limits: JsonLimitSettings::default(),
+            concurrency: ConcurrencySettings::default(),
+            default_user_settings: DefaultUserSettings::default(),
+            event_publisher: EventPublisherSettings::default(),
+            http_client: HttpClientSettings::default(),
+            eventstore: EventStoreSettings::default(),
+            live_events: LiveEventsSettings::default(),
+            webhooks: WebhookSettings::default(),
+            seed: SeedSettings::default(),
+            response_envelope: ResponseEnvelopeSettings::default(),
+            request_timeouts: RequestTimeoutSettings::default(),
+            maintenance: MaintenanceSettings::default(),
+            routes: RouteSettings::default(),
         }
     }
 }
@@ -130,9 +245,20 @@ pub struct DatabaseSettings {
     pub idle_timeout: u64,
     #[serde(default = "default_max_lifetime")]
     pub max_lifetime: u64,
+    /// Open `min_connections` connections at startup instead of lazily
+    #[serde(default)]
+    pub warm_up: bool,
+    /// Service database calls taking this long or longer are logged and
+    /// counted in `db_slow_queries_total`, in milliseconds
+    #[serde(default = "default_slow_query_threshold_ms")]
+    pub slow_query_threshold_ms: u64,
 }
 
 impl DatabaseSettings {
+    pub fn slow_query_threshold(&self) -> std::time::Duration {
+        std::time::Duration::from_millis(self.slow_query_threshold_ms)
+    }
+
     pub fn to_connect_options(&self) -> ConnectOptions {
         let mut opt = ConnectOptions::new(format!(
             "postgres://{}:{}@{}:{}/{}",
@@ -174,10 +300,21 @@ fn default_max_lifetime() -> u64 {
     1800 // seconds
 }
 
+pub(crate) fn default_slow_query_threshold_ms() -> u64 {
+    500
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[allow(dead_code)]
 pub struct RedisSettings {
     pub url: String,
+    /// Prefix put in front of every Redis key this application writes
+    #[serde(default = "default_redis_key_prefix")]
+    pub key_prefix: String,
+}
+
+fn default_redis_key_prefix() -> String {
+    "acci".to_string()
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -191,6 +328,912 @@ pub struct KeycloakConfig {
     pub verify_token: bool,
     #[serde(default = "default_public_key_cache_ttl")]
     pub public_key_cache_ttl: u64,
+    /// Reject tokens that carry none of the claims in `role_sources`
+    #[serde(default)]
+    pub require_roles: bool,
+    /// Token claims whose entries make up the user's roles, merged in this
+    /// order
+    #[serde(default = "default_role_sources")]
+    pub role_sources: Vec<RoleSource>,
+    /// Token claim holding the tenant id; preferred over `tenant_`-prefixed
+    /// roles when present
+    #[serde(default = "default_tenant_claim")]
+    pub tenant_claim: String,
+    /// Reject tokens issued (`iat`) more than this many seconds ago, even if
+    /// they have not expired yet
+    #[serde(default)]
+    pub max_token_age: Option<u64>,
+    /// Lifetime in seconds of the CSRF/PKCE cookies set by the login flow
+    #[serde(default = "default_auth_flow_cookie_max_age")]
+    pub auth_flow_cookie_max_age: u64,
+    /// Longest CSRF/PKCE cookie value in bytes the login callback accepts
+    #[serde(default = "default_auth_flow_cookie_max_size")]
+    pub auth_flow_cookie_max_size: usize,
+    /// Most CSRF/PKCE cookies the login callback accepts in one request;
+    /// other cookies on the domain aren't counted
+    #[serde(default = "default_auth_flow_max_cookies")]
+    pub auth_flow_max_cookies: usize,
+    /// Retries of the authorization code exchange when the token endpoint is
+    /// unreachable or fails with a server error
+    #[serde(default = "default_token_exchange_max_retries")]
+    pub token_exchange_max_retries: u32,
+    /// Delay before the first exchange retry in milliseconds, doubled on
+    /// every further retry
+    #[serde(default = "default_token_exchange_retry_delay_ms")]
+    pub token_exchange_retry_delay_ms: u64,
+}
+
+/// Token claim trusted to carry roles
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RoleSource {
+    /// Realm roles, `realm_access.roles`
+    Realm,
+    /// Roles of this backend's client, `resource_access.<client_id>.roles`
+    Client,
+    /// Group memberships, `groups`; a group path like `/staff/manager`
+    /// counts as its last segment
+    Groups,
+}
+
+fn default_role_sources() -> Vec<RoleSource> {
+    vec![RoleSource::Realm]
+}
+
+fn default_auth_flow_cookie_max_age() -> u64 {
+    600 // 10 minutes
+}
+
+fn default_auth_flow_cookie_max_size() -> usize {
+    256
+}
+
+fn default_auth_flow_max_cookies() -> usize {
+    64
+}
+
+fn default_token_exchange_max_retries() -> u32 {
+    2
+}
+
+fn default_token_exchange_retry_delay_ms() -> u64 {
+    200
+}
+
+fn default_tenant_claim() -> String {
+    "tenant_id".to_string()
+}
+
+/// Mapping from role names to the fine-grained permissions they grant
+/// (e.g. `tenant_admin = ["tenant:read", "tenant:write"]`)
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct Permissions(HashMap<String, HashSet<String>>);
+
+impl Permissions {
+    #[allow(dead_code)]
+    pub fn with_role(mut self, role: impl Into<String>, permissions: &[&str]) -> Self {
+        self.0
+            .entry(role.into())
+            .or_default()
+            .extend(permissions.iter().map(|p| p.to_string()));
+        self
+    }
+
+    /// Returns the union of all permissions granted by the given roles
+    pub fn for_roles(&self, roles: &[String]) -> HashSet<String> {
+        roles
+            .iter()
+            .filter_map(|role| self.0.get(role))
+            .flatten()
+            .cloned()
+            .collect()
+    }
+}
+
+/// Resource usage thresholds (in percent) used by the health checks
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct HealthSettings {
+    /// Return component and system details to every caller instead of only
+    /// to callers with the `health:details` permission
+    #[serde(default)]
+    pub expose_details: bool,
+    #[serde(default)]
+    pub cpu: ResourceThresholds,
+    #[serde(default)]
+    pub memory: ResourceThresholds,
+    #[serde(default)]
+    pub disk: ResourceThresholds,
+    /// How often the shared system snapshot is refreshed, in seconds
+    #[serde(default = "default_refresh_interval_secs")]
+    pub refresh_interval_secs: u64,
+    /// Connection attempts per optional dependency at startup
+    #[serde(default = "default_startup_check_attempts")]
+    pub startup_check_attempts: u32,
+    /// Pause between startup connection attempts, in milliseconds
+    #[serde(default = "default_startup_retry_delay_ms")]
+    pub startup_retry_delay_ms: u64,
+    /// How long a computed health result is reused by later probes, in
+    /// milliseconds; 0 checks the dependencies on every probe
+    #[serde(default = "default_health_cache_ttl_ms")]
+    pub cache_ttl_ms: u64,
+    /// Components whose failure makes the service not ready
+    #[serde(default)]
+    pub critical: CriticalComponents,
+    /// Time the Keycloak probe waits for the realm's OpenID configuration,
+    /// in milliseconds
+    #[serde(default = "default_keycloak_probe_timeout_ms")]
+    pub keycloak_probe_timeout_ms: u64,
+}
+
+impl Default for HealthSettings {
+    fn default() -> Self {
+        Self {
+            expose_details: false,
+            cpu: ResourceThresholds::default(),
+            memory: ResourceThresholds::default(),
+            disk: ResourceThresholds::default(),
+            refresh_interval_secs: default_refresh_interval_secs(),
+            startup_check_attempts: default_startup_check_attempts(),
+            startup_retry_delay_ms: default_startup_retry_delay_ms(),
+            cache_ttl_ms: default_health_cache_ttl_ms(),
+            critical: CriticalComponents::default(),
+            keycloak_probe_timeout_ms: default_keycloak_probe_timeout_ms(),
+        }
+    }
+}
+
+impl HealthSettings {
+    pub fn refresh_interval(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.refresh_interval_secs.max(1))
+    }
+
+    pub fn cache_ttl(&self) -> std::time::Duration {
+        std::time::Duration::from_millis(self.cache_ttl_ms)
+    }
+
+    pub fn keycloak_probe_timeout(&self) -> std::time::Duration {
+        std::time::Duration::from_millis(self.keycloak_probe_timeout_ms)
+    }
+}
+
+fn default_refresh_interval_secs() -> u64 {
+    5
+}
+
+fn default_startup_check_attempts() -> u32 {
+    3
+}
+
+fn default_startup_retry_delay_ms() -> u64 {
+    1000
+}
+
+fn default_health_cache_ttl_ms() -> u64 {
+    2000
+}
+
+fn default_keycloak_probe_timeout_ms() -> u64 {
+    1000
+}
+
+/// Whether a failing component fails readiness (critical) or only degrades
+/// it. By default only the database is critical.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+pub struct CriticalComponents {
+    #[serde(default = "default_database_critical")]
+    pub database: bool,
+    #[serde(default)]
+    pub cache: bool,
+    #[serde(default)]
+    pub event_store: bool,
+    #[serde(default)]
+    pub event_processing: bool,
+    #[serde(default)]
+    pub message_broker: bool,
+    #[serde(default)]
+    pub keycloak: bool,
+}
+
+impl Default for CriticalComponents {
+    fn default() -> Self {
+        Self {
+            database: true,
+            cache: false,
+            event_store: false,
+            event_processing: false,
+            message_broker: false,
+            keycloak: false,
+        }
+    }
+}
+
+fn default_database_critical() -> bool {
+    true
+}
+
+/// Usage levels at which a resource is reported as degraded or unhealthy
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+pub struct ResourceThresholds {
+    #[serde(default = "default_degraded_threshold")]
+    pub degraded: f64,
+    #[serde(default = "default_unhealthy_threshold")]
+    pub unhealthy: f64,
+}
+
+impl Default for ResourceThresholds {
+    fn default() -> Self {
+        Self {
+            degraded: default_degraded_threshold(),
+            unhealthy: default_unhealthy_threshold(),
+        }
+    }
+}
+
+fn default_degraded_threshold() -> f64 {
+    90.0 // percent
+}
+
+fn default_unhealthy_threshold() -> f64 {
+    98.0 // percent
+}
+
+/// Tokio runtime tuning; unset values keep Tokio's defaults
+/// (one worker per core, 512 blocking threads)
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct RuntimeSettings {
+    #[serde(default)]
+    pub worker_threads: Option<usize>,
+    #[serde(default)]
+    pub max_blocking_threads: Option<usize>,
+}
+
+impl RuntimeSettings {
+    pub fn to_runtime_builder(&self) -> tokio::runtime::Builder {
+        let mut builder = tokio::runtime::Builder::new_multi_thread();
+        builder.enable_all();
+
+        if let Some(worker_threads) = self.worker_threads {
+            builder.worker_threads(worker_threads);
+        }
+        if let Some(max_blocking_threads) = self.max_blocking_threads {
+            builder.max_blocking_threads(max_blocking_threads);
+        }
+
+        builder
+    }
+
+    /// Tokio panics on a runtime without worker or blocking threads
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        for (key, value) in [
+            ("runtime.worker_threads", self.worker_threads),
+            ("runtime.max_blocking_threads", self.max_blocking_threads),
+        ] {
+            if value == Some(0) {
+                return Err(ConfigError::Message(format!(
+                    "{} must be greater than 0",
+                    key
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Page size limits applied to list endpoints
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PaginationSettings {
+    #[serde(default = "default_page_size")]
+    pub default_page_size: u64,
+    #[serde(default = "default_max_page_size")]
+    pub max_page_size: u64,
+    /// Events returned by event reads when the client doesn't ask for a count
+    #[serde(default = "default_event_count")]
+    pub default_event_count: u64,
+    /// Upper bound for the `count` of event reads
+    #[serde(default = "default_max_event_count")]
+    pub max_event_count: u64,
+    /// Order of the tenant list when the client doesn't pass `?sort=`: a
+    /// sortable field, prefixed with `-` for descending
+    #[serde(default = "default_tenant_sort")]
+    pub default_tenant_sort: String,
+    /// Order of a tenant's user list when the client doesn't pass `?sort=`
+    #[serde(default = "default_user_sort")]
+    pub default_user_sort: String,
+}
+
+impl Default for PaginationSettings {
+    fn default() -> Self {
+        Self {
+            default_page_size: default_page_size(),
+            max_page_size: default_max_page_size(),
+            default_event_count: default_event_count(),
+            max_event_count: default_max_event_count(),
+            default_tenant_sort: default_tenant_sort(),
+            default_user_sort: default_user_sort(),
+        }
+    }
+}
+
+fn default_page_size() -> u64 {
+    20
+}
+
+fn default_max_page_size() -> u64 {
+    100
+}
+
+fn default_event_count() -> u64 {
+    100
+}
+
+fn default_max_event_count() -> u64 {
+    1000
+}
+
+fn default_tenant_sort() -> String {
+    "name".to_string()
+}
+
+fn default_user_sort() -> String {
+    "username".to_string()
+}
+
+/// In-memory cache maintenance
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CacheSettings {
+    /// How often expired entries are swept, in seconds
+    #[serde(default = "default_sweep_interval_secs")]
+    pub sweep_interval_secs: u64,
+}
+
+impl Default for CacheSettings {
+    fn default() -> Self {
+        Self {
+            sweep_interval_secs: default_sweep_interval_secs(),
+        }
+    }
+}
+
+impl CacheSettings {
+    pub fn sweep_interval(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.sweep_interval_secs.max(1))
+    }
+}
+
+fn default_sweep_interval_secs() -> u64 {
+    60
+}
+
+/// Limits applied to inbound JSON request bodies
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct JsonLimitSettings {
+    /// Maximum nesting of objects and arrays
+    #[serde(default = "default_json_max_depth")]
+    pub max_depth: usize,
+    /// Maximum body size in bytes
+    #[serde(default = "default_json_max_bytes")]
+    pub max_bytes: usize,
+}
+
+impl Default for JsonLimitSettings {
+    fn default() -> Self {
+        Self {
+            max_depth: default_json_max_depth(),
+            max_bytes: default_json_max_bytes(),
+        }
+    }
+}
+
+fn default_json_max_depth() -> usize {
+    32
+}
+
+fn default_json_max_bytes() -> usize {
+    1024 * 1024 // 1MB
+}
+
+/// Connection pool of the clients making outbound HTTP calls (Keycloak JWKS,
+/// EventStore). Fields missing from the configuration keep the EventStore
+/// client's pool defaults.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct HttpClientSettings {
+    /// Idle pooled connections are closed after this many seconds
+    pub pool_idle_timeout_secs: u64,
+    /// Idle connections kept open per host
+    pub pool_max_idle_per_host: usize,
+    /// Interval of TCP keep-alive probes on open connections, in seconds
+    pub tcp_keepalive_secs: u64,
+    /// Timeout of a whole request, in seconds
+    pub timeout_secs: u64,
+}
+
+impl Default for HttpClientSettings {
+    fn default() -> Self {
+        let pool = event_store::HttpPoolConfig::default();
+        Self {
+            pool_idle_timeout_secs: pool.pool_idle_timeout_secs,
+            pool_max_idle_per_host: pool.pool_max_idle_per_host,
+            tcp_keepalive_secs: pool.tcp_keepalive_secs,
+            timeout_secs: pool.timeout_secs,
+        }
+    }
+}
+
+/// Load shedding for everything except the health probes
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ConcurrencySettings {
+    /// Requests handled at once before new ones are rejected with 503
+    #[serde(default = "default_max_in_flight")]
+    pub max_in_flight: usize,
+    /// Value of the `Retry-After` header on shed requests, in seconds
+    #[serde(default = "default_retry_after_secs")]
+    pub retry_after_secs: u64,
+}
+
+impl Default for ConcurrencySettings {
+    fn default() -> Self {
+        Self {
+            max_in_flight: default_max_in_flight(),
+            retry_after_secs: default_retry_after_secs(),
+        }
+    }
+}
+
+fn default_max_in_flight() -> usize {
+    512
+}
+
+fn default_retry_after_secs() -> u64 {
+    1
+}
+
+/// Maintenance mode, in which only the health, metrics and admin routes are
+/// served
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MaintenanceSettings {
+    /// Start in maintenance mode; `/admin/maintenance` switches it at runtime
+    #[serde(default)]
+    pub enabled: bool,
+    /// Value of the `Retry-After` header on rejected requests, in seconds
+    #[serde(default = "default_maintenance_retry_after_secs")]
+    pub retry_after_secs: u64,
+}
+
+impl Default for MaintenanceSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            retry_after_secs: default_maintenance_retry_after_secs(),
+        }
+    }
+}
+
+fn default_maintenance_retry_after_secs() -> u64 {
+    300
+}
+
+/// Groups of API routes a deployment can leave out. Disabled routes aren't
+/// mounted at all, so they answer 404 like any unknown path.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RouteSettings {
+    /// Creating, updating, deleting and reactivating tenants
+    #[serde(default = "default_route_enabled")]
+    pub tenant_write: bool,
+    /// Export, erasure and the event history of tenants
+    #[serde(default = "default_route_enabled")]
+    pub tenant_data: bool,
+    /// Changing a user's settings
+    #[serde(default = "default_route_enabled")]
+    pub user_write: bool,
+    /// The `/admin` routes
+    #[serde(default = "default_route_enabled")]
+    pub admin: bool,
+    /// `GET /admin/i18n/render`, mounted along with the other admin routes;
+    /// when unset, it is mounted in every mode except prod
+    #[serde(default)]
+    pub i18n_debug: Option<bool>,
+}
+
+impl RouteSettings {
+    pub fn i18n_debug_enabled(&self, run_mode: &str) -> bool {
+        self.admin && self.i18n_debug.unwrap_or(run_mode != "prod")
+    }
+}
+
+impl Default for RouteSettings {
+    fn default() -> Self {
+        Self {
+            tenant_write: default_route_enabled(),
+            tenant_data: default_route_enabled(),
+            user_write: default_route_enabled(),
+            admin: default_route_enabled(),
+            i18n_debug: None,
+        }
+    }
+}
+
+fn default_route_enabled() -> bool {
+    true
+}
+
+/// Tuning of the EventStore client. Fields missing from the configuration
+/// keep the client's defaults.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct EventStoreSettings {
+    /// Retries of a call that failed because EventStore was unavailable
+    pub max_retries: u32,
+    /// Wait before the first retry in milliseconds, doubled for every
+    /// further retry
+    pub retry_delay: u64,
+    /// Events posted per append request; larger appends are split
+    pub max_append_size: MaxAppendSize,
+    /// Events a subscription reads per poll
+    pub subscription_read_count: u64,
+    /// Upper bound of any per-poll read count of a subscription
+    pub max_read_count: u64,
+    /// How long a caught-up subscription waits before polling again, in
+    /// milliseconds
+    pub subscription_poll_interval_ms: u64,
+    pub append_defaults: EventStoreAppendSettings,
+    pub compression: EventStoreCompressionSettings,
+    pub circuit_breaker: EventStoreCircuitBreakerSettings,
+}
+
+impl Default for EventStoreSettings {
+    fn default() -> Self {
+        let config = event_store::EventStoreConfig::default();
+        Self {
+            max_retries: config.max_retries,
+            retry_delay: config.retry_delay,
+            max_append_size: MaxAppendSize::Events(config.max_append_size),
+            subscription_read_count: config.subscription_read_count,
+            max_read_count: config.max_read_count,
+            subscription_poll_interval_ms: config.subscription_poll_interval_ms,
+            append_defaults: EventStoreAppendSettings::default(),
+            compression: EventStoreCompressionSettings::default(),
+            circuit_breaker: EventStoreCircuitBreakerSettings::default(),
+        }
+    }
+}
+
+/// `max_append_size` of the EventStore client. A string, as environment
+/// variables give, is read as the event count. Configurations generated from
+/// templates older than the event count hold a byte size such as `"1MB"`
+/// instead, which is ignored in favour of the client default.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum MaxAppendSize {
+    Events(usize),
+    Text(String),
+}
+
+impl MaxAppendSize {
+    /// Events posted per append request
+    pub fn events(&self) -> usize {
+        self.configured()
+            .unwrap_or(event_store::EventStoreConfig::default().max_append_size)
+    }
+
+    /// The configured event count, `None` for a legacy byte size
+    pub fn configured(&self) -> Option<usize> {
+        match self {
+            Self::Events(events) => Some(*events),
+            Self::Text(text) => text.parse().ok(),
+        }
+    }
+}
+
+/// Write options of appends that don't pass their own
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct EventStoreAppendSettings {
+    /// Only accept writes on the cluster master
+    pub require_master: bool,
+    /// Wait for EventStore to acknowledge a write; when disabled failed
+    /// appends are only logged
+    pub await_commit: bool,
+}
+
+impl Default for EventStoreAppendSettings {
+    fn default() -> Self {
+        let options = event_store::AppendOptions::default();
+        Self {
+            require_master: options.require_master,
+            await_commit: options.await_commit,
+        }
+    }
+}
+
+/// Gzip compression of append request bodies
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct EventStoreCompressionSettings {
+    pub enabled: bool,
+    /// Bodies smaller than this many bytes are sent uncompressed
+    pub min_size_bytes: usize,
+}
+
+impl Default for EventStoreCompressionSettings {
+    fn default() -> Self {
+        let compression = event_store::CompressionConfig::default();
+        Self {
+            enabled: compression.enabled,
+            min_size_bytes: compression.min_size_bytes,
+        }
+    }
+}
+
+/// When to stop calling an unreachable EventStore and fail fast instead
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct EventStoreCircuitBreakerSettings {
+    /// Consecutive failed calls after which the breaker opens
+    pub failure_threshold: u32,
+    /// How long an open breaker rejects calls before letting a probe through,
+    /// in milliseconds
+    pub cool_down_ms: u64,
+}
+
+impl Default for EventStoreCircuitBreakerSettings {
+    fn default() -> Self {
+        let breaker = event_store::CircuitBreakerConfig::default();
+        Self {
+            failure_threshold: breaker.failure_threshold,
+            cool_down_ms: breaker.cool_down_ms,
+        }
+    }
+}
+
+/// Background publication of domain events to the EventStore
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct EventPublisherSettings {
+    /// Appends waiting to be written before new events are dropped
+    #[serde(default = "default_publish_queue_capacity")]
+    pub queue_capacity: usize,
+}
+
+impl Default for EventPublisherSettings {
+    fn default() -> Self {
+        Self {
+            queue_capacity: default_publish_queue_capacity(),
+        }
+    }
+}
+
+fn default_publish_queue_capacity() -> usize {
+    1024
+}
+
+/// Live tenant event WebSockets, all fed by one EventStore subscription
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct LiveEventsSettings {
+    /// Open sockets before new upgrades are refused with 503
+    #[serde(default = "default_max_live_subscriptions")]
+    pub max_subscriptions: usize,
+    /// Events buffered per tenant; a socket that falls further behind skips
+    /// the events it missed
+    #[serde(default = "default_live_event_buffer")]
+    pub buffer: usize,
+}
+
+impl Default for LiveEventsSettings {
+    fn default() -> Self {
+        Self {
+            max_subscriptions: default_max_live_subscriptions(),
+            buffer: default_live_event_buffer(),
+        }
+    }
+}
+
+fn default_max_live_subscriptions() -> usize {
+    256
+}
+
+fn default_live_event_buffer() -> usize {
+    256
+}
+
+/// Delivery of tenant events to their registered webhooks
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct WebhookSettings {
+    /// Tries per delivery, including the first, before it is dead-lettered
+    #[serde(default = "default_webhook_max_attempts")]
+    pub max_attempts: u32,
+    /// Wait before the first retry, doubled for every further one
+    #[serde(default = "default_webhook_retry_delay_ms")]
+    pub retry_delay_ms: u64,
+    /// Stream the deliveries that ran out of attempts are appended to
+    #[serde(default = "default_webhook_dead_letter_stream")]
+    pub dead_letter_stream: String,
+    /// Events whose deliveries run at once; the subscription waits while
+    /// that many are in flight
+    #[serde(default = "default_webhook_max_concurrent_dispatches")]
+    pub max_concurrent_dispatches: usize,
+    /// Lets webhooks target loopback, link-local and private addresses;
+    /// only for development, as tenants could reach internal services
+    #[serde(default)]
+    pub allow_private_targets: bool,
+}
+
+impl WebhookSettings {
+    pub fn retry_delay(&self) -> std::time::Duration {
+        std::time::Duration::from_millis(self.retry_delay_ms)
+    }
+}
+
+impl Default for WebhookSettings {
+    fn default() -> Self {
+        Self {
+            max_attempts: default_webhook_max_attempts(),
+            retry_delay_ms: default_webhook_retry_delay_ms(),
+            dead_letter_stream: default_webhook_dead_letter_stream(),
+            max_concurrent_dispatches: default_webhook_max_concurrent_dispatches(),
+            allow_private_targets: false,
+        }
+    }
+}
+
+fn default_webhook_max_attempts() -> u32 {
+    5
+}
+
+fn default_webhook_retry_delay_ms() -> u64 {
+    1000
+}
+
+fn default_webhook_dead_letter_stream() -> String {
+    "webhook-dead-letters".to_string()
+}
+
+fn default_webhook_max_concurrent_dispatches() -> usize {
+    32
+}
+
+/// Wrapping of JSON responses in `{ data, meta, errors }`
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ResponseEnvelopeSettings {
+    /// Envelope responses unless a request sends `X-Response-Envelope: false`
+    #[serde(default)]
+    pub enabled: bool,
+}
+
+/// Time a handler gets to produce its response before the request fails
+/// with 408
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RequestTimeoutSettings {
+    /// Applies to every route without an override
+    #[serde(default = "default_request_timeout_secs")]
+    pub default_secs: u64,
+    /// Overrides by route pattern, e.g. `"/health" = 2`. A pattern ending in
+    /// `/*` covers every route below it, the longest matching one wins.
+    #[serde(default)]
+    pub routes: HashMap<String, u64>,
+}
+
+impl Default for RequestTimeoutSettings {
+    fn default() -> Self {
+        Self {
+            default_secs: default_request_timeout_secs(),
+            routes: HashMap::new(),
+        }
+    }
+}
+
+fn default_request_timeout_secs() -> u64 {
+    30
+}
+
+/// Default tenant and admin user created on first start, while the tenants
+/// table is still empty
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SeedSettings {
+    /// Whether to seed; when unset, seeding runs in every mode except prod
+    #[serde(default)]
+    pub enabled: Option<bool>,
+    #[serde(default = "default_seed_tenant_name")]
+    pub tenant_name: String,
+    #[serde(default = "default_seed_tenant_domain")]
+    pub tenant_domain: String,
+    #[serde(default = "default_seed_admin_email")]
+    pub admin_email: String,
+    #[serde(default = "default_seed_admin_username")]
+    pub admin_username: String,
+}
+
+impl SeedSettings {
+    /// Prod is only seeded when `enabled` is set explicitly
+    pub fn should_run(&self, run_mode: &str) -> bool {
+        self.enabled.unwrap_or(run_mode != "prod")
+    }
+}
+
+impl Default for SeedSettings {
+    fn default() -> Self {
+        Self {
+            enabled: None,
+            tenant_name: default_seed_tenant_name(),
+            tenant_domain: default_seed_tenant_domain(),
+            admin_email: default_seed_admin_email(),
+            admin_username: default_seed_admin_username(),
+        }
+    }
+}
+
+fn default_seed_tenant_name() -> String {
+    "System".to_string()
+}
+
+fn default_seed_tenant_domain() -> String {
+    "system.localhost".to_string()
+}
+
+fn default_seed_admin_email() -> String {
+    "admin@system.localhost".to_string()
+}
+
+fn default_seed_admin_username() -> String {
+    "admin".to_string()
+}
+
+/// Settings given to users created without any
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct DefaultUserSettings {
+    #[serde(default = "default_user_language")]
+    pub language: String,
+    #[serde(default = "default_user_timezone")]
+    pub timezone: String,
+    #[serde(default = "default_user_theme")]
+    pub theme: String,
+    #[serde(default = "default_items_per_page")]
+    pub items_per_page: i32,
+    #[serde(default = "default_notifications_enabled")]
+    pub email_notifications: bool,
+    #[serde(default = "default_notifications_enabled")]
+    pub in_app_notifications: bool,
+    /// Names of `NotificationType` values, e.g. `system` or `security`
+    #[serde(default = "default_notification_types")]
+    pub notification_types: Vec<String>,
+}
+
+impl Default for DefaultUserSettings {
+    fn default() -> Self {
+        Self {
+            language: default_user_language(),
+            timezone: default_user_timezone(),
+            theme: default_user_theme(),
+            items_per_page: default_items_per_page(),
+            email_notifications: default_notifications_enabled(),
+            in_app_notifications: default_notifications_enabled(),
+            notification_types: default_notification_types(),
+        }
+    }
+}
+
+fn default_user_language() -> String {
+    "en".to_string()
+}
+
+fn default_user_timezone() -> String {
+    "UTC".to_string()
+}
+
+fn default_user_theme() -> String {
+    "light".to_string()
+}
+
+fn default_items_per_page() -> i32 {
+    25
+}
+
+fn default_notifications_enabled() -> bool {
+    true
+}
+
+fn default_notification_types() -> Vec<String> {
+    vec!["system".to_string(), "security".to_string()]
 }
 
 fn default_verify_token() -> bool {
@@ -209,27 +1252,36 @@ impl Settings {
                 server: ServerSettings {
                     backend_port: 3333,
                     default_language: String::from("en"),
+                    public_base_url: default_public_base_url(),
                 },
                 logging: LoggingSettings {
                     level: String::from("debug"),
+                    request_logging: default_request_logging(),
+                    sensitive_fields: default_sensitive_fields(),
                 },
             },
             "prod" => Settings {
                 server: ServerSettings {
                     backend_port: 8080,
                     default_language: String::from("en"),
+                    public_base_url: default_public_base_url(),
                 },
                 logging: LoggingSettings {
                     level: String::from("info"),
+                    request_logging: default_request_logging(),
+                    sensitive_fields: default_sensitive_fields(),
                 },
             },
             "test" => Settings {
                 server: ServerSettings {
                     backend_port: 3333,
                     default_language: String::from("en"),
+                    public_base_url: default_public_base_url(),
                 },
                 logging: LoggingSettings {
                     level: String::from("debug"),
+                    request_logging: default_request_logging(),
+                    sensitive_fields: default_sensitive_fields(),
                 },
             },
             _ => {
@@ -420,6 +1472,10 @@ impl AppConfig {
             .set_default(
                 "keycloak.public_key_cache_ttl",
                 default_config.keycloak.public_key_cache_ttl,
+            )?
+            .set_default(
+                "keycloak.require_roles",
+                default_config.keycloak.require_roles,
             )?;
 
         // Then load environment-specific config file (middle priority)
@@ -436,8 +1492,30 @@ impl AppConfig {
                 .try_parsing(true),
         );
 
-        builder.build()?.try_deserialize()
+        let config: Self = builder.build()?.try_deserialize()?;
+        config.validate(&run_mode)?;
+        Ok(config)
     }
+
+    /// Startup checks for settings that must never reach certain environments
+    /// or that the runtime can't be built with
+    pub fn validate(&self, run_mode: &str) -> Result<(), ConfigError> {
+        if run_mode == "prod" && !self.keycloak.verify_token {
+            return Err(ConfigError::Message(
+                "keycloak.verify_token must be enabled when RUN_MODE=prod".to_string(),
+            ));
+        }
+        self.runtime.validate()
+    }
+}
+
+/// `RUN_MODE` the configuration was loaded for, `dev` when unset
+pub fn get_run_mode() -> String {
+    env::var("RUN_MODE").unwrap_or_else(|_| "dev".into())
+}
+
+pub fn get_app_config() -> AppConfig {
+    APP_CONFIG.clone()
 }
 
 pub fn get_database_config() -> DatabaseSettings {
@@ -619,4 +1697,90 @@ level = "debug"
         let override_settings = Settings::new().unwrap();
         assert_eq!(override_settings.server.backend_port, 5000);
     }
+
+    #[test]
+    fn test_runtime_builder_from_config() {
+        let settings = RuntimeSettings {
+            worker_threads: Some(2),
+            max_blocking_threads: Some(4),
+        };
+
+        let runtime = settings
+            .to_runtime_builder()
+            .build()
+            .expect("runtime builds");
+        assert_eq!(runtime.metrics().num_workers(), 2);
+        assert_eq!(runtime.block_on(async { 40 + 2 }), 42);
+    }
+
+    #[test]
+    fn test_eventstore_tuning_keeps_defaults_for_unset_keys() -> Result<(), ConfigError> {
+        let config: AppConfig = Config::builder()
+            .add_source(Config::try_from(&AppConfig::default())?)
+            .add_source(File::from_str(
+                "[eventstore.circuit_breaker]\nfailure_threshold = 2\n",
+                config::FileFormat::Toml,
+            ))
+            .build()?
+            .try_deserialize()?;
+
+        let breaker = event_store::CircuitBreakerConfig::from(&config.eventstore.circuit_breaker);
+        assert_eq!(breaker.failure_threshold, 2);
+        assert_eq!(
+            breaker.cool_down_ms,
+            event_store::CircuitBreakerConfig::default().cool_down_ms
+        );
+        assert!(!config.eventstore.compression.enabled);
+        Ok(())
+    }
+
+    #[test]
+    fn test_legacy_max_append_size_keeps_the_default() -> Result<(), ConfigError> {
+        let config: AppConfig = Config::builder()
+            .add_source(Config::try_from(&AppConfig::default())?)
+            .add_source(File::from_str(
+                "[eventstore]\nmax_append_size = \"1MB\"\n",
+                config::FileFormat::Toml,
+            ))
+            .build()?
+            .try_deserialize()?;
+
+        assert_eq!(config.eventstore.max_append_size.configured(), None);
+        assert_eq!(
+            config.eventstore.max_append_size.events(),
+            event_store::EventStoreConfig::default().max_append_size
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_max_append_size_is_read_from_strings() {
+        assert_eq!(MaxAppendSize::Text("500".to_string()).events(), 500);
+        assert_eq!(MaxAppendSize::Events(500).events(), 500);
+    }
+
+    #[test]
+    fn test_prod_requires_verify_token() {
+        let mut config = AppConfig::default();
+        assert!(config.validate("prod").is_ok());
+
+        config.keycloak.verify_token = false;
+        assert!(config.validate("prod").is_err());
+        assert!(config.validate("dev").is_ok());
+        assert!(config.validate("test").is_ok());
+    }
+
+    #[test]
+    fn test_runtime_without_threads_is_rejected() {
+        let mut config = AppConfig::default();
+        config.runtime.worker_threads = Some(0);
+        assert!(config.validate("dev").is_err());
+
+        config.runtime.worker_threads = Some(2);
+        config.runtime.max_blocking_threads = Some(0);
+        assert!(config.validate("dev").is_err());
+
+        config.runtime.max_blocking_threads = Some(4);
+        assert!(config.validate("dev").is_ok());
+    }
 }
@@ -38,12 +38,38 @@ pub enum ErrorKind {
     AuthError(String),
     #[error("Serialization error: {0}")]
     SerializationError(String),
+    #[error("Payload error: {0}")]
+    PayloadError(String),
+    #[error("Precondition failed: {0}")]
+    PreconditionFailed(String),
+    /// Carries an already localized message
+    #[error("{0}")]
+    UnsupportedMediaType(String),
+    /// Carries an already localized message
+    #[error("{0}")]
+    Maintenance(String),
+    /// A service we depend on failed or couldn't be reached
+    #[error("Upstream error: {0}")]
+    UpstreamError(String),
     #[error("Internal error: {0}")]
     InternalError(String),
 }
 
+impl ErrorKind {
+    /// Stable code for clients to match on where the message is localized
+    fn code(&self) -> Option<&'static str> {
+        match self {
+            ErrorKind::UnsupportedMediaType(_) => Some("UNSUPPORTED_MEDIA_TYPE"),
+            ErrorKind::Maintenance(_) => Some("MAINTENANCE"),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Serialize)]
 struct ErrorResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    code: Option<&'static str>,
     message: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     context: Option<String>,
@@ -63,10 +89,16 @@ impl IntoResponse for AppError {
             ErrorKind::UserError(_) => StatusCode::BAD_REQUEST,
             ErrorKind::AuthError(_) => StatusCode::UNAUTHORIZED,
             ErrorKind::SerializationError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            ErrorKind::PayloadError(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            ErrorKind::PreconditionFailed(_) => StatusCode::PRECONDITION_FAILED,
+            ErrorKind::UnsupportedMediaType(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            ErrorKind::Maintenance(_) => StatusCode::SERVICE_UNAVAILABLE,
+            ErrorKind::UpstreamError(_) => StatusCode::BAD_GATEWAY,
             ErrorKind::InternalError(_) => StatusCode::INTERNAL_SERVER_ERROR,
         };
 
         let body = Json(ErrorResponse {
+            code: self.kind.code(),
             message: self.kind.to_string(),
             context: self.context.message,
         });
@@ -102,8 +134,30 @@ impl From<std::io::Error> for AppError {
     }
 }
 
+// Crossing between `anyhow` and `AppError`:
+//
+// - Infrastructure code and the `event_store` crate return `anyhow::Result`.
+//   When they need a specific HTTP outcome they return an `AppError` or a
+//   typed error (e.g. `event_store::StreamNotFound`) inside the `anyhow::Error`
+//   instead of a formatted string; `AppError` implements `std::error::Error`
+//   so `?` wraps it without losing its kind.
+// - Handlers and domain services convert back with `?` / `AppError::from`,
+//   which recovers that kind here. Anything unrecognised becomes an internal
+//   error.
 impl From<anyhow::Error> for AppError {
     fn from(err: anyhow::Error) -> Self {
+        let err = match err.downcast::<AppError>() {
+            Ok(app_error) => return app_error,
+            Err(err) => err,
+        };
+
+        if let Some(not_found) = err.downcast_ref::<event_store::StreamNotFound>() {
+            return Self::not_found(not_found.to_string());
+        }
+        if let Some(db_err) = err.downcast_ref::<DbErr>() {
+            return Self::database(db_err.to_string());
+        }
+
         Self::internal(err.to_string())
     }
 }
@@ -201,6 +255,32 @@ impl AppError {
         )
     }
 
+    pub fn payload(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::PayloadError(message.into()), "Payload error")
+    }
+
+    pub fn precondition_failed(message: impl Into<String>) -> Self {
+        Self::new(
+            ErrorKind::PreconditionFailed(message.into()),
+            "Precondition failed",
+        )
+    }
+
+    pub fn unsupported_media_type(message: impl Into<String>) -> Self {
+        Self::new(
+            ErrorKind::UnsupportedMediaType(message.into()),
+            "Unsupported media type",
+        )
+    }
+
+    pub fn maintenance(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::Maintenance(message.into()), "Maintenance")
+    }
+
+    pub fn upstream(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::UpstreamError(message.into()), "Upstream error")
+    }
+
     pub fn internal(message: impl Into<String>) -> Self {
         Self::new(ErrorKind::InternalError(message.into()), "Internal error")
     }
@@ -217,6 +297,12 @@ impl std::fmt::Display for AppError {
     }
 }
 
+impl std::error::Error for AppError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(self.kind.as_ref())
+    }
+}
+
 #[derive(Debug, Default, Serialize)]
 pub struct ErrorContext {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -247,3 +333,31 @@ impl ErrorContext {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stream_not_found_maps_to_not_found() {
+        let err = anyhow::Error::new(event_store::StreamNotFound("tenant-42".to_string()));
+
+        let app_error = AppError::from(err);
+        assert!(matches!(*app_error.kind, ErrorKind::NotFoundError(_)));
+        assert_eq!(app_error.into_response().status(), StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn test_app_error_round_trips_through_anyhow() {
+        let err: anyhow::Error = AppError::validation("Tenant name cannot be empty").into();
+
+        let app_error = AppError::from(err.context("creating tenant"));
+        assert!(matches!(*app_error.kind, ErrorKind::ValidationError(_)));
+    }
+
+    #[test]
+    fn test_unknown_anyhow_error_is_internal() {
+        let app_error = AppError::from(anyhow::anyhow!("connection reset"));
+        assert!(matches!(*app_error.kind, ErrorKind::InternalError(_)));
+    }
+}
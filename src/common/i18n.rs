@@ -1,10 +1,17 @@
 use {
     crate::common::error::{AppError, AppResult},
-    fluent::{FluentArgs, FluentResource},
+    chrono::{DateTime, Datelike, Timelike, Utc},
+    fixed_decimal::{FixedDecimal, FloatPrecision},
+    fluent::{FluentArgs, FluentResource, FluentValue},
     fluent_bundle::bundle::FluentBundle,
+    icu_calendar::Gregorian,
+    icu_datetime::{options::length, TypedDateTimeFormatter},
+    icu_decimal::FixedDecimalFormatter,
+    icu_provider::DataLocale,
     intl_memoizer::concurrent::IntlLangMemoizer,
-    std::{collections::HashMap, fs, path::PathBuf, sync::Arc},
+    std::{collections::HashMap, fs, path::PathBuf, str::FromStr, sync::Arc},
     tokio::sync::RwLock,
+    tracing::warn,
 };
 
 type ConcurrentBundle = FluentBundle<FluentResource, IntlLangMemoizer>;
@@ -46,6 +53,98 @@ impl SupportedLanguage {
             Self::Sq => "sq",
         }
     }
+
+    /// Like `str::parse`, for callers that only care whether the tag matched
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        tag.parse().ok()
+    }
+
+    /// Locale the ICU formatters look up their data for
+    fn data_locale(self) -> AppResult<DataLocale> {
+        self.as_str()
+            .parse::<icu_locid::Locale>()
+            .map(|locale| DataLocale::from(&locale))
+            .map_err(|e| AppError::i18n(format!("Invalid locale {}: {:?}", self, e)))
+    }
+
+    /// Renders `value` with the digit grouping and decimal separator of the
+    /// language, e.g. `1,234.5` in English and `1.234,5` in German. Messages
+    /// call it as `{ NUMBER($count) }`.
+    pub fn format_number(self, value: f64) -> AppResult<String> {
+        let decimal = FixedDecimal::try_from_f64(value, FloatPrecision::Floating)
+            .map_err(|e| AppError::i18n(format!("Cannot format number {}: {:?}", value, e)))?;
+        let formatter = FixedDecimalFormatter::try_new(&self.data_locale()?, Default::default())
+            .map_err(|e| AppError::i18n(format!("No number format for {}: {:?}", self, e)))?;
+        Ok(formatter.format_to_string(&decimal))
+    }
+
+    /// Renders `dt` as a medium date with a short time in the conventions of
+    /// the language, e.g. `Aug 31, 2022, 1:02 PM` in English. The time is
+    /// shown in UTC. Messages call it as `{ DATETIME($at) }` with an RFC 3339
+    /// argument.
+    pub fn format_datetime(self, dt: &DateTime<Utc>) -> AppResult<String> {
+        // chrono's fields are all in range for these conversions
+        let datetime = icu_calendar::DateTime::try_new_gregorian_datetime(
+            dt.year(),
+            dt.month() as u8,
+            dt.day() as u8,
+            dt.hour() as u8,
+            dt.minute() as u8,
+            dt.second() as u8,
+        )
+        .map_err(|e| AppError::i18n(format!("Cannot format date {}: {:?}", dt, e)))?;
+        let options = length::Bag::from_date_time_style(length::Date::Medium, length::Time::Short);
+        let formatter =
+            TypedDateTimeFormatter::<Gregorian>::try_new(&self.data_locale()?, options.into())
+                .map_err(|e| AppError::i18n(format!("No date format for {}: {:?}", self, e)))?;
+        Ok(formatter.format_to_string(&datetime))
+    }
+}
+
+/// The `NUMBER` and `DATETIME` functions of a language's messages. Arguments
+/// reach messages as strings, so they're parsed here; anything unparsable
+/// renders as a Fluent error instead of failing the message.
+fn add_format_functions(bundle: &mut ConcurrentBundle, lang: SupportedLanguage) -> AppResult<()> {
+    let added = bundle.add_function("NUMBER", move |positional, _| {
+        let value = match positional.first() {
+            Some(FluentValue::Number(number)) => Some(number.value),
+            Some(FluentValue::String(value)) => value.trim().parse().ok(),
+            _ => None,
+        };
+        value
+            .and_then(|value| lang.format_number(value).ok())
+            .map_or(FluentValue::Error, FluentValue::from)
+    });
+    added
+        .and_then(|_| {
+            bundle.add_function("DATETIME", move |positional, _| {
+                let value = match positional.first() {
+                    Some(FluentValue::String(value)) => DateTime::parse_from_rfc3339(value).ok(),
+                    _ => None,
+                };
+                value
+                    .and_then(|dt| lang.format_datetime(&dt.with_timezone(&Utc)).ok())
+                    .map_or(FluentValue::Error, FluentValue::from)
+            })
+        })
+        .map_err(|e| AppError::i18n(format!("Failed to add format functions: {:?}", e)))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Unsupported language: {0}")]
+pub struct UnsupportedLanguage(pub String);
+
+/// Parses a language tag by its primary subtag, so region-qualified tags
+/// resolve to their language, e.g. `de-CH` -> `De`
+impl FromStr for SupportedLanguage {
+    type Err = UnsupportedLanguage;
+
+    fn from_str(tag: &str) -> Result<Self, Self::Err> {
+        let primary = tag.split(['-', '_']).next().unwrap_or_default().trim();
+        Self::iter()
+            .find(|l| l.as_str().eq_ignore_ascii_case(primary))
+            .ok_or_else(|| UnsupportedLanguage(tag.to_string()))
+    }
 }
 
 const LOCALES_DIR: &str = "locales";
@@ -54,12 +153,14 @@ const LOCALES_DIR: &str = "locales";
 pub struct I18nManager {
     bundles: Arc<RwLock<HashMap<String, Arc<ConcurrentBundle>>>>,
     default_lang: String,
+    fallbacks: HashMap<String, Vec<String>>,
 }
 
 impl std::fmt::Debug for I18nManager {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("I18nManager")
             .field("default_lang", &self.default_lang)
+            .field("fallbacks", &self.fallbacks)
             .finish_non_exhaustive()
     }
 }
@@ -69,16 +170,81 @@ pub trait ResourceProvider: Send + Sync {
     async fn get_resource(&self, lang: SupportedLanguage) -> AppResult<String>;
 }
 
-pub struct FileResourceProvider;
+/// File format of a language's translations
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LocaleFormat {
+    /// Fluent source, `main.ftl`
+    Ftl,
+    /// Flat JSON object of message id to Fluent pattern, `main.json`
+    Json,
+}
+
+impl LocaleFormat {
+    /// Formats in the order a language directory is searched
+    const SEARCH_ORDER: [Self; 2] = [Self::Ftl, Self::Json];
+
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            Self::Ftl => "main.ftl",
+            Self::Json => "main.json",
+        }
+    }
+
+    /// Converts a file's content into Fluent source
+    pub fn to_fluent(self, source: String) -> AppResult<String> {
+        match self {
+            Self::Ftl => Ok(source),
+            Self::Json => json_to_fluent(&source),
+        }
+    }
+}
+
+/// Converts `{"message-id": "pattern"}` into Fluent messages. Values are
+/// Fluent patterns, so they may use placeables like `{ $name }`; further
+/// lines of a multi-line value are indented as Fluent requires.
+fn json_to_fluent(source: &str) -> AppResult<String> {
+    let messages: std::collections::BTreeMap<String, String> = serde_json::from_str(source)
+        .map_err(|e| AppError::i18n(format!("Invalid JSON translations: {}", e)))?;
+
+    Ok(messages
+        .into_iter()
+        .map(|(id, value)| format!("{} = {}\n", id, value.replace('\n', "\n    ")))
+        .collect())
+}
+
+/// Reads each language from `<dir>/<lang>/`, using `main.ftl` or, when a
+/// language has none, `main.json`
+pub struct FileResourceProvider {
+    dir: PathBuf,
+}
+
+impl FileResourceProvider {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+}
+
+impl Default for FileResourceProvider {
+    fn default() -> Self {
+        Self::new(LOCALES_DIR)
+    }
+}
 
 #[async_trait::async_trait]
 impl ResourceProvider for FileResourceProvider {
     async fn get_resource(&self, lang: SupportedLanguage) -> AppResult<String> {
-        let path = PathBuf::from(LOCALES_DIR)
-            .join(lang.as_str())
-            .join("main.ftl");
-        fs::read_to_string(&path)
-            .map_err(|e| AppError::i18n(format!("Failed to read file: {:?}", e)))
+        let lang_dir = self.dir.join(lang.as_str());
+        let (format, path) = LocaleFormat::SEARCH_ORDER
+            .into_iter()
+            .map(|format| (format, lang_dir.join(format.file_name())))
+            .find(|(_, path)| path.is_file())
+            .ok_or_else(|| {
+                AppError::i18n(format!("No translations found in {}", lang_dir.display()))
+            })?;
+
+        let source = fs::read_to_string(&path)
+            .map_err(|e| AppError::i18n(format!("Failed to read file: {:?}", e)))?;
+        format.to_fluent(source)
     }
 }
 
@@ -104,9 +270,21 @@ impl I18nManager {
         Ok(Self {
             bundles: Arc::new(RwLock::new(bundles)),
             default_lang: default_lang.as_str().to_string(),
+            fallbacks: HashMap::new(),
         })
     }
 
+    /// Sets the ordered list of languages tried for `lang` before falling
+    /// back to the default language, e.g. `es-MX` -> `["es-419", "es"]`.
+    #[allow(dead_code)]
+    pub fn with_fallback_chain(mut self, lang: &str, chain: &[&str]) -> Self {
+        self.fallbacks.insert(
+            lang.to_string(),
+            chain.iter().map(|l| l.to_string()).collect(),
+        );
+        self
+    }
+
     pub async fn format_message(
         &self,
         lang: SupportedLanguage,
@@ -137,9 +315,26 @@ impl I18nManager {
 
     async fn get_bundle(&self, lang: &str) -> AppResult<Arc<ConcurrentBundle>> {
         let bundles = self.bundles.read().await;
-        bundles
+        let chain = self
+            .fallbacks
             .get(lang)
-            .or_else(|| bundles.get(&self.default_lang))
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        if let Some(bundle) = std::iter::once(lang)
+            .chain(chain.iter().map(String::as_str))
+            .find_map(|l| bundles.get(l))
+        {
+            return Ok(bundle.clone());
+        }
+
+        // A gap in the loaded bundles is served in the default language
+        // rather than failing the request
+        warn!(
+            "No bundle loaded for language {}, falling back to {}",
+            lang, self.default_lang
+        );
+        bundles
+            .get(&self.default_lang)
             .cloned()
             .ok_or_else(|| AppError::i18n("No bundle found and no default fallback available"))
     }
@@ -153,6 +348,8 @@ impl I18nManager {
             .parse()
             .map_err(|e| AppError::i18n(format!("Failed to parse language: {:?}", e)))?]);
 
+        add_format_functions(&mut bundle, lang)?;
+
         let source = provider.get_resource(lang).await?;
 
         let resource = FluentResource::try_new(source)
@@ -187,6 +384,11 @@ system-ready-message = System is ready to accept requests";
 
         Self { resources }
     }
+
+    pub fn with_resource(mut self, lang: SupportedLanguage, content: &str) -> Self {
+        self.resources.insert(lang, content.to_string());
+        self
+    }
 }
 
 #[cfg(test)]
@@ -207,6 +409,32 @@ mod tests {
         I18nManager::new(SupportedLanguage::En, Arc::new(TestResourceProvider::new())).await
     }
 
+    #[test]
+    fn test_parse_supported_language() {
+        assert_eq!("de".parse(), Ok(SupportedLanguage::De));
+        assert_eq!("SQ".parse(), Ok(SupportedLanguage::Sq));
+        for language in SupportedLanguage::iter() {
+            assert_eq!(language.as_str().parse(), Ok(language));
+        }
+    }
+
+    #[test]
+    fn test_parse_region_qualified_language() {
+        assert_eq!("de-CH".parse(), Ok(SupportedLanguage::De));
+        assert_eq!("es_419".parse(), Ok(SupportedLanguage::Es));
+        assert_eq!(" fr-CA ".parse(), Ok(SupportedLanguage::Fr));
+    }
+
+    #[test]
+    fn test_parse_unsupported_language() {
+        assert_eq!(
+            "ja-JP".parse::<SupportedLanguage>(),
+            Err(UnsupportedLanguage("ja-JP".to_string()))
+        );
+        assert!("".parse::<SupportedLanguage>().is_err());
+        assert!("english".parse::<SupportedLanguage>().is_err());
+    }
+
     #[tokio::test]
     async fn test_i18n_manager_creation() -> AppResult<()> {
         let manager = setup().await?;
@@ -255,4 +483,175 @@ mod tests {
         assert_eq!(message, "Test message content");
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_missing_bundle_is_served_by_default_language() -> AppResult<()> {
+        let provider = TestResourceProvider::new()
+            .with_resource(SupportedLanguage::En, "test-message = Default content");
+        let manager = I18nManager::new(SupportedLanguage::En, Arc::new(provider)).await?;
+        // As left behind by a partial load
+        manager.bundles.write().await.remove("de");
+
+        let message = manager
+            .format_message(SupportedLanguage::De, "test-message", None)
+            .await?;
+        assert_eq!(message, "Default content");
+        Ok(())
+    }
+
+    #[test]
+    fn test_json_translations_convert_to_fluent() -> AppResult<()> {
+        let source = LocaleFormat::Json.to_fluent(
+            r#"{"greeting": "Hallo { $name }", "notice": "Erste Zeile\nZweite Zeile"}"#.to_string(),
+        )?;
+        assert_eq!(
+            source,
+            "greeting = Hallo { $name }\nnotice = Erste Zeile\n    Zweite Zeile\n"
+        );
+
+        assert!(LocaleFormat::Json
+            .to_fluent(r#"["not", "a", "map"]"#.to_string())
+            .is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_file_provider_loads_json_translations() -> AppResult<()> {
+        let dir = std::env::temp_dir().join(format!("acci-locales-{}", uuid::Uuid::new_v4()));
+        for lang in SupportedLanguage::iter() {
+            let lang_dir = dir.join(lang.as_str());
+            fs::create_dir_all(&lang_dir)?;
+            if lang == SupportedLanguage::De {
+                fs::write(
+                    lang_dir.join("main.json"),
+                    r#"{"welcome": "Willkommen, { $name }!"}"#,
+                )?;
+            } else {
+                fs::write(lang_dir.join("main.ftl"), "welcome = Welcome, { $name }!")?;
+            }
+        }
+
+        let manager = I18nManager::new(
+            SupportedLanguage::En,
+            Arc::new(FileResourceProvider::new(&dir)),
+        )
+        .await;
+        fs::remove_dir_all(&dir)?;
+
+        let args = HashMap::from([("name".to_string(), "Ada".to_string())]);
+        let message = manager?
+            .format_message(SupportedLanguage::De, "welcome", Some(args))
+            .await?;
+        // Fluent wraps arguments in bidi isolation marks
+        assert_eq!(message, "Willkommen, \u{2068}Ada\u{2069}!");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_multi_step_fallback_chain() -> AppResult<()> {
+        let provider = TestResourceProvider::new()
+            .with_resource(SupportedLanguage::Es, "test-message = Mensaje de prueba");
+        let manager = I18nManager::new(SupportedLanguage::En, Arc::new(provider))
+            .await?
+            .with_fallback_chain("es-MX", &["es-419", "es"]);
+
+        // es-MX and es-419 have no bundle, so the chain resolves to es
+        let bundle = manager.get_bundle("es-MX").await?;
+        let msg = bundle.get_message("test-message").expect("message");
+        let value = bundle.format_pattern(msg.value().expect("value"), None, &mut vec![]);
+        assert_eq!(value, "Mensaje de prueba");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_exhausted_fallback_chain_uses_default() -> AppResult<()> {
+        let provider = TestResourceProvider::new()
+            .with_resource(SupportedLanguage::En, "test-message = Default content");
+        let manager = I18nManager::new(SupportedLanguage::En, Arc::new(provider))
+            .await?
+            .with_fallback_chain("pt-BR", &["pt-PT", "pt"]);
+
+        let bundle = manager.get_bundle("pt-BR").await?;
+        let msg = bundle.get_message("test-message").expect("message");
+        let value = bundle.format_pattern(msg.value().expect("value"), None, &mut vec![]);
+        assert_eq!(value, "Default content");
+        Ok(())
+    }
+
+    #[test]
+    fn test_numbers_use_locale_separators() -> AppResult<()> {
+        assert_eq!(
+            SupportedLanguage::En.format_number(1234567.891)?,
+            "1,234,567.891"
+        );
+        assert_eq!(
+            SupportedLanguage::De.format_number(1234567.891)?,
+            "1.234.567,891"
+        );
+        assert_eq!(SupportedLanguage::De.format_number(-0.5)?, "-0,5");
+        assert!(SupportedLanguage::En.format_number(f64::NAN).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_datetimes_use_locale_conventions() -> AppResult<()> {
+        let dt = DateTime::parse_from_rfc3339("2022-08-31T13:02:03Z")
+            .expect("valid timestamp")
+            .with_timezone(&Utc);
+        assert_eq!(
+            SupportedLanguage::En.format_datetime(&dt)?,
+            "Aug 31, 2022, 1:02\u{202f}PM"
+        );
+        assert_eq!(
+            SupportedLanguage::De.format_datetime(&dt)?,
+            "31.08.2022, 13:02"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_messages_format_numbers_and_dates_by_language() -> AppResult<()> {
+        let content = "usage = { NUMBER($count) } at { DATETIME($at) }";
+        let provider = TestResourceProvider::new()
+            .with_resource(SupportedLanguage::En, content)
+            .with_resource(SupportedLanguage::De, content);
+        let manager = I18nManager::new(SupportedLanguage::En, Arc::new(provider)).await?;
+        let args = HashMap::from([
+            ("count".to_string(), "1234.5".to_string()),
+            ("at".to_string(), "2022-08-31T13:02:03Z".to_string()),
+        ]);
+
+        let message = manager
+            .format_message(SupportedLanguage::De, "usage", Some(args.clone()))
+            .await?;
+        assert_eq!(
+            message,
+            "\u{2068}1.234,5\u{2069} at \u{2068}31.08.2022, 13:02\u{2069}"
+        );
+
+        let message = manager
+            .format_message(SupportedLanguage::En, "usage", Some(args))
+            .await?;
+        assert!(message.starts_with("\u{2068}1,234.5\u{2069} at "));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_unparsable_format_arguments_do_not_fail_the_message() -> AppResult<()> {
+        let provider = TestResourceProvider::new().with_resource(
+            SupportedLanguage::En,
+            "usage = { NUMBER($count) } at { DATETIME($at) }",
+        );
+        let manager = I18nManager::new(SupportedLanguage::En, Arc::new(provider)).await?;
+        let args = HashMap::from([
+            ("count".to_string(), "many".to_string()),
+            ("at".to_string(), "yesterday".to_string()),
+        ]);
+
+        let message = manager
+            .format_message(SupportedLanguage::En, "usage", Some(args))
+            .await?;
+        assert!(message.contains(" at "));
+        Ok(())
+    }
 }
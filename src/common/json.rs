@@ -0,0 +1,183 @@
+use axum::{
+    extract::{FromRequest, Request},
+    http::header,
+};
+use serde::de::DeserializeOwned;
+
+use crate::common::{
+    config::JsonLimitSettings,
+    error::{AppError, AppResult},
+    middleware::content_type::is_json,
+};
+use crate::infrastructure::state::AppState;
+
+/// JSON body extractor that enforces the configured `[json_limits]` before
+/// deserializing, so oversized or deeply nested payloads are rejected with
+/// 422 instead of being handed to serde.
+pub struct LimitedJson<T>(pub T);
+
+impl<T> FromRequest<AppState> for LimitedJson<T>
+where
+    T: DeserializeOwned,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &AppState) -> Result<Self, Self::Rejection> {
+        parse_limited(req, &state.config.json_limits)
+            .await
+            .map(LimitedJson)
+    }
+}
+
+async fn parse_limited<T: DeserializeOwned>(
+    req: Request,
+    limits: &JsonLimitSettings,
+) -> AppResult<T> {
+    let is_json = req
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(is_json);
+    if !is_json {
+        return Err(AppError::validation(
+            "Expected request with `Content-Type: application/json`",
+        ));
+    }
+
+    let body = axum::body::to_bytes(req.into_body(), limits.max_bytes)
+        .await
+        .map_err(|_| {
+            AppError::payload(format!(
+                "JSON body exceeds the limit of {} bytes",
+                limits.max_bytes
+            ))
+        })?;
+
+    if exceeds_depth(&body, limits.max_depth) {
+        return Err(AppError::payload(format!(
+            "JSON body exceeds the maximum nesting depth of {}",
+            limits.max_depth
+        )));
+    }
+
+    serde_json::from_slice(&body).map_err(|e| AppError::payload(e.to_string()))
+}
+
+/// Scans raw JSON for object/array nesting deeper than `max_depth` without
+/// building any intermediate value
+fn exceeds_depth(bytes: &[u8], max_depth: usize) -> bool {
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for &byte in bytes {
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if byte == b'\\' {
+                escaped = true;
+            } else if byte == b'"' {
+                in_string = false;
+            }
+            continue;
+        }
+
+        match byte {
+            b'"' => in_string = true,
+            b'{' | b'[' => {
+                depth += 1;
+                if depth > max_depth {
+                    return true;
+                }
+            },
+            b'}' | b']' => depth = depth.saturating_sub(1),
+            _ => {},
+        }
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::StatusCode, response::IntoResponse};
+    use serde_json::Value;
+
+    fn json_request(body: String) -> Request {
+        request_of_type("application/json", body)
+    }
+
+    fn request_of_type(content_type: &str, body: String) -> Request {
+        Request::builder()
+            .method("POST")
+            .uri("/tenants")
+            .header(header::CONTENT_TYPE, content_type)
+            .body(Body::from(body))
+            .expect("valid request")
+    }
+
+    #[test]
+    fn test_depth_ignores_brackets_in_strings() {
+        assert!(!exceeds_depth(br#"{"a": "[[[[{{{{\"]]"}"#, 1));
+        assert!(exceeds_depth(br#"{"a": [1]}"#, 1));
+        assert!(!exceeds_depth(br#"{"a": [1]}"#, 2));
+    }
+
+    #[tokio::test]
+    async fn test_deeply_nested_body_is_rejected() {
+        let limits = JsonLimitSettings::default();
+        let nested = format!("{}{}", "[".repeat(10_000), "]".repeat(10_000));
+
+        let err = parse_limited::<Value>(json_request(nested), &limits)
+            .await
+            .expect_err("nested body should be rejected");
+        assert_eq!(
+            err.into_response().status(),
+            StatusCode::UNPROCESSABLE_ENTITY
+        );
+    }
+
+    #[tokio::test]
+    async fn test_oversized_body_is_rejected() {
+        let limits = JsonLimitSettings {
+            max_bytes: 16,
+            ..Default::default()
+        };
+        let body = format!(r#"{{"name": "{}"}}"#, "x".repeat(64));
+
+        let err = parse_limited::<Value>(json_request(body), &limits)
+            .await
+            .expect_err("oversized body should be rejected");
+        assert_eq!(
+            err.into_response().status(),
+            StatusCode::UNPROCESSABLE_ENTITY
+        );
+    }
+
+    #[tokio::test]
+    async fn test_body_within_limits_is_parsed() -> AppResult<()> {
+        let limits = JsonLimitSettings::default();
+        let value: Value =
+            parse_limited(json_request(r#"{"a": {"b": [1, 2]}}"#.to_string()), &limits).await?;
+        assert_eq!(value["a"]["b"][1], 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_json_media_types_are_parsed() -> AppResult<()> {
+        let limits = JsonLimitSettings::default();
+        for content_type in [
+            "application/merge-patch+json",
+            "Application/JSON; charset=utf-8",
+        ] {
+            let request = request_of_type(content_type, r#"{"a": 1}"#.to_string());
+            let value: Value = parse_limited(request, &limits).await?;
+            assert_eq!(value["a"], 1, "{}", content_type);
+        }
+
+        let request = request_of_type("application/jsonp", r#"{"a": 1}"#.to_string());
+        assert!(parse_limited::<Value>(request, &limits).await.is_err());
+        Ok(())
+    }
+}
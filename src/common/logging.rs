@@ -34,6 +34,29 @@ pub fn init() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+/// Installs a debug-level subscriber writing to the test output. Safe to call
+/// from every test: only the first call installs anything, and a subscriber
+/// that is already in place is left alone.
+#[cfg(test)]
+pub fn init_for_tests() {
+    static INIT: std::sync::Once = std::sync::Once::new();
+
+    INIT.call_once(|| {
+        let formatting_layer = fmt::layer()
+            .with_test_writer()
+            .with_target(true)
+            .with_thread_ids(true)
+            .with_file(true)
+            .with_line_number(true)
+            .with_span_events(FmtSpan::NEW | FmtSpan::CLOSE);
+
+        let _ = tracing_subscriber::registry()
+            .with(EnvFilter::new("debug"))
+            .with(formatting_layer)
+            .try_init();
+    });
+}
+
 #[allow(clippy::disallowed_methods)]
 #[allow(dead_code)]
 pub fn request_span(
@@ -125,29 +148,10 @@ pub async fn setup_request_span(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::sync::Once;
     use tracing::{event, Level};
-    use tracing_subscriber::fmt::TestWriter;
-
-    static INIT: Once = Once::new();
 
     fn setup_test_logging() {
-        INIT.call_once(|| {
-            let env_filter = EnvFilter::new("debug");
-            let _test_writer = TestWriter::new();
-            let formatting_layer = fmt::layer()
-                .with_test_writer()
-                .with_target(true)
-                .with_thread_ids(true)
-                .with_file(true)
-                .with_line_number(true)
-                .with_span_events(FmtSpan::NEW | FmtSpan::CLOSE);
-
-            tracing_subscriber::registry()
-                .with(env_filter)
-                .with(formatting_layer)
-                .init();
-        });
+        init_for_tests();
     }
 
     #[test]
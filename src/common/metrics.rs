@@ -21,6 +21,18 @@ pub fn init_metrics() -> Result<PrometheusHandle, AppError> {
         .map_err(|e| AppError::configuration(format!("Failed to install metrics recorder: {}", e)))
 }
 
+/// Handle of the recorder installed by `init_metrics`, for tests that build
+/// the app more than once. The global recorder can only be installed once
+/// per process, so later calls return the same handle.
+#[cfg(test)]
+pub fn init_for_tests() -> PrometheusHandle {
+    static HANDLE: std::sync::OnceLock<PrometheusHandle> = std::sync::OnceLock::new();
+
+    HANDLE
+        .get_or_init(|| init_metrics().expect("no other metrics recorder installed"))
+        .clone()
+}
+
 /// Record an HTTP request
 #[allow(dead_code)]
 pub fn record_request(path: &str, method: &str, status: u16, duration: Duration) {
@@ -0,0 +1,185 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::State,
+    http::{header, HeaderMap, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use tracing::{debug, error};
+
+use crate::common::error::ErrorKind;
+use crate::common::middleware::auth::{record_auth_failure, AuthFailureReason, UserInfo};
+use crate::domain::api_key::{ApiKey, ApiKeyService};
+
+/// Authentication method reported in `UserInfo::auth_methods` for API keys
+pub const API_KEY_AUTH_METHOD: &str = "api_key";
+
+#[derive(Clone)]
+pub struct ApiKeyState {
+    pub api_keys: Arc<dyn ApiKeyService>,
+}
+
+impl ApiKeyState {
+    pub fn new(api_keys: Arc<dyn ApiKeyService>) -> Self {
+        Self { api_keys }
+    }
+}
+
+/// Extracts the key from an `Authorization: ApiKey <key>` header
+fn api_key(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("ApiKey ")
+}
+
+impl From<ApiKey> for UserInfo {
+    fn from(api_key: ApiKey) -> Self {
+        Self {
+            sub: format!("api-key:{}", api_key.id),
+            preferred_username: api_key.name,
+            email: None,
+            roles: Vec::new(),
+            tenant_id: Some(api_key.tenant_id.to_string()),
+            permissions: api_key.scopes.into_iter().collect(),
+            auth_methods: vec![API_KEY_AUTH_METHOD.to_string()],
+        }
+    }
+}
+
+/// Authenticates requests carrying `Authorization: ApiKey <key>` as an
+/// alternative to Keycloak tokens.
+///
+/// The key's tenant and scopes become the `UserInfo` of the request, so the
+/// tenant and permission checks further in treat it like any other caller.
+/// Requests with another kind of credential pass through untouched; unknown,
+/// revoked and expired keys are rejected with 401.
+pub async fn api_key_middleware(
+    State(state): State<ApiKeyState>,
+    mut req: Request<Body>,
+    next: Next,
+) -> Response {
+    let Some(key) = api_key(req.headers()) else {
+        return next.run(req).await;
+    };
+
+    match state.api_keys.authenticate(key.trim()).await {
+        Ok(api_key) => {
+            debug!(
+                api_key_id = %api_key.id,
+                tenant_id = %api_key.tenant_id,
+                "API key validated successfully"
+            );
+            req.extensions_mut().insert(UserInfo::from(api_key));
+            next.run(req).await
+        },
+        Err(e) if matches!(*e.kind, ErrorKind::AuthenticationError(_)) => {
+            record_auth_failure(AuthFailureReason::InvalidApiKey, Some(&e));
+            StatusCode::UNAUTHORIZED.into_response()
+        },
+        Err(e) => {
+            error!("Failed to authenticate API key: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::get, Extension, Router};
+    use chrono::Utc;
+    use sea_orm::{DatabaseBackend, MockDatabase};
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    use crate::domain::api_key::hash_api_key;
+    use crate::infrastructure::database::entities::api_key;
+    use crate::infrastructure::services::api_key_service::ApiKeyServiceImpl;
+
+    async fn whoami(user: Option<Extension<UserInfo>>) -> String {
+        match user {
+            Some(Extension(user)) => format!(
+                "{}|{}",
+                user.tenant_id.as_deref().unwrap_or_default(),
+                user.has_permission("tenant:read")
+            ),
+            None => "anonymous".to_string(),
+        }
+    }
+
+    fn app(stored: Vec<api_key::Model>) -> Router {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![stored])
+            .into_connection();
+        Router::new()
+            .route("/whoami", get(whoami))
+            .layer(axum::middleware::from_fn_with_state(
+                ApiKeyState::new(Arc::new(ApiKeyServiceImpl::new(Arc::new(db)))),
+                api_key_middleware,
+            ))
+    }
+
+    fn stored_key(key: &str, revoked: bool) -> api_key::Model {
+        api_key::Model {
+            id: Uuid::new_v4(),
+            tenant_id: Uuid::new_v4(),
+            name: "billing-sync".to_string(),
+            key_hash: hash_api_key(key),
+            scopes: serde_json::to_value(vec!["tenant:read"]).expect("serializable scopes"),
+            created_at: Utc::now().into(),
+            expires_at: None,
+            revoked_at: revoked.then(|| Utc::now().into()),
+        }
+    }
+
+    async fn call(app: Router, authorization: &str) -> (StatusCode, String) {
+        let request = Request::builder()
+            .uri("/whoami")
+            .header(header::AUTHORIZATION, authorization)
+            .body(Body::empty())
+            .expect("valid request");
+        let response = app.oneshot(request).await.expect("response");
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("response body");
+        (
+            status,
+            String::from_utf8(body.to_vec()).expect("utf-8 body"),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_valid_key_sets_tenant_and_scopes() {
+        let stored = stored_key("acci_valid", false);
+        let tenant_id = stored.tenant_id;
+
+        let (status, body) = call(app(vec![stored]), "ApiKey acci_valid").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, format!("{}|true", tenant_id));
+    }
+
+    #[tokio::test]
+    async fn test_revoked_and_unknown_keys_are_rejected() {
+        let (status, _) = call(
+            app(vec![stored_key("acci_revoked", true)]),
+            "ApiKey acci_revoked",
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let (status, _) = call(app(vec![]), "ApiKey acci_unknown").await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_other_credentials_pass_through() {
+        let (status, body) = call(app(vec![]), "Bearer some.jwt.token").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, "anonymous");
+    }
+}
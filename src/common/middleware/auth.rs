@@ -11,24 +11,33 @@
 //! - Redis-based JWKS caching
 //! - Comprehensive metrics and monitoring
 
-use std::sync::Arc;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use std::convert::Infallible;
 
 use axum::{
     body::Body,
-    extract::State,
-    http::{Request, StatusCode},
+    extract::{FromRef, FromRequestParts, State},
+    http::{request::Parts, HeaderMap, Request, StatusCode},
     middleware::Next,
     response::Response,
 };
-use jsonwebtoken::{decode, Algorithm, DecodingKey, Validation};
+use jsonwebtoken::{decode, errors::ErrorKind as JwtErrorKind, Algorithm, DecodingKey, Validation};
 use metrics::{counter, histogram};
 use oauth2::{basic::BasicClient, AuthUrl, ClientId, ClientSecret, TokenUrl};
 use redis::AsyncCommands;
 use reqwest;
 use serde::{Deserialize, Serialize};
-use tracing::{debug, error, info, instrument, warn};
+use tracing::{debug, info, instrument, warn};
 
-use crate::common::{config::AppConfig, error::AppError};
+use crate::common::{
+    config::{AppConfig, RoleSource},
+    error::{AppError, ErrorKind},
+};
+use crate::infrastructure::{http_client::outbound_client, redis::RedisKeys};
 
 #[allow(dead_code)]
 const JWKS_CACHE_KEY: &str = "keycloak:jwks";
@@ -40,6 +49,11 @@ pub struct AuthState {
     pub config: Arc<AppConfig>,
     pub oauth_client: Arc<BasicClient>,
     pub redis_client: Arc<redis::Client>,
+    /// Shared by all JWKS fetches, so they reuse pooled connections
+    pub http_client: reqwest::Client,
+    /// Let requests without a bearer token through anonymously instead of
+    /// rejecting them
+    pub optional: bool,
 }
 
 /// Claims extracted from the JWT token
@@ -53,8 +67,89 @@ pub struct Claims {
     pub email: Option<String>,
     /// Realm access containing roles
     pub realm_access: Option<RealmAccess>,
+    /// Client roles, keyed by client id
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resource_access: Option<HashMap<String, ResourceAccess>>,
+    /// Paths of the groups the user belongs to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub groups: Option<Vec<String>>,
     /// Token expiration timestamp
     pub exp: usize,
+    /// Token issue timestamp
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iat: Option<usize>,
+    /// Tenant id, for realms that map it into a `tenant_id` claim
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant_id: Option<String>,
+    /// Authentication methods used to obtain the token (RFC 8176)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub amr: Option<Vec<String>>,
+    /// Claims not modelled above, so a differently named tenant claim can
+    /// still be read
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+impl Claims {
+    /// Returns the tenant id carried in the claim named `claim`
+    pub fn tenant_claim(&self, claim: &str) -> Option<&str> {
+        if claim == "tenant_id" {
+            return self.tenant_id.as_deref();
+        }
+        self.extra.get(claim).and_then(serde_json::Value::as_str)
+    }
+
+    /// Returns the roles found in the trusted `sources`, merged in source
+    /// order without duplicates, or `None` when the token carries none of
+    /// those claims. `client_id` selects the client roles that apply.
+    pub fn roles(&self, sources: &[RoleSource], client_id: &str) -> Option<Vec<String>> {
+        let mut roles: Option<Vec<String>> = None;
+        for source in sources {
+            let found: Option<Vec<&str>> = match source {
+                RoleSource::Realm => self
+                    .realm_access
+                    .as_ref()
+                    .map(|access| access.roles.iter().map(String::as_str).collect()),
+                RoleSource::Client => self
+                    .resource_access
+                    .as_ref()
+                    .and_then(|clients| clients.get(client_id))
+                    .map(|access| access.roles.iter().map(String::as_str).collect()),
+                RoleSource::Groups => self.groups.as_ref().map(|groups| {
+                    groups
+                        .iter()
+                        .filter_map(|group| group.rsplit('/').next())
+                        .filter(|name| !name.is_empty())
+                        .collect()
+                }),
+            };
+            let Some(found) = found else {
+                continue;
+            };
+
+            let roles = roles.get_or_insert_with(Vec::new);
+            for role in found {
+                if !roles.iter().any(|known| known == role) {
+                    roles.push(role.to_string());
+                }
+            }
+        }
+        roles
+    }
+
+    /// Returns the authentication methods the token was obtained with, taken
+    /// from `amr` or, for realms that only issue it, the `acr` claim
+    pub fn auth_methods(&self) -> Vec<String> {
+        match &self.amr {
+            Some(amr) => amr.clone(),
+            None => self
+                .extra
+                .get("acr")
+                .and_then(serde_json::Value::as_str)
+                .map(|acr| vec![acr.to_string()])
+                .unwrap_or_default(),
+        }
+    }
 }
 
 /// Realm access containing user roles
@@ -64,6 +159,13 @@ pub struct RealmAccess {
     pub roles: Vec<String>,
 }
 
+/// Roles of the user for one client
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceAccess {
+    /// List of client roles assigned to the user
+    pub roles: Vec<String>,
+}
+
 /// JWKS (JSON Web Key Set) structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Jwks {
@@ -98,6 +200,83 @@ pub struct UserInfo {
     pub roles: Vec<String>,
     /// Tenant identifier (optional)
     pub tenant_id: Option<String>,
+    /// Permissions granted by the user's roles
+    pub permissions: HashSet<String>,
+    /// Authentication methods the token was obtained with
+    pub auth_methods: Vec<String>,
+}
+
+/// Bounded set of reasons an authentication attempt can fail, used as the
+/// `reason` label of `auth_failures_total` and in the failure log
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthFailureReason {
+    MissingToken,
+    Malformed,
+    Expired,
+    BadSignature,
+    UnknownKid,
+    InvalidClaims,
+    MissingRoles,
+    KeysUnavailable,
+    TooOld,
+    InvalidApiKey,
+}
+
+impl AuthFailureReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::MissingToken => "missing_token",
+            Self::Malformed => "malformed",
+            Self::Expired => "expired",
+            Self::BadSignature => "bad_signature",
+            Self::UnknownKid => "unknown_kid",
+            Self::InvalidClaims => "invalid_claims",
+            Self::MissingRoles => "missing_roles",
+            Self::KeysUnavailable => "keys_unavailable",
+            Self::TooOld => "too_old",
+            Self::InvalidApiKey => "invalid_api_key",
+        }
+    }
+
+    fn from_jwt_error(error: &jsonwebtoken::errors::Error) -> Self {
+        match error.kind() {
+            JwtErrorKind::ExpiredSignature => Self::Expired,
+            JwtErrorKind::InvalidSignature => Self::BadSignature,
+            JwtErrorKind::InvalidIssuer
+            | JwtErrorKind::InvalidAudience
+            | JwtErrorKind::InvalidSubject
+            | JwtErrorKind::ImmatureSignature
+            | JwtErrorKind::MissingRequiredClaim(_) => Self::InvalidClaims,
+            _ => Self::Malformed,
+        }
+    }
+}
+
+/// A failed authentication attempt together with its classified reason
+#[derive(Debug)]
+pub struct AuthFailure {
+    pub reason: AuthFailureReason,
+    pub error: AppError,
+}
+
+impl AuthFailure {
+    fn new(reason: AuthFailureReason, error: AppError) -> Self {
+        Self { reason, error }
+    }
+}
+
+impl From<AuthFailure> for AppError {
+    fn from(failure: AuthFailure) -> Self {
+        failure.error
+    }
+}
+
+impl UserInfo {
+    /// Checks whether any of the user's roles grants the given permission
+    #[allow(dead_code)]
+    pub fn has_permission(&self, permission: &str) -> bool {
+        self.permissions.contains(permission)
+    }
 }
 
 #[allow(dead_code)]
@@ -135,13 +314,25 @@ impl AuthState {
             ),
         );
 
+        let http_client = outbound_client(&config.http_client)?;
+
         Ok(Self {
             config,
             oauth_client: Arc::new(client),
             redis_client,
+            http_client,
+            optional: false,
         })
     }
 
+    /// Passes requests without a bearer token through untouched, for
+    /// mounting in front of routes that don't all require a login. Invalid
+    /// tokens are still rejected.
+    pub fn optional(mut self) -> Self {
+        self.optional = true;
+        self
+    }
+
     /// Retrieves the JWKS from cache or Keycloak
     ///
     /// First attempts to get the JWKS from Redis cache. If not found or invalid,
@@ -154,9 +345,11 @@ impl AuthState {
             .await
             .map_err(|e| AppError::authentication(format!("Redis connection failed: {}", e)))?;
 
+        let cache_key = RedisKeys::new(self.config.redis.key_prefix.clone()).global(JWKS_CACHE_KEY);
+
         // Use AsyncCommands trait for Redis operations
         let cached_jwks: Option<String> = redis_conn
-            .get(JWKS_CACHE_KEY)
+            .get(&cache_key)
             .await
             .map_err(|e| AppError::authentication(format!("Redis get failed: {}", e)))?;
 
@@ -167,21 +360,7 @@ impl AuthState {
             }
         }
 
-        // Fetch new JWKS from Keycloak
-        debug!("Fetching new JWKS from Keycloak");
-        let jwks_url = format!(
-            "{}/realms/{}/protocol/openid-connect/certs",
-            self.config.keycloak.url, self.config.keycloak.realm
-        );
-
-        let jwks: Jwks = reqwest::Client::new()
-            .get(&jwks_url)
-            .send()
-            .await
-            .map_err(|e| AppError::authentication(format!("Failed to fetch JWKS: {}", e)))?
-            .json()
-            .await
-            .map_err(|e| AppError::authentication(format!("Failed to parse JWKS: {}", e)))?;
+        let jwks = self.fetch_jwks().await?;
 
         // Cache the JWKS
         let jwks_str = serde_json::to_string(&jwks)
@@ -189,7 +368,7 @@ impl AuthState {
 
         let _: () = redis_conn
             .set_ex(
-                JWKS_CACHE_KEY,
+                &cache_key,
                 jwks_str,
                 self.config.keycloak.public_key_cache_ttl,
             )
@@ -199,28 +378,62 @@ impl AuthState {
         Ok(jwks)
     }
 
+    /// Fetches the JWKS from Keycloak, bypassing the cache
+    pub(crate) async fn fetch_jwks(&self) -> Result<Jwks, AppError> {
+        debug!("Fetching new JWKS from Keycloak");
+        let jwks_url = format!(
+            "{}/realms/{}/protocol/openid-connect/certs",
+            self.config.keycloak.url, self.config.keycloak.realm
+        );
+
+        self.http_client
+            .get(&jwks_url)
+            .send()
+            .await
+            .map_err(|e| AppError::authentication(format!("Failed to fetch JWKS: {}", e)))?
+            .json()
+            .await
+            .map_err(|e| AppError::authentication(format!("Failed to parse JWKS: {}", e)))
+    }
+
     /// Creates a JWT decoding key from JWKS
-    fn create_decoding_key(jwks: &Jwks, token: &str) -> Result<DecodingKey, AppError> {
+    pub(crate) fn create_decoding_key(
+        jwks: &Jwks,
+        token: &str,
+    ) -> Result<DecodingKey, AuthFailure> {
         // Extract kid from token header if available
         let header = jsonwebtoken::decode_header(token).map_err(|e| {
-            AppError::authentication(format!("Failed to decode token header: {}", e))
+            AuthFailure::new(
+                AuthFailureReason::Malformed,
+                AppError::authentication(format!("Failed to decode token header: {}", e)),
+            )
         })?;
 
         let key = if let Some(kid) = header.kid {
             // Find the key with matching kid
             jwks.keys.iter().find(|k| k.kid == kid).ok_or_else(|| {
-                AppError::authentication(format!("No key found with kid: {}", kid))
+                AuthFailure::new(
+                    AuthFailureReason::UnknownKid,
+                    AppError::authentication(format!("No key found with kid: {}", kid)),
+                )
             })?
         } else {
             // Fallback to first key if no kid in token
-            jwks.keys
-                .first()
-                .ok_or_else(|| AppError::authentication("No keys found in JWKS".to_string()))?
+            jwks.keys.first().ok_or_else(|| {
+                AuthFailure::new(
+                    AuthFailureReason::UnknownKid,
+                    AppError::authentication("No keys found in JWKS".to_string()),
+                )
+            })?
         };
 
         // Convert RSA components to PEM format
-        DecodingKey::from_rsa_components(&key.n, &key.e)
-            .map_err(|e| AppError::authentication(format!("Failed to create decoding key: {}", e)))
+        DecodingKey::from_rsa_components(&key.n, &key.e).map_err(|e| {
+            AuthFailure::new(
+                AuthFailureReason::KeysUnavailable,
+                AppError::authentication(format!("Failed to create decoding key: {}", e)),
+            )
+        })
     }
 
     /// Validates a Keycloak token and extracts user information
@@ -233,6 +446,12 @@ impl AuthState {
     ///
     /// Returns a Result containing UserInfo or an AppError
     pub async fn validate_keycloak_token(&self, token: &str) -> Result<UserInfo, AppError> {
+        self.authenticate(token).await.map_err(AppError::from)
+    }
+
+    /// Validates a token like `validate_keycloak_token`, but keeps the
+    /// classified failure reason for metrics and audit logging
+    pub async fn authenticate(&self, token: &str) -> Result<UserInfo, AuthFailure> {
         // Test mode with simplified validation
         if !self.config.keycloak.verify_token {
             warn!("Running in test mode - token verification is disabled!");
@@ -252,33 +471,21 @@ impl AuthState {
 
             // Validate the token structure
             let token_data = decode::<Claims>(token, &key, &validation).map_err(|e| {
-                AppError::authentication(format!("Test token validation failed: {}", e))
+                AuthFailure::new(
+                    AuthFailureReason::from_jwt_error(&e),
+                    AppError::authentication(format!("Test token validation failed: {}", e)),
+                )
             })?;
 
             debug!("Test mode: Successfully validated token structure");
 
-            let tenant_id = token_data.claims.realm_access.as_ref().and_then(|access| {
-                access
-                    .roles
-                    .iter()
-                    .find(|role| role.starts_with("tenant_"))
-                    .map(|role| role.trim_start_matches("tenant_").to_string())
-            });
-
-            return Ok(UserInfo {
-                sub: token_data.claims.sub,
-                preferred_username: token_data.claims.preferred_username,
-                email: token_data.claims.email,
-                roles: token_data
-                    .claims
-                    .realm_access
-                    .map(|access| access.roles)
-                    .unwrap_or_default(),
-                tenant_id,
-            });
+            return self.build_user_info(token_data.claims);
         }
 
-        let jwks = self.get_jwks().await?;
+        let jwks = self
+            .get_jwks()
+            .await
+            .map_err(|e| AuthFailure::new(AuthFailureReason::KeysUnavailable, e))?;
         let key = Self::create_decoding_key(&jwks, token)?;
 
         let mut validation = Validation::new(Algorithm::RS256);
@@ -288,30 +495,92 @@ impl AuthState {
             self.config.keycloak.url, self.config.keycloak.realm
         )]);
 
-        let token_data = decode::<Claims>(token, &key, &validation)
-            .map_err(|e| AppError::authentication(format!("Token validation failed: {}", e)))?;
+        let token_data = decode::<Claims>(token, &key, &validation).map_err(|e| {
+            AuthFailure::new(
+                AuthFailureReason::from_jwt_error(&e),
+                AppError::authentication(format!("Token validation failed: {}", e)),
+            )
+        })?;
+
+        self.build_user_info(token_data.claims)
+    }
 
-        let tenant_id = token_data.claims.realm_access.as_ref().and_then(|access| {
-            access
-                .roles
+    /// Builds the user information from validated claims
+    ///
+    /// Takes the tenant from the configured tenant claim, falling back to
+    /// `tenant_`-prefixed roles, and resolves the permissions granted by the
+    /// user's roles from the configured mapping.
+    /// Roles are merged from the configured `role_sources`. Fails with an
+    /// authorization error when roles are required but the token carries
+    /// none of those claims.
+    fn build_user_info(&self, claims: Claims) -> Result<UserInfo, AuthFailure> {
+        self.check_token_age(&claims)?;
+
+        let claimed_tenant = claims
+            .tenant_claim(&self.config.keycloak.tenant_claim)
+            .map(str::to_string);
+        let auth_methods = claims.auth_methods();
+
+        let keycloak = &self.config.keycloak;
+        let roles = match claims.roles(&keycloak.role_sources, &keycloak.client_id) {
+            Some(roles) => roles,
+            None if self.config.keycloak.require_roles => {
+                return Err(AuthFailure::new(
+                    AuthFailureReason::MissingRoles,
+                    AppError::authorization("no roles in token"),
+                ));
+            },
+            None => Vec::new(),
+        };
+
+        let tenant_id = claimed_tenant.or_else(|| {
+            roles
                 .iter()
                 .find(|role| role.starts_with("tenant_"))
                 .map(|role| role.trim_start_matches("tenant_").to_string())
         });
 
+        let permissions = self.config.permissions.for_roles(&roles);
+
         Ok(UserInfo {
-            sub: token_data.claims.sub,
-            preferred_username: token_data.claims.preferred_username,
-            email: token_data.claims.email,
-            roles: token_data
-                .claims
-                .realm_access
-                .map(|access| access.roles)
-                .unwrap_or_default(),
+            sub: claims.sub,
+            preferred_username: claims.preferred_username,
+            email: claims.email,
+            roles,
             tenant_id,
+            permissions,
+            auth_methods,
         })
     }
 
+    /// Enforces `keycloak.max_token_age` on the token's `iat`. Tokens without
+    /// `iat` are rejected when a limit is configured, since their age is
+    /// unknown.
+    fn check_token_age(&self, claims: &Claims) -> Result<(), AuthFailure> {
+        let Some(max_age) = self.config.keycloak.max_token_age else {
+            return Ok(());
+        };
+
+        let now = chrono::Utc::now().timestamp().max(0) as u64;
+        match claims.iat {
+            Some(iat) if now.saturating_sub(iat as u64) <= max_age => Ok(()),
+            Some(iat) => Err(AuthFailure::new(
+                AuthFailureReason::TooOld,
+                AppError::authentication(format!(
+                    "Token was issued {}s ago, exceeding the maximum token age of {}s",
+                    now.saturating_sub(iat as u64),
+                    max_age
+                )),
+            )),
+            None => Err(AuthFailure::new(
+                AuthFailureReason::TooOld,
+                AppError::authentication(
+                    "Token has no issue time (iat) but a maximum token age is configured",
+                ),
+            )),
+        }
+    }
+
     /// Verifies if a user has a specific role
     ///
     /// # Arguments
@@ -364,6 +633,68 @@ impl AuthState {
     }
 }
 
+/// Counts and logs a failed authentication attempt by reason
+pub(crate) fn record_auth_failure(reason: AuthFailureReason, error: Option<&AppError>) {
+    counter!("auth_failures_total", "reason" => reason.as_str()).increment(1);
+    warn!(reason = reason.as_str(), error = ?error, "Authentication failed");
+}
+
+/// Extracts the Bearer token from the Authorization header
+pub(crate) fn bearer_token(headers: &HeaderMap) -> Result<String, AuthFailureReason> {
+    let value = headers
+        .get("Authorization")
+        .ok_or(AuthFailureReason::MissingToken)?;
+
+    value
+        .to_str()
+        .ok()
+        .and_then(|header| header.strip_prefix("Bearer "))
+        .filter(|token| !token.is_empty())
+        .map(|token| token.to_string())
+        .ok_or(AuthFailureReason::Malformed)
+}
+
+/// The caller's identity on routes that work with or without a login.
+///
+/// Reuses the `UserInfo` set by `auth_middleware` when it ran, and otherwise
+/// validates the bearer token itself. A missing token yields `None`, and so
+/// does an invalid one: the failure is still counted and logged, but the
+/// request carries on as anonymous instead of being rejected.
+#[derive(Debug, Clone)]
+#[allow(dead_code)]
+pub struct OptionalAuth(pub Option<UserInfo>);
+
+impl<S> FromRequestParts<S> for OptionalAuth
+where
+    AuthState: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        if let Some(user_info) = parts.extensions.get::<UserInfo>() {
+            return Ok(Self(Some(user_info.clone())));
+        }
+
+        let token = match bearer_token(&parts.headers) {
+            Ok(token) => token,
+            Err(AuthFailureReason::MissingToken) => return Ok(Self(None)),
+            Err(reason) => {
+                record_auth_failure(reason, None);
+                return Ok(Self(None));
+            },
+        };
+
+        match AuthState::from_ref(state).authenticate(&token).await {
+            Ok(user_info) => Ok(Self(Some(user_info))),
+            Err(failure) => {
+                record_auth_failure(failure.reason, Some(&failure.error));
+                Ok(Self(None))
+            },
+        }
+    }
+}
+
 /// Authentication middleware for Axum
 ///
 /// This middleware:
@@ -401,23 +732,9 @@ pub async fn auth_middleware(
         .record_auth_metrics(auth_result.is_ok(), duration)
         .await;
 
-    // Enhanced logging
-    match &auth_result {
-        Ok(_) => {
-            if let Some(user_info) = req.extensions().get::<UserInfo>() {
-                info!(
-                    tenant_id = ?user_info.tenant_id,
-                    user_id = ?user_info.sub,
-                    "Authentication successful"
-                );
-            }
-        },
-        Err(status) => {
-            warn!(
-                status = ?status,
-                "Authentication failed"
-            );
-        },
+    // The failure reason is logged where it is classified
+    if let Err(status) = &auth_result {
+        debug!(status = ?status, "Authentication rejected");
     }
 
     auth_result
@@ -429,40 +746,38 @@ async fn process_auth(
     req: &mut Request<Body>,
     next: Next,
 ) -> Result<Response, StatusCode> {
-    let auth_header = req
-        .headers()
-        .get("Authorization")
-        .and_then(|header| header.to_str().ok())
-        .and_then(|header| {
-            header
-                .strip_prefix("Bearer ")
-                .map(|token| token.to_string())
-        });
+    // Already authenticated by an API key
+    if req.extensions().get::<UserInfo>().is_some() {
+        return Ok(next.run(std::mem::take(req)).await);
+    }
 
-    let token = auth_header.ok_or_else(|| {
-        warn!("Missing authorization header");
-        StatusCode::UNAUTHORIZED
-    })?;
+    let token = match bearer_token(req.headers()) {
+        Ok(token) => token,
+        Err(AuthFailureReason::MissingToken) if state.optional => {
+            return Ok(next.run(std::mem::take(req)).await);
+        },
+        Err(reason) => {
+            record_auth_failure(reason, None);
+            return Err(StatusCode::UNAUTHORIZED);
+        },
+    };
 
-    match state.validate_keycloak_token(&token).await {
+    match state.authenticate(&token).await {
         Ok(user_info) => {
-            debug!(
-                user_id = ?user_info.sub,
+            info!(
                 tenant_id = ?user_info.tenant_id,
-                "Token validated successfully"
+                user_id = ?user_info.sub,
+                "Authentication successful"
             );
             req.extensions_mut().insert(user_info);
-            // Create a new request with the same parts but empty body
-            let mut new_req = Request::new(Body::empty());
-            *new_req.uri_mut() = req.uri().clone();
-            *new_req.method_mut() = req.method().clone();
-            *new_req.headers_mut() = req.headers().clone();
-            *new_req.extensions_mut() = req.extensions().clone();
-            Ok(next.run(new_req).await)
+            Ok(next.run(std::mem::take(req)).await)
         },
-        Err(e) => {
-            error!(error = ?e, "Token validation failed");
-            Err(StatusCode::UNAUTHORIZED)
+        Err(failure) => {
+            record_auth_failure(failure.reason, Some(&failure.error));
+            match *failure.error.kind {
+                ErrorKind::AuthorizationError(_) => Err(StatusCode::FORBIDDEN),
+                _ => Err(StatusCode::UNAUTHORIZED),
+            }
         },
     }
 }
@@ -5,7 +5,7 @@ use std::time::Instant;
 use axum::{
     body::Body,
     extract::Extension,
-    http::{Request, StatusCode},
+    http::{header::AUTHORIZATION, HeaderMap, HeaderValue, Request, StatusCode},
     response::Response,
     routing::get,
     Router,
@@ -16,8 +16,12 @@ use tokio::test;
 use tower::ServiceExt;
 
 use crate::common::{
-    config::{AppConfig, KeycloakConfig},
-    middleware::auth::{auth_middleware, AuthState, Claims, RealmAccess, UserInfo},
+    config::{AppConfig, KeycloakConfig, Permissions, RoleSource},
+    error::ErrorKind,
+    middleware::auth::{
+        auth_middleware, bearer_token, AuthFailureReason, AuthState, Claims, Jwks, JwksKey,
+        OptionalAuth, RealmAccess, ResourceAccess, UserInfo,
+    },
 };
 
 #[allow(dead_code)]
@@ -96,6 +100,41 @@ impl From<MockRedisClient> for RedisClient {
 
 // Helper function to create test configuration and state
 async fn create_test_state() -> (AuthState, Arc<AppConfig>) {
+    create_test_state_with_permissions(Permissions::default()).await
+}
+
+// Helper function to create test state that requires realm roles in tokens
+async fn create_test_state_requiring_roles() -> (AuthState, Arc<AppConfig>) {
+    let (state, config) = create_test_state().await;
+    let mut config = (*config).clone();
+    config.keycloak.require_roles = true;
+    let config = Arc::new(config);
+
+    let state = AuthState::new(config.clone(), state.redis_client)
+        .await
+        .expect("Failed to create auth state");
+
+    (state, config)
+}
+
+// Helper function to create test state trusting the given role claims
+async fn create_test_state_with_role_sources(
+    role_sources: Vec<RoleSource>,
+    permissions: Permissions,
+) -> AuthState {
+    let (state, config) = create_test_state_with_permissions(permissions).await;
+    let mut config = (*config).clone();
+    config.keycloak.role_sources = role_sources;
+
+    AuthState::new(Arc::new(config), state.redis_client)
+        .await
+        .expect("Failed to create auth state")
+}
+
+// Helper function to create test state with a role to permission mapping
+async fn create_test_state_with_permissions(
+    permissions: Permissions,
+) -> (AuthState, Arc<AppConfig>) {
     let config = Arc::new(AppConfig {
         keycloak: KeycloakConfig {
             url: "http://localhost:8080".to_string(),
@@ -104,7 +143,17 @@ async fn create_test_state() -> (AuthState, Arc<AppConfig>) {
             client_secret: "test-secret".to_string(),
             public_key_cache_ttl: 3600,
             verify_token: false, // Disable token verification for testing
+            require_roles: false,
+            role_sources: vec![RoleSource::Realm],
+            tenant_claim: "tenant_id".to_string(),
+            max_token_age: None,
+            auth_flow_cookie_max_age: 600,
+            auth_flow_cookie_max_size: 256,
+            auth_flow_max_cookies: 64,
+            token_exchange_max_retries: 2,
+            token_exchange_retry_delay_ms: 200,
         },
+        permissions,
         ..Default::default()
     });
 
@@ -124,7 +173,13 @@ fn create_test_claims(roles: Vec<String>) -> Claims {
         preferred_username: "testuser".to_string(),
         email: Some("test@example.com".to_string()),
         realm_access: Some(RealmAccess { roles }),
+        resource_access: None,
+        groups: None,
         exp: (chrono::Utc::now() + chrono::Duration::hours(1)).timestamp() as usize,
+        iat: Some(chrono::Utc::now().timestamp() as usize),
+        tenant_id: None,
+        amr: None,
+        extra: HashMap::new(),
     }
 }
 
@@ -199,6 +254,111 @@ async fn test_missing_token() {
     assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
 }
 
+async fn caller_handler(user: Option<Extension<UserInfo>>, body: String) -> String {
+    let caller = user.map_or_else(|| "anonymous".to_string(), |Extension(user)| user.sub);
+    format!("{}|{}", caller, body)
+}
+
+async fn optional_middleware_response(
+    authorization: Option<String>,
+    body: &'static str,
+) -> (StatusCode, String) {
+    let (state, _) = create_test_state().await;
+    let app = Router::new()
+        .route("/caller", axum::routing::post(caller_handler))
+        .layer(axum::middleware::from_fn_with_state(
+            state.optional(),
+            auth_middleware,
+        ));
+
+    let mut req = Request::builder().method("POST").uri("/caller");
+    if let Some(authorization) = authorization {
+        req = req.header("Authorization", authorization);
+    }
+    let response = app
+        .oneshot(req.body(Body::from(body)).expect("valid request"))
+        .await
+        .expect("infallible router");
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .expect("complete body");
+    (status, String::from_utf8(body.to_vec()).expect("utf8 body"))
+}
+
+#[test]
+async fn test_optional_middleware_authenticates_bearer_tokens() {
+    let token = create_test_token(&create_test_claims(vec!["user".to_string()]));
+
+    let response = optional_middleware_response(Some(format!("Bearer {}", token)), "{}").await;
+
+    // The request body reaches the handler along with the caller
+    assert_eq!(response, (StatusCode::OK, "test-user|{}".to_string()));
+}
+
+#[test]
+async fn test_optional_middleware_passes_anonymous_requests() {
+    let response = optional_middleware_response(None, "{}").await;
+    assert_eq!(response, (StatusCode::OK, "anonymous|{}".to_string()));
+
+    let (status, _) =
+        optional_middleware_response(Some("Bearer invalid_token".to_string()), "{}").await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+async fn optional_auth_handler(OptionalAuth(user): OptionalAuth) -> String {
+    user.map_or_else(|| "anonymous".to_string(), |user| user.sub)
+}
+
+async fn optional_auth_response(state: AuthState, authorization: Option<String>) -> String {
+    let app = Router::new()
+        .route("/landing", get(optional_auth_handler))
+        .with_state(state);
+
+    let mut req = Request::builder().uri("/landing");
+    if let Some(authorization) = authorization {
+        req = req.header("Authorization", authorization);
+    }
+    let response = app
+        .oneshot(req.body(Body::empty()).expect("valid request"))
+        .await
+        .expect("infallible router");
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .expect("complete body");
+    String::from_utf8(body.to_vec()).expect("utf8 body")
+}
+
+#[test]
+async fn test_optional_auth_with_valid_token() {
+    let (state, _) = create_test_state().await;
+    let token = create_test_token(&create_test_claims(vec!["user".to_string()]));
+
+    let caller = optional_auth_response(state, Some(format!("Bearer {}", token))).await;
+    assert_eq!(caller, "test-user");
+}
+
+#[test]
+async fn test_optional_auth_with_invalid_token() {
+    let (state, _) = create_test_state().await;
+
+    let caller =
+        optional_auth_response(state.clone(), Some("Bearer invalid_token".to_string())).await;
+    assert_eq!(caller, "anonymous");
+    let caller = optional_auth_response(state, Some("Basic dXNlcjpwdw==".to_string())).await;
+    assert_eq!(caller, "anonymous");
+}
+
+#[test]
+async fn test_optional_auth_without_token() {
+    let (state, _) = create_test_state().await;
+
+    let caller = optional_auth_response(state, None).await;
+    assert_eq!(caller, "anonymous");
+}
+
 #[test]
 async fn test_tenant_access() {
     let (state, _) = create_test_state().await;
@@ -227,6 +387,103 @@ async fn test_tenant_access() {
     assert!(!state.verify_tenant_access(&user_info, "456").await);
 }
 
+#[test]
+async fn test_tenant_from_claim_preferred_over_role() {
+    let (state, _) = create_test_state().await;
+
+    let mut claims = create_test_claims(vec!["tenant_from-role".to_string()]);
+    claims.tenant_id = Some("from-claim".to_string());
+    let token = create_test_token(&claims);
+
+    let user_info = state.authenticate(&token).await.expect("valid token");
+    assert_eq!(user_info.tenant_id.as_deref(), Some("from-claim"));
+
+    // Without the claim the role prefix still works
+    let claims = create_test_claims(vec!["tenant_from-role".to_string()]);
+    let user_info = state
+        .authenticate(&create_test_token(&claims))
+        .await
+        .expect("valid token");
+    assert_eq!(user_info.tenant_id.as_deref(), Some("from-role"));
+}
+
+#[test]
+async fn test_tenant_from_custom_claim() {
+    let (state, config) = create_test_state().await;
+    let mut config = (*config).clone();
+    config.keycloak.tenant_claim = "org".to_string();
+    let state = AuthState::new(Arc::new(config), state.redis_client)
+        .await
+        .expect("Failed to create auth state");
+
+    let mut claims = create_test_claims(vec!["user".to_string()]);
+    claims
+        .extra
+        .insert("org".to_string(), serde_json::Value::from("acme"));
+    let token = create_test_token(&claims);
+
+    let user_info = state.authenticate(&token).await.expect("valid token");
+    assert_eq!(user_info.tenant_id.as_deref(), Some("acme"));
+}
+
+#[test]
+async fn test_auth_methods_from_amr_or_acr() {
+    let (state, _) = create_test_state().await;
+
+    let mut claims = create_test_claims(vec!["user".to_string()]);
+    claims.amr = Some(vec!["pwd".to_string(), "otp".to_string()]);
+    let user_info = state
+        .authenticate(&create_test_token(&claims))
+        .await
+        .expect("valid token");
+    assert_eq!(user_info.auth_methods, vec!["pwd", "otp"]);
+
+    let mut claims = create_test_claims(vec!["user".to_string()]);
+    claims
+        .extra
+        .insert("acr".to_string(), serde_json::Value::from("sso"));
+    let user_info = state
+        .authenticate(&create_test_token(&claims))
+        .await
+        .expect("valid token");
+    assert_eq!(user_info.auth_methods, vec!["sso"]);
+}
+
+#[test]
+async fn test_old_unexpired_token_rejected_when_max_age_set() {
+    let (state, config) = create_test_state().await;
+    let mut config = (*config).clone();
+    config.keycloak.max_token_age = Some(15 * 60);
+    let state = AuthState::new(Arc::new(config), state.redis_client)
+        .await
+        .expect("Failed to create auth state");
+
+    let fresh = create_test_claims(vec!["user".to_string()]);
+    assert!(state.authenticate(&create_test_token(&fresh)).await.is_ok());
+
+    // Issued 30 minutes ago but still valid for another hour
+    let mut old = create_test_claims(vec!["user".to_string()]);
+    old.iat = Some((chrono::Utc::now() - chrono::Duration::minutes(30)).timestamp() as usize);
+    let failure = state
+        .authenticate(&create_test_token(&old))
+        .await
+        .expect_err("old token should be rejected");
+    assert_eq!(failure.reason, AuthFailureReason::TooOld);
+    assert!(matches!(
+        *failure.error.kind,
+        ErrorKind::AuthenticationError(_)
+    ));
+}
+
+#[test]
+async fn test_old_token_accepted_without_max_age() {
+    let (state, _) = create_test_state().await;
+
+    let mut old = create_test_claims(vec!["user".to_string()]);
+    old.iat = Some((chrono::Utc::now() - chrono::Duration::days(1)).timestamp() as usize);
+    assert!(state.authenticate(&create_test_token(&old)).await.is_ok());
+}
+
 #[test]
 async fn test_role_verification() {
     let (state, _) = create_test_state().await;
@@ -265,3 +522,279 @@ async fn test_expired_token() {
     let response = app.oneshot(req).await.unwrap();
     assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
 }
+
+#[test]
+async fn test_role_grants_multiple_permissions() {
+    let permissions = Permissions::default()
+        .with_role("manager", &["tenant:read", "tenant:write"])
+        .with_role("user", &["tenant:read"]);
+    let (state, _) = create_test_state_with_permissions(permissions).await;
+
+    let claims = create_test_claims(vec!["manager".to_string()]);
+    let token = create_test_token(&claims);
+
+    let user_info = state
+        .validate_keycloak_token(&token)
+        .await
+        .expect("valid token");
+    assert!(user_info.has_permission("tenant:read"));
+    assert!(user_info.has_permission("tenant:write"));
+    assert!(!user_info.has_permission("user:write"));
+}
+
+#[test]
+async fn test_role_without_permissions() {
+    let permissions = Permissions::default().with_role("manager", &["tenant:write"]);
+    let (state, _) = create_test_state_with_permissions(permissions).await;
+
+    let claims = create_test_claims(vec!["guest".to_string()]);
+    let token = create_test_token(&claims);
+
+    let user_info = state
+        .validate_keycloak_token(&token)
+        .await
+        .expect("valid token");
+    assert!(user_info.permissions.is_empty());
+    assert!(!user_info.has_permission("tenant:write"));
+}
+
+#[test]
+async fn test_token_without_realm_access() {
+    let mut claims = create_test_claims(vec![]);
+    claims.realm_access = None;
+    let token = create_test_token(&claims);
+
+    // Without the flag, a token lacking realm_access yields an empty role list
+    let (state, _) = create_test_state().await;
+    let user_info = state
+        .validate_keycloak_token(&token)
+        .await
+        .expect("valid token");
+    assert!(user_info.roles.is_empty());
+
+    // With the flag, it is rejected with a clear authorization error
+    let (state, _) = create_test_state_requiring_roles().await;
+    let error = state.validate_keycloak_token(&token).await.unwrap_err();
+    match *error.kind {
+        ErrorKind::AuthorizationError(ref message) => assert_eq!(message, "no roles in token"),
+        _ => panic!("Expected AuthorizationError, got {:?}", error),
+    }
+
+    let app = Router::new()
+        .route("/test", get(test_handler))
+        .layer(axum::middleware::from_fn_with_state(state, auth_middleware));
+
+    let req = Request::builder()
+        .uri("/test")
+        .header("Authorization", format!("Bearer {}", token))
+        .body(Body::empty())
+        .expect("valid request");
+
+    let response = app.oneshot(req).await.expect("response");
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+// Claims carrying roles in all three places Keycloak can put them
+fn create_claims_with_client_roles_and_groups() -> Claims {
+    let mut claims = create_test_claims(vec!["user".to_string()]);
+    claims.resource_access = Some(HashMap::from([
+        (
+            "test-client".to_string(),
+            ResourceAccess {
+                roles: vec!["manager".to_string(), "user".to_string()],
+            },
+        ),
+        // Roles for another client don't apply to this backend
+        (
+            "other-client".to_string(),
+            ResourceAccess {
+                roles: vec!["tenant_admin".to_string()],
+            },
+        ),
+    ]));
+    claims.groups = Some(vec!["/staff/auditor".to_string()]);
+    claims
+}
+
+#[test]
+async fn test_client_roles_and_groups_are_merged() {
+    let permissions = Permissions::default()
+        .with_role("manager", &["user:write"])
+        .with_role("auditor", &["audit:read"])
+        .with_role("tenant_admin", &["tenant:write"]);
+    let state = create_test_state_with_role_sources(
+        vec![RoleSource::Realm, RoleSource::Client, RoleSource::Groups],
+        permissions,
+    )
+    .await;
+    let token = create_test_token(&create_claims_with_client_roles_and_groups());
+
+    let user_info = state
+        .validate_keycloak_token(&token)
+        .await
+        .expect("valid token");
+    assert_eq!(user_info.roles, ["user", "manager", "auditor"]);
+    assert!(user_info.has_permission("user:write"));
+    assert!(user_info.has_permission("audit:read"));
+    assert!(!user_info.has_permission("tenant:write"));
+}
+
+#[test]
+async fn test_untrusted_role_sources_are_ignored() {
+    let permissions = Permissions::default().with_role("manager", &["user:write"]);
+    let token = create_test_token(&create_claims_with_client_roles_and_groups());
+
+    // Realm roles only, the default
+    let (state, _) = create_test_state_with_permissions(permissions.clone()).await;
+    let user_info = state
+        .validate_keycloak_token(&token)
+        .await
+        .expect("valid token");
+    assert_eq!(user_info.roles, ["user"]);
+    assert!(!user_info.has_permission("user:write"));
+
+    // Client roles only; the token's realm roles don't count
+    let state = create_test_state_with_role_sources(vec![RoleSource::Client], permissions).await;
+    let user_info = state
+        .validate_keycloak_token(&token)
+        .await
+        .expect("valid token");
+    assert_eq!(user_info.roles, ["manager", "user"]);
+}
+
+async fn failure_reason(state: &AuthState, token: &str) -> AuthFailureReason {
+    state
+        .authenticate(token)
+        .await
+        .expect_err("authentication should fail")
+        .reason
+}
+
+#[test]
+async fn test_failure_reason_missing_token() {
+    assert_eq!(
+        bearer_token(&HeaderMap::new()),
+        Err(AuthFailureReason::MissingToken)
+    );
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        AUTHORIZATION,
+        HeaderValue::from_static("Basic dXNlcjpwYXNz"),
+    );
+    assert_eq!(bearer_token(&headers), Err(AuthFailureReason::Malformed));
+
+    headers.insert(AUTHORIZATION, HeaderValue::from_static("Bearer abc"));
+    assert_eq!(bearer_token(&headers), Ok("abc".to_string()));
+}
+
+#[test]
+async fn test_failure_reason_malformed() {
+    let (state, _) = create_test_state().await;
+    assert_eq!(
+        failure_reason(&state, "invalid_token").await,
+        AuthFailureReason::Malformed
+    );
+}
+
+#[test]
+async fn test_failure_reason_expired() {
+    let (state, _) = create_test_state().await;
+    let mut claims = create_test_claims(vec!["user".to_string()]);
+    claims.exp = (chrono::Utc::now() - chrono::Duration::hours(1)).timestamp() as usize;
+
+    let token = create_test_token(&claims);
+    assert_eq!(
+        failure_reason(&state, &token).await,
+        AuthFailureReason::Expired
+    );
+}
+
+#[test]
+async fn test_failure_reason_bad_signature() {
+    let (state, _) = create_test_state().await;
+    let claims = create_test_claims(vec!["user".to_string()]);
+    let token = encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(b"some_other_key"),
+    )
+    .expect("Failed to create token");
+
+    assert_eq!(
+        failure_reason(&state, &token).await,
+        AuthFailureReason::BadSignature
+    );
+}
+
+#[test]
+async fn test_failure_reason_unknown_kid() {
+    let jwks = Jwks {
+        keys: vec![JwksKey {
+            kid: "known_key_id".to_string(),
+            kty: "RSA".to_string(),
+            n: "AQAB".to_string(),
+            e: "AQAB".to_string(),
+        }],
+    };
+    let token = create_test_token(&create_test_claims(vec![]));
+
+    let failure = AuthState::create_decoding_key(&jwks, &token)
+        .err()
+        .expect("decoding key lookup should fail");
+    assert_eq!(failure.reason, AuthFailureReason::UnknownKid);
+}
+
+#[test]
+async fn test_failure_reason_missing_roles() {
+    let (state, _) = create_test_state_requiring_roles().await;
+    let mut claims = create_test_claims(vec![]);
+    claims.realm_access = None;
+
+    let token = create_test_token(&claims);
+    assert_eq!(
+        failure_reason(&state, &token).await,
+        AuthFailureReason::MissingRoles
+    );
+    assert_eq!(AuthFailureReason::MissingRoles.as_str(), "missing_roles");
+}
+
+#[test]
+async fn test_jwks_fetches_reuse_one_client() {
+    use crate::infrastructure::http_client::BUILT_CLIENTS;
+
+    let certs = Router::new().route(
+        "/realms/test-realm/protocol/openid-connect/certs",
+        get(|| async {
+            axum::Json(Jwks {
+                keys: vec![JwksKey {
+                    kid: "test_key_id".to_string(),
+                    kty: "RSA".to_string(),
+                    n: "AQAB".to_string(),
+                    e: "AQAB".to_string(),
+                }],
+            })
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("Failed to bind JWKS server");
+    let addr = listener.local_addr().expect("JWKS server address");
+    tokio::spawn(async move { axum::serve(listener, certs).await });
+
+    let (state, config) = create_test_state().await;
+    let mut config = (*config).clone();
+    config.keycloak.url = format!("http://{}", addr);
+
+    let built_before = BUILT_CLIENTS.with(|built| built.get());
+    let state = AuthState::new(Arc::new(config), state.redis_client)
+        .await
+        .expect("Failed to create auth state");
+    for _ in 0..2 {
+        let jwks = state.fetch_jwks().await.expect("Failed to fetch JWKS");
+        assert_eq!(jwks.keys[0].kid, "test_key_id");
+    }
+
+    // One client for the state, none per fetch
+    assert_eq!(BUILT_CLIENTS.with(|built| built.get()), built_before + 1);
+}
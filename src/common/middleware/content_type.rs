@@ -0,0 +1,88 @@
+use axum::{
+    body::Body,
+    extract::State,
+    http::{header, HeaderMap, Method, Request},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use tracing::debug;
+
+use super::language::LangPreferences;
+use crate::common::error::AppError;
+use crate::infrastructure::state::AppState;
+
+/// Whether the media type is `application/json` or a `+json` type such as
+/// `application/merge-patch+json`, ignoring parameters like `charset`
+pub(crate) fn is_json(content_type: &str) -> bool {
+    let essence = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    essence == "application/json"
+        || essence
+            .strip_prefix("application/")
+            .is_some_and(|subtype| subtype.ends_with("+json"))
+}
+
+/// Whether the request comes with a body, going by its headers
+fn has_body(method: &Method, headers: &HeaderMap) -> bool {
+    if !matches!(*method, Method::POST | Method::PUT | Method::PATCH) {
+        return false;
+    }
+    headers.contains_key(header::TRANSFER_ENCODING)
+        || headers
+            .get(header::CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<u64>().ok())
+            .is_some_and(|length| length > 0)
+}
+
+/// Rejects request bodies that aren't JSON with 415 and a localized error,
+/// before a handler's extractor answers with its own message
+pub async fn require_json(
+    State(state): State<AppState>,
+    req: Request<Body>,
+    next: Next,
+) -> Response {
+    if !has_body(req.method(), req.headers()) {
+        return next.run(req).await;
+    }
+    let content_type = req
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok());
+    if content_type.is_some_and(is_json) {
+        return next.run(req).await;
+    }
+
+    debug!(
+        "Rejecting {} {} with content type {:?}",
+        req.method(),
+        req.uri(),
+        content_type
+    );
+    let language = LangPreferences::of_request(&req).primary();
+    let message = state
+        .i18n
+        .format_message(language, "error-unsupported-media-type", None)
+        .await
+        .unwrap_or_else(|_| "Request body must be sent as application/json".to_string());
+    AppError::unsupported_media_type(message).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_media_types() {
+        assert!(is_json("application/json"));
+        assert!(is_json("Application/JSON; charset=utf-8"));
+        assert!(is_json("application/merge-patch+json"));
+        assert!(!is_json("text/plain"));
+        assert!(!is_json("application/jsonx"));
+        assert!(!is_json("text/x+json"));
+    }
+}
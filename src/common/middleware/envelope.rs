@@ -0,0 +1,209 @@
+use axum::{
+    body::{Body, HttpBody},
+    extract::State,
+    http::{header, HeaderMap, HeaderValue, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use serde_json::Value;
+use tracing::warn;
+
+use crate::common::config::ResponseEnvelopeSettings;
+
+/// Request header choosing the envelope for one request, `true` or `false`
+pub const RESPONSE_ENVELOPE_HEADER: &str = "x-response-envelope";
+
+/// Wraps JSON responses in `{ "data", "meta", "errors" }`.
+///
+/// Successful responses put their body in `data`; error responses leave
+/// `data` null and put the error body in `errors`. Only JSON bodies of known
+/// length are wrapped, so streamed exports, empty bodies and other media
+/// types pass through unchanged.
+#[derive(Debug, Clone)]
+pub struct ResponseEnvelope {
+    enabled: bool,
+}
+
+impl ResponseEnvelope {
+    pub fn new(settings: &ResponseEnvelopeSettings) -> Self {
+        Self {
+            enabled: settings.enabled,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Envelope {
+    data: Value,
+    meta: Meta,
+    errors: Vec<Value>,
+}
+
+#[derive(Debug, Serialize)]
+struct Meta {
+    status: u16,
+}
+
+/// `X-Response-Envelope` of the request, ignored unless `true` or `false`
+fn requested(headers: &HeaderMap) -> Option<bool> {
+    let value = headers.get(RESPONSE_ENVELOPE_HEADER)?.to_str().ok()?;
+    if value.eq_ignore_ascii_case("true") {
+        Some(true)
+    } else if value.eq_ignore_ascii_case("false") {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+pub async fn response_envelope(
+    State(envelope): State<ResponseEnvelope>,
+    req: Request<Body>,
+    next: Next,
+) -> Response {
+    let wanted = requested(req.headers()).unwrap_or(envelope.enabled);
+    let mut response = next.run(req).await;
+
+    // The same URL may come back either way
+    response.headers_mut().append(
+        header::VARY,
+        HeaderValue::from_static(RESPONSE_ENVELOPE_HEADER),
+    );
+    if wanted {
+        wrap(response).await
+    } else {
+        response
+    }
+}
+
+async fn wrap(response: Response) -> Response {
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"));
+    let buffered = response
+        .body()
+        .size_hint()
+        .exact()
+        .is_some_and(|length| length > 0);
+    if !is_json || !buffered {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            warn!("Failed to read response body to envelope: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        },
+    };
+    let Ok(body) = serde_json::from_slice::<Value>(&bytes) else {
+        return Response::from_parts(parts, Body::from(bytes));
+    };
+
+    let status = parts.status;
+    let (data, errors) = if status.is_client_error() || status.is_server_error() {
+        (Value::Null, vec![body])
+    } else {
+        (body, Vec::new())
+    };
+    let envelope = Envelope {
+        data,
+        meta: Meta {
+            status: status.as_u16(),
+        },
+        errors,
+    };
+
+    match serde_json::to_vec(&envelope) {
+        Ok(enveloped) => {
+            parts.headers.remove(header::CONTENT_LENGTH);
+            Response::from_parts(parts, Body::from(enveloped))
+        },
+        Err(e) => {
+            warn!("Failed to serialize response envelope: {}", e);
+            Response::from_parts(parts, Body::from(bytes))
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::error::AppError;
+    use axum::{extract::Path, routing::get, Json, Router};
+    use tower::ServiceExt;
+
+    #[derive(Serialize)]
+    struct Greeting {
+        name: String,
+    }
+
+    fn app(enabled: bool) -> Router {
+        Router::new()
+            .route(
+                "/greetings/{name}",
+                get(|Path(name): Path<String>| async move {
+                    if name == "nobody" {
+                        Err(AppError::not_found("No greeting for nobody"))
+                    } else {
+                        Ok(Json(Greeting { name }))
+                    }
+                }),
+            )
+            .layer(axum::middleware::from_fn_with_state(
+                ResponseEnvelope::new(&ResponseEnvelopeSettings { enabled }),
+                response_envelope,
+            ))
+    }
+
+    async fn get_json(app: Router, uri: &str, envelope: Option<&str>) -> (StatusCode, Value) {
+        let mut request = Request::builder().uri(uri);
+        if let Some(envelope) = envelope {
+            request = request.header(RESPONSE_ENVELOPE_HEADER, envelope);
+        }
+        let response = app
+            .oneshot(request.body(Body::empty()).expect("valid request"))
+            .await
+            .expect("infallible router");
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("complete body");
+        (
+            status,
+            serde_json::from_slice(&bytes).expect("JSON response"),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_same_endpoint_bare_and_enveloped() {
+        let (status, bare) = get_json(app(false), "/greetings/jane", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(bare["name"], "jane");
+        assert!(bare.get("data").is_none());
+
+        let (status, enveloped) = get_json(app(false), "/greetings/jane", Some("true")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(enveloped["data"]["name"], "jane");
+        assert_eq!(enveloped["meta"]["status"], 200);
+        assert_eq!(enveloped["errors"], Value::Array(vec![]));
+    }
+
+    #[tokio::test]
+    async fn test_errors_are_enveloped_too() {
+        let (status, enveloped) = get_json(app(true), "/greetings/nobody", None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(enveloped["data"], Value::Null);
+        assert_eq!(enveloped["meta"]["status"], 404);
+        assert!(enveloped["errors"][0]["message"].is_string());
+
+        // A request can still opt out of the configured envelope
+        let (_, bare) = get_json(app(true), "/greetings/nobody", Some("false")).await;
+        assert!(bare["message"].is_string());
+        assert!(bare.get("errors").is_none());
+    }
+}
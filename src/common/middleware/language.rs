@@ -1,10 +1,11 @@
+use std::convert::Infallible;
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
 use std::task::{Context, Poll};
 
-use axum::extract::Query;
-use axum::http::Request;
+use axum::extract::{FromRequestParts, Query};
+use axum::http::{request::Parts, Request};
 use serde::Deserialize;
 use tower::{Layer, Service};
 
@@ -49,6 +50,100 @@ struct LanguageQuery {
     lang: Option<String>,
 }
 
+/// Supported languages acceptable to the caller, most preferred first.
+///
+/// Built by `LanguageMiddleware` from the `lang` query parameter followed by
+/// the `Accept-Language` entries ordered by their q-values. Never empty: the
+/// default language is used when nothing acceptable was requested.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LangPreferences(pub Vec<SupportedLanguage>);
+
+impl LangPreferences {
+    fn from_request(query: Option<&str>, accept_language: Option<&str>) -> Self {
+        let mut languages: Vec<SupportedLanguage> = Vec::new();
+        let requested = query
+            .and_then(SupportedLanguage::from_tag)
+            .into_iter()
+            .chain(
+                accept_language
+                    .map(parse_accept_language)
+                    .unwrap_or_default(),
+            );
+        for language in requested {
+            if !languages.contains(&language) {
+                languages.push(language);
+            }
+        }
+
+        if languages.is_empty() {
+            languages.push(
+                SupportedLanguage::from_tag(config::get_default_language())
+                    .unwrap_or(SupportedLanguage::En),
+            );
+        }
+        Self(languages)
+    }
+
+    /// Preferences set by `LanguageMiddleware`, or read from the request
+    /// itself where it didn't run
+    pub fn of_request<B>(request: &Request<B>) -> Self {
+        if let Some(preferences) = request.extensions().get::<Self>() {
+            return preferences.clone();
+        }
+        let query = Query::<LanguageQuery>::try_from_uri(request.uri())
+            .ok()
+            .and_then(|q| q.0.lang);
+        Self::from_request(
+            query.as_deref(),
+            request
+                .headers()
+                .get(ACCEPT_LANGUAGE_HEADER)
+                .and_then(|h| h.to_str().ok()),
+        )
+    }
+
+    /// The most preferred language
+    pub fn primary(&self) -> SupportedLanguage {
+        self.0[0]
+    }
+}
+
+/// Parses an `Accept-Language` header into supported languages ordered by
+/// descending q-value; entries with equal weight keep their header order and
+/// `q=0` entries are dropped
+fn parse_accept_language(header: &str) -> Vec<SupportedLanguage> {
+    let mut weighted: Vec<(SupportedLanguage, f32)> = header
+        .split(',')
+        .filter_map(|entry| {
+            let mut parts = entry.split(';');
+            let language = SupportedLanguage::from_tag(parts.next()?.trim())?;
+            let quality = parts
+                .find_map(|param| param.trim().strip_prefix("q="))
+                .map_or(Some(1.0), |q| q.trim().parse::<f32>().ok())?;
+            (quality > 0.0).then_some((language, quality))
+        })
+        .collect();
+
+    // `sort_by` is stable, so ties keep the order they were sent in
+    weighted.sort_by(|a, b| b.1.total_cmp(&a.1));
+    weighted.into_iter().map(|(language, _)| language).collect()
+}
+
+impl<S> FromRequestParts<S> for LangPreferences
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(parts
+            .extensions
+            .get::<LangPreferences>()
+            .cloned()
+            .unwrap_or_else(|| LangPreferences::from_request(None, None)))
+    }
+}
+
 impl<S, B> Service<Request<B>> for LanguageMiddleware<S>
 where
     S: Service<Request<B>> + Send + Clone + 'static,
@@ -74,12 +169,20 @@ where
                 .ok()
                 .and_then(|q| q.lang.clone());
 
+            let preferences = LangPreferences::from_request(
+                query.as_deref(),
+                request
+                    .headers()
+                    .get(ACCEPT_LANGUAGE_HEADER)
+                    .and_then(|h| h.to_str().ok()),
+            );
+
             // 2. Check Accept-Language header
             let accept_language = request
                 .headers()
                 .get(ACCEPT_LANGUAGE_HEADER)
                 .and_then(|h| h.to_str().ok())
-                .and_then(|h| h.split(',').next())
+                .and_then(|h| h.split([',', ';']).next())
                 .unwrap_or(config::get_default_language());
 
             // Determine the language to use
@@ -88,15 +191,17 @@ where
                 .unwrap_or_else(|| config::get_default_language().to_string());
 
             // Validate the language
-            let valid_language = if SupportedLanguage::iter().any(|l| l.as_str() == language) {
-                language
-            } else {
-                config::get_default_language().to_string()
-            };
+            let valid_language = language
+                .parse::<SupportedLanguage>()
+                .map(|l| l.as_str().to_string())
+                .unwrap_or_else(|_| config::get_default_language().to_string());
 
             // Add language to request extensions
             request.extensions_mut().insert(valid_language.clone());
 
+            // Add the full ordered list of acceptable languages
+            request.extensions_mut().insert(preferences);
+
             // Add i18n manager to request extensions
             request.extensions_mut().insert(i18n_manager);
 
@@ -158,6 +263,27 @@ mod tests {
         assert_eq!(response.extensions().get::<String>().unwrap(), "de");
     }
 
+    #[tokio::test]
+    async fn test_region_qualified_query_resolves_to_language() {
+        let i18n_manager = setup_i18n().await;
+        let middleware = LanguageLayer::new(i18n_manager);
+        let service = middleware.layer(TestService);
+
+        let request = Request::builder()
+            .uri("/?lang=de-CH")
+            .body(Full::new(Bytes::new()))
+            .expect("valid request");
+
+        let response = service.oneshot(request).await.expect("service failed");
+        assert_eq!(
+            response
+                .extensions()
+                .get::<String>()
+                .expect("language not set"),
+            "de"
+        );
+    }
+
     #[tokio::test]
     async fn test_language_detection_from_header() {
         let i18n_manager = setup_i18n().await;
@@ -188,4 +314,44 @@ mod tests {
         let response = service.oneshot(request).await.unwrap();
         assert_eq!(response.extensions().get::<String>().unwrap(), "en");
     }
+
+    #[tokio::test]
+    async fn test_preferences_follow_q_values() {
+        let i18n_manager = setup_i18n().await;
+        let middleware = LanguageLayer::new(i18n_manager);
+        let service = middleware.layer(TestService);
+
+        let request = Request::builder()
+            .header(
+                header::ACCEPT_LANGUAGE,
+                "es;q=0.5, fr;q=0.2, de-CH, ja;q=0.9, en;q=0.8",
+            )
+            .uri("/")
+            .body(Full::new(Bytes::new()))
+            .expect("valid request");
+
+        let response = service.oneshot(request).await.expect("service failed");
+        assert_eq!(
+            response
+                .extensions()
+                .get::<LangPreferences>()
+                .expect("preferences not set"),
+            &LangPreferences(vec![
+                SupportedLanguage::De,
+                SupportedLanguage::En,
+                SupportedLanguage::Es,
+                SupportedLanguage::Fr,
+            ])
+        );
+    }
+
+    #[test]
+    fn test_preferences_put_query_first_and_skip_rejected() {
+        let preferences = LangPreferences::from_request(Some("sq"), Some("de;q=0, fr, sq;q=0.7"));
+        assert_eq!(
+            preferences,
+            LangPreferences(vec![SupportedLanguage::Sq, SupportedLanguage::Fr])
+        );
+        assert_eq!(preferences.primary(), SupportedLanguage::Sq);
+    }
 }
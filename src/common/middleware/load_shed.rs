@@ -0,0 +1,122 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::State,
+    http::{header, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use metrics::counter;
+use tokio::sync::Semaphore;
+use tracing::warn;
+
+use crate::common::config::ConcurrencySettings;
+
+/// Shared in-flight request budget for the routes behind `shed_load`.
+///
+/// Requests beyond `max_in_flight` are rejected immediately with 503 rather
+/// than queued, so a burst can't pile up latency for everyone else.
+#[derive(Debug, Clone)]
+pub struct ConcurrencyLimit {
+    permits: Arc<Semaphore>,
+    retry_after_secs: u64,
+}
+
+impl ConcurrencyLimit {
+    pub fn new(settings: &ConcurrencySettings) -> Self {
+        Self {
+            permits: Arc::new(Semaphore::new(settings.max_in_flight.max(1))),
+            retry_after_secs: settings.retry_after_secs,
+        }
+    }
+}
+
+pub async fn shed_load(
+    State(limit): State<ConcurrencyLimit>,
+    req: Request<Body>,
+    next: Next,
+) -> Response {
+    // Held until the inner response has been produced
+    let Ok(_permit) = limit.permits.clone().try_acquire_owned() else {
+        counter!("http_requests_shed_total").increment(1);
+        warn!(
+            "Shedding {} {}: too many requests in flight",
+            req.method(),
+            req.uri()
+        );
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            [(header::RETRY_AFTER, limit.retry_after_secs.to_string())],
+        )
+            .into_response();
+    };
+
+    next.run(req).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::get, Router};
+    use tokio::sync::Notify;
+    use tower::ServiceExt;
+
+    fn request() -> Request<Body> {
+        Request::builder()
+            .uri("/slow")
+            .body(Body::empty())
+            .expect("valid request")
+    }
+
+    #[tokio::test]
+    async fn test_requests_beyond_limit_are_shed() {
+        let release = Arc::new(Notify::new());
+        let handler_release = release.clone();
+        let limit = ConcurrencyLimit::new(&ConcurrencySettings {
+            max_in_flight: 1,
+            retry_after_secs: 7,
+        });
+        let app = Router::new()
+            .route(
+                "/slow",
+                get(move || async move {
+                    handler_release.notified().await;
+                    "done"
+                }),
+            )
+            .layer(axum::middleware::from_fn_with_state(
+                limit.clone(),
+                shed_load,
+            ));
+
+        // Occupy the only permit
+        let in_flight = tokio::spawn(app.clone().oneshot(request()));
+        while limit.permits.available_permits() > 0 {
+            tokio::task::yield_now().await;
+        }
+
+        let shed = app
+            .clone()
+            .oneshot(request())
+            .await
+            .expect("infallible router");
+        assert_eq!(shed.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(
+            shed.headers()
+                .get(header::RETRY_AFTER)
+                .expect("Retry-After header"),
+            "7"
+        );
+
+        release.notify_one();
+        let completed = in_flight
+            .await
+            .expect("request task panicked")
+            .expect("infallible router");
+        assert_eq!(completed.status(), StatusCode::OK);
+
+        // The permit is returned once the first request finishes
+        assert_eq!(limit.permits.available_permits(), 1);
+    }
+}
@@ -0,0 +1,162 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::State,
+    http::{header, HeaderValue, Request},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use tracing::info;
+
+use super::language::LangPreferences;
+use crate::common::{config::MaintenanceSettings, error::AppError};
+use crate::infrastructure::state::AppState;
+
+/// Routes served during maintenance, with everything below them
+const EXEMPT_ROUTES: &[&str] = &["/health", "/ready", "/metrics", "/admin"];
+
+/// Maintenance switch shared by the middleware and `/admin/maintenance`
+#[derive(Debug, Clone)]
+pub struct MaintenanceMode {
+    enabled: Arc<AtomicBool>,
+    retry_after_secs: u64,
+}
+
+impl MaintenanceMode {
+    pub fn new(settings: &MaintenanceSettings) -> Self {
+        Self {
+            enabled: Arc::new(AtomicBool::new(settings.enabled)),
+            retry_after_secs: settings.retry_after_secs,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    pub fn set_enabled(&self, enabled: bool) {
+        if self.enabled.swap(enabled, Ordering::Relaxed) != enabled {
+            info!(
+                "Maintenance mode {}",
+                if enabled { "enabled" } else { "disabled" }
+            );
+        }
+    }
+
+    pub fn retry_after_secs(&self) -> u64 {
+        self.retry_after_secs
+    }
+}
+
+fn is_exempt(path: &str) -> bool {
+    EXEMPT_ROUTES.iter().any(|route| {
+        path.strip_prefix(route)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+    })
+}
+
+/// Rejects every request but those to the health, metrics and admin routes
+/// with a localized 503 while maintenance mode is on
+pub async fn maintenance_mode(
+    State(state): State<AppState>,
+    req: Request<Body>,
+    next: Next,
+) -> Response {
+    if !state.maintenance.is_enabled() || is_exempt(req.uri().path()) {
+        return next.run(req).await;
+    }
+
+    let language = LangPreferences::of_request(&req).primary();
+    let message = state
+        .i18n
+        .format_message(language, "health-maintenance", None)
+        .await
+        .unwrap_or_else(|_| "System is under maintenance".to_string());
+    let mut response = AppError::maintenance(message).into_response();
+    response.headers_mut().insert(
+        header::RETRY_AFTER,
+        HeaderValue::from(state.maintenance.retry_after_secs()),
+    );
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::i18n::{I18nManager, SupportedLanguage, TestResourceProvider};
+    use crate::infrastructure::services::tenant_service::TenantServiceImpl;
+    use crate::router::create_router;
+    use axum::http::StatusCode;
+    use sea_orm::{DatabaseBackend, MockDatabase};
+    use tower::ServiceExt;
+
+    async fn state_in_maintenance() -> AppState {
+        let i18n = I18nManager::new(
+            SupportedLanguage::En,
+            Arc::new(TestResourceProvider::new().with_resource(
+                SupportedLanguage::De,
+                "health-maintenance = System befindet sich in Wartung",
+            )),
+        )
+        .await
+        .expect("i18n manager");
+        let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+        let state = AppState::for_test(
+            Arc::new(TenantServiceImpl::new(Arc::new(db))),
+            Arc::new(i18n),
+        );
+        state.maintenance.set_enabled(true);
+        state
+    }
+
+    async fn call(state: &AppState, uri: &str) -> Response {
+        let request = Request::builder()
+            .uri(uri)
+            .header(header::ACCEPT_LANGUAGE, "de")
+            .body(Body::empty())
+            .expect("valid request");
+        create_router(state.clone())
+            .oneshot(request)
+            .await
+            .expect("infallible router")
+    }
+
+    #[tokio::test]
+    async fn test_normal_routes_are_blocked() {
+        let state = state_in_maintenance().await;
+        let response = call(&state, "/tenants").await;
+
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(
+            response.headers().get(header::RETRY_AFTER),
+            Some(&HeaderValue::from_static("300"))
+        );
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("response body");
+        let error: serde_json::Value = serde_json::from_slice(&body).expect("JSON error");
+        assert_eq!(error["code"], "MAINTENANCE");
+        assert_eq!(error["message"], "System befindet sich in Wartung");
+    }
+
+    #[tokio::test]
+    async fn test_health_metrics_and_admin_stay_available() {
+        let state = state_in_maintenance().await;
+
+        assert_eq!(call(&state, "/metrics").await.status(), StatusCode::OK);
+        // Reaches the handler, which wants a permission
+        assert_eq!(
+            call(&state, "/admin/subscriptions").await.status(),
+            StatusCode::FORBIDDEN
+        );
+        assert!(is_exempt("/health"));
+        assert!(is_exempt("/ready"));
+        assert!(!is_exempt("/healthz"));
+        assert!(!is_exempt("/tenants"));
+
+        state.maintenance.set_enabled(false);
+        assert_eq!(call(&state, "/openapi.json").await.status(), StatusCode::OK);
+    }
+}
@@ -1,6 +1,14 @@
+pub mod access_log;
+pub mod api_key;
 pub mod auth;
+pub mod content_type;
+pub mod envelope;
 mod language;
-mod tenant;
+pub mod load_shed;
+pub mod maintenance;
+pub mod rate_limit;
+pub mod tenant;
+pub mod timeout;
 
 #[cfg(test)]
 mod auth_test;
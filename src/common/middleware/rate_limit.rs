@@ -0,0 +1,246 @@
+use std::time::{Duration, Instant};
+
+use axum::{
+    body::Body,
+    extract::State,
+    http::{header, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use tracing::warn;
+
+use crate::common::metrics::record_rate_limit_metrics;
+use crate::common::middleware::tenant::{is_write_method, TenantInfo};
+use crate::domain::tenant::RateLimitBucket;
+use crate::infrastructure::cache::CacheConnection;
+
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// Fixed one-minute windows per tenant and `RateLimitBucket`, so reads and
+/// writes of a tenant use up independent limits. The windows are expiring
+/// counters in the in-memory cache, whose sweeper evicts them once they
+/// have ended.
+#[derive(Clone, Default)]
+pub struct TenantRateLimiter {
+    cache: CacheConnection,
+}
+
+impl TenantRateLimiter {
+    pub fn new(cache: CacheConnection) -> Self {
+        Self { cache }
+    }
+
+    /// Counts a request against the bucket and returns how long to wait
+    /// when `limit` is already used up in the current window
+    pub async fn acquire(
+        &self,
+        tenant_id: &str,
+        bucket: RateLimitBucket,
+        limit: i32,
+        now: Instant,
+    ) -> Result<(), Duration> {
+        self.cache
+            .count_in_window(&window_key(tenant_id, bucket), limit, WINDOW, now)
+            .await
+    }
+
+    /// Requests counted against the bucket in its current window
+    pub async fn usage(&self, tenant_id: &str, bucket: RateLimitBucket, now: Instant) -> i32 {
+        self.cache
+            .window_count(&window_key(tenant_id, bucket), now)
+            .await
+    }
+}
+
+fn window_key(tenant_id: &str, bucket: RateLimitBucket) -> String {
+    format!("ratelimit:{}:{}", tenant_id, bucket.as_str())
+}
+
+/// Bucket a request counts against: mutating methods are writes, the rest
+/// reads
+fn request_bucket<B>(req: &Request<B>) -> RateLimitBucket {
+    if is_write_method(req.method()) {
+        RateLimitBucket::Write
+    } else {
+        RateLimitBucket::Read
+    }
+}
+
+/// Enforces the per-bucket limits of the `TenantInfo` set by
+/// `tenant_middleware`, so it has to run inside it. Requests over the limit
+/// are rejected with 429 and a `Retry-After` until the window ends.
+pub async fn rate_limit_middleware(
+    State(limiter): State<TenantRateLimiter>,
+    req: Request<Body>,
+    next: Next,
+) -> Response {
+    let Some(tenant) = req.extensions().get::<TenantInfo>() else {
+        return next.run(req).await;
+    };
+    let bucket = request_bucket(&req);
+    let Some(&limit) = tenant.rate_limits.get(&bucket) else {
+        return next.run(req).await;
+    };
+
+    match limiter
+        .acquire(&tenant.id, bucket, limit, Instant::now())
+        .await
+    {
+        Ok(()) => {
+            record_rate_limit_metrics(&tenant.id, 1, 0);
+            next.run(req).await
+        },
+        Err(retry_after) => {
+            record_rate_limit_metrics(&tenant.id, 0, 1);
+            warn!(
+                "Tenant {} exceeded its {} rate limit of {} per minute",
+                tenant.id,
+                bucket.as_str(),
+                limit
+            );
+            (
+                StatusCode::TOO_MANY_REQUESTS,
+                [(
+                    header::RETRY_AFTER,
+                    retry_after.as_secs().max(1).to_string(),
+                )],
+            )
+                .into_response()
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::{BTreeMap, HashSet};
+
+    use axum::{routing::get, Extension, Router};
+    use tower::ServiceExt;
+
+    fn tenant(read: i32, write: i32) -> TenantInfo {
+        TenantInfo {
+            id: "00000000-0000-0000-0000-000000000005".to_string(),
+            domain: "limited.example.com".to_string(),
+            is_active: true,
+            read_only: false,
+            allowed_auth_methods: HashSet::new(),
+            rate_limits: BTreeMap::from([
+                (RateLimitBucket::Read, read),
+                (RateLimitBucket::Write, write),
+            ]),
+        }
+    }
+
+    fn app(tenant: TenantInfo) -> Router {
+        app_with(TenantRateLimiter::default(), tenant)
+    }
+
+    fn app_with(limiter: TenantRateLimiter, tenant: TenantInfo) -> Router {
+        Router::new()
+            .route("/items", get(|| async { "list" }).post(|| async { "created" }))
+            .layer(axum::middleware::from_fn_with_state(
+                limiter,
+                rate_limit_middleware,
+            ))
+            // Stands in for tenant_middleware, which runs outside
+            .layer(Extension(tenant))
+    }
+
+    async fn call(app: &Router, method: &str) -> StatusCode {
+        let request = Request::builder()
+            .method(method)
+            .uri("/items")
+            .body(Body::empty())
+            .expect("valid request");
+        app.clone()
+            .oneshot(request)
+            .await
+            .expect("infallible router")
+            .status()
+    }
+
+    #[tokio::test]
+    async fn test_writes_count_against_write_bucket() {
+        let app = app(tenant(3, 1));
+
+        assert_eq!(call(&app, "POST").await, StatusCode::OK);
+        assert_eq!(call(&app, "POST").await, StatusCode::TOO_MANY_REQUESTS);
+
+        // Reads still have their own budget
+        for _ in 0..3 {
+            assert_eq!(call(&app, "GET").await, StatusCode::OK);
+        }
+        assert_eq!(call(&app, "GET").await, StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    #[tokio::test]
+    async fn test_requests_show_up_in_usage() {
+        let limiter = TenantRateLimiter::default();
+        let tenant = tenant(10, 10);
+        let app = app_with(limiter.clone(), tenant.clone());
+
+        assert_eq!(call(&app, "GET").await, StatusCode::OK);
+        assert_eq!(call(&app, "GET").await, StatusCode::OK);
+        assert_eq!(call(&app, "POST").await, StatusCode::OK);
+
+        let now = Instant::now();
+        assert_eq!(
+            limiter.usage(&tenant.id, RateLimitBucket::Read, now).await,
+            2
+        );
+        assert_eq!(
+            limiter.usage(&tenant.id, RateLimitBucket::Write, now).await,
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_window_resets_after_a_minute() {
+        let limiter = TenantRateLimiter::default();
+        let start = Instant::now();
+
+        assert!(limiter
+            .acquire("tenant", RateLimitBucket::Write, 1, start)
+            .await
+            .is_ok());
+        let retry_after = limiter
+            .acquire(
+                "tenant",
+                RateLimitBucket::Write,
+                1,
+                start + Duration::from_secs(45),
+            )
+            .await
+            .expect_err("limit used up");
+        assert_eq!(retry_after, Duration::from_secs(15));
+
+        assert!(limiter
+            .acquire("tenant", RateLimitBucket::Write, 1, start + WINDOW)
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_ended_windows_are_swept_from_the_cache() {
+        let cache = CacheConnection::default();
+        let limiter = TenantRateLimiter::new(cache.clone());
+        let now = Instant::now();
+        let ended = now.checked_sub(WINDOW * 2).expect("instant in the past");
+
+        let _ = limiter
+            .acquire("idle", RateLimitBucket::Read, 10, ended)
+            .await;
+        let _ = limiter
+            .acquire("busy", RateLimitBucket::Read, 10, now)
+            .await;
+        assert_eq!(cache.len().await, 2);
+
+        let sweeper = cache.spawn_sweeper(Duration::from_millis(10));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        sweeper.shutdown().await;
+
+        assert_eq!(cache.len().await, 1);
+        assert_eq!(limiter.usage("busy", RateLimitBucket::Read, now).await, 1);
+    }
+}
@@ -1,9 +1,10 @@
+use std::collections::{BTreeMap, HashSet};
 use std::sync::Arc;
 
 use axum::{
     body::Body,
     extract::State,
-    http::{Request, StatusCode},
+    http::{Method, Request, StatusCode},
     middleware::Next,
     response::Response,
 };
@@ -11,12 +12,14 @@ use tracing::{debug, error, instrument};
 
 use crate::common::error::{AppError, ErrorKind};
 use crate::common::middleware::auth::UserInfo;
-use crate::infrastructure::database::DatabaseConnectionTrait;
+use crate::domain::tenant::{is_auth_method_allowed, RateLimitBucket, Tenant, TenantService};
 
 #[derive(Clone)]
-#[allow(dead_code)]
 pub struct TenantState {
-    pub db: Arc<dyn DatabaseConnectionTrait>,
+    pub tenant_service: Arc<dyn TenantService>,
+    /// Let requests without a caller or without a tenant through instead of
+    /// rejecting them
+    pub optional: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -25,53 +28,81 @@ pub struct TenantInfo {
     pub id: String,
     pub domain: String,
     pub is_active: bool,
+    pub read_only: bool,
+    pub allowed_auth_methods: HashSet<String>,
+    /// Requests per minute per bucket; buckets without an entry are not
+    /// limited
+    pub rate_limits: BTreeMap<RateLimitBucket, i32>,
+}
+
+impl From<Tenant> for TenantInfo {
+    fn from(tenant: Tenant) -> Self {
+        let rate_limits = [RateLimitBucket::Read, RateLimitBucket::Write]
+            .into_iter()
+            .map(|bucket| (bucket, tenant.settings.rate_limit(bucket)))
+            .collect();
+        Self {
+            id: tenant.id.to_string(),
+            domain: tenant.domain,
+            is_active: tenant.is_active,
+            read_only: tenant.settings.read_only,
+            allowed_auth_methods: tenant.settings.allowed_auth_methods,
+            rate_limits,
+        }
+    }
 }
 
 impl TenantState {
-    #[allow(dead_code)]
-    pub fn new(db: Arc<dyn DatabaseConnectionTrait>) -> Self {
-        Self { db }
+    pub fn new(tenant_service: Arc<dyn TenantService>) -> Self {
+        Self {
+            tenant_service,
+            optional: false,
+        }
+    }
+
+    /// Passes anonymous requests and callers without a tenant through
+    /// untouched, for mounting in front of routes that don't all require a
+    /// login. Authentication itself stays with the handlers.
+    pub fn optional(mut self) -> Self {
+        self.optional = true;
+        self
     }
 
-    #[allow(dead_code)]
     async fn get_tenant(&self, tenant_id: &str) -> Result<TenantInfo, AppError> {
-        // Mock implementation for testing
-        let inactive_id = "00000000-0000-0000-0000-000000000001";
-        let not_found_id = "00000000-0000-0000-0000-000000000002";
-
-        if tenant_id == inactive_id {
-            Ok(TenantInfo {
-                id: tenant_id.to_string(),
-                domain: format!("{}.example.com", tenant_id),
-                is_active: false,
-            })
-        } else if tenant_id == not_found_id {
-            Err(AppError::not_found("Tenant not found"))
-        } else {
-            Ok(TenantInfo {
-                id: tenant_id.to_string(),
-                domain: format!("{}.example.com", tenant_id),
-                is_active: true,
-            })
-        }
+        self.tenant_service
+            .find_by_id(tenant_id)
+            .await
+            .map(TenantInfo::from)
     }
 }
 
+// Mutating methods are blocked for read-only tenants
+pub(crate) fn is_write_method(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::POST | Method::PUT | Method::PATCH | Method::DELETE
+    )
+}
+
 #[instrument(skip(state, req, next))]
 pub async fn tenant_middleware(
     State(state): State<TenantState>,
     mut req: Request<Body>,
     next: Next,
 ) -> Result<Response, StatusCode> {
-    let user_info = req
-        .extensions()
-        .get::<UserInfo>()
-        .ok_or(StatusCode::UNAUTHORIZED)?;
+    let Some(user_info) = req.extensions().get::<UserInfo>() else {
+        if state.optional {
+            return Ok(next.run(req).await);
+        }
+        return Err(StatusCode::UNAUTHORIZED);
+    };
 
-    let tenant_id = user_info
-        .tenant_id
-        .as_ref()
-        .ok_or(StatusCode::BAD_REQUEST)?;
+    let Some(tenant_id) = user_info.tenant_id.as_ref() else {
+        if state.optional {
+            return Ok(next.run(req).await);
+        }
+        return Err(StatusCode::BAD_REQUEST);
+    };
 
     // Validate tenant ID format
     if uuid::Uuid::parse_str(tenant_id).is_err() {
@@ -86,6 +117,23 @@ pub async fn tenant_middleware(
                 return Err(StatusCode::FORBIDDEN);
             }
 
+            if tenant_info.read_only && is_write_method(req.method()) {
+                error!(
+                    "Tenant {} is read-only, rejecting {} request",
+                    tenant_id,
+                    req.method()
+                );
+                return Err(StatusCode::FORBIDDEN);
+            }
+
+            if !is_auth_method_allowed(&tenant_info.allowed_auth_methods, &user_info.auth_methods) {
+                error!(
+                    "Tenant {} does not allow login via {:?}",
+                    tenant_id, user_info.auth_methods
+                );
+                return Err(StatusCode::FORBIDDEN);
+            }
+
             debug!("Tenant {} is valid", tenant_id);
             req.extensions_mut().insert(tenant_info);
             Ok(next.run(req).await)
@@ -3,10 +3,11 @@ use std::sync::Arc;
 use axum::{
     body::Body,
     http::{Request, StatusCode},
-    routing::get,
+    routing::{get, post},
     Router,
 };
-use sea_orm::{DatabaseBackend, DatabaseConnection, MockDatabase};
+use chrono::Utc;
+use sea_orm::{DatabaseBackend, MockDatabase};
 use tower::ServiceExt;
 use uuid::Uuid;
 
@@ -15,32 +16,41 @@ use super::{
     tenant::{tenant_middleware, TenantState},
 };
 use crate::{
-    common::error::AppResult,
-    domain::tenant::{Tenant, TenantFeatures, TenantSettings},
-    infrastructure::database::connection::DatabaseConnectionTrait,
+    domain::tenant::{Tenant, TenantFeatures, TenantSettings, TENANT_SETTINGS_VERSION},
+    infrastructure::{database::entities::tenant, services::tenant_service::TenantServiceImpl},
 };
 
-#[derive(Clone)]
-pub struct MockDatabaseConnection;
-
-#[async_trait::async_trait]
-impl DatabaseConnectionTrait for MockDatabaseConnection {
-    async fn connect(&self) -> AppResult<DatabaseConnection> {
-        Ok(MockDatabase::new(DatabaseBackend::Postgres).into_connection())
-    }
+async fn test_endpoint() -> &'static str {
+    "Hello, World!"
+}
 
-    fn clone_box(&self) -> Box<dyn DatabaseConnectionTrait> {
-        Box::new(self.clone())
-    }
+/// Tenant state over a database that answers the tenant lookup with
+/// `stored`, or finds nothing when it is `None`
+fn tenant_state(stored: Option<&Tenant>) -> TenantState {
+    let rows: Vec<tenant::Model> = stored.map(stored_model).into_iter().collect();
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results(vec![rows])
+        .into_connection();
+    TenantState::new(Arc::new(TenantServiceImpl::new(Arc::new(db))))
 }
 
-async fn test_endpoint() -> &'static str {
-    "Hello, World!"
+fn stored_model(tenant: &Tenant) -> tenant::Model {
+    tenant::Model {
+        id: tenant.id,
+        name: tenant.name.clone(),
+        domain: tenant.domain.clone(),
+        is_active: tenant.is_active,
+        settings: serde_json::to_value(&tenant.settings).expect("serializable settings"),
+        created_at: Utc::now().naive_utc(),
+        updated_at: Utc::now().naive_utc(),
+        deleted_at: None,
+    }
 }
 
 fn create_test_router(tenant_state: TenantState) -> Router {
     Router::new()
         .route("/test", get(test_endpoint))
+        .route("/test", post(test_endpoint))
         .layer(axum::middleware::from_fn_with_state(
             tenant_state,
             tenant_middleware,
@@ -54,6 +64,8 @@ fn create_test_user(tenant_id: Option<&str>) -> UserInfo {
         email: Some("testuser@example.com".to_string()),
         roles: vec!["user".to_string()],
         tenant_id: tenant_id.map(String::from),
+        permissions: Default::default(),
+        auth_methods: vec![],
     }
 }
 
@@ -73,15 +85,30 @@ fn create_test_tenant(is_active: bool) -> Tenant {
                 api_access: true,
                 audit_logging: true,
             },
+            read_only: false,
+            allowed_auth_methods: Default::default(),
+            rate_limits: Default::default(),
+            settings_version: TENANT_SETTINGS_VERSION,
         },
+        updated_at: None,
     }
 }
 
+fn read_only_tenant() -> Tenant {
+    let mut tenant = create_test_tenant(true);
+    tenant.settings.read_only = true;
+    tenant
+}
+
+fn sso_only_tenant() -> Tenant {
+    let mut tenant = create_test_tenant(true);
+    tenant.settings.allowed_auth_methods = ["sso".to_string()].into();
+    tenant
+}
+
 #[tokio::test]
 async fn test_tenant_middleware_no_user_info() {
-    let db = Arc::new(MockDatabaseConnection);
-    let tenant_state = TenantState::new(db);
-    let app = create_test_router(tenant_state);
+    let app = create_test_router(tenant_state(None));
 
     let response = app
         .oneshot(Request::builder().uri("/test").body(Body::empty()).unwrap())
@@ -93,9 +120,7 @@ async fn test_tenant_middleware_no_user_info() {
 
 #[tokio::test]
 async fn test_tenant_middleware_no_tenant_id() {
-    let db = Arc::new(MockDatabaseConnection);
-    let tenant_state = TenantState::new(db);
-    let app = create_test_router(tenant_state);
+    let app = create_test_router(tenant_state(None));
 
     let mut request = Request::builder().uri("/test").body(Body::empty()).unwrap();
     request.extensions_mut().insert(create_test_user(None));
@@ -107,9 +132,7 @@ async fn test_tenant_middleware_no_tenant_id() {
 
 #[tokio::test]
 async fn test_tenant_middleware_invalid_tenant_id() {
-    let db = Arc::new(MockDatabaseConnection);
-    let tenant_state = TenantState::new(db);
-    let app = create_test_router(tenant_state);
+    let app = create_test_router(tenant_state(None));
 
     let mut request = Request::builder().uri("/test").body(Body::empty()).unwrap();
     request
@@ -123,14 +146,12 @@ async fn test_tenant_middleware_invalid_tenant_id() {
 
 #[tokio::test]
 async fn test_tenant_middleware_tenant_not_found() {
-    let db = Arc::new(MockDatabaseConnection);
-    let tenant_state = TenantState::new(db);
-    let app = create_test_router(tenant_state);
+    let app = create_test_router(tenant_state(None));
 
     let mut request = Request::builder().uri("/test").body(Body::empty()).unwrap();
-    request.extensions_mut().insert(create_test_user(Some(
-        "00000000-0000-0000-0000-000000000002",
-    )));
+    request
+        .extensions_mut()
+        .insert(create_test_user(Some(&Uuid::new_v4().to_string())));
 
     let response = app.oneshot(request).await.unwrap();
 
@@ -139,14 +160,13 @@ async fn test_tenant_middleware_tenant_not_found() {
 
 #[tokio::test]
 async fn test_tenant_middleware_inactive_tenant() {
-    let db = Arc::new(MockDatabaseConnection);
-    let tenant_state = TenantState::new(db);
-    let app = create_test_router(tenant_state);
+    let tenant = create_test_tenant(false);
+    let app = create_test_router(tenant_state(Some(&tenant)));
 
     let mut request = Request::builder().uri("/test").body(Body::empty()).unwrap();
-    request.extensions_mut().insert(create_test_user(Some(
-        "00000000-0000-0000-0000-000000000001",
-    )));
+    request
+        .extensions_mut()
+        .insert(create_test_user(Some(&tenant.id.to_string())));
 
     let response = app.oneshot(request).await.unwrap();
 
@@ -155,11 +175,9 @@ async fn test_tenant_middleware_inactive_tenant() {
 
 #[tokio::test]
 async fn test_tenant_middleware_valid_tenant() {
-    let db = Arc::new(MockDatabaseConnection);
-    let tenant_state = TenantState::new(db);
-    let app = create_test_router(tenant_state);
-
     let tenant = create_test_tenant(true);
+    let app = create_test_router(tenant_state(Some(&tenant)));
+
     let mut request = Request::builder().uri("/test").body(Body::empty()).unwrap();
     request
         .extensions_mut()
@@ -173,3 +191,118 @@ async fn test_tenant_middleware_valid_tenant() {
         .unwrap();
     assert_eq!(&body[..], b"Hello, World!");
 }
+
+#[tokio::test]
+async fn test_tenant_middleware_read_only_tenant_allows_get() {
+    let tenant = read_only_tenant();
+    let app = create_test_router(tenant_state(Some(&tenant)));
+
+    let mut request = Request::builder()
+        .uri("/test")
+        .body(Body::empty())
+        .expect("valid request");
+    request
+        .extensions_mut()
+        .insert(create_test_user(Some(&tenant.id.to_string())));
+
+    let response = app.oneshot(request).await.expect("response");
+
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_tenant_middleware_read_only_tenant_rejects_post() {
+    let tenant = read_only_tenant();
+    let app = create_test_router(tenant_state(Some(&tenant)));
+
+    let mut request = Request::builder()
+        .method("POST")
+        .uri("/test")
+        .body(Body::empty())
+        .expect("valid request");
+    request
+        .extensions_mut()
+        .insert(create_test_user(Some(&tenant.id.to_string())));
+
+    let response = app.oneshot(request).await.expect("response");
+
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_tenant_middleware_allows_permitted_auth_method() {
+    let tenant = sso_only_tenant();
+    let app = create_test_router(tenant_state(Some(&tenant)));
+
+    let mut user = create_test_user(Some(&tenant.id.to_string()));
+    user.auth_methods = vec!["sso".to_string()];
+    let mut request = Request::builder()
+        .uri("/test")
+        .body(Body::empty())
+        .expect("valid request");
+    request.extensions_mut().insert(user);
+
+    let response = app.oneshot(request).await.expect("infallible router");
+
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_tenant_middleware_rejects_disallowed_auth_method() {
+    let tenant = sso_only_tenant();
+    let app = create_test_router(tenant_state(Some(&tenant)));
+
+    let mut user = create_test_user(Some(&tenant.id.to_string()));
+    user.auth_methods = vec!["pwd".to_string()];
+    let mut request = Request::builder()
+        .uri("/test")
+        .body(Body::empty())
+        .expect("valid request");
+    request.extensions_mut().insert(user);
+
+    let response = app.oneshot(request).await.expect("infallible router");
+
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_optional_tenant_middleware_passes_anonymous_requests() {
+    let app = create_test_router(tenant_state(None).optional());
+
+    let request = Request::builder()
+        .uri("/test")
+        .body(Body::empty())
+        .expect("valid request");
+    let response = app
+        .clone()
+        .oneshot(request)
+        .await
+        .expect("infallible router");
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let mut request = Request::builder()
+        .uri("/test")
+        .body(Body::empty())
+        .expect("valid request");
+    request.extensions_mut().insert(create_test_user(None));
+    let response = app.oneshot(request).await.expect("infallible router");
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_optional_tenant_middleware_still_enforces_read_only() {
+    let tenant = read_only_tenant();
+    let app = create_test_router(tenant_state(Some(&tenant)).optional());
+
+    let mut request = Request::builder()
+        .method("POST")
+        .uri("/test")
+        .body(Body::empty())
+        .expect("valid request");
+    request
+        .extensions_mut()
+        .insert(create_test_user(Some(&tenant.id.to_string())));
+    let response = app.oneshot(request).await.expect("infallible router");
+
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
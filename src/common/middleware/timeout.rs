@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::{
+    body::Body,
+    extract::{MatchedPath, State},
+    http::{Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use metrics::counter;
+use tracing::warn;
+
+use crate::common::config::RequestTimeoutSettings;
+
+/// Per-route time limits for producing a response.
+///
+/// Routes are looked up by their pattern, e.g. `/tenants/{id}/export-data`:
+/// an exact override wins, then the longest `/*` group covering the route,
+/// then the default. The limit ends once the response head is ready, so a
+/// streamed body may take longer.
+#[derive(Debug, Clone)]
+pub struct RequestTimeouts {
+    default: Duration,
+    routes: Arc<HashMap<String, Duration>>,
+    groups: Arc<Vec<(String, Duration)>>,
+}
+
+impl RequestTimeouts {
+    pub fn new(settings: &RequestTimeoutSettings) -> Self {
+        let mut routes = HashMap::new();
+        let mut groups = Vec::new();
+        for (pattern, secs) in &settings.routes {
+            let timeout = Duration::from_secs(*secs);
+            match pattern.strip_suffix("/*") {
+                Some(prefix) => groups.push((prefix.to_string(), timeout)),
+                None => {
+                    routes.insert(pattern.clone(), timeout);
+                },
+            }
+        }
+        groups.sort_by_key(|(prefix, _)| std::cmp::Reverse(prefix.len()));
+
+        Self {
+            default: Duration::from_secs(settings.default_secs),
+            routes: Arc::new(routes),
+            groups: Arc::new(groups),
+        }
+    }
+
+    /// Limit for the route with the given pattern
+    pub fn for_route(&self, route: &str) -> Duration {
+        if let Some(timeout) = self.routes.get(route) {
+            return *timeout;
+        }
+        self.groups
+            .iter()
+            .find(|(prefix, _)| {
+                route
+                    .strip_prefix(prefix.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+            })
+            .map_or(self.default, |(_, timeout)| *timeout)
+    }
+}
+
+pub async fn request_timeout(
+    State(timeouts): State<RequestTimeouts>,
+    req: Request<Body>,
+    next: Next,
+) -> Response {
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string());
+    let limit = route
+        .as_deref()
+        .map_or(timeouts.default, |route| timeouts.for_route(route));
+    let (method, uri) = (req.method().clone(), req.uri().clone());
+
+    match tokio::time::timeout(limit, next.run(req)).await {
+        Ok(response) => response,
+        Err(_) => {
+            counter!("http_requests_timed_out_total").increment(1);
+            warn!(
+                "Timed out {} {} after {}s",
+                method,
+                uri,
+                limit.as_secs_f64()
+            );
+            StatusCode::REQUEST_TIMEOUT.into_response()
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::get, Router};
+    use tower::ServiceExt;
+
+    async fn slow() -> &'static str {
+        tokio::time::sleep(Duration::from_secs(60)).await;
+        "done"
+    }
+
+    fn app() -> Router {
+        let timeouts = RequestTimeouts::new(&RequestTimeoutSettings {
+            default_secs: 30,
+            routes: HashMap::from([("/health".to_string(), 2), ("/bulk/*".to_string(), 120)]),
+        });
+        Router::new()
+            .route("/tenants", get(slow))
+            .route("/bulk/import", get(slow))
+            .layer(axum::middleware::from_fn_with_state(
+                timeouts,
+                request_timeout,
+            ))
+    }
+
+    async fn status_of(uri: &str) -> StatusCode {
+        app()
+            .oneshot(
+                Request::builder()
+                    .uri(uri)
+                    .body(Body::empty())
+                    .expect("valid request"),
+            )
+            .await
+            .expect("infallible router")
+            .status()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_bulk_route_gets_its_longer_timeout() {
+        let started = tokio::time::Instant::now();
+        assert_eq!(status_of("/bulk/import").await, StatusCode::OK);
+        assert_eq!(started.elapsed(), Duration::from_secs(60));
+
+        // The same handler on a normal route is cut off at the default
+        let started = tokio::time::Instant::now();
+        assert_eq!(status_of("/tenants").await, StatusCode::REQUEST_TIMEOUT);
+        assert_eq!(started.elapsed(), Duration::from_secs(30));
+    }
+
+    #[test]
+    fn test_most_specific_timeout_wins() {
+        let timeouts = RequestTimeouts::new(&RequestTimeoutSettings {
+            default_secs: 30,
+            routes: HashMap::from([
+                ("/tenants/*".to_string(), 60),
+                ("/tenants/{id}/events/*".to_string(), 5),
+                ("/tenants/{id}/export-data".to_string(), 300),
+            ]),
+        });
+        assert_eq!(timeouts.for_route("/health"), Duration::from_secs(30));
+        assert_eq!(timeouts.for_route("/tenants"), Duration::from_secs(60));
+        assert_eq!(timeouts.for_route("/tenants/{id}"), Duration::from_secs(60));
+        assert_eq!(
+            timeouts.for_route("/tenants/{id}/events/live"),
+            Duration::from_secs(5)
+        );
+        assert_eq!(
+            timeouts.for_route("/tenants/{id}/export-data"),
+            Duration::from_secs(300)
+        );
+        assert_eq!(timeouts.for_route("/tenantsx"), Duration::from_secs(30));
+    }
+}
@@ -1,8 +1,10 @@
 pub mod config;
 pub mod error;
 pub mod i18n;
+pub mod json;
 pub mod logging;
 pub mod metrics;
 pub mod middleware;
+pub mod pagination;
 
 pub use logging::init as setup_logging;
@@ -0,0 +1,334 @@
+use axum::http::{header, HeaderMap, HeaderValue};
+use serde::Deserialize;
+
+use crate::common::config::{PaginationSettings, ServerSettings};
+use crate::common::error::AppError;
+
+/// Raw `?page=&page_size=` query parameters of list endpoints
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PaginationParams {
+    pub page: Option<u64>,
+    pub page_size: Option<u64>,
+}
+
+/// Validated pagination window (pages are 1-based)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pagination {
+    pub page: u64,
+    pub page_size: u64,
+}
+
+impl PaginationParams {
+    /// Applies the configured default and clamps `page_size` to the maximum
+    pub fn resolve(&self, settings: &PaginationSettings) -> Pagination {
+        let max_page_size = settings.max_page_size.max(1);
+        let page_size = self
+            .page_size
+            .unwrap_or(settings.default_page_size)
+            .clamp(1, max_page_size);
+
+        Pagination {
+            page: self.page.unwrap_or(1).max(1),
+            page_size,
+        }
+    }
+}
+
+impl Pagination {
+    pub fn offset(&self) -> u64 {
+        (self.page - 1).saturating_mul(self.page_size)
+    }
+
+    /// `Link` header with the previous and next pages of `path` as absolute
+    /// URLs. There is no total count, so a full page is taken to have a next
+    /// page. A query string in `path`, e.g. with filters, is kept.
+    pub fn links(&self, server: &ServerSettings, path: &str, returned: usize) -> HeaderMap {
+        let separator = if path.contains('?') { '&' } else { '?' };
+        let page = |page: u64| {
+            server.absolute_url(&format!(
+                "{}{}page={}&page_size={}",
+                path, separator, page, self.page_size
+            ))
+        };
+        let mut links = Vec::new();
+        if self.page > 1 {
+            links.push((page(self.page - 1), "prev"));
+        }
+        if returned as u64 >= self.page_size {
+            links.push((page(self.page + 1), "next"));
+        }
+        link_header(links)
+    }
+}
+
+/// Field a list can be sorted by. Implemented by one enum per list, so only
+/// its variants, never the raw query parameter, reach the `ORDER BY`.
+pub trait SortField: Copy + 'static {
+    /// Names accepted by `?sort=`, with the field each one stands for
+    const FIELDS: &'static [(&'static str, Self)];
+}
+
+/// Validated sort order of a list, from `?sort=field` (ascending) or
+/// `?sort=-field` (descending)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sort<F> {
+    pub field: F,
+    pub descending: bool,
+}
+
+impl<F: SortField> Sort<F> {
+    /// Parses a `?sort=` value, rejecting fields outside `F::FIELDS` as a
+    /// validation error
+    pub fn parse(value: &str) -> Result<Self, AppError> {
+        let (name, descending) = match value.strip_prefix('-') {
+            Some(name) => (name, true),
+            None => (value, false),
+        };
+        let field = F::FIELDS
+            .iter()
+            .find(|(field_name, _)| *field_name == name)
+            .map(|(_, field)| *field)
+            .ok_or_else(|| {
+                let names: Vec<_> = F::FIELDS.iter().map(|(name, _)| *name).collect();
+                AppError::validation(format!(
+                    "Cannot sort by '{}', sortable fields are: {}",
+                    name,
+                    names.join(", ")
+                ))
+            })?;
+
+        Ok(Self { field, descending })
+    }
+}
+
+/// Raw `?from=&count=` query parameters of event stream reads
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct EventWindowParams {
+    pub from: Option<u64>,
+    pub count: Option<u64>,
+}
+
+/// Validated window of events starting at stream position `from`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EventWindow {
+    pub from: u64,
+    pub count: u64,
+}
+
+impl EventWindowParams {
+    /// Applies the configured default and clamps `count` to the maximum
+    pub fn resolve(&self, settings: &PaginationSettings) -> EventWindow {
+        let max_count = settings.max_event_count.max(1);
+        let count = self
+            .count
+            .unwrap_or(settings.default_event_count)
+            .clamp(1, max_count);
+
+        EventWindow {
+            from: self.from.unwrap_or(0),
+            count,
+        }
+    }
+}
+
+impl EventWindow {
+    /// Position to continue from after `read` events came back, or `None`
+    /// once a short read shows the end of the stream was reached
+    pub fn next(&self, read: usize) -> Option<u64> {
+        (read as u64 >= self.count).then(|| self.from.saturating_add(self.count))
+    }
+
+    /// `Link` header with the absolute URL of the window after this one, if
+    /// `next` says there may be one
+    pub fn links(&self, server: &ServerSettings, path: &str, read: usize) -> HeaderMap {
+        link_header(self.next(read).map(|next| {
+            (
+                server.absolute_url(&format!("{}?from={}&count={}", path, next, self.count)),
+                "next",
+            )
+        }))
+    }
+}
+
+/// Formats `(url, rel)` pairs as a single `Link` header. A base URL that
+/// isn't a valid header value only costs the links, not the response.
+fn link_header(links: impl IntoIterator<Item = (String, &'static str)>) -> HeaderMap {
+    let value = links
+        .into_iter()
+        .map(|(url, rel)| format!("<{}>; rel=\"{}\"", url, rel))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let mut headers = HeaderMap::new();
+    if let Ok(value) = HeaderValue::from_str(&value) {
+        if !value.is_empty() {
+            headers.insert(header::LINK, value);
+        }
+    }
+    headers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings() -> PaginationSettings {
+        PaginationSettings {
+            default_page_size: 20,
+            max_page_size: 100,
+            default_event_count: 50,
+            max_event_count: 200,
+            default_tenant_sort: "name".to_string(),
+            default_user_sort: "username".to_string(),
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Field {
+        Name,
+        CreatedAt,
+    }
+
+    impl SortField for Field {
+        const FIELDS: &'static [(&'static str, Self)] =
+            &[("name", Field::Name), ("created_at", Field::CreatedAt)];
+    }
+
+    #[test]
+    fn test_sort_direction_follows_prefix() {
+        assert_eq!(
+            Sort::<Field>::parse("name").expect("sortable"),
+            Sort {
+                field: Field::Name,
+                descending: false
+            }
+        );
+        assert_eq!(
+            Sort::<Field>::parse("-created_at").expect("sortable"),
+            Sort {
+                field: Field::CreatedAt,
+                descending: true
+            }
+        );
+    }
+
+    #[test]
+    fn test_sort_rejects_fields_outside_whitelist() {
+        for value in ["password", "name; DROP TABLE tenant", "--name", ""] {
+            let error = Sort::<Field>::parse(value).expect_err("not sortable");
+            assert!(
+                matches!(
+                    *error.kind,
+                    crate::common::error::ErrorKind::ValidationError(_)
+                ),
+                "{:?}",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn test_defaults_when_omitted() {
+        let pagination = PaginationParams::default().resolve(&settings());
+        assert_eq!(
+            pagination,
+            Pagination {
+                page: 1,
+                page_size: 20
+            }
+        );
+        assert_eq!(pagination.offset(), 0);
+    }
+
+    #[test]
+    fn test_page_size_is_clamped() {
+        let params = PaginationParams {
+            page: Some(3),
+            page_size: Some(1_000_000),
+        };
+        let pagination = params.resolve(&settings());
+        assert_eq!(pagination.page_size, 100);
+        assert_eq!(pagination.offset(), 200);
+
+        let params = PaginationParams {
+            page: Some(0),
+            page_size: Some(0),
+        };
+        let pagination = params.resolve(&settings());
+        assert_eq!(
+            pagination,
+            Pagination {
+                page: 1,
+                page_size: 1
+            }
+        );
+    }
+
+    #[test]
+    fn test_event_count_is_clamped_and_pages_continue() {
+        let params = EventWindowParams {
+            from: Some(400),
+            count: Some(1_000_000),
+        };
+        let window = params.resolve(&settings());
+        assert_eq!(
+            window,
+            EventWindow {
+                from: 400,
+                count: 200
+            }
+        );
+        // A full window may be followed by more events
+        assert_eq!(window.next(200), Some(600));
+        assert_eq!(window.next(120), None);
+
+        let window = EventWindowParams::default().resolve(&settings());
+        assert_eq!(window, EventWindow { from: 0, count: 50 });
+    }
+
+    fn server(public_base_url: &str) -> ServerSettings {
+        ServerSettings {
+            backend_port: 3333,
+            default_language: "en".to_string(),
+            public_base_url: public_base_url.to_string(),
+        }
+    }
+
+    fn link(headers: &HeaderMap) -> Option<&str> {
+        headers
+            .get(header::LINK)
+            .map(|value| value.to_str().expect("ASCII link header"))
+    }
+
+    #[test]
+    fn test_links_use_public_base_url() {
+        let server = server("https://api.example.com/");
+        let pagination = Pagination {
+            page: 2,
+            page_size: 20,
+        };
+
+        assert_eq!(
+            link(&pagination.links(&server, "/tenants", 20)),
+            Some(
+                "<https://api.example.com/tenants?page=1&page_size=20>; rel=\"prev\", \
+                 <https://api.example.com/tenants?page=3&page_size=20>; rel=\"next\""
+            )
+        );
+        // A short page is the last one
+        assert_eq!(
+            link(&pagination.links(&server, "/tenants", 5)),
+            Some("<https://api.example.com/tenants?page=1&page_size=20>; rel=\"prev\"")
+        );
+
+        let window = EventWindow {
+            from: 100,
+            count: 50,
+        };
+        assert_eq!(
+            link(&window.links(&server, "/tenants/42/events", 50)),
+            Some("<https://api.example.com/tenants/42/events?from=150&count=50>; rel=\"next\"")
+        );
+        assert!(link(&window.links(&server, "/tenants/42/events", 10)).is_none());
+    }
+}
@@ -0,0 +1,63 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::common::{error::AppResult, pagination::Pagination};
+
+/// One recorded action on a tenant's data
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    /// Subject of the user or API key that acted; `None` for the system
+    pub actor: Option<String>,
+    /// What was done, e.g. `tenant.update`
+    pub action: String,
+    /// Kind of the affected entity, e.g. `tenant` or `user`
+    pub entity_type: String,
+    pub entity_id: Option<String>,
+    pub details: Option<Value>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl AuditEntry {
+    /// Entry for an action taken now
+    pub fn new(
+        tenant_id: Uuid,
+        actor: Option<String>,
+        action: impl Into<String>,
+        entity_type: impl Into<String>,
+        entity_id: impl ToString,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            tenant_id,
+            actor,
+            action: action.into(),
+            entity_type: entity_type.into(),
+            entity_id: Some(entity_id.to_string()),
+            details: None,
+            created_at: Utc::now(),
+        }
+    }
+}
+
+/// Criteria entries must all match; absent fields match every entry
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AuditFilter {
+    pub action: Option<String>,
+    pub entity_type: Option<String>,
+}
+
+#[async_trait::async_trait]
+pub trait AuditLogService: Send + Sync + 'static {
+    async fn record(&self, entry: AuditEntry) -> AppResult<()>;
+    /// One page of the tenant's entries matching `filter`, newest first
+    async fn list(
+        &self,
+        tenant_id: &Uuid,
+        filter: &AuditFilter,
+        pagination: Pagination,
+    ) -> AppResult<Vec<AuditEntry>>;
+}
@@ -1,5 +1,8 @@
+pub mod api_key;
+pub mod audit;
 pub mod tenant;
 pub mod user;
+pub mod webhook;
 
 #[allow(unused_imports)]
 pub use tenant::*;
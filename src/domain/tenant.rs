@@ -1,10 +1,14 @@
 use crate::common::{
     error::{AppError, AppResult, ErrorContext},
     i18n::{I18nManager, SupportedLanguage},
+    pagination::{Pagination, Sort, SortField},
 };
+use chrono::{DateTime, Utc};
+use icu_normalizer::ComposingNormalizer;
 use lazy_static::lazy_static;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
 use uuid::Uuid;
 
 lazy_static! {
@@ -14,6 +18,43 @@ lazy_static! {
     ).expect("Invalid domain validation regex pattern");
 }
 
+/// Canonical form of a tenant domain: lowercase and without the trailing
+/// dot of a fully qualified name, so `Example.COM.` and `example.com` are
+/// the same tenant.
+pub fn normalize_domain(domain: &str) -> String {
+    domain.trim_end_matches('.').to_ascii_lowercase()
+}
+
+/// Canonical form of a display name: NFC-normalized and without surrounding
+/// whitespace, so a name typed with a combining accent and one typed with
+/// the precomposed letter are stored alike.
+pub fn normalize_name(name: &str) -> String {
+    ComposingNormalizer::new_nfc()
+        .normalize(name)
+        .trim()
+        .to_string()
+}
+
+/// Fields `GET /tenants?sort=` accepts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TenantSortField {
+    Name,
+    Domain,
+    CreatedAt,
+    UpdatedAt,
+}
+
+impl SortField for TenantSortField {
+    const FIELDS: &'static [(&'static str, Self)] = &[
+        ("name", TenantSortField::Name),
+        ("domain", TenantSortField::Domain),
+        ("created_at", TenantSortField::CreatedAt),
+        ("updated_at", TenantSortField::UpdatedAt),
+    ];
+}
+
+pub type TenantSort = Sort<TenantSortField>;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Tenant {
     pub id: Uuid,
@@ -21,14 +62,86 @@ pub struct Tenant {
     pub domain: String,
     pub is_active: bool,
     pub settings: TenantSettings,
+    /// Time of the last stored change; `None` until the tenant is persisted
+    #[serde(default)]
+    pub updated_at: Option<DateTime<Utc>>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+/// Version of the stored `TenantSettings` JSON shape. Bump it whenever a
+/// field is added and teach `TenantServiceImpl` how to upgrade older rows.
+pub const TENANT_SETTINGS_VERSION: u32 = 4;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TenantSettings {
     pub max_users: i32,
     pub storage_limit: i64,  // in bytes
     pub api_rate_limit: i32, // requests per minute
     pub features: TenantFeatures,
+    #[serde(default)]
+    pub read_only: bool, // reads allowed, writes rejected
+    /// Login methods (`amr` values such as `pwd`, `otp` or an IdP alias) a
+    /// token must have been obtained with; empty allows every method
+    #[serde(default)]
+    pub allowed_auth_methods: HashSet<String>,
+    /// Per-minute limits of individual buckets; a bucket without its own
+    /// limit falls back to `api_rate_limit`
+    #[serde(default)]
+    pub rate_limits: BTreeMap<RateLimitBucket, i32>,
+    #[serde(default = "default_settings_version")]
+    pub settings_version: u32,
+}
+
+impl Default for TenantSettings {
+    fn default() -> Self {
+        Self {
+            max_users: 0,
+            storage_limit: 0,
+            api_rate_limit: 0,
+            features: TenantFeatures::default(),
+            read_only: false,
+            allowed_auth_methods: HashSet::new(),
+            rate_limits: BTreeMap::new(),
+            settings_version: TENANT_SETTINGS_VERSION,
+        }
+    }
+}
+
+/// Class of API requests counted against its own rate limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RateLimitBucket {
+    Read,
+    Write,
+}
+
+impl RateLimitBucket {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Read => "read",
+            Self::Write => "write",
+        }
+    }
+}
+
+impl TenantSettings {
+    /// Requests per minute allowed in `bucket`
+    pub fn rate_limit(&self, bucket: RateLimitBucket) -> i32 {
+        self.rate_limits
+            .get(&bucket)
+            .copied()
+            .unwrap_or(self.api_rate_limit)
+    }
+}
+
+/// Whether a token obtained with `used` methods may access a tenant that
+/// allows `allowed`. A token without any method recorded is rejected unless
+/// the tenant allows every method.
+pub fn is_auth_method_allowed(allowed: &HashSet<String>, used: &[String]) -> bool {
+    allowed.is_empty() || used.iter().any(|method| allowed.contains(method))
+}
+
+fn default_settings_version() -> u32 {
+    TENANT_SETTINGS_VERSION
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -39,6 +152,19 @@ pub struct TenantFeatures {
     pub audit_logging: bool,
 }
 
+/// Recorded on the tenant's stream when a deactivated or soft-deleted tenant
+/// is brought back
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TenantReactivated {
+    pub tenant_id: Uuid,
+}
+
+impl event_store::TypeName for TenantReactivated {
+    fn type_name(&self) -> String {
+        "TenantReactivated".to_string()
+    }
+}
+
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
 pub struct TenantContext {
@@ -47,6 +173,12 @@ pub struct TenantContext {
 }
 
 impl Tenant {
+    // Bring user-supplied fields into their canonical form; call before validate
+    pub fn normalize(&mut self) {
+        self.name = normalize_name(&self.name);
+        self.domain = normalize_domain(&self.domain);
+    }
+
     // Main validation method that checks all tenant fields
     #[allow(dead_code)]
     pub fn validate(&self) -> AppResult<()> {
@@ -66,6 +198,11 @@ impl Tenant {
                 "Tenant name cannot exceed 100 characters",
             ));
         }
+        if self.name.chars().any(char::is_control) {
+            return Err(AppError::validation(
+                "Tenant name cannot contain control characters",
+            ));
+        }
         Ok(())
     }
 
@@ -91,6 +228,18 @@ impl Tenant {
             return Err(AppError::validation("API rate limit must be at least 1"));
         }
 
+        if let Some((bucket, _)) = self
+            .settings
+            .rate_limits
+            .iter()
+            .find(|(_, limit)| **limit < 1)
+        {
+            return Err(AppError::validation(format!(
+                "Rate limit of the {} bucket must be at least 1",
+                bucket.as_str()
+            )));
+        }
+
         Ok(())
     }
 
@@ -141,13 +290,30 @@ impl TenantContext {
 
 #[async_trait::async_trait]
 pub trait TenantService: Send + Sync + 'static {
-    async fn list(&self) -> AppResult<Vec<Tenant>>;
+    async fn list(&self, pagination: Pagination, sort: TenantSort) -> AppResult<Vec<Tenant>>;
     async fn find_by_id(&self, id: &str) -> AppResult<Tenant>;
     #[allow(dead_code)]
     async fn find_by_domain(&self, domain: &str) -> AppResult<Tenant>;
     async fn create(&self, tenant: Tenant) -> AppResult<Tenant>;
     async fn update(&self, tenant: Tenant) -> AppResult<Tenant>;
+    /// Like `update`, but only while the stored tenant still has the
+    /// `updated_at` it was read with; fails with a precondition error when
+    /// it changed in between
+    async fn update_unmodified(&self, tenant: Tenant) -> AppResult<Tenant>;
     async fn delete(&self, id: &str) -> AppResult<()>;
+    /// Soft-deletes the tenant for an erasure request: its users are
+    /// removed, and it is deactivated and hidden from listings and lookups,
+    /// but the row is kept. Returns the ids of the removed users, whose
+    /// streams the caller still has to tombstone.
+    async fn erase(&self, id: &str) -> AppResult<Vec<Uuid>>;
+    /// Brings a deactivated or soft-deleted tenant back, provided it still
+    /// passes validation and its domain hasn't been taken meanwhile
+    async fn reactivate(&self, id: &str) -> AppResult<Tenant>;
+    /// Tenants whose most recent user login predates `inactive_since`,
+    /// including tenants without any users or logins. Erased tenants are
+    /// left out.
+    #[allow(dead_code)]
+    async fn find_stale(&self, inactive_since: DateTime<Utc>) -> AppResult<Vec<Tenant>>;
 }
 
 #[cfg(test)]
@@ -170,7 +336,12 @@ mod tests {
                     api_access: true,
                     audit_logging: true,
                 },
+                read_only: false,
+                allowed_auth_methods: Default::default(),
+                rate_limits: Default::default(),
+                settings_version: TENANT_SETTINGS_VERSION,
             },
+            updated_at: None,
         }
     }
 
@@ -187,6 +358,33 @@ mod tests {
         assert!(tenant.validate_domain().is_err());
     }
 
+    #[test]
+    fn test_normalize_domain() {
+        let mut tenant = create_test_tenant(true);
+        tenant.domain = "Test.Example.COM.".to_string();
+        tenant.normalize();
+        assert_eq!(tenant.domain, "test.example.com");
+        assert!(tenant.validate_domain().is_ok());
+    }
+
+    #[test]
+    fn test_name_with_control_characters_is_rejected() {
+        let mut tenant = create_test_tenant(true);
+        tenant.name = "Test\u{0007}Tenant".to_string();
+        tenant.normalize();
+        assert!(tenant.validate_name().is_err());
+    }
+
+    #[test]
+    fn test_normalize_name() {
+        let mut tenant = create_test_tenant(true);
+        // "Café" with a combining acute accent
+        tenant.name = "  Cafe\u{0301} GmbH ".to_string();
+        tenant.normalize();
+        assert_eq!(tenant.name, "Caf\u{00e9} GmbH");
+        assert!(tenant.validate_name().is_ok());
+    }
+
     #[test]
     fn test_invalid_settings() {
         let mut tenant = create_test_tenant(true);
@@ -202,6 +400,26 @@ mod tests {
         assert!(tenant.validate_settings().is_err());
     }
 
+    #[test]
+    fn test_rate_limit_buckets_fall_back_to_api_rate_limit() {
+        let mut tenant = create_test_tenant(true);
+        tenant.settings.api_rate_limit = 600;
+        tenant
+            .settings
+            .rate_limits
+            .insert(RateLimitBucket::Write, 60);
+
+        assert_eq!(tenant.settings.rate_limit(RateLimitBucket::Read), 600);
+        assert_eq!(tenant.settings.rate_limit(RateLimitBucket::Write), 60);
+        assert!(tenant.validate_settings().is_ok());
+
+        tenant
+            .settings
+            .rate_limits
+            .insert(RateLimitBucket::Write, 0);
+        assert!(tenant.validate_settings().is_err());
+    }
+
     #[test]
     fn test_tenant_context_new() {
         let tenant = create_test_tenant(true);
@@ -1,11 +1,15 @@
 use chrono::{DateTime, Utc};
+use icu_timezone::TimeZoneIdMapper;
 use lazy_static::lazy_static;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::common::config::DefaultUserSettings;
 use crate::common::error::{AppError, AppResult};
-use crate::domain::tenant::TenantContext;
+use crate::common::i18n::SupportedLanguage;
+use crate::common::pagination::{Pagination, Sort, SortField};
+use crate::domain::tenant::{normalize_name, TenantContext};
 
 lazy_static! {
     static ref EMAIL_REGEX: Regex = Regex::new(
@@ -15,6 +19,28 @@ lazy_static! {
         .expect("Invalid username validation regex pattern");
 }
 
+/// Fields `GET /tenants/{id}/users?sort=` accepts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UserSortField {
+    Username,
+    Email,
+    FullName,
+    CreatedAt,
+    UpdatedAt,
+}
+
+impl SortField for UserSortField {
+    const FIELDS: &'static [(&'static str, Self)] = &[
+        ("username", UserSortField::Username),
+        ("email", UserSortField::Email),
+        ("full_name", UserSortField::FullName),
+        ("created_at", UserSortField::CreatedAt),
+        ("updated_at", UserSortField::UpdatedAt),
+    ];
+}
+
+pub type UserSort = Sort<UserSortField>;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct User {
     pub id: Uuid,
@@ -30,15 +56,22 @@ pub struct User {
     pub last_login_at: Option<DateTime<Utc>>,
 }
 
+/// Serialized like the `user_role` values in the database. The aliases
+/// accept the variant names that were written before.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
 pub enum UserRole {
+    #[serde(alias = "TenantAdmin")]
     TenantAdmin,
+    #[serde(alias = "Manager")]
     Manager,
+    #[serde(alias = "User")]
     User,
+    #[serde(alias = "ReadOnly")]
     ReadOnly,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserSettings {
     pub language: String,
     pub timezone: String,
@@ -46,6 +79,18 @@ pub struct UserSettings {
     pub ui_preferences: UiPreferences,
 }
 
+// Hand-written so the defaults pass `validate_settings`
+impl Default for UserSettings {
+    fn default() -> Self {
+        Self {
+            language: "en".to_string(),
+            timezone: "UTC".to_string(),
+            notification_preferences: NotificationPreferences::default(),
+            ui_preferences: UiPreferences::default(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct NotificationPreferences {
     pub email_notifications: bool,
@@ -53,21 +98,105 @@ pub struct NotificationPreferences {
     pub notification_types: Vec<NotificationType>,
 }
 
+/// Serialized like the `notification_type` values in the database. The
+/// aliases keep settings stored with the variant names readable.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum NotificationType {
+    #[serde(alias = "System")]
     System,
+    #[serde(alias = "Security")]
     Security,
+    #[serde(alias = "Updates")]
     Updates,
+    #[serde(alias = "Mentions")]
     Mentions,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UiPreferences {
     pub theme: String,
     pub sidebar_collapsed: bool,
     pub items_per_page: i32,
 }
 
+impl Default for UiPreferences {
+    fn default() -> Self {
+        Self {
+            theme: "light".to_string(),
+            sidebar_collapsed: false,
+            items_per_page: 25,
+        }
+    }
+}
+
+/// Fails for a notification type name that is not a `NotificationType`
+impl TryFrom<&DefaultUserSettings> for UserSettings {
+    type Error = AppError;
+
+    fn try_from(defaults: &DefaultUserSettings) -> AppResult<Self> {
+        let notification_types = defaults
+            .notification_types
+            .iter()
+            .map(|name| {
+                serde_json::from_value(serde_json::Value::from(name.as_str())).map_err(|_| {
+                    AppError::configuration(format!("Unknown notification type: {}", name))
+                })
+            })
+            .collect::<AppResult<Vec<NotificationType>>>()?;
+
+        Ok(Self {
+            language: defaults.language.clone(),
+            timezone: defaults.timezone.clone(),
+            notification_preferences: NotificationPreferences {
+                email_notifications: defaults.email_notifications,
+                in_app_notifications: defaults.in_app_notifications,
+                notification_types,
+            },
+            ui_preferences: UiPreferences {
+                theme: defaults.theme.clone(),
+                sidebar_collapsed: false,
+                items_per_page: defaults.items_per_page,
+            },
+        })
+    }
+}
+
+/// Whether `timezone` is an IANA time zone name such as `Europe/Berlin`,
+/// including aliases like `UTC`. Names are matched ignoring ASCII case.
+pub fn is_known_timezone(timezone: &str) -> bool {
+    TimeZoneIdMapper::new()
+        .as_borrowed()
+        .iana_to_bcp47(timezone)
+        .is_some()
+}
+
+/// Recorded on a user's stream when they lose access without the user record
+/// itself being edited
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct UserDeactivated {
+    pub user_id: Uuid,
+    pub tenant_id: Uuid,
+    pub reason: DeactivationReason,
+}
+
+/// The variant names are the `reason` stored in `UserDeactivated` events
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[allow(clippy::enum_variant_names)]
+pub enum DeactivationReason {
+    TenantDeactivated,
+    TenantDeleted,
+    /// No longer recorded, since erasure tombstones the users' streams, but
+    /// kept so events stored before that still read
+    TenantErased,
+}
+
+impl event_store::TypeName for UserDeactivated {
+    fn type_name(&self) -> String {
+        "UserDeactivated".to_string()
+    }
+}
+
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
 pub struct UserContext {
@@ -77,6 +206,11 @@ pub struct UserContext {
 }
 
 impl User {
+    // Bring user-supplied fields into their canonical form; call before validate
+    pub fn normalize(&mut self) {
+        self.full_name = normalize_name(&self.full_name);
+    }
+
     // Validate all user fields
     #[allow(dead_code)]
     pub fn validate(&self) -> AppResult<()> {
@@ -115,36 +249,17 @@ impl User {
                 "Full name cannot exceed 100 characters",
             ));
         }
+        if self.full_name.chars().any(char::is_control) {
+            return Err(AppError::validation(
+                "Full name cannot contain control characters",
+            ));
+        }
         Ok(())
     }
 
     // Validate user settings
     fn validate_settings(&self) -> AppResult<()> {
-        // Validate UI preferences
-        if self.settings.ui_preferences.items_per_page < 1
-            || self.settings.ui_preferences.items_per_page > 100
-        {
-            return Err(AppError::validation(
-                "Items per page must be between 1 and 100",
-            ));
-        }
-
-        // Validate language code format (basic check)
-        if !self
-            .settings
-            .language
-            .chars()
-            .all(|c| c.is_ascii_alphabetic() || c == '-')
-        {
-            return Err(AppError::validation("Invalid language code format"));
-        }
-
-        // Validate timezone format (basic check)
-        if self.settings.timezone.trim().is_empty() {
-            return Err(AppError::validation("Timezone cannot be empty"));
-        }
-
-        Ok(())
+        self.settings.validate()
     }
 
     #[allow(dead_code)]
@@ -192,11 +307,121 @@ pub struct UpdateUserDto {
     pub settings: Option<UserSettings>,
 }
 
+/// Partial `UserSettings` for PATCH requests; absent fields keep their
+/// stored value
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UserSettingsPatch {
+    pub language: Option<String>,
+    pub timezone: Option<String>,
+    pub notification_preferences: Option<NotificationPreferencesPatch>,
+    pub ui_preferences: Option<UiPreferencesPatch>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NotificationPreferencesPatch {
+    pub email_notifications: Option<bool>,
+    pub in_app_notifications: Option<bool>,
+    pub notification_types: Option<Vec<NotificationType>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UiPreferencesPatch {
+    pub theme: Option<String>,
+    pub sidebar_collapsed: Option<bool>,
+    pub items_per_page: Option<i32>,
+}
+
+impl UserSettings {
+    // Validate settings of a user, or the configured defaults at startup
+    pub fn validate(&self) -> AppResult<()> {
+        // Validate UI preferences
+        if self.ui_preferences.items_per_page < 1 || self.ui_preferences.items_per_page > 100 {
+            return Err(AppError::validation(
+                "Items per page must be between 1 and 100",
+            ));
+        }
+
+        // Validate language code format, then that its language is supported
+        if !self
+            .language
+            .chars()
+            .all(|c| c.is_ascii_alphabetic() || c == '-')
+        {
+            return Err(AppError::validation("Invalid language code format"));
+        }
+        if self.language.parse::<SupportedLanguage>().is_err() {
+            return Err(AppError::validation(format!(
+                "Unsupported language: {}",
+                self.language
+            )));
+        }
+
+        // Validate timezone against the IANA time zone database
+        if self.timezone.trim().is_empty() {
+            return Err(AppError::validation("Timezone cannot be empty"));
+        }
+        if !is_known_timezone(&self.timezone) {
+            return Err(AppError::validation(format!(
+                "Unknown timezone: {}",
+                self.timezone
+            )));
+        }
+
+        Ok(())
+    }
+
+    // Apply the fields present in `patch`, field by field for the nested
+    // preferences
+    pub fn merge(&mut self, patch: UserSettingsPatch) {
+        if let Some(language) = patch.language {
+            self.language = language;
+        }
+        if let Some(timezone) = patch.timezone {
+            self.timezone = timezone;
+        }
+        if let Some(notifications) = patch.notification_preferences {
+            let prefs = &mut self.notification_preferences;
+            if let Some(email) = notifications.email_notifications {
+                prefs.email_notifications = email;
+            }
+            if let Some(in_app) = notifications.in_app_notifications {
+                prefs.in_app_notifications = in_app;
+            }
+            if let Some(types) = notifications.notification_types {
+                prefs.notification_types = types;
+            }
+        }
+        if let Some(ui) = patch.ui_preferences {
+            let prefs = &mut self.ui_preferences;
+            if let Some(theme) = ui.theme {
+                prefs.theme = theme;
+            }
+            if let Some(collapsed) = ui.sidebar_collapsed {
+                prefs.sidebar_collapsed = collapsed;
+            }
+            if let Some(items_per_page) = ui.items_per_page {
+                prefs.items_per_page = items_per_page;
+            }
+        }
+    }
+}
+
 #[async_trait::async_trait]
 #[allow(dead_code)]
 pub trait UserService: Send + Sync + 'static {
     async fn find_by_id(&self, tenant_id: &Uuid, user_id: &Uuid) -> Result<User, AppError>;
+    /// Finds the user provisioned for a token subject. Users are stored
+    /// under their Keycloak subject id.
+    async fn find_by_subject(&self, subject: &str) -> Result<User, AppError>;
     async fn find_by_email(&self, tenant_id: &Uuid, email: &str) -> Result<User, AppError>;
+    async fn list_by_tenant(&self, tenant_id: &Uuid) -> Result<Vec<User>, AppError>;
+    /// One page of the users of a tenant in the given order
+    async fn list(
+        &self,
+        tenant_id: &Uuid,
+        pagination: Pagination,
+        sort: UserSort,
+    ) -> Result<Vec<User>, AppError>;
     async fn create(&self, tenant_id: &Uuid, user: CreateUserDto) -> Result<User, AppError>;
     async fn update(
         &self,
@@ -204,6 +429,12 @@ pub trait UserService: Send + Sync + 'static {
         user_id: &Uuid,
         user: UpdateUserDto,
     ) -> Result<User, AppError>;
+    async fn update_settings(
+        &self,
+        tenant_id: &Uuid,
+        user_id: &Uuid,
+        patch: UserSettingsPatch,
+    ) -> Result<User, AppError>;
     async fn deactivate(&self, tenant_id: &Uuid, user_id: &Uuid) -> Result<(), AppError>;
 }
 
@@ -248,6 +479,12 @@ mod tests {
         assert!(context.validate_active().is_err());
     }
 
+    #[test]
+    fn test_user_with_default_settings_validates() {
+        let user = create_test_user(true);
+        assert!(user.validate().is_ok());
+    }
+
     #[test]
     fn test_email_validation() {
         let mut user = create_test_user(true);
@@ -283,10 +520,134 @@ mod tests {
         // Test too long name
         user.full_name = "a".repeat(101);
         assert!(user.validate_full_name().is_err());
+
+        // Test control characters
+        user.full_name = "Test\nUser".to_string();
+        assert!(user.validate_full_name().is_err());
+    }
+
+    #[test]
+    fn test_full_name_is_normalized() {
+        let mut user = create_test_user(true);
+        // "Zoë" with a combining diaeresis
+        user.full_name = "Zoe\u{0308} Test\t".to_string();
+        user.normalize();
+        assert_eq!(user.full_name, "Zo\u{00eb} Test");
+        assert!(user.validate_full_name().is_ok());
+    }
+
+    #[test]
+    fn test_roles_serialize_like_the_database_enum() {
+        let json = serde_json::to_string(&UserRole::TenantAdmin).expect("serializable role");
+        assert_eq!(json, r#""tenant_admin""#);
+        let role: UserRole = serde_json::from_str(&json).expect("role round-trips");
+        assert_eq!(role, UserRole::TenantAdmin);
+
+        // Written before roles were snake_case
+        let role: UserRole = serde_json::from_str(r#""ReadOnly""#).expect("legacy role name");
+        assert_eq!(role, UserRole::ReadOnly);
+    }
+
+    #[test]
+    fn test_stored_settings_with_legacy_notification_types_still_load() {
+        let stored = r#"{"email_notifications":true,"in_app_notifications":false,"notification_types":["System","security"]}"#;
+        let preferences: NotificationPreferences =
+            serde_json::from_str(stored).expect("stored preferences");
+        assert!(matches!(
+            preferences.notification_types[..],
+            [NotificationType::System, NotificationType::Security]
+        ));
+        assert_eq!(
+            serde_json::to_value(&preferences.notification_types).expect("serializable types"),
+            serde_json::Value::from(vec!["system", "security"])
+        );
+    }
+
+    #[test]
+    fn test_configured_defaults_name_notification_types() {
+        let mut defaults = DefaultUserSettings {
+            notification_types: vec!["Security".to_string(), "updates".to_string()],
+            ..DefaultUserSettings::default()
+        };
+        let settings = UserSettings::try_from(&defaults).expect("known notification types");
+        assert!(matches!(
+            settings.notification_preferences.notification_types[..],
+            [NotificationType::Security, NotificationType::Updates]
+        ));
+
+        defaults.notification_types.push("newsletter".to_string());
+        assert!(UserSettings::try_from(&defaults).is_err());
+    }
+
+    #[test]
+    fn test_unknown_timezone_is_rejected() {
+        let mut user = create_test_user(true);
+        for timezone in ["UTC", "Europe/Berlin", "America/Argentina/Buenos_Aires"] {
+            user.settings.timezone = timezone.to_string();
+            assert!(user.validate_settings().is_ok(), "{}", timezone);
+        }
+
+        user.settings.timezone = "Not/AZone".to_string();
+        assert!(user.validate_settings().is_err());
+        user.settings.timezone = "CEST+2".to_string();
+        assert!(user.validate_settings().is_err());
+    }
+
+    #[test]
+    fn test_unsupported_language_is_rejected() {
+        let mut user = create_test_user(true);
+        user.settings.language = "de-AT".to_string();
+        assert!(user.validate_settings().is_ok());
+
+        user.settings.language = "xx-ZZ".to_string();
+        assert!(user.validate_settings().is_err());
+        user.settings.language = "klingon".to_string();
+        assert!(user.validate_settings().is_err());
+    }
+
+    #[test]
+    fn test_settings_merge_only_changes_provided_fields() {
+        let mut settings = UserSettings {
+            language: "en".to_string(),
+            timezone: "UTC".to_string(),
+            notification_preferences: NotificationPreferences {
+                email_notifications: true,
+                in_app_notifications: true,
+                notification_types: vec![NotificationType::Security],
+            },
+            ui_preferences: UiPreferences {
+                theme: "light".to_string(),
+                sidebar_collapsed: false,
+                items_per_page: 20,
+            },
+        };
+
+        let patch: UserSettingsPatch = serde_json::from_str(
+            r#"{
+                "notification_preferences": { "email_notifications": false },
+                "ui_preferences": { "theme": "dark" }
+            }"#,
+        )
+        .expect("valid patch");
+        settings.merge(patch);
+
+        assert_eq!(settings.language, "en");
+        assert_eq!(settings.timezone, "UTC");
+        assert!(!settings.notification_preferences.email_notifications);
+        assert!(settings.notification_preferences.in_app_notifications);
+        assert_eq!(
+            settings.notification_preferences.notification_types.len(),
+            1
+        );
+        assert_eq!(settings.ui_preferences.theme, "dark");
+        assert!(!settings.ui_preferences.sidebar_collapsed);
+        assert_eq!(settings.ui_preferences.items_per_page, 20);
     }
 
     fn create_test_tenant() -> crate::domain::tenant::Tenant {
-        use crate::domain::tenant::{Tenant, TenantFeatures, TenantSettings};
+        use crate::domain::tenant::{
+            Tenant, TenantFeatures, TenantSettings, TENANT_SETTINGS_VERSION,
+        };
 
         Tenant {
             id: Uuid::new_v4(),
@@ -303,7 +664,12 @@ mod tests {
                     api_access: true,
                     audit_logging: true,
                 },
+                read_only: false,
+                allowed_auth_methods: Default::default(),
+                rate_limits: Default::default(),
+                settings_version: TENANT_SETTINGS_VERSION,
             },
+            updated_at: None,
         }
     }
 }
@@ -1,32 +1,67 @@
 use crate::common::error::AppResult;
 use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::time::{Duration, Instant};
+use tokio::sync::{oneshot, RwLock};
+use tokio::task::JoinHandle;
+use tracing::debug;
+
+#[derive(Clone)]
+struct CacheEntry {
+    value: Vec<u8>,
+    expires_at: Option<Instant>,
+}
+
+impl CacheEntry {
+    fn is_expired(&self, now: Instant) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+}
 
 #[derive(Clone)]
-#[allow(dead_code)]
 pub struct CacheConnection {
-    store: Arc<RwLock<HashMap<String, Vec<u8>>>>,
+    store: Arc<RwLock<HashMap<String, CacheEntry>>>,
 }
 
 impl CacheConnection {
-    #[allow(dead_code)]
     pub async fn new() -> AppResult<Self> {
-        Ok(Self {
-            store: Arc::new(RwLock::new(HashMap::new())),
-        })
+        Ok(Self::default())
     }
 
     #[allow(dead_code)]
     pub async fn get(&self, key: &str) -> AppResult<Option<Vec<u8>>> {
         let store = self.store.read().await;
-        Ok(store.get(key).cloned())
+        Ok(store
+            .get(key)
+            .filter(|entry| !entry.is_expired(Instant::now()))
+            .map(|entry| entry.value.clone()))
     }
 
     #[allow(dead_code)]
     pub async fn set(&self, key: String, value: Vec<u8>) -> AppResult<()> {
         let mut store = self.store.write().await;
-        store.insert(key, value);
+        store.insert(
+            key,
+            CacheEntry {
+                value,
+                expires_at: None,
+            },
+        );
+        Ok(())
+    }
+
+    /// Stores a value that expires after `ttl` (e.g. idempotency or
+    /// rate-limit keys)
+    #[allow(dead_code)]
+    pub async fn set_ex(&self, key: String, value: Vec<u8>, ttl: Duration) -> AppResult<()> {
+        let mut store = self.store.write().await;
+        store.insert(
+            key,
+            CacheEntry {
+                value,
+                expires_at: Some(Instant::now() + ttl),
+            },
+        );
         Ok(())
     }
 
@@ -36,6 +71,136 @@ impl CacheConnection {
         store.remove(key);
         Ok(())
     }
+
+    /// Counts a hit in the fixed window under `key`, starting a new window
+    /// of length `window` at `now` when there is none or it has ended.
+    /// Fails with the time left in the window when `limit` hits were
+    /// already counted in it; those are not counted.
+    pub async fn count_in_window(
+        &self,
+        key: &str,
+        limit: i32,
+        window: Duration,
+        now: Instant,
+    ) -> Result<(), Duration> {
+        let mut store = self.store.write().await;
+        let entry = store
+            .entry(key.to_string())
+            .and_modify(|entry| {
+                if entry.is_expired(now) {
+                    *entry = new_window(window, now);
+                }
+            })
+            .or_insert_with(|| new_window(window, now));
+
+        let count = decode_count(&entry.value);
+        if count >= limit {
+            let ends = entry.expires_at.unwrap_or(now);
+            return Err(ends.saturating_duration_since(now));
+        }
+        entry.value = (count + 1).to_be_bytes().to_vec();
+        Ok(())
+    }
+
+    /// Hits counted by `count_in_window` in the window under `key` that is
+    /// still open at `now`
+    pub async fn window_count(&self, key: &str, now: Instant) -> i32 {
+        let store = self.store.read().await;
+        store
+            .get(key)
+            .filter(|entry| !entry.is_expired(now))
+            .map_or(0, |entry| decode_count(&entry.value))
+    }
+
+    /// Entries stored, including expired ones not swept yet
+    #[cfg(test)]
+    pub async fn len(&self) -> usize {
+        self.store.read().await.len()
+    }
+
+    /// Removes all expired entries and returns how many were evicted
+    pub async fn purge_expired(&self) -> usize {
+        let now = Instant::now();
+        let mut store = self.store.write().await;
+        let before = store.len();
+        store.retain(|_, entry| !entry.is_expired(now));
+        before - store.len()
+    }
+
+    /// Starts a background task that purges expired entries every `interval`
+    /// until the returned handle is shut down or dropped
+    pub fn spawn_sweeper(&self, interval: Duration) -> CacheSweeper {
+        let cache = self.clone();
+        let (shutdown_tx, mut shutdown_rx) = oneshot::channel();
+
+        let handle = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.tick().await; // the first tick completes immediately
+
+            loop {
+                tokio::select! {
+                    _ = &mut shutdown_rx => break,
+                    _ = ticker.tick() => {
+                        let evicted = cache.purge_expired().await;
+                        if evicted > 0 {
+                            debug!("Evicted {} expired cache entries", evicted);
+                        }
+                    },
+                }
+            }
+        });
+
+        CacheSweeper {
+            shutdown: Some(shutdown_tx),
+            handle: Some(handle),
+        }
+    }
+}
+
+impl Default for CacheConnection {
+    fn default() -> Self {
+        Self {
+            store: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+}
+
+fn new_window(window: Duration, now: Instant) -> CacheEntry {
+    CacheEntry {
+        value: 0i32.to_be_bytes().to_vec(),
+        expires_at: Some(now + window),
+    }
+}
+
+/// Window counters are stored as big-endian `i32`; anything else reads as 0
+fn decode_count(value: &[u8]) -> i32 {
+    value.try_into().map_or(0, i32::from_be_bytes)
+}
+
+/// Handle to the background sweeper started by `CacheConnection::spawn_sweeper`
+pub struct CacheSweeper {
+    shutdown: Option<oneshot::Sender<()>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl CacheSweeper {
+    /// Stops the sweeper and waits for the task to finish
+    pub async fn shutdown(mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.await;
+        }
+    }
+}
+
+impl Drop for CacheSweeper {
+    fn drop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            handle.abort();
+        }
+    }
 }
 
 #[cfg(test)]
@@ -74,4 +239,28 @@ mod tests {
         assert!(get_result.is_none());
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_sweeper_evicts_expired_entries() -> AppResult<()> {
+        let cache = CacheConnection::new().await?;
+        cache
+            .set_ex(
+                "idempotency:1".to_string(),
+                vec![1],
+                Duration::from_millis(10),
+            )
+            .await?;
+        cache.set("permanent".to_string(), vec![2]).await?;
+
+        let sweeper = cache.spawn_sweeper(Duration::from_millis(20));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let store = cache.store.read().await;
+        assert!(!store.contains_key("idempotency:1"));
+        assert!(store.contains_key("permanent"));
+        drop(store);
+
+        sweeper.shutdown().await;
+        Ok(())
+    }
 }
@@ -16,6 +16,8 @@ pub struct RedisConfig {
 #[derive(Debug, Deserialize)]
 pub struct EventStoreConfig {
     pub url: String,
+    /// Cluster node URLs in failover order; `url` is used when empty
+    pub nodes: Vec<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -33,6 +35,16 @@ impl Config {
             event_store: EventStoreConfig {
                 url: env::var("EVENTSTORE_URL")
                     .unwrap_or_else(|_| "http://localhost:2113".to_string()),
+                nodes: env::var("EVENTSTORE_NODES")
+                    .map(|nodes| {
+                        nodes
+                            .split(',')
+                            .map(str::trim)
+                            .filter(|node| !node.is_empty())
+                            .map(str::to_string)
+                            .collect()
+                    })
+                    .unwrap_or_default(),
             },
             rabbitmq: RabbitMQConfig {
                 url: env::var("RABBITMQ_URL")
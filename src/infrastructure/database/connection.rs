@@ -1,6 +1,7 @@
 use async_trait::async_trait;
+use futures::future::try_join_all;
 use migration::MigratorTrait;
-use sea_orm::{Database, DatabaseConnection};
+use sea_orm::{ConnectionTrait, Database, DatabaseConnection, DbErr};
 use tracing::{debug, info, warn};
 
 use crate::common::config;
@@ -57,6 +58,17 @@ pub async fn establish_connection() -> anyhow::Result<DatabaseConnection> {
     }
     debug!("Database ping test successful");
 
+    if db_config.warm_up {
+        info!(
+            "Warming up {} database connections",
+            db_config.min_connections
+        );
+        if let Err(e) = warm_up_pool(&connection, db_config.min_connections).await {
+            warn!("Database pool warm-up failed: {}", e);
+            return Err(e.into());
+        }
+    }
+
     // Run migrations
     info!("Running database migrations");
     if let Err(e) = migration::Migrator::up(&connection, None).await {
@@ -67,3 +79,34 @@ pub async fn establish_connection() -> anyhow::Result<DatabaseConnection> {
 
     Ok(connection)
 }
+
+/// Opens `connections` pool connections up front by running a trivial query
+/// on each of them concurrently, so the first requests after startup don't
+/// pay the connection setup latency
+pub async fn warm_up_pool(connection: &DatabaseConnection, connections: u32) -> Result<(), DbErr> {
+    // The queries run concurrently, so each one checks out its own connection
+    try_join_all((0..connections).map(|_| connection.execute_unprepared("SELECT 1"))).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult};
+
+    #[tokio::test]
+    async fn test_warm_up_pings_each_connection() -> Result<(), DbErr> {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_exec_results((0..5).map(|_| MockExecResult::default()))
+            .into_connection();
+
+        warm_up_pool(&db, 5).await?;
+
+        let log = db.into_transaction_log();
+        assert_eq!(log.len(), 5);
+        assert!(log
+            .iter()
+            .all(|transaction| format!("{:?}", transaction).contains("SELECT 1")));
+        Ok(())
+    }
+}
@@ -1 +1,6 @@
+pub mod api_key;
+pub mod audit_log;
+pub mod subscription_checkpoint;
 pub mod tenant;
+pub mod user;
+pub mod webhook;
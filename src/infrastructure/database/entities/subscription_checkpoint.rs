@@ -0,0 +1,18 @@
+#![allow(clippy::disallowed_methods)]
+use sea_orm::entity::prelude::*;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "subscription_checkpoints")]
+pub struct Model {
+    /// Name the subscription is registered under
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub name: String,
+    /// `$all` position of the first event the subscription hasn't handled
+    pub position: i64,
+    pub updated_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
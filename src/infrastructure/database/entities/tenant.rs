@@ -12,9 +12,20 @@ pub struct Model {
     pub settings: Json,
     pub created_at: DateTime,
     pub updated_at: DateTime,
+    /// Set when the tenant has been erased; the row is kept for auditing
+    pub deleted_at: Option<DateTime>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
-pub enum Relation {}
+pub enum Relation {
+    #[sea_orm(has_many = "super::user::Entity")]
+    Users,
+}
+
+impl Related<super::user::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Users.def()
+    }
+}
 
 impl ActiveModelBehavior for ActiveModel {}
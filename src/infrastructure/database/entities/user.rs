@@ -0,0 +1,51 @@
+#![allow(clippy::disallowed_methods)]
+use sea_orm::entity::prelude::*;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "users")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub email: String,
+    pub username: String,
+    pub full_name: String,
+    pub is_active: bool,
+    pub role: Role,
+    pub settings: Json,
+    pub created_at: DateTimeWithTimeZone,
+    pub updated_at: DateTimeWithTimeZone,
+    pub last_login_at: Option<DateTimeWithTimeZone>,
+}
+
+/// Postgres `user_role` enum
+#[derive(Clone, Debug, PartialEq, Eq, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "user_role")]
+pub enum Role {
+    #[sea_orm(string_value = "tenant_admin")]
+    TenantAdmin,
+    #[sea_orm(string_value = "manager")]
+    Manager,
+    #[sea_orm(string_value = "user")]
+    User,
+    #[sea_orm(string_value = "read_only")]
+    ReadOnly,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::tenant::Entity",
+        from = "Column::TenantId",
+        to = "super::tenant::Column::Id"
+    )]
+    Tenant,
+}
+
+impl Related<super::tenant::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Tenant.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -1,4 +1,3 @@
 pub mod connection;
 pub mod entities;
-
-pub use connection::*;
+pub mod slow_query;
@@ -0,0 +1,77 @@
+use std::future::Future;
+use std::time::Duration;
+
+use metrics::counter;
+use tokio::time::Instant;
+use tracing::warn;
+
+/// Counter of database calls that took at least the slow-query threshold,
+/// labelled with the service operation
+pub const SLOW_QUERY_COUNTER: &str = "db_slow_queries_total";
+
+/// Times the database calls of a service and reports the slow ones
+#[derive(Debug, Clone, Copy)]
+pub struct SlowQueryMonitor {
+    threshold: Duration,
+}
+
+impl SlowQueryMonitor {
+    pub fn new(threshold: Duration) -> Self {
+        Self { threshold }
+    }
+
+    /// Awaits `query`, logging it and counting it in `SLOW_QUERY_COUNTER`
+    /// when it took the threshold or longer. `operation` names the call,
+    /// e.g. `tenant.find_by_id`.
+    pub async fn observe<F: Future>(&self, operation: &'static str, query: F) -> F::Output {
+        let started = Instant::now();
+        let output = query.await;
+        let elapsed = started.elapsed();
+        if elapsed >= self.threshold {
+            counter!(SLOW_QUERY_COUNTER, "operation" => operation).increment(1);
+            warn!(
+                "Slow query {} took {}ms (threshold {}ms)",
+                operation,
+                elapsed.as_millis(),
+                self.threshold.as_millis()
+            );
+        }
+        output
+    }
+}
+
+impl Default for SlowQueryMonitor {
+    fn default() -> Self {
+        Self::new(Duration::from_millis(
+            crate::common::config::default_slow_query_threshold_ms(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use metrics_exporter_prometheus::PrometheusBuilder;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_only_queries_over_the_threshold_are_counted() {
+        let recorder = PrometheusBuilder::new().build_recorder();
+        let metrics = recorder.handle();
+        let _guard = metrics::set_default_local_recorder(&recorder);
+        let monitor = SlowQueryMonitor::new(Duration::from_millis(500));
+
+        let delayed = |millis| async move {
+            tokio::time::sleep(Duration::from_millis(millis)).await;
+            millis
+        };
+        assert_eq!(
+            monitor.observe("tenant.find_by_id", delayed(600)).await,
+            600
+        );
+        assert_eq!(monitor.observe("tenant.list", delayed(10)).await, 10);
+
+        let rendered = metrics.render();
+        assert!(rendered.contains(r#"db_slow_queries_total{operation="tenant.find_by_id"} 1"#));
+        assert!(!rendered.contains(r#"operation="tenant.list""#));
+    }
+}
@@ -0,0 +1,333 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use event_store::{Event, EventData, TypeName};
+use metrics::{counter, gauge};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+use tracing::{error, warn, Instrument, Span};
+use uuid::Uuid;
+
+use crate::infrastructure::event_store::EventStoreClient;
+
+const QUEUE_DEPTH_GAUGE: &str = "event_publish_queue_depth";
+const DROPPED_COUNTER: &str = "event_publish_dropped_total";
+
+/// Destination of the queued events, the EventStore outside of tests
+#[async_trait]
+pub trait EventSink: Send + Sync + 'static {
+    async fn publish(&self, stream_name: &str, events: Vec<EventData>) -> anyhow::Result<()>;
+}
+
+#[async_trait]
+impl EventSink for EventStoreClient {
+    async fn publish(&self, stream_name: &str, events: Vec<EventData>) -> anyhow::Result<()> {
+        self.append_event_data(stream_name, events).await
+    }
+}
+
+struct QueuedAppend {
+    stream_name: String,
+    events: Vec<EventData>,
+    /// Span of the request that published the events, so the worker's logs
+    /// stay correlated with it
+    span: Span,
+}
+
+/// Hands events to a background worker through a bounded queue, so a slow
+/// EventStore doesn't hold up the request that produced them.
+///
+/// When the queue is full new events are dropped and counted in
+/// `event_publish_dropped_total` instead of making the caller wait.
+/// Each write runs in the span that was current when it was published.
+#[derive(Clone)]
+pub struct EventPublisher {
+    sender: mpsc::Sender<QueuedAppend>,
+}
+
+impl EventPublisher {
+    pub fn spawn(sink: Arc<dyn EventSink>, capacity: usize) -> (Self, PublisherWorker) {
+        let (sender, receiver) = mpsc::channel(capacity.max(1));
+        let (shutdown_tx, shutdown_rx) = oneshot::channel();
+        let handle = tokio::spawn(run(sink, receiver, shutdown_rx));
+
+        (
+            Self { sender },
+            PublisherWorker {
+                shutdown: Some(shutdown_tx),
+                handle: Some(handle),
+            },
+        )
+    }
+
+    /// Like `publish`, with every event stamped with `tenant_id` so the
+    /// `$all` consumers (live event sockets, webhooks) can route it
+    pub fn publish_for_tenant<T>(
+        &self,
+        tenant_id: Uuid,
+        stream_name: String,
+        mut events: Vec<Event<T>>,
+    ) where
+        T: Serialize + for<'de> Deserialize<'de> + Clone + TypeName,
+    {
+        for event in &mut events {
+            event.tenant_id = Some(tenant_id);
+        }
+        self.publish(stream_name, events);
+    }
+
+    /// Queues the events for `stream_name` without waiting for them to be
+    /// written
+    pub fn publish<T>(&self, stream_name: String, events: Vec<Event<T>>)
+    where
+        T: Serialize + for<'de> Deserialize<'de> + Clone + TypeName,
+    {
+        let events = match events
+            .iter()
+            .map(Event::to_event_data)
+            .collect::<anyhow::Result<Vec<_>>>()
+        {
+            Ok(events) => events,
+            Err(e) => {
+                error!("Failed to serialize events for {}: {}", stream_name, e);
+                return;
+            },
+        };
+
+        let (append, reason) = match self.sender.try_send(QueuedAppend {
+            stream_name,
+            events,
+            span: Span::current(),
+        }) {
+            Ok(()) => {
+                let depth = self.sender.max_capacity() - self.sender.capacity();
+                gauge!(QUEUE_DEPTH_GAUGE).set(depth as f64);
+                return;
+            },
+            Err(mpsc::error::TrySendError::Full(append)) => (append, "full"),
+            Err(mpsc::error::TrySendError::Closed(append)) => (append, "closed"),
+        };
+
+        warn!(
+            "Event publish queue is {}, dropping {} events for {}",
+            reason,
+            append.events.len(),
+            append.stream_name
+        );
+        counter!(DROPPED_COUNTER, "reason" => reason).increment(append.events.len() as u64);
+    }
+}
+
+async fn run(
+    sink: Arc<dyn EventSink>,
+    mut receiver: mpsc::Receiver<QueuedAppend>,
+    mut shutdown: oneshot::Receiver<()>,
+) {
+    loop {
+        tokio::select! {
+            _ = &mut shutdown => break,
+            append = receiver.recv() => match append {
+                Some(append) => write(&*sink, append, receiver.len()).await,
+                None => return,
+            },
+        }
+    }
+
+    // Refuse new events, but write everything that was already queued
+    receiver.close();
+    while let Some(append) = receiver.recv().await {
+        write(&*sink, append, receiver.len()).await;
+    }
+}
+
+async fn write(sink: &dyn EventSink, append: QueuedAppend, depth: usize) {
+    gauge!(QUEUE_DEPTH_GAUGE).set(depth as f64);
+    let QueuedAppend {
+        stream_name,
+        events,
+        span,
+    } = append;
+    async {
+        if let Err(e) = sink.publish(&stream_name, events).await {
+            counter!("event_publish_failures_total").increment(1);
+            error!("Failed to publish events to {}: {}", stream_name, e);
+        }
+    }
+    .instrument(span)
+    .await
+}
+
+/// Handle to the background worker started by `EventPublisher::spawn`
+pub struct PublisherWorker {
+    shutdown: Option<oneshot::Sender<()>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl PublisherWorker {
+    /// Stops accepting events, writes the ones still queued and waits for
+    /// the worker to finish
+    pub async fn shutdown(mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.await;
+        }
+    }
+}
+
+impl Drop for PublisherWorker {
+    fn drop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            handle.abort();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::sync::Mutex;
+    use std::time::Duration;
+    use tracing_subscriber::fmt::MakeWriter;
+
+    use crate::common::logging::request_span;
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    struct Pinged {}
+
+    impl TypeName for Pinged {
+        fn type_name(&self) -> String {
+            "Pinged".to_string()
+        }
+    }
+
+    /// Records the streams written to, each write taking `delay`
+    #[derive(Default)]
+    struct SlowSink {
+        delay: Duration,
+        written: Mutex<Vec<String>>,
+    }
+
+    impl SlowSink {
+        fn written(&self) -> Vec<String> {
+            self.written.lock().expect("sink lock").clone()
+        }
+    }
+
+    #[async_trait]
+    impl EventSink for SlowSink {
+        async fn publish(&self, stream_name: &str, _events: Vec<EventData>) -> anyhow::Result<()> {
+            tokio::time::sleep(self.delay).await;
+            self.written
+                .lock()
+                .expect("sink lock")
+                .push(stream_name.to_string());
+            Ok(())
+        }
+    }
+
+    fn ping() -> Vec<Event<Pinged>> {
+        vec![Event::new(Pinged {}, 1, None, None, None)]
+    }
+
+    #[tokio::test]
+    async fn test_events_are_published_after_publish_returns() {
+        let sink = Arc::new(SlowSink {
+            delay: Duration::from_millis(50),
+            ..Default::default()
+        });
+        let (publisher, _worker) = EventPublisher::spawn(sink.clone(), 8);
+
+        publisher.publish("tenant-1".to_string(), ping());
+        // Returned before the slow write happened
+        assert!(sink.written().is_empty());
+
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while sink.written().is_empty() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("event eventually published");
+        assert_eq!(sink.written(), vec!["tenant-1"]);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_drains_queue() {
+        let sink = Arc::new(SlowSink {
+            delay: Duration::from_millis(10),
+            ..Default::default()
+        });
+        let (publisher, worker) = EventPublisher::spawn(sink.clone(), 8);
+
+        for stream in ["tenant-1", "tenant-2", "tenant-3"] {
+            publisher.publish(stream.to_string(), ping());
+        }
+        worker.shutdown().await;
+
+        assert_eq!(sink.written(), vec!["tenant-1", "tenant-2", "tenant-3"]);
+        // Nothing is accepted once the worker has stopped
+        publisher.publish("tenant-4".to_string(), ping());
+        assert_eq!(sink.written().len(), 3);
+    }
+
+    struct FailingSink;
+
+    #[async_trait]
+    impl EventSink for FailingSink {
+        async fn publish(&self, _stream_name: &str, _events: Vec<EventData>) -> anyhow::Result<()> {
+            anyhow::bail!("EventStore is down")
+        }
+    }
+
+    #[derive(Clone, Default)]
+    struct CapturedLogs(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for CapturedLogs {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().expect("log buffer").extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl<'a> MakeWriter<'a> for CapturedLogs {
+        type Writer = CapturedLogs;
+
+        fn make_writer(&'a self) -> Self::Writer {
+            self.clone()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_worker_logs_carry_the_publishing_request() {
+        let logs = CapturedLogs::default();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(logs.clone())
+            .with_ansi(false)
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let (publisher, worker) = EventPublisher::spawn(Arc::new(FailingSink), 8);
+        let request_id = Uuid::new_v4();
+        request_span(Some("tenant-1".to_string()), None, request_id)
+            .in_scope(|| publisher.publish("tenant-1".to_string(), ping()));
+        // The write happens on the worker task, after the request span was left
+        worker.shutdown().await;
+
+        let output =
+            String::from_utf8(logs.0.lock().expect("log buffer").clone()).expect("utf8 log output");
+        let line = output
+            .lines()
+            .find(|line| line.contains("Failed to publish events to tenant-1"))
+            .expect("failure logged by the worker");
+        assert!(line.contains(&format!("request_id=\"{}\"", request_id)));
+        assert!(line.contains("tenant_id=\"tenant-1\""));
+    }
+}
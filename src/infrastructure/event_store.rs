@@ -1,7 +1,18 @@
+use std::sync::Arc;
+
 use anyhow::Result;
-use event_store::{EventStoreClient as EsClient, TypeName};
+use event_store::{
+    EventData, EventStoreClient as EsClient, RecordedEvent, StreamPosition, SubscriptionError,
+    TypeName,
+};
+use futures::stream::{self, Stream};
 use serde::{Deserialize, Serialize};
+use tracing::warn;
 
+use crate::common::config::{
+    EventStoreAppendSettings, EventStoreCircuitBreakerSettings, EventStoreCompressionSettings,
+    EventStoreSettings,
+};
 use crate::infrastructure::config::EventStoreConfig;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -13,14 +24,38 @@ impl TypeName for TestEvent {
     }
 }
 
+/// Events fetched per request when paging through a stream
+const READ_PAGE_SIZE: u64 = 100;
+
 pub struct EventStoreClient {
     client: EsClient,
 }
 
 impl EventStoreClient {
-    pub fn new(config: EventStoreConfig) -> Result<Self> {
+    pub fn new(
+        config: EventStoreConfig,
+        settings: &EventStoreSettings,
+        http: event_store::HttpPoolConfig,
+    ) -> Result<Self> {
+        if settings.max_append_size.configured().is_none() {
+            warn!(
+                "Ignoring eventstore.max_append_size = {:?}, which must be an event count",
+                settings.max_append_size
+            );
+        }
         let client = EsClient::new(event_store::EventStoreConfig {
             connection_string: config.url,
+            nodes: config.nodes,
+            max_retries: settings.max_retries,
+            retry_delay: settings.retry_delay,
+            max_append_size: settings.max_append_size.events(),
+            subscription_read_count: settings.subscription_read_count,
+            max_read_count: settings.max_read_count,
+            subscription_poll_interval_ms: settings.subscription_poll_interval_ms,
+            append_defaults: (&settings.append_defaults).into(),
+            compression: (&settings.compression).into(),
+            circuit_breaker: (&settings.circuit_breaker).into(),
+            http,
             ..Default::default()
         })?;
         Ok(Self { client })
@@ -31,4 +66,212 @@ impl EventStoreClient {
         let _: Vec<event_store::Event<TestEvent>> = self.client.read_stream("$all", 0, 1).await?;
         Ok(())
     }
+
+    /// Reads a whole stream page by page, ending after the first short page
+    pub fn read_pages(
+        self: Arc<Self>,
+        stream_name: String,
+    ) -> impl Stream<Item = Result<Vec<RecordedEvent>>> + Send + 'static {
+        stream::try_unfold(Some(0u64), move |position| {
+            let client = Arc::clone(&self);
+            let stream_name = stream_name.clone();
+            async move {
+                let Some(position) = position else {
+                    return Ok(None);
+                };
+                let page = client
+                    .client
+                    .read_recorded(&stream_name, position, READ_PAGE_SIZE)
+                    .await?;
+                let next = (page.len() as u64 == READ_PAGE_SIZE).then(|| position + READ_PAGE_SIZE);
+                Ok(Some((page, next)))
+            }
+        })
+    }
+
+    /// Reads up to `count` events of a stream starting at `from`
+    pub async fn read_window(
+        &self,
+        stream_name: &str,
+        from: u64,
+        count: u64,
+    ) -> Result<Vec<RecordedEvent>> {
+        self.client.read_recorded(stream_name, from, count).await
+    }
+
+    /// Number of events in a stream, which is also the position the next
+    /// appended event gets. Found by probing single-event reads, doubling
+    /// the position until a read comes back empty and then bisecting.
+    pub async fn head_position(&self, stream_name: &str) -> Result<u64> {
+        let mut present = 0;
+        let mut absent = 1;
+        if self.read_window(stream_name, 0, 1).await?.is_empty() {
+            return Ok(0);
+        }
+        while !self.read_window(stream_name, absent, 1).await?.is_empty() {
+            present = absent;
+            absent *= 2;
+        }
+        while absent - present > 1 {
+            let middle = present + (absent - present) / 2;
+            if self.read_window(stream_name, middle, 1).await?.is_empty() {
+                absent = middle;
+            } else {
+                present = middle;
+            }
+        }
+        Ok(absent)
+    }
+
+    /// Follows a stream from `from`, yielding events as they are appended
+    pub fn subscribe(
+        &self,
+        stream_name: &str,
+        from: u64,
+    ) -> impl Stream<Item = Result<RecordedEvent, SubscriptionError>> + '_ {
+        self.client.follow_stream(stream_name, StreamPosition(from))
+    }
+
+    pub async fn append_event_data(&self, stream_name: &str, events: Vec<EventData>) -> Result<()> {
+        self.client.append_event_data(stream_name, events).await?;
+        Ok(())
+    }
+
+    pub async fn tombstone(&self, stream_name: &str) -> Result<()> {
+        self.client.tombstone_stream(stream_name).await
+    }
+}
+
+impl From<&EventStoreAppendSettings> for event_store::AppendOptions {
+    fn from(settings: &EventStoreAppendSettings) -> Self {
+        Self {
+            require_master: settings.require_master,
+            await_commit: settings.await_commit,
+        }
+    }
+}
+
+impl From<&EventStoreCompressionSettings> for event_store::CompressionConfig {
+    fn from(settings: &EventStoreCompressionSettings) -> Self {
+        Self {
+            enabled: settings.enabled,
+            min_size_bytes: settings.min_size_bytes,
+        }
+    }
+}
+
+impl From<&EventStoreCircuitBreakerSettings> for event_store::CircuitBreakerConfig {
+    fn from(settings: &EventStoreCircuitBreakerSettings) -> Self {
+        Self {
+            failure_threshold: settings.failure_threshold,
+            cool_down_ms: settings.cool_down_ms,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::config::MaxAppendSize;
+    use metrics_exporter_prometheus::PrometheusBuilder;
+    use wiremock::{
+        matchers::{header, method},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    #[tokio::test]
+    async fn test_client_metrics_reach_the_app_recorder() -> Result<()> {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(Vec::<RecordedEvent>::new()))
+            .mount(&server)
+            .await;
+        let recorder = PrometheusBuilder::new().build_recorder();
+        let metrics = recorder.handle();
+        let _guard = metrics::set_default_local_recorder(&recorder);
+
+        let client = EventStoreClient::new(
+            EventStoreConfig {
+                url: server.uri(),
+                nodes: vec![],
+            },
+            &EventStoreSettings::default(),
+            event_store::HttpPoolConfig::default(),
+        )?;
+        client.read_window("tenant-1", 0, 10).await?;
+
+        assert!(metrics.render().contains("eventstore_read_success_total 1"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_configured_append_options_reach_the_client() -> Result<()> {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(header("ES-RequireMaster", "False"))
+            .respond_with(ResponseTemplate::new(201))
+            .expect(2)
+            .mount(&server)
+            .await;
+        let settings = EventStoreSettings {
+            max_append_size: MaxAppendSize::Events(1),
+            append_defaults: EventStoreAppendSettings {
+                require_master: false,
+                await_commit: true,
+            },
+            ..Default::default()
+        };
+
+        let client = EventStoreClient::new(
+            EventStoreConfig {
+                url: server.uri(),
+                nodes: vec![],
+            },
+            &settings,
+            event_store::HttpPoolConfig::default(),
+        )?;
+        let events = (0..2)
+            .map(|_| {
+                EventData::new(
+                    "TestEvent".to_string(),
+                    TestEvent {},
+                    serde_json::Value::Null,
+                )
+            })
+            .collect::<Result<_, _>>()?;
+        client.append_event_data("tenant-1", events).await?;
+
+        // One request per event, each without requiring the master
+        server.verify().await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_configured_retries_reach_the_client() -> Result<()> {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(503))
+            .expect(2)
+            .mount(&server)
+            .await;
+        let settings = EventStoreSettings {
+            max_retries: 1,
+            retry_delay: 1,
+            ..Default::default()
+        };
+
+        let client = EventStoreClient::new(
+            EventStoreConfig {
+                url: server.uri(),
+                nodes: vec![],
+            },
+            &settings,
+            event_store::HttpPoolConfig::default(),
+        )?;
+
+        // The first try and one retry, then the failure is returned
+        assert!(client.read_window("tenant-1", 0, 10).await.is_err());
+        server.verify().await;
+        Ok(())
+    }
 }
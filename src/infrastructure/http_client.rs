@@ -0,0 +1,121 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+
+use crate::common::{
+    config::HttpClientSettings,
+    error::{AppError, AppResult},
+};
+use crate::domain::webhook::is_public_address;
+
+#[cfg(test)]
+thread_local! {
+    /// Clients built on the current thread, so tests can check that callers
+    /// reuse their client instead of building one per call
+    pub(crate) static BUILT_CLIENTS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+/// Builds the client for outbound HTTP calls with the configured pool.
+///
+/// Build it once per caller and keep it: clones share the connection pool,
+/// so repeated calls to the same host reuse connections and skip the TLS
+/// handshake.
+pub fn outbound_client(settings: &HttpClientSettings) -> AppResult<reqwest::Client> {
+    build(pooled(settings))
+}
+
+/// Builds the client delivering webhooks to tenant-chosen URLs. It doesn't
+/// follow redirects, and unless `allow_private_targets` is set it refuses to
+/// connect to hosts resolving to non-public addresses, so a receiver can't
+/// steer deliveries into our own network by redirecting or by changing its
+/// DNS records after it was registered.
+pub fn webhook_client(
+    settings: &HttpClientSettings,
+    allow_private_targets: bool,
+) -> AppResult<reqwest::Client> {
+    let builder = pooled(settings).redirect(reqwest::redirect::Policy::none());
+    if allow_private_targets {
+        build(builder)
+    } else {
+        build(builder.dns_resolver(Arc::new(PublicOnlyResolver)))
+    }
+}
+
+fn pooled(settings: &HttpClientSettings) -> reqwest::ClientBuilder {
+    reqwest::Client::builder()
+        .timeout(Duration::from_secs(settings.timeout_secs))
+        .pool_idle_timeout(Duration::from_secs(settings.pool_idle_timeout_secs))
+        .pool_max_idle_per_host(settings.pool_max_idle_per_host)
+        .tcp_keepalive(Duration::from_secs(settings.tcp_keepalive_secs))
+}
+
+fn build(builder: reqwest::ClientBuilder) -> AppResult<reqwest::Client> {
+    #[cfg(test)]
+    BUILT_CLIENTS.with(|built| built.set(built.get() + 1));
+
+    builder
+        .build()
+        .map_err(|e| AppError::configuration(format!("Failed to build HTTP client: {}", e)))
+}
+
+/// Resolves through the system resolver, failing when any address of the
+/// host isn't public
+struct PublicOnlyResolver;
+
+impl Resolve for PublicOnlyResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let addresses: Vec<SocketAddr> =
+                tokio::net::lookup_host((name.as_str(), 0)).await?.collect();
+            if let Some(address) = addresses
+                .iter()
+                .find(|address| !is_public_address(address.ip()))
+            {
+                return Err(format!(
+                    "{} resolves to the non-public address {}",
+                    name.as_str(),
+                    address.ip()
+                )
+                .into());
+            }
+            let addresses: Addrs = Box::new(addresses.into_iter());
+            Ok(addresses)
+        })
+    }
+}
+
+impl From<&HttpClientSettings> for event_store::HttpPoolConfig {
+    fn from(settings: &HttpClientSettings) -> Self {
+        Self {
+            pool_idle_timeout_secs: settings.pool_idle_timeout_secs,
+            pool_max_idle_per_host: settings.pool_max_idle_per_host,
+            tcp_keepalive_secs: settings.tcp_keepalive_secs,
+            timeout_secs: settings.timeout_secs,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::{matchers::any, Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_webhook_client_refuses_hosts_resolving_to_loopback() {
+        let server = MockServer::start().await;
+        Mock::given(any())
+            .respond_with(ResponseTemplate::new(204))
+            .mount(&server)
+            .await;
+        let url = format!("http://localhost:{}/hooks", server.address().port());
+
+        let client = webhook_client(&HttpClientSettings::default(), false).expect("client");
+        assert!(client.post(&url).send().await.is_err());
+
+        let client = webhook_client(&HttpClientSettings::default(), true).expect("client");
+        let response = client.post(&url).send().await.expect("delivered");
+        assert_eq!(response.status(), reqwest::StatusCode::NO_CONTENT);
+    }
+}
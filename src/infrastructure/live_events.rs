@@ -0,0 +1,247 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use event_store::RecordedEvent;
+use metrics::counter;
+use tokio::sync::{broadcast, OwnedSemaphorePermit, Semaphore};
+use tokio::task::JoinHandle;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::common::config::LiveEventsSettings;
+use crate::infrastructure::event_store::EventStoreClient;
+use crate::infrastructure::subscriptions::{event_tenant_id, follow_all, SubscriptionRegistry};
+
+/// Name the `$all` subscription feeding the sockets is registered under
+const LIVE_EVENTS_SUBSCRIPTION: &str = "live-events";
+
+/// Fans out a single `$all` subscription to the live event sockets of each
+/// tenant, instead of every socket following EventStore on its own.
+///
+/// At most `max_subscriptions` sockets are served at once; `subscribe`
+/// refuses new ones until an open one is dropped. Events are routed by the
+/// `tenant_id` in their metadata and events without one are not delivered.
+#[derive(Clone)]
+pub struct LiveEvents {
+    permits: Arc<Semaphore>,
+    buffer: usize,
+    tenants: Arc<Mutex<HashMap<Uuid, broadcast::Sender<RecordedEvent>>>>,
+}
+
+/// Events of one tenant for one socket; holds its share of the cap until
+/// dropped
+pub struct LiveSubscription {
+    tenant_id: Uuid,
+    receiver: broadcast::Receiver<RecordedEvent>,
+    _permit: OwnedSemaphorePermit,
+}
+
+impl LiveSubscription {
+    /// Next event of the tenant. A subscriber that fell more than `buffer`
+    /// events behind skips the ones it missed.
+    pub async fn recv(&mut self) -> Option<RecordedEvent> {
+        loop {
+            match self.receiver.recv().await {
+                Ok(event) => return Some(event),
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    counter!("live_events_skipped_total").increment(skipped);
+                    warn!(
+                        "Live event socket of tenant {} skipped {} events",
+                        self.tenant_id, skipped
+                    );
+                },
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
+impl LiveEvents {
+    pub fn new(settings: &LiveEventsSettings) -> Self {
+        Self {
+            permits: Arc::new(Semaphore::new(settings.max_subscriptions)),
+            buffer: settings.buffer.max(1),
+            tenants: Arc::default(),
+        }
+    }
+
+    /// Starts receiving the events of `tenant_id`, or `None` when
+    /// `max_subscriptions` sockets are already open
+    pub fn subscribe(&self, tenant_id: Uuid) -> Option<LiveSubscription> {
+        let permit = self.permits.clone().try_acquire_owned().ok()?;
+        let receiver = self
+            .tenants
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(tenant_id)
+            .or_insert_with(|| broadcast::channel(self.buffer).0)
+            .subscribe();
+
+        Some(LiveSubscription {
+            tenant_id,
+            receiver,
+            _permit: permit,
+        })
+    }
+
+    /// Hands the event to the sockets of its tenant
+    pub fn dispatch(&self, event: RecordedEvent) {
+        let Some(tenant_id) = event_tenant_id(&event) else {
+            return;
+        };
+
+        let mut tenants = self.tenants.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(sender) = tenants.get(&tenant_id) {
+            // Fails only once every socket of the tenant has closed
+            if sender.send(event).is_err() {
+                tenants.remove(&tenant_id);
+            }
+        }
+    }
+
+    /// Follows `$all` from its current end and dispatches every new event
+    pub fn spawn(
+        &self,
+        client: Arc<EventStoreClient>,
+        registry: SubscriptionRegistry,
+    ) -> JoinHandle<()> {
+        let live_events = self.clone();
+        follow_all(client, registry, LIVE_EVENTS_SUBSCRIPTION, move |event| {
+            live_events.dispatch(event)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use event_store::EventData;
+    use sea_orm::{DatabaseBackend, MockDatabase};
+    use std::time::Duration;
+
+    use crate::domain::tenant::{TenantService, TenantSettings};
+    use crate::infrastructure::database::entities::tenant;
+    use crate::infrastructure::event_publisher::{EventPublisher, EventSink};
+    use crate::infrastructure::services::tenant_service::TenantServiceImpl;
+
+    fn live_events(max_subscriptions: usize) -> LiveEvents {
+        LiveEvents::new(&LiveEventsSettings {
+            max_subscriptions,
+            buffer: 8,
+        })
+    }
+
+    fn event_of(tenant_id: Uuid) -> RecordedEvent {
+        let mut metadata = serde_json::Map::new();
+        metadata.insert("tenant_id".to_string(), tenant_id.to_string().into());
+        RecordedEvent {
+            event_id: Uuid::new_v4(),
+            event_type: "TenantUpdated".to_string(),
+            data: serde_json::Value::Null,
+            metadata: metadata.into(),
+            created: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_subscription_cap_is_enforced() {
+        let live_events = live_events(2);
+        let first = live_events.subscribe(Uuid::new_v4()).expect("first socket");
+        let _second = live_events
+            .subscribe(Uuid::new_v4())
+            .expect("second socket");
+        assert!(live_events.subscribe(Uuid::new_v4()).is_none());
+
+        // Closing a socket frees its place
+        drop(first);
+        assert!(live_events.subscribe(Uuid::new_v4()).is_some());
+    }
+
+    /// Hands published events straight to the live events, standing in for
+    /// EventStore and the `$all` subscription
+    struct LoopbackSink(LiveEvents);
+
+    #[async_trait::async_trait]
+    impl EventSink for LoopbackSink {
+        async fn publish(&self, _stream_name: &str, events: Vec<EventData>) -> anyhow::Result<()> {
+            for event in events {
+                self.0.dispatch(RecordedEvent {
+                    event_id: event.event_id,
+                    event_type: event.event_type,
+                    data: event.data,
+                    metadata: event.metadata,
+                    created: Utc::now(),
+                });
+            }
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tenant_service_events_reach_the_tenant_socket() {
+        let live_events = live_events(4);
+        let tenant_id = Uuid::new_v4();
+        let mut socket = live_events.subscribe(tenant_id).expect("socket");
+        let mut other = live_events.subscribe(Uuid::new_v4()).expect("socket");
+
+        let stored = tenant::Model {
+            id: tenant_id,
+            name: "Acme".to_string(),
+            domain: "acme.example.com".to_string(),
+            is_active: false,
+            settings: serde_json::to_value(TenantSettings {
+                max_users: 10,
+                storage_limit: 1024 * 1024 * 1024,
+                api_rate_limit: 100,
+                ..Default::default()
+            })
+            .expect("serializable settings"),
+            created_at: Utc::now().naive_utc(),
+            updated_at: Utc::now().naive_utc(),
+            deleted_at: None,
+        };
+        let reactivated = tenant::Model {
+            is_active: true,
+            ..stored.clone()
+        };
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![stored]])
+            .append_query_results::<tenant::Model, _, _>(vec![vec![]])
+            .append_query_results(vec![vec![reactivated]])
+            .into_connection();
+        let (publisher, _worker) =
+            EventPublisher::spawn(Arc::new(LoopbackSink(live_events.clone())), 8);
+        let service = TenantServiceImpl::new(Arc::new(db)).with_event_publisher(publisher);
+
+        service
+            .reactivate(&tenant_id.to_string())
+            .await
+            .expect("tenant reactivated");
+
+        let event = tokio::time::timeout(Duration::from_secs(5), socket.recv())
+            .await
+            .expect("event delivered")
+            .expect("socket open");
+        assert_eq!(event.event_type, "TenantReactivated");
+        assert_eq!(event_tenant_id(&event), Some(tenant_id));
+        assert!(other.receiver.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_events_reach_only_their_tenant() {
+        let live_events = live_events(4);
+        let (tenant_a, tenant_b) = (Uuid::new_v4(), Uuid::new_v4());
+        let mut a1 = live_events.subscribe(tenant_a).expect("socket");
+        let mut a2 = live_events.subscribe(tenant_a).expect("socket");
+        let mut b = live_events.subscribe(tenant_b).expect("socket");
+
+        let event = event_of(tenant_a);
+        live_events.dispatch(event.clone());
+        live_events.dispatch(event_of(Uuid::new_v4()));
+
+        assert_eq!(a1.recv().await.map(|e| e.event_id), Some(event.event_id));
+        assert_eq!(a2.recv().await.map(|e| e.event_id), Some(event.event_id));
+        assert!(b.receiver.try_recv().is_err());
+    }
+}
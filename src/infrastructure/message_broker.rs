@@ -8,10 +8,8 @@ pub struct MessageBroker {
 }
 
 impl MessageBroker {
-    pub fn new(config: &RabbitMQConfig) -> Result<Self> {
-        let connection = tokio::runtime::Handle::current().block_on(async {
-            Connection::connect(&config.url, ConnectionProperties::default()).await
-        })?;
+    pub async fn connect(config: &RabbitMQConfig) -> Result<Self> {
+        let connection = Connection::connect(&config.url, ConnectionProperties::default()).await?;
         Ok(Self { connection })
     }
 
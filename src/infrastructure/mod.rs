@@ -1,11 +1,20 @@
 // Infrastructure module for external service integrations
+pub mod cache;
 pub mod config;
 pub mod database;
+pub mod event_publisher;
 pub mod event_store;
+pub mod http_client;
+pub mod live_events;
 pub mod message_broker;
 pub mod redis;
+pub mod seed;
 pub mod services;
+pub mod startup;
 pub mod state;
+pub mod subscriptions;
+pub mod system_monitor;
+pub mod webhooks;
 
 // Re-exports
 // pub use cache::CacheConnection;
@@ -3,6 +3,28 @@ use redis::Client;
 
 use crate::infrastructure::config::RedisConfig;
 
+/// Builds namespaced Redis keys.
+///
+/// Every key starts with the application prefix from config, so several
+/// deployments can share one Redis without their keys colliding.
+#[derive(Debug, Clone)]
+pub struct RedisKeys {
+    prefix: String,
+}
+
+impl RedisKeys {
+    pub fn new(prefix: impl Into<String>) -> Self {
+        Self {
+            prefix: prefix.into(),
+        }
+    }
+
+    /// Key shared by all tenants, e.g. the cached JWKS
+    pub fn global(&self, key: &str) -> String {
+        format!("{}:global:{}", self.prefix, key)
+    }
+}
+
 pub struct RedisClient {
     client: Client,
 }
@@ -13,9 +35,31 @@ impl RedisClient {
         Ok(Self { client })
     }
 
+    /// The underlying client, e.g. for the JWKS cache of the auth middleware
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
     pub async fn ping(&self) -> Result<()> {
         let mut conn = self.client.get_async_connection().await?;
         redis::cmd("PING").query_async::<_, ()>(&mut conn).await?;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keys_are_namespaced() {
+        assert_eq!(
+            RedisKeys::new("acci").global("keycloak:jwks"),
+            "acci:global:keycloak:jwks"
+        );
+        assert_ne!(
+            RedisKeys::new("acci").global("keycloak:jwks"),
+            RedisKeys::new("other").global("keycloak:jwks")
+        );
+    }
+}
@@ -0,0 +1,243 @@
+use sea_orm::{DatabaseConnection, EntityTrait, PaginatorTrait};
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::common::{
+    config::SeedSettings,
+    error::{AppError, AppResult, ErrorContext, ErrorKind},
+};
+use crate::domain::{
+    tenant::{Tenant, TenantFeatures, TenantService, TenantSettings, TENANT_SETTINGS_VERSION},
+    user::{CreateUserDto, User, UserRole, UserService},
+};
+use crate::infrastructure::database::entities::tenant::Entity as TenantEntity;
+
+/// What a seeding run created
+#[derive(Debug)]
+pub struct Seeded {
+    pub tenant: Tenant,
+    pub admin: User,
+}
+
+/// Creates the configured default tenant and its admin user, but only while
+/// the tenants table is empty. Erased tenants keep their row, so a database
+/// that ever had a tenant is never seeded again. Each step is idempotent: a
+/// run that created the tenant but failed on the admin leaves a tenant
+/// without users, and the next run adds the admin to it. Returns `None`
+/// when there was nothing to do.
+pub async fn seed_defaults(
+    db: &DatabaseConnection,
+    tenants: &dyn TenantService,
+    users: &dyn UserService,
+    settings: &SeedSettings,
+) -> AppResult<Option<Seeded>> {
+    let existing = TenantEntity::find().count(db).await.map_err(|e| {
+        error!("Failed to count tenants: {}", e);
+        AppError::database(e.to_string())
+            .with_context(ErrorContext::new().with_message("Failed to count tenants".to_string()))
+    })?;
+    let tenant = if existing == 0 {
+        seed_tenant(tenants, settings).await?
+    } else {
+        match unfinished_seed_tenant(tenants, users, settings).await? {
+            Some(tenant) => {
+                info!(
+                    "Seeded tenant {} has no admin yet, finishing seeding",
+                    tenant.id
+                );
+                tenant
+            },
+            None => {
+                info!("Found {} tenants, skipping seeding", existing);
+                return Ok(None);
+            },
+        }
+    };
+
+    let admin = users
+        .create(
+            &tenant.id,
+            CreateUserDto {
+                email: settings.admin_email.clone(),
+                username: settings.admin_username.clone(),
+                full_name: "Administrator".to_string(),
+                role: UserRole::TenantAdmin,
+                settings: None,
+            },
+        )
+        .await?;
+
+    Ok(Some(Seeded { tenant, admin }))
+}
+
+async fn seed_tenant(tenants: &dyn TenantService, settings: &SeedSettings) -> AppResult<Tenant> {
+    let mut tenant = Tenant {
+        id: Uuid::new_v4(),
+        name: settings.tenant_name.clone(),
+        domain: settings.tenant_domain.clone(),
+        is_active: true,
+        settings: TenantSettings {
+            max_users: 10,
+            storage_limit: 1024 * 1024 * 1024, // 1GB
+            api_rate_limit: 100,
+            features: TenantFeatures {
+                advanced_security: false,
+                custom_branding: false,
+                api_access: true,
+                audit_logging: false,
+            },
+            read_only: false,
+            allowed_auth_methods: Default::default(),
+            rate_limits: Default::default(),
+            settings_version: TENANT_SETTINGS_VERSION,
+        },
+        updated_at: None,
+    };
+    tenant.normalize();
+    tenant.validate()?;
+    let tenant = tenants.create(tenant).await?;
+    info!("Seeded tenant {} ({})", tenant.domain, tenant.id);
+    Ok(tenant)
+}
+
+/// The configured default tenant if an earlier run created it but not its
+/// admin
+async fn unfinished_seed_tenant(
+    tenants: &dyn TenantService,
+    users: &dyn UserService,
+    settings: &SeedSettings,
+) -> AppResult<Option<Tenant>> {
+    let tenant = match tenants.find_by_domain(&settings.tenant_domain).await {
+        Ok(tenant) => tenant,
+        Err(e) if matches!(*e.kind, ErrorKind::NotFoundError(_)) => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    if users.list_by_tenant(&tenant.id).await?.is_empty() {
+        Ok(Some(tenant))
+    } else {
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::database::entities::{tenant, user};
+    use crate::infrastructure::services::{
+        tenant_service::TenantServiceImpl, user_service::UserServiceImpl,
+    };
+    use chrono::Utc;
+    use sea_orm::{DatabaseBackend, MockDatabase, Value};
+    use std::collections::BTreeMap;
+    use std::sync::Arc;
+
+    fn tenant_count(count: i64) -> Vec<BTreeMap<String, Value>> {
+        vec![BTreeMap::from([(
+            "num_items".to_string(),
+            Value::BigInt(Some(count)),
+        )])]
+    }
+
+    fn stored(settings: &SeedSettings) -> (tenant::Model, user::Model) {
+        let tenant_id = Uuid::new_v4();
+        let stored_tenant = tenant::Model {
+            id: tenant_id,
+            name: settings.tenant_name.clone(),
+            domain: settings.tenant_domain.clone(),
+            is_active: true,
+            settings: serde_json::to_value(TenantSettings::default())
+                .expect("serializable settings"),
+            created_at: Utc::now().naive_utc(),
+            updated_at: Utc::now().naive_utc(),
+            deleted_at: None,
+        };
+        let stored_admin = user::Model {
+            id: Uuid::new_v4(),
+            tenant_id,
+            email: settings.admin_email.clone(),
+            username: settings.admin_username.clone(),
+            full_name: "Administrator".to_string(),
+            is_active: true,
+            role: user::Role::TenantAdmin,
+            settings: serde_json::to_value(crate::domain::user::UserSettings::default())
+                .expect("serializable settings"),
+            created_at: Utc::now().into(),
+            updated_at: Utc::now().into(),
+            last_login_at: None,
+        };
+        (stored_tenant, stored_admin)
+    }
+
+    #[tokio::test]
+    async fn test_seeding_is_idempotent() {
+        let settings = SeedSettings::default();
+        let (stored_tenant, stored_admin) = stored(&settings);
+
+        // First run: empty table, domain free, tenant and admin inserted.
+        // Second run: one tenant, the seeded one, which already has users.
+        let db = Arc::new(
+            MockDatabase::new(DatabaseBackend::Postgres)
+                .append_query_results(vec![tenant_count(0)])
+                .append_query_results::<tenant::Model, _, _>(vec![vec![]])
+                .append_query_results(vec![vec![stored_tenant.clone()]])
+                .append_query_results(vec![vec![stored_admin.clone()]])
+                .append_query_results(vec![tenant_count(1)])
+                .append_query_results(vec![vec![stored_tenant]])
+                .append_query_results(vec![vec![stored_admin]])
+                .into_connection(),
+        );
+        let tenants = TenantServiceImpl::new(Arc::clone(&db));
+        let users = UserServiceImpl::new(Arc::clone(&db));
+
+        let seeded = seed_defaults(&db, &tenants, &users, &settings)
+            .await
+            .expect("first run seeds")
+            .expect("tenant and admin created");
+        assert_eq!(seeded.tenant.domain, "system.localhost");
+        assert_eq!(seeded.admin.tenant_id, seeded.tenant.id);
+        assert_eq!(seeded.admin.role, UserRole::TenantAdmin);
+
+        let again = seed_defaults(&db, &tenants, &users, &settings)
+            .await
+            .expect("second run succeeds");
+        assert!(again.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_seeded_tenant_without_admin_gets_one_on_the_next_run() {
+        let settings = SeedSettings::default();
+        let (stored_tenant, stored_admin) = stored(&settings);
+        let tenant_id = stored_tenant.id;
+
+        // A previous run inserted the tenant and failed before the admin
+        let db = Arc::new(
+            MockDatabase::new(DatabaseBackend::Postgres)
+                .append_query_results(vec![tenant_count(1)])
+                .append_query_results(vec![vec![stored_tenant]])
+                .append_query_results::<user::Model, _, _>(vec![vec![]])
+                .append_query_results(vec![vec![stored_admin]])
+                .into_connection(),
+        );
+        let tenants = TenantServiceImpl::new(Arc::clone(&db));
+        let users = UserServiceImpl::new(Arc::clone(&db));
+
+        let seeded = seed_defaults(&db, &tenants, &users, &settings)
+            .await
+            .expect("run succeeds")
+            .expect("admin created");
+        assert_eq!(seeded.tenant.id, tenant_id);
+        assert_eq!(seeded.admin.tenant_id, tenant_id);
+    }
+
+    #[test]
+    fn test_prod_is_only_seeded_when_enabled() {
+        let mut settings = SeedSettings::default();
+        assert!(settings.should_run("dev"));
+        assert!(!settings.should_run("prod"));
+
+        settings.enabled = Some(true);
+        assert!(settings.should_run("prod"));
+        settings.enabled = Some(false);
+        assert!(!settings.should_run("dev"));
+    }
+}
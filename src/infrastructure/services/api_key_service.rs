@@ -0,0 +1,209 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, IntoActiveModel,
+    QueryFilter, Set,
+};
+use tracing::{error, info, instrument};
+use uuid::Uuid;
+
+use crate::{
+    common::error::{AppError, AppResult, ErrorContext},
+    domain::api_key::{hash_api_key, ApiKey, ApiKeyService},
+    infrastructure::database::entities::{api_key, api_key::Entity as ApiKeyEntity},
+};
+
+/// Prefix of generated keys, so leaked keys are easy to recognize
+const KEY_PREFIX: &str = "acci_";
+
+#[derive(Clone)]
+pub struct ApiKeyServiceImpl {
+    db: Arc<DatabaseConnection>,
+}
+
+impl ApiKeyServiceImpl {
+    pub fn new(db: Arc<DatabaseConnection>) -> Self {
+        Self { db }
+    }
+}
+
+fn database_error(message: &str, error: DbErr) -> AppError {
+    error!("{}: {}", message, error);
+    AppError::database(error.to_string())
+        .with_context(ErrorContext::new().with_message(message.to_string()))
+}
+
+fn map_to_domain(model: api_key::Model) -> AppResult<ApiKey> {
+    let scopes = serde_json::from_value(model.scopes).map_err(|e| {
+        error!("Invalid scopes for API key {}: {}", model.id, e);
+        AppError::serialization(format!("Invalid scopes for API key {}", model.id))
+    })?;
+
+    Ok(ApiKey {
+        id: model.id,
+        tenant_id: model.tenant_id,
+        name: model.name,
+        scopes,
+        created_at: model.created_at.with_timezone(&Utc),
+        expires_at: model.expires_at.map(|at| at.with_timezone(&Utc)),
+        revoked_at: model.revoked_at.map(|at| at.with_timezone(&Utc)),
+    })
+}
+
+/// 256 bits of randomness from two v4 UUIDs
+fn generate_key() -> String {
+    format!(
+        "{}{}{}",
+        KEY_PREFIX,
+        Uuid::new_v4().simple(),
+        Uuid::new_v4().simple()
+    )
+}
+
+#[async_trait]
+impl ApiKeyService for ApiKeyServiceImpl {
+    #[instrument(skip(self, scopes))]
+    async fn create(
+        &self,
+        tenant_id: &Uuid,
+        name: String,
+        scopes: Vec<String>,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> AppResult<(ApiKey, String)> {
+        let key = generate_key();
+        let model = api_key::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            tenant_id: Set(*tenant_id),
+            name: Set(name),
+            key_hash: Set(hash_api_key(&key)),
+            scopes: Set(serde_json::to_value(&scopes)?),
+            created_at: Set(Utc::now().into()),
+            expires_at: Set(expires_at.map(Into::into)),
+            revoked_at: Set(None),
+        };
+
+        let result = model
+            .insert(&*self.db)
+            .await
+            .map_err(|e| database_error("Failed to create API key", e))?;
+
+        info!("Created API key {} for tenant {}", result.id, tenant_id);
+        Ok((map_to_domain(result)?, key))
+    }
+
+    #[instrument(skip(self, key))]
+    async fn authenticate(&self, key: &str) -> AppResult<ApiKey> {
+        let model = ApiKeyEntity::find()
+            .filter(api_key::Column::KeyHash.eq(hash_api_key(key)))
+            .one(&*self.db)
+            .await
+            .map_err(|e| database_error("Failed to look up API key", e))?
+            .ok_or_else(|| AppError::authentication("Invalid API key"))?;
+
+        let api_key = map_to_domain(model)?;
+        api_key.validate_usable(Utc::now())?;
+        Ok(api_key)
+    }
+
+    #[instrument(skip(self))]
+    async fn revoke(&self, tenant_id: &Uuid, id: &Uuid) -> AppResult<()> {
+        let model = ApiKeyEntity::find_by_id(*id)
+            .filter(api_key::Column::TenantId.eq(*tenant_id))
+            .one(&*self.db)
+            .await
+            .map_err(|e| database_error("Failed to find API key", e))?
+            .ok_or_else(|| AppError::not_found("API key not found"))?;
+        if model.revoked_at.is_some() {
+            return Ok(());
+        }
+
+        let mut model = model.into_active_model();
+        model.revoked_at = Set(Some(Utc::now().into()));
+        model
+            .update(&*self.db)
+            .await
+            .map_err(|e| database_error("Failed to revoke API key", e))?;
+
+        info!("Revoked API key {} of tenant {}", id, tenant_id);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::error::ErrorKind;
+    use chrono::Duration;
+    use sea_orm::{DatabaseBackend, MockDatabase};
+
+    const KEY: &str = "acci_test-key";
+
+    fn stored_key(
+        expires_at: Option<DateTime<Utc>>,
+        revoked_at: Option<DateTime<Utc>>,
+    ) -> api_key::Model {
+        api_key::Model {
+            id: Uuid::new_v4(),
+            tenant_id: Uuid::new_v4(),
+            name: "billing-sync".to_string(),
+            key_hash: hash_api_key(KEY),
+            scopes: serde_json::to_value(vec!["tenant:read"]).expect("serializable scopes"),
+            created_at: Utc::now().into(),
+            expires_at: expires_at.map(Into::into),
+            revoked_at: revoked_at.map(Into::into),
+        }
+    }
+
+    async fn authenticate(model: api_key::Model) -> AppResult<ApiKey> {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![model]])
+            .into_connection();
+        ApiKeyServiceImpl::new(Arc::new(db)).authenticate(KEY).await
+    }
+
+    #[tokio::test]
+    async fn test_valid_key_authenticates() {
+        let model = stored_key(Some(Utc::now() + Duration::days(1)), None);
+        let tenant_id = model.tenant_id;
+
+        let api_key = authenticate(model).await.expect("valid key");
+        assert_eq!(api_key.tenant_id, tenant_id);
+        assert_eq!(api_key.scopes, vec!["tenant:read"]);
+    }
+
+    #[tokio::test]
+    async fn test_revoked_key_is_rejected() {
+        let error = authenticate(stored_key(None, Some(Utc::now())))
+            .await
+            .expect_err("revoked key");
+        assert!(matches!(
+            *error.kind,
+            ErrorKind::AuthenticationError(ref message) if message.contains("revoked")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_expired_key_is_rejected() {
+        let error = authenticate(stored_key(Some(Utc::now() - Duration::minutes(1)), None))
+            .await
+            .expect_err("expired key");
+        assert!(matches!(
+            *error.kind,
+            ErrorKind::AuthenticationError(ref message) if message.contains("expired")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_key_is_rejected() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results::<api_key::Model, _, _>(vec![vec![]])
+            .into_connection();
+        let error = ApiKeyServiceImpl::new(Arc::new(db))
+            .authenticate("acci_unknown")
+            .await
+            .expect_err("unknown key");
+        assert!(matches!(*error.kind, ErrorKind::AuthenticationError(_)));
+    }
+}
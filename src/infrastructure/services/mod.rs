@@ -1 +1,5 @@
+pub mod api_key_service;
+pub mod audit_log_service;
 pub mod tenant_service;
+pub mod user_service;
+pub mod webhook_service;
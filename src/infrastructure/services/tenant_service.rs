@@ -1,51 +1,335 @@
 use std::sync::Arc;
+use std::time::Duration;
 
 use async_trait::async_trait;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
+use event_store::{Event, StreamName};
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, ModelTrait, QueryFilter, Set,
+    sea_query::{Expr, Func, SimpleExpr},
+    ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, DatabaseConnection, DbErr,
+    EntityTrait, JoinType, ModelTrait, NotSet, Order, QueryFilter, QueryOrder, QuerySelect,
+    RelationTrait, Set, TransactionTrait,
 };
+use serde_json::Value;
 use tracing::{error, info, instrument};
 
 use crate::{
-    common::error::{AppError, AppResult, ErrorContext},
-    domain::tenant::{Tenant, TenantService},
-    infrastructure::database::entities::{tenant, tenant::Entity as TenantEntity},
+    common::{
+        error::{AppError, AppResult, ErrorContext},
+        pagination::Pagination,
+    },
+    domain::{
+        tenant::{
+            normalize_domain, Tenant, TenantReactivated, TenantService, TenantSort,
+            TenantSortField, TENANT_SETTINGS_VERSION,
+        },
+        user::{DeactivationReason, UserDeactivated},
+    },
+    infrastructure::{
+        database::{
+            entities::{tenant, tenant::Entity as TenantEntity, user},
+            slow_query::SlowQueryMonitor,
+        },
+        event_publisher::EventPublisher,
+    },
 };
 
 #[derive(Clone)]
 pub struct TenantServiceImpl {
     db: Arc<DatabaseConnection>,
+    events: Option<EventPublisher>,
+    slow_queries: SlowQueryMonitor,
 }
 
 impl TenantServiceImpl {
     pub fn new(db: Arc<DatabaseConnection>) -> Self {
-        Self { db }
+        Self {
+            db,
+            events: None,
+            slow_queries: SlowQueryMonitor::default(),
+        }
     }
 
-    fn map_to_domain(&self, model: tenant::Model) -> Tenant {
-        Tenant {
+    /// Reports database calls taking `threshold` or longer as slow queries
+    pub fn with_slow_query_threshold(mut self, threshold: Duration) -> Self {
+        self.slow_queries = SlowQueryMonitor::new(threshold);
+        self
+    }
+
+    /// Publishes `UserDeactivated` and `TenantReactivated` events on tenant
+    /// lifecycle changes
+    pub fn with_event_publisher(mut self, events: EventPublisher) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Queues the events after the transaction has committed, so a failed
+    /// append is logged instead of undoing the deactivation
+    fn publish_user_deactivations(&self, users: &[user::Model], reason: DeactivationReason) {
+        let Some(events) = &self.events else {
+            return;
+        };
+
+        for (stream_name, event) in user_deactivated_events(users, reason) {
+            events.publish_for_tenant(event.data.tenant_id, stream_name, vec![event]);
+        }
+    }
+
+    fn publish_tenant_reactivated(&self, tenant_id: uuid::Uuid) {
+        if let Some(events) = &self.events {
+            let event = Event::new(TenantReactivated { tenant_id }, 1, None, None, None);
+            events.publish_for_tenant(tenant_id, StreamName::tenant_stream(tenant_id), vec![event]);
+        }
+    }
+
+    /// Fails with a serialization error when the stored settings can't be
+    /// read, rather than hiding the corrupt row behind default settings
+    fn map_to_domain(&self, model: tenant::Model) -> AppResult<Tenant> {
+        let settings = migrate_settings(model.id, model.settings);
+        let settings = serde_json::from_value(settings).map_err(|e| {
+            error!("Invalid settings for tenant {}: {}", model.id, e);
+            AppError::serialization(format!("Invalid settings for tenant {}", model.id))
+        })?;
+
+        Ok(Tenant {
             id: model.id,
             name: model.name,
             domain: model.domain,
             is_active: model.is_active,
-            settings: serde_json::from_value(model.settings).unwrap_or_default(),
+            settings,
+            updated_at: Some(model.updated_at.and_utc()),
+        })
+    }
+
+    /// Rejects `domain` when another tenant already uses it in any spelling
+    async fn ensure_domain_available(&self, domain: &str, id: uuid::Uuid) -> AppResult<()> {
+        let query = TenantEntity::find()
+            .filter(domain_matches(domain))
+            .filter(tenant::Column::Id.ne(id))
+            .one(&*self.db);
+        let existing = self
+            .slow_queries
+            .observe("tenant.check_domain", query)
+            .await
+            .map_err(|e| {
+                error!("Failed to check tenant domain: {}", e);
+                AppError::database(e.to_string()).with_context(
+                    ErrorContext::new().with_message("Failed to check tenant domain".to_string()),
+                )
+            })?;
+
+        if existing.is_some() {
+            return Err(AppError::validation("Tenant domain is already in use"));
         }
+        Ok(())
+    }
+
+    /// Writes the tenant, and with `expected_updated_at` only while the
+    /// stored row still has that `updated_at`, so a concurrent change in
+    /// between fails with 412 instead of being overwritten
+    async fn update_where(
+        &self,
+        tenant: Tenant,
+        expected_updated_at: Option<chrono::NaiveDateTime>,
+    ) -> AppResult<Tenant> {
+        let domain = normalize_domain(&tenant.domain);
+        self.ensure_domain_available(&domain, tenant.id).await?;
+
+        let model = tenant::ActiveModel {
+            id: Set(tenant.id),
+            name: Set(tenant.name),
+            domain: Set(domain),
+            is_active: Set(tenant.is_active),
+            settings: Set(serde_json::to_value(&tenant.settings)?),
+            created_at: NotSet,
+            updated_at: Set(Utc::now().naive_utc()),
+            deleted_at: NotSet,
+        };
+
+        // Users of an inactive tenant must not stay active, so both change
+        // together or not at all
+        let txn = self.db.begin().await.map_err(transaction_error)?;
+        let deactivated = if tenant.is_active {
+            Vec::new()
+        } else {
+            self.slow_queries
+                .observe("tenant.deactivate_users", deactivate_users(&txn, tenant.id))
+                .await?
+        };
+        let mut query = TenantEntity::update(model);
+        if let Some(expected) = expected_updated_at {
+            query = query.filter(tenant::Column::UpdatedAt.eq(expected));
+        }
+        let result = self
+            .slow_queries
+            .observe("tenant.update", query.exec(&txn))
+            .await
+            .map_err(|e| match e {
+                sea_orm::DbErr::RecordNotUpdated if expected_updated_at.is_some() => {
+                    AppError::precondition_failed("Tenant was modified concurrently")
+                },
+                e => {
+                    error!("Failed to update tenant: {}", e);
+                    AppError::database(e.to_string()).with_context(
+                        ErrorContext::new().with_message("Failed to update tenant".to_string()),
+                    )
+                },
+            })?;
+        txn.commit().await.map_err(transaction_error)?;
+
+        if !deactivated.is_empty() {
+            info!(
+                "Deactivated {} users of tenant {}",
+                deactivated.len(),
+                tenant.id
+            );
+        }
+        self.publish_user_deactivations(&deactivated, DeactivationReason::TenantDeactivated);
+
+        self.map_to_domain(result)
     }
 }
 
+fn transaction_error(e: DbErr) -> AppError {
+    error!("Tenant transaction failed: {}", e);
+    AppError::database(e.to_string())
+        .with_context(ErrorContext::new().with_message("Tenant transaction failed".to_string()))
+}
+
+/// Deactivates every still-active user of the tenant so their tokens stop
+/// authorizing anything, returning the users that were deactivated
+async fn deactivate_users<C: ConnectionTrait>(
+    conn: &C,
+    tenant_id: uuid::Uuid,
+) -> AppResult<Vec<user::Model>> {
+    user::Entity::update_many()
+        .col_expr(user::Column::IsActive, Expr::value(false))
+        .col_expr(user::Column::UpdatedAt, Expr::value(Utc::now()))
+        .filter(user::Column::TenantId.eq(tenant_id))
+        .filter(user::Column::IsActive.eq(true))
+        .exec_with_returning(conn)
+        .await
+        .map_err(|e| {
+            error!("Failed to deactivate users of tenant {}: {}", tenant_id, e);
+            AppError::database(e.to_string()).with_context(
+                ErrorContext::new().with_message("Failed to deactivate tenant users".to_string()),
+            )
+        })
+}
+
+/// One `UserDeactivated` event per user, keyed by the user's stream
+fn user_deactivated_events(
+    users: &[user::Model],
+    reason: DeactivationReason,
+) -> Vec<(String, Event<UserDeactivated>)> {
+    users
+        .iter()
+        .map(|user| {
+            let event = UserDeactivated {
+                user_id: user.id,
+                tenant_id: user.tenant_id,
+                reason: reason.clone(),
+            };
+            (
+                StreamName::user_stream(user.tenant_id, user.id),
+                Event::new(event, 1, None, None, None),
+            )
+        })
+        .collect()
+}
+
+/// Case-insensitive match on the stored domain, so rows written before
+/// domains were normalized are still found
+fn domain_matches(domain: &str) -> SimpleExpr {
+    Expr::expr(Func::lower(Expr::col(tenant::Column::Domain))).eq(normalize_domain(domain))
+}
+
+/// Upgrades stored settings JSON to the current `TenantSettings` shape.
+/// Rows written before versioning was introduced are treated as v1.
+fn migrate_settings(tenant_id: uuid::Uuid, mut settings: Value) -> Value {
+    let Some(fields) = settings.as_object_mut() else {
+        return settings;
+    };
+
+    let from = fields
+        .get("settings_version")
+        .and_then(Value::as_u64)
+        .unwrap_or(1) as u32;
+    if from >= TENANT_SETTINGS_VERSION {
+        return settings;
+    }
+
+    // v1 -> v2: introduced `read_only`
+    if from < 2 {
+        fields.entry("read_only").or_insert(Value::Bool(false));
+    }
+
+    // v2 -> v3: introduced `allowed_auth_methods`
+    if from < 3 {
+        fields
+            .entry("allowed_auth_methods")
+            .or_insert(Value::Array(Vec::new()));
+    }
+
+    // v3 -> v4: introduced `rate_limits`; the single limit becomes the read
+    // bucket's
+    if from < 4 {
+        let read_limit = fields.get("api_rate_limit").cloned();
+        fields.entry("rate_limits").or_insert_with(|| {
+            let mut buckets = serde_json::Map::new();
+            if let Some(limit) = read_limit {
+                buckets.insert("read".to_string(), limit);
+            }
+            Value::Object(buckets)
+        });
+    }
+
+    fields.insert(
+        "settings_version".to_string(),
+        Value::from(TENANT_SETTINGS_VERSION),
+    );
+    info!(
+        "Upgraded settings for tenant {} from v{} to v{}",
+        tenant_id, from, TENANT_SETTINGS_VERSION
+    );
+    settings
+}
+
 #[async_trait]
 impl TenantService for TenantServiceImpl {
     #[instrument(skip(self))]
-    async fn list(&self) -> AppResult<Vec<Tenant>> {
-        let models = TenantEntity::find().all(&*self.db).await.map_err(|e| {
-            error!("Failed to list tenants: {}", e);
-            AppError::database(e.to_string()).with_context(
-                ErrorContext::new().with_message("Failed to list tenants".to_string()),
-            )
-        })?;
+    async fn list(&self, pagination: Pagination, sort: TenantSort) -> AppResult<Vec<Tenant>> {
+        let column = match sort.field {
+            TenantSortField::Name => tenant::Column::Name,
+            TenantSortField::Domain => tenant::Column::Domain,
+            TenantSortField::CreatedAt => tenant::Column::CreatedAt,
+            TenantSortField::UpdatedAt => tenant::Column::UpdatedAt,
+        };
+        let order = if sort.descending {
+            Order::Desc
+        } else {
+            Order::Asc
+        };
+        // The id breaks ties, so pages don't overlap
+        let query = TenantEntity::find()
+            .filter(tenant::Column::DeletedAt.is_null())
+            .order_by(column, order.clone())
+            .order_by(tenant::Column::Id, order)
+            .offset(pagination.offset())
+            .limit(pagination.page_size)
+            .all(&*self.db);
+        let models = self
+            .slow_queries
+            .observe("tenant.list", query)
+            .await
+            .map_err(|e| {
+                error!("Failed to list tenants: {}", e);
+                AppError::database(e.to_string()).with_context(
+                    ErrorContext::new().with_message("Failed to list tenants".to_string()),
+                )
+            })?;
 
-        Ok(models.into_iter().map(|m| self.map_to_domain(m)).collect())
+        models.into_iter().map(|m| self.map_to_domain(m)).collect()
     }
 
     #[instrument(skip(self))]
@@ -55,8 +339,13 @@ impl TenantService for TenantServiceImpl {
             AppError::validation("Invalid UUID format")
         })?;
 
-        let model = TenantEntity::find_by_id(uuid)
-            .one(&*self.db)
+        // Erased tenants are only visible to `reactivate`
+        let query = TenantEntity::find_by_id(uuid)
+            .filter(tenant::Column::DeletedAt.is_null())
+            .one(&*self.db);
+        let model = self
+            .slow_queries
+            .observe("tenant.find_by_id", query)
             .await
             .map_err(|e| {
                 error!("Failed to find tenant: {}", e);
@@ -66,14 +355,18 @@ impl TenantService for TenantServiceImpl {
             })?
             .ok_or_else(|| AppError::not_found("Tenant not found"))?;
 
-        Ok(self.map_to_domain(model))
+        self.map_to_domain(model)
     }
 
     #[instrument(skip(self))]
     async fn find_by_domain(&self, domain: &str) -> AppResult<Tenant> {
-        let model = TenantEntity::find()
-            .filter(tenant::Column::Domain.eq(domain))
-            .one(&*self.db)
+        let query = TenantEntity::find()
+            .filter(domain_matches(domain))
+            .filter(tenant::Column::DeletedAt.is_null())
+            .one(&*self.db);
+        let model = self
+            .slow_queries
+            .observe("tenant.find_by_domain", query)
             .await
             .map_err(|e| {
                 error!("Failed to find tenant by domain: {}", e);
@@ -83,62 +376,164 @@ impl TenantService for TenantServiceImpl {
             })?
             .ok_or_else(|| AppError::not_found("Tenant not found"))?;
 
-        Ok(self.map_to_domain(model))
+        self.map_to_domain(model)
     }
 
     #[instrument(skip(self, tenant))]
     async fn create(&self, tenant: Tenant) -> AppResult<Tenant> {
+        let domain = normalize_domain(&tenant.domain);
+        self.ensure_domain_available(&domain, tenant.id).await?;
+
         let model = tenant::ActiveModel {
             id: Set(tenant.id),
             name: Set(tenant.name),
-            domain: Set(tenant.domain),
+            domain: Set(domain),
             is_active: Set(tenant.is_active),
             settings: Set(serde_json::to_value(&tenant.settings)?),
             created_at: Set(Utc::now().naive_utc()),
             updated_at: Set(Utc::now().naive_utc()),
+            deleted_at: Set(None),
         };
 
-        let result = model.insert(&*self.db).await.map_err(|e| {
-            error!("Failed to create tenant: {}", e);
-            AppError::database(e.to_string()).with_context(
-                ErrorContext::new().with_message("Failed to create tenant".to_string()),
-            )
-        })?;
+        let query = model.insert(&*self.db);
+        let result = self
+            .slow_queries
+            .observe("tenant.create", query)
+            .await
+            .map_err(|e| {
+                error!("Failed to create tenant: {}", e);
+                AppError::database(e.to_string()).with_context(
+                    ErrorContext::new().with_message("Failed to create tenant".to_string()),
+                )
+            })?;
 
-        Ok(self.map_to_domain(result))
+        self.map_to_domain(result)
     }
 
     #[instrument(skip(self, tenant))]
     async fn update(&self, tenant: Tenant) -> AppResult<Tenant> {
+        self.update_where(tenant, None).await
+    }
+
+    #[instrument(skip(self, tenant))]
+    async fn update_unmodified(&self, tenant: Tenant) -> AppResult<Tenant> {
+        let expected = tenant.updated_at.map(|updated_at| updated_at.naive_utc());
+        self.update_where(tenant, expected).await
+    }
+
+    #[instrument(skip(self))]
+    async fn delete(&self, id: &str) -> AppResult<()> {
+        let uuid = uuid::Uuid::parse_str(id).map_err(|e| {
+            error!("Invalid UUID format: {}", e);
+            AppError::validation("Invalid UUID format")
+        })?;
+
+        let txn = self.db.begin().await.map_err(transaction_error)?;
+        let query = TenantEntity::find_by_id(uuid).one(&txn);
+        let model = self
+            .slow_queries
+            .observe("tenant.find_by_id", query)
+            .await
+            .map_err(|e| {
+                error!("Failed to find tenant: {}", e);
+                AppError::database(e.to_string()).with_context(
+                    ErrorContext::new().with_message("Failed to find tenant".to_string()),
+                )
+            })?
+            .ok_or_else(|| AppError::not_found("Tenant not found"))?;
+
+        // The rows go with the tenant, but the deactivations are still
+        // recorded on the users' streams
+        let deactivated = self
+            .slow_queries
+            .observe("tenant.deactivate_users", deactivate_users(&txn, uuid))
+            .await?;
+        let query = model.delete(&txn);
+        self.slow_queries
+            .observe("tenant.delete", query)
+            .await
+            .map_err(|e| {
+                error!("Failed to delete tenant: {}", e);
+                AppError::database(e.to_string()).with_context(
+                    ErrorContext::new().with_message("Failed to delete tenant".to_string()),
+                )
+            })?;
+        txn.commit().await.map_err(transaction_error)?;
+
+        self.publish_user_deactivations(&deactivated, DeactivationReason::TenantDeleted);
+
+        info!("Deleted tenant with ID: {}", id);
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn erase(&self, id: &str) -> AppResult<Vec<uuid::Uuid>> {
+        let uuid = uuid::Uuid::parse_str(id).map_err(|e| {
+            error!("Invalid UUID format: {}", e);
+            AppError::validation("Invalid UUID format")
+        })?;
+
+        // No deactivations are published: the users' streams are about to
+        // be tombstoned, and an event on them would only add personal data
+        let txn = self.db.begin().await.map_err(transaction_error)?;
+        let query = user::Entity::delete_many()
+            .filter(user::Column::TenantId.eq(uuid))
+            .exec_with_returning(&txn);
+        let erased_users = self
+            .slow_queries
+            .observe("tenant.erase_users", query)
+            .await
+            .map_err(|e| {
+                error!("Failed to erase users of tenant {}: {}", id, e);
+                AppError::database(e.to_string()).with_context(
+                    ErrorContext::new().with_message("Failed to erase tenant users".to_string()),
+                )
+            })?;
+
+        let now = Utc::now().naive_utc();
         let model = tenant::ActiveModel {
-            id: Set(tenant.id),
-            name: Set(tenant.name),
-            domain: Set(tenant.domain),
-            is_active: Set(tenant.is_active),
-            settings: Set(serde_json::to_value(&tenant.settings)?),
-            created_at: Set(Utc::now().naive_utc()),
-            updated_at: Set(Utc::now().naive_utc()),
+            id: Set(uuid),
+            is_active: Set(false),
+            updated_at: Set(now),
+            deleted_at: Set(Some(now)),
+            ..Default::default()
         };
 
-        let result = model.update(&*self.db).await.map_err(|e| {
-            error!("Failed to update tenant: {}", e);
-            AppError::database(e.to_string()).with_context(
-                ErrorContext::new().with_message("Failed to update tenant".to_string()),
-            )
-        })?;
+        let query = model.update(&txn);
+        self.slow_queries
+            .observe("tenant.erase", query)
+            .await
+            .map_err(|e| {
+                error!("Failed to erase tenant: {}", e);
+                match e {
+                    sea_orm::DbErr::RecordNotUpdated => AppError::not_found("Tenant not found"),
+                    e => AppError::database(e.to_string()).with_context(
+                        ErrorContext::new().with_message("Failed to erase tenant".to_string()),
+                    ),
+                }
+            })?;
+        txn.commit().await.map_err(transaction_error)?;
 
-        Ok(self.map_to_domain(result))
+        info!(
+            "Erased tenant with ID: {} and its {} users",
+            id,
+            erased_users.len()
+        );
+        Ok(erased_users.into_iter().map(|user| user.id).collect())
     }
 
     #[instrument(skip(self))]
-    async fn delete(&self, id: &str) -> AppResult<()> {
+    async fn reactivate(&self, id: &str) -> AppResult<Tenant> {
         let uuid = uuid::Uuid::parse_str(id).map_err(|e| {
             error!("Invalid UUID format: {}", e);
             AppError::validation("Invalid UUID format")
         })?;
 
-        let model = TenantEntity::find_by_id(uuid)
-            .one(&*self.db)
+        // Soft-deleted tenants are included on purpose
+        let query = TenantEntity::find_by_id(uuid).one(&*self.db);
+        let model = self
+            .slow_queries
+            .observe("tenant.find_by_id", query)
             .await
             .map_err(|e| {
                 error!("Failed to find tenant: {}", e);
@@ -148,15 +543,64 @@ impl TenantService for TenantServiceImpl {
             })?
             .ok_or_else(|| AppError::not_found("Tenant not found"))?;
 
-        model.delete(&*self.db).await.map_err(|e| {
-            error!("Failed to delete tenant: {}", e);
-            AppError::database(e.to_string()).with_context(
-                ErrorContext::new().with_message("Failed to delete tenant".to_string()),
+        // Rules may have tightened and the domain may have been reused while
+        // the tenant was inactive
+        let tenant = self.map_to_domain(model)?;
+        tenant.validate()?;
+        self.ensure_domain_available(&tenant.domain, uuid).await?;
+
+        let now = Utc::now().naive_utc();
+        let model = tenant::ActiveModel {
+            id: Set(uuid),
+            is_active: Set(true),
+            updated_at: Set(now),
+            deleted_at: Set(None),
+            ..Default::default()
+        };
+        let query = model.update(&*self.db);
+        let result = self
+            .slow_queries
+            .observe("tenant.reactivate", query)
+            .await
+            .map_err(|e| {
+                error!("Failed to reactivate tenant: {}", e);
+                AppError::database(e.to_string()).with_context(
+                    ErrorContext::new().with_message("Failed to reactivate tenant".to_string()),
+                )
+            })?;
+
+        self.publish_tenant_reactivated(uuid);
+
+        info!("Reactivated tenant with ID: {}", id);
+        self.map_to_domain(result)
+    }
+
+    #[instrument(skip(self))]
+    async fn find_stale(&self, inactive_since: DateTime<Utc>) -> AppResult<Vec<Tenant>> {
+        let last_login = || Func::max(Expr::col((user::Entity, user::Column::LastLoginAt)));
+
+        let query = TenantEntity::find()
+            .join(JoinType::LeftJoin, tenant::Relation::Users.def())
+            .filter(tenant::Column::DeletedAt.is_null())
+            .group_by(tenant::Column::Id)
+            .having(
+                Condition::any()
+                    .add(Expr::expr(last_login()).is_null())
+                    .add(Expr::expr(last_login()).lt(inactive_since)),
             )
-        })?;
+            .all(&*self.db);
+        let models = self
+            .slow_queries
+            .observe("tenant.find_stale", query)
+            .await
+            .map_err(|e| {
+                error!("Failed to find stale tenants: {}", e);
+                AppError::database(e.to_string()).with_context(
+                    ErrorContext::new().with_message("Failed to find stale tenants".to_string()),
+                )
+            })?;
 
-        info!("Deleted tenant with ID: {}", id);
-        Ok(())
+        models.into_iter().map(|m| self.map_to_domain(m)).collect()
     }
 }
 
@@ -165,7 +609,7 @@ mod tests {
     use super::*;
     use crate::{
         common::error::ErrorKind,
-        domain::tenant::{TenantFeatures, TenantSettings},
+        domain::tenant::{TenantFeatures, TenantSettings, TENANT_SETTINGS_VERSION},
     };
     use sea_orm::{DatabaseBackend, MockDatabase};
 
@@ -185,10 +629,49 @@ mod tests {
                     api_access: true,
                     audit_logging: true,
                 },
+                read_only: false,
+                allowed_auth_methods: Default::default(),
+                rate_limits: Default::default(),
+                settings_version: TENANT_SETTINGS_VERSION,
             },
+            updated_at: None,
         }
     }
 
+    async fn listing_sql(sort: TenantSort) -> String {
+        let db = Arc::new(
+            MockDatabase::new(DatabaseBackend::Postgres)
+                .append_query_results::<tenant::Model, _, _>(vec![vec![]])
+                .into_connection(),
+        );
+        let service = TenantServiceImpl::new(Arc::clone(&db));
+        let pagination = Pagination {
+            page: 1,
+            page_size: 20,
+        };
+        service.list(pagination, sort).await.expect("tenants");
+
+        drop(service);
+        let log = Arc::try_unwrap(db)
+            .expect("service dropped")
+            .into_transaction_log();
+        log[0].statements()[0].sql.clone()
+    }
+
+    #[tokio::test]
+    async fn test_list_sorts_ascending() {
+        let sql = listing_sql(TenantSort::parse("name").expect("sortable")).await;
+
+        assert!(sql.contains(r#"ORDER BY "tenants"."name" ASC, "tenants"."id" ASC LIMIT"#));
+    }
+
+    #[tokio::test]
+    async fn test_list_sorts_descending() {
+        let sql = listing_sql(TenantSort::parse("-created_at").expect("sortable")).await;
+
+        assert!(sql.contains(r#"ORDER BY "tenants"."created_at" DESC, "tenants"."id" DESC LIMIT"#));
+    }
+
     #[tokio::test]
     async fn test_find_by_id_not_found() {
         let db = MockDatabase::new(DatabaseBackend::Postgres)
@@ -211,6 +694,8 @@ mod tests {
     async fn test_create_tenant() {
         let tenant = create_test_tenant();
         let db = MockDatabase::new(DatabaseBackend::Postgres)
+            // No other tenant uses the domain
+            .append_query_results::<tenant::Model, _, _>(vec![vec![]])
             .append_query_results(vec![vec![tenant::Model {
                 id: tenant.id,
                 name: tenant.name.clone(),
@@ -219,6 +704,7 @@ mod tests {
                 settings: serde_json::to_value(&tenant.settings).unwrap(),
                 created_at: Utc::now().naive_utc(),
                 updated_at: Utc::now().naive_utc(),
+                deleted_at: None,
             }]])
             .into_connection();
 
@@ -237,6 +723,8 @@ mod tests {
     async fn test_update_tenant() {
         let tenant = create_test_tenant();
         let db = MockDatabase::new(DatabaseBackend::Postgres)
+            // No other tenant uses the domain
+            .append_query_results::<tenant::Model, _, _>(vec![vec![]])
             .append_query_results(vec![vec![tenant::Model {
                 id: tenant.id,
                 name: tenant.name.clone(),
@@ -245,6 +733,7 @@ mod tests {
                 settings: serde_json::to_value(&tenant.settings).unwrap(),
                 created_at: Utc::now().naive_utc(),
                 updated_at: Utc::now().naive_utc(),
+                deleted_at: None,
             }]])
             .into_connection();
 
@@ -258,4 +747,474 @@ mod tests {
         assert_eq!(updated.domain, tenant.domain);
         assert_eq!(updated.is_active, tenant.is_active);
     }
+
+    fn stored_model(tenant: &Tenant) -> tenant::Model {
+        tenant::Model {
+            id: tenant.id,
+            name: tenant.name.clone(),
+            domain: tenant.domain.clone(),
+            is_active: tenant.is_active,
+            settings: serde_json::to_value(&tenant.settings).expect("serializable settings"),
+            created_at: Utc::now().naive_utc(),
+            updated_at: Utc::now().naive_utc(),
+            deleted_at: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_stores_normalized_domain() {
+        let mut tenant = create_test_tenant();
+        let stored = stored_model(&tenant);
+        tenant.domain = "Test.Example.COM.".to_string();
+        let db = Arc::new(
+            MockDatabase::new(DatabaseBackend::Postgres)
+                .append_query_results::<tenant::Model, _, _>(vec![vec![]])
+                .append_query_results(vec![vec![stored]])
+                .into_connection(),
+        );
+
+        let service = TenantServiceImpl::new(Arc::clone(&db));
+        let created = service.create(tenant).await.expect("tenant created");
+        assert_eq!(created.domain, "test.example.com");
+
+        drop(service);
+        let log = Arc::try_unwrap(db)
+            .expect("service dropped")
+            .into_transaction_log();
+        let lookup = log[0].statements()[0].sql.clone();
+        assert!(lookup.contains(r#"LOWER("domain") = $1"#));
+        let insert = format!("{:?}", log[1].statements()[0].values);
+        assert!(insert.contains(r#""test.example.com""#));
+        assert!(!insert.contains("Test.Example.COM."));
+    }
+
+    #[tokio::test]
+    async fn test_create_rejects_domain_in_other_case() {
+        let existing = create_test_tenant();
+        let mut duplicate = create_test_tenant();
+        duplicate.domain = "TEST.example.com.".to_string();
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![stored_model(&existing)]])
+            .into_connection();
+
+        let service = TenantServiceImpl::new(Arc::new(db));
+        let error = service
+            .create(duplicate)
+            .await
+            .expect_err("domain is already taken");
+
+        assert!(matches!(*error.kind, ErrorKind::ValidationError(_)));
+    }
+
+    #[tokio::test]
+    async fn test_find_by_domain_ignores_case() {
+        let tenant = create_test_tenant();
+        let db = Arc::new(
+            MockDatabase::new(DatabaseBackend::Postgres)
+                .append_query_results(vec![vec![stored_model(&tenant)]])
+                .into_connection(),
+        );
+
+        let service = TenantServiceImpl::new(Arc::clone(&db));
+        let found = service
+            .find_by_domain("Test.Example.Com.")
+            .await
+            .expect("tenant found");
+        assert_eq!(found.id, tenant.id);
+
+        drop(service);
+        let log = Arc::try_unwrap(db)
+            .expect("service dropped")
+            .into_transaction_log();
+        let statement = &log[0].statements()[0];
+        assert!(statement.sql.contains(r#"LOWER("domain") = $1"#));
+        assert!(format!("{:?}", statement.values).contains(r#""test.example.com""#));
+    }
+
+    fn active_user(tenant_id: uuid::Uuid) -> user::Model {
+        user::Model {
+            id: uuid::Uuid::new_v4(),
+            tenant_id,
+            email: "jane@example.com".to_string(),
+            username: "jane".to_string(),
+            full_name: "Jane Doe".to_string(),
+            is_active: true,
+            role: user::Role::User,
+            settings: serde_json::Value::Object(Default::default()),
+            created_at: Utc::now().into(),
+            updated_at: Utc::now().into(),
+            last_login_at: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_deactivating_tenant_deactivates_its_users() {
+        let mut tenant = create_test_tenant();
+        tenant.is_active = false;
+        let users: Vec<user::Model> = (0..2)
+            .map(|_| user::Model {
+                is_active: false,
+                ..active_user(tenant.id)
+            })
+            .collect();
+        let db = Arc::new(
+            MockDatabase::new(DatabaseBackend::Postgres)
+                // No other tenant uses the domain
+                .append_query_results::<tenant::Model, _, _>(vec![vec![]])
+                .append_query_results(vec![users.clone()])
+                .append_query_results(vec![vec![stored_model(&tenant)]])
+                .into_connection(),
+        );
+
+        let service = TenantServiceImpl::new(Arc::clone(&db));
+        let updated = service
+            .update(tenant.clone())
+            .await
+            .expect("tenant updated");
+        assert!(!updated.is_active);
+
+        drop(service);
+        let log = Arc::try_unwrap(db)
+            .expect("service dropped")
+            .into_transaction_log();
+        // Users and tenant are updated in one transaction
+        let statements = log[1].statements();
+        assert_eq!(statements.len(), 4);
+        assert_eq!(statements[0].sql, "BEGIN");
+        assert!(statements[1].sql.starts_with(
+            r#"UPDATE "users" SET "is_active" = $1, "updated_at" = $2 WHERE "users"."tenant_id" = $3 AND "users"."is_active" = $4"#
+        ));
+        assert!(statements[2].sql.starts_with(r#"UPDATE "tenants""#));
+        assert_eq!(statements[3].sql, "COMMIT");
+
+        let events = user_deactivated_events(&users, DeactivationReason::TenantDeactivated);
+        assert_eq!(events.len(), users.len());
+        for ((stream_name, event), user) in events.iter().zip(&users) {
+            assert_eq!(stream_name, &StreamName::user_stream(tenant.id, user.id));
+            assert_eq!(event.data.user_id, user.id);
+            assert_eq!(event.data.reason, DeactivationReason::TenantDeactivated);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_active_tenant_update_leaves_users_alone() {
+        let tenant = create_test_tenant();
+        let db = Arc::new(
+            MockDatabase::new(DatabaseBackend::Postgres)
+                .append_query_results::<tenant::Model, _, _>(vec![vec![]])
+                .append_query_results(vec![vec![stored_model(&tenant)]])
+                .into_connection(),
+        );
+
+        let service = TenantServiceImpl::new(Arc::clone(&db));
+        service.update(tenant).await.expect("tenant updated");
+
+        drop(service);
+        let log = Arc::try_unwrap(db)
+            .expect("service dropped")
+            .into_transaction_log();
+        assert!(log
+            .iter()
+            .flat_map(|transaction| transaction.statements())
+            .all(|statement| !statement.sql.contains(r#"UPDATE "users""#)));
+    }
+
+    #[tokio::test]
+    async fn test_erase_soft_deletes_tenant_and_removes_its_users() {
+        let mut erased = stored_model(&create_test_tenant());
+        erased.is_active = false;
+        erased.deleted_at = Some(Utc::now().naive_utc());
+        let users = vec![active_user(erased.id), active_user(erased.id)];
+        let db = Arc::new(
+            MockDatabase::new(DatabaseBackend::Postgres)
+                .append_query_results(vec![users.clone()])
+                .append_query_results(vec![vec![erased.clone()]])
+                .into_connection(),
+        );
+
+        let service = TenantServiceImpl::new(Arc::clone(&db));
+        let erased_users = service
+            .erase(&erased.id.to_string())
+            .await
+            .expect("tenant erased");
+        assert_eq!(
+            erased_users,
+            users.iter().map(|user| user.id).collect::<Vec<_>>()
+        );
+
+        drop(service);
+        let log = Arc::try_unwrap(db)
+            .expect("service dropped")
+            .into_transaction_log();
+        // Users and tenant are changed in one transaction
+        let statements = log[0].statements();
+        assert_eq!(statements.len(), 4);
+        assert_eq!(statements[0].sql, "BEGIN");
+        assert!(statements[1]
+            .sql
+            .starts_with(r#"DELETE FROM "users" WHERE "users"."tenant_id" = $1 RETURNING"#));
+        assert!(statements[2].sql.starts_with(
+            r#"UPDATE "tenants" SET "is_active" = $1, "updated_at" = $2, "deleted_at" = $3"#
+        ));
+        let values = format!("{:?}", statements[2].values);
+        assert!(values.contains("Bool(Some(false))"));
+        assert!(!values.contains("ChronoDateTime(None)"));
+        assert_eq!(statements[3].sql, "COMMIT");
+    }
+
+    #[tokio::test]
+    async fn test_update_unmodified_checks_updated_at_in_the_update() {
+        let mut tenant = create_test_tenant();
+        let read_at = Utc::now() - chrono::Duration::hours(1);
+        tenant.updated_at = Some(read_at);
+        let db = Arc::new(
+            MockDatabase::new(DatabaseBackend::Postgres)
+                // No other tenant uses the domain
+                .append_query_results::<tenant::Model, _, _>(vec![vec![]])
+                .append_query_results(vec![vec![stored_model(&tenant)]])
+                .into_connection(),
+        );
+
+        let service = TenantServiceImpl::new(Arc::clone(&db));
+        service
+            .update_unmodified(tenant.clone())
+            .await
+            .expect("tenant updated");
+
+        drop(service);
+        let log = Arc::try_unwrap(db)
+            .expect("service dropped")
+            .into_transaction_log();
+        let update = &log[1].statements()[1];
+        assert!(update.sql.starts_with(r#"UPDATE "tenants""#));
+        assert!(!update.sql.contains(r#""created_at" ="#));
+        assert!(update
+            .sql
+            .contains(r#"WHERE "tenants"."id" = $6 AND "tenants"."updated_at" = $7"#));
+        assert!(format!("{:?}", update.values).contains(&format!("{:?}", read_at.naive_utc())));
+    }
+
+    #[tokio::test]
+    async fn test_find_by_id_skips_erased_tenants() {
+        let db = Arc::new(
+            MockDatabase::new(DatabaseBackend::Postgres)
+                .append_query_results::<tenant::Model, _, _>(vec![vec![]])
+                .into_connection(),
+        );
+
+        let service = TenantServiceImpl::new(Arc::clone(&db));
+        let error = service
+            .find_by_id(&uuid::Uuid::new_v4().to_string())
+            .await
+            .expect_err("erased tenant is not found");
+        assert!(matches!(*error.kind, ErrorKind::NotFoundError(_)));
+
+        drop(service);
+        let log = Arc::try_unwrap(db)
+            .expect("service dropped")
+            .into_transaction_log();
+        assert!(log[0].statements()[0]
+            .sql
+            .contains(r#""tenants"."deleted_at" IS NULL"#));
+    }
+
+    #[tokio::test]
+    async fn test_reactivate_deactivated_tenant() {
+        let tenant = create_test_tenant();
+        let mut deactivated = stored_model(&tenant);
+        deactivated.is_active = false;
+        deactivated.deleted_at = Some(Utc::now().naive_utc());
+        let db = Arc::new(
+            MockDatabase::new(DatabaseBackend::Postgres)
+                .append_query_results(vec![vec![deactivated]])
+                .append_query_results::<tenant::Model, _, _>(vec![vec![]])
+                .append_query_results(vec![vec![stored_model(&tenant)]])
+                .into_connection(),
+        );
+
+        let service = TenantServiceImpl::new(Arc::clone(&db));
+        let reactivated = service
+            .reactivate(&tenant.id.to_string())
+            .await
+            .expect("tenant reactivated");
+        assert!(reactivated.is_active);
+
+        drop(service);
+        let log = Arc::try_unwrap(db)
+            .expect("sole owner of the mock connection")
+            .into_transaction_log();
+        let statement = &log[2].statements()[0];
+        assert!(statement.sql.starts_with(
+            r#"UPDATE "tenants" SET "is_active" = $1, "updated_at" = $2, "deleted_at" = $3"#
+        ));
+        let values = format!("{:?}", statement.values);
+        assert!(values.contains("Bool(Some(true))"));
+        assert!(values.contains("ChronoDateTime(None)"));
+    }
+
+    #[tokio::test]
+    async fn test_reactivate_rejects_domain_taken_meanwhile() {
+        let tenant = create_test_tenant();
+        let mut deactivated = stored_model(&tenant);
+        deactivated.is_active = false;
+        let mut other = stored_model(&create_test_tenant());
+        other.id = uuid::Uuid::new_v4();
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![deactivated]])
+            .append_query_results(vec![vec![other]])
+            .into_connection();
+
+        let service = TenantServiceImpl::new(Arc::new(db));
+        let error = service
+            .reactivate(&tenant.id.to_string())
+            .await
+            .expect_err("domain is in use");
+
+        assert!(matches!(*error.kind, ErrorKind::ValidationError(_)));
+    }
+
+    #[test]
+    fn test_migrate_v1_settings() {
+        let v1 = serde_json::from_str(
+            r#"{
+                "max_users": 50,
+                "storage_limit": 1048576,
+                "api_rate_limit": 100,
+                "features": {
+                    "advanced_security": false,
+                    "custom_branding": true,
+                    "api_access": true,
+                    "audit_logging": false
+                }
+            }"#,
+        )
+        .expect("valid JSON");
+
+        let upgraded = migrate_settings(uuid::Uuid::new_v4(), v1);
+        assert_eq!(upgraded["settings_version"], TENANT_SETTINGS_VERSION);
+        assert_eq!(upgraded["read_only"], false);
+        assert_eq!(
+            upgraded["allowed_auth_methods"],
+            serde_json::Value::Array(vec![])
+        );
+        assert_eq!(upgraded["rate_limits"]["read"], 100);
+
+        let settings: TenantSettings =
+            serde_json::from_value(upgraded).expect("upgraded settings deserialize");
+        assert_eq!(settings.max_users, 50);
+        assert_eq!(settings.api_rate_limit, 100);
+        assert!(settings.features.custom_branding);
+        assert!(!settings.read_only);
+    }
+
+    #[test]
+    fn test_migrate_current_settings_is_noop() {
+        let current =
+            serde_json::to_value(&create_test_tenant().settings).expect("serializable settings");
+        assert_eq!(
+            migrate_settings(uuid::Uuid::new_v4(), current.clone()),
+            current
+        );
+    }
+
+    #[tokio::test]
+    async fn test_find_by_id_upgrades_v1_settings() {
+        let id = uuid::Uuid::new_v4();
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![tenant::Model {
+                id,
+                name: "Legacy Tenant".to_string(),
+                domain: "legacy.example.com".to_string(),
+                is_active: true,
+                settings: serde_json::from_str(
+                    r#"{
+                        "max_users": 10,
+                        "storage_limit": 1048576,
+                        "api_rate_limit": 60,
+                        "features": {
+                            "advanced_security": false,
+                            "custom_branding": false,
+                            "api_access": true,
+                            "audit_logging": false
+                        }
+                    }"#,
+                )
+                .expect("valid JSON"),
+                created_at: Utc::now().naive_utc(),
+                updated_at: Utc::now().naive_utc(),
+                deleted_at: None,
+            }]])
+            .into_connection();
+
+        let service = TenantServiceImpl::new(Arc::new(db));
+        let tenant = service
+            .find_by_id(&id.to_string())
+            .await
+            .expect("v1 settings are upgraded");
+
+        assert_eq!(tenant.settings.settings_version, TENANT_SETTINGS_VERSION);
+        assert_eq!(tenant.settings.max_users, 10);
+        assert!(tenant.settings.features.api_access);
+    }
+
+    #[tokio::test]
+    async fn test_find_by_id_surfaces_corrupt_settings() {
+        let id = uuid::Uuid::new_v4();
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![tenant::Model {
+                id,
+                name: "Corrupt Tenant".to_string(),
+                domain: "corrupt.example.com".to_string(),
+                is_active: true,
+                settings: serde_json::from_str(r#"{"max_users": "many"}"#).expect("valid JSON"),
+                created_at: Utc::now().naive_utc(),
+                updated_at: Utc::now().naive_utc(),
+                deleted_at: None,
+            }]])
+            .into_connection();
+
+        let service = TenantServiceImpl::new(Arc::new(db));
+        let error = service
+            .find_by_id(&id.to_string())
+            .await
+            .expect_err("corrupt settings are not replaced by defaults");
+
+        assert!(matches!(*error.kind, ErrorKind::SerializationError(_)));
+    }
+
+    #[tokio::test]
+    async fn test_find_stale_tenants() {
+        let tenant = create_test_tenant();
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![tenant::Model {
+                id: tenant.id,
+                name: tenant.name.clone(),
+                domain: tenant.domain.clone(),
+                is_active: tenant.is_active,
+                settings: serde_json::to_value(&tenant.settings).expect("serializable settings"),
+                created_at: Utc::now().naive_utc(),
+                updated_at: Utc::now().naive_utc(),
+                deleted_at: None,
+            }]])
+            .into_connection();
+        let db = Arc::new(db);
+
+        let service = TenantServiceImpl::new(Arc::clone(&db));
+        let cutoff = Utc::now() - chrono::Duration::days(90);
+        let stale = service.find_stale(cutoff).await.expect("stale tenants");
+
+        assert_eq!(stale.len(), 1);
+        assert_eq!(stale[0].id, tenant.id);
+
+        drop(service);
+        let log = Arc::try_unwrap(db)
+            .expect("service dropped")
+            .into_transaction_log();
+        let sql = log[0].statements()[0].sql.clone();
+        assert!(sql.contains(r#"LEFT JOIN "users" ON "tenants"."id" = "users"."tenant_id""#));
+        assert!(sql.contains(r#"WHERE "tenants"."deleted_at" IS NULL GROUP BY "tenants"."id""#));
+        assert!(sql.contains(
+            r#"HAVING MAX("users"."last_login_at") IS NULL OR MAX("users"."last_login_at") < $1"#
+        ));
+    }
 }
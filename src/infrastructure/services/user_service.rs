@@ -0,0 +1,603 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, Order, QueryFilter,
+    QueryOrder, QuerySelect, Set,
+};
+use tracing::{error, info, instrument};
+use uuid::Uuid;
+
+use crate::{
+    common::{
+        error::{AppError, AppResult, ErrorContext},
+        pagination::Pagination,
+    },
+    domain::user::{
+        CreateUserDto, UpdateUserDto, User, UserRole, UserService, UserSettings, UserSettingsPatch,
+        UserSort, UserSortField,
+    },
+    infrastructure::database::{
+        entities::{user, user::Entity as UserEntity},
+        slow_query::SlowQueryMonitor,
+    },
+};
+
+#[derive(Clone)]
+pub struct UserServiceImpl {
+    db: Arc<DatabaseConnection>,
+    default_settings: UserSettings,
+    slow_queries: SlowQueryMonitor,
+}
+
+impl UserServiceImpl {
+    pub fn new(db: Arc<DatabaseConnection>) -> Self {
+        Self {
+            db,
+            default_settings: UserSettings::default(),
+            slow_queries: SlowQueryMonitor::default(),
+        }
+    }
+
+    /// Reports database calls taking `threshold` or longer as slow queries
+    pub fn with_slow_query_threshold(mut self, threshold: Duration) -> Self {
+        self.slow_queries = SlowQueryMonitor::new(threshold);
+        self
+    }
+
+    /// Settings given to users created without any
+    pub fn with_default_settings(mut self, settings: UserSettings) -> Self {
+        self.default_settings = settings;
+        self
+    }
+
+    fn map_to_domain(&self, model: user::Model) -> User {
+        let settings = serde_json::from_value(model.settings).unwrap_or_else(|e| {
+            error!(
+                "Invalid settings for user {}, using defaults: {}",
+                model.id, e
+            );
+            Default::default()
+        });
+
+        User {
+            id: model.id,
+            tenant_id: model.tenant_id,
+            email: model.email,
+            username: model.username,
+            full_name: model.full_name,
+            is_active: model.is_active,
+            role: role_to_domain(model.role),
+            settings,
+            created_at: model.created_at.with_timezone(&Utc),
+            updated_at: model.updated_at.with_timezone(&Utc),
+            last_login_at: model.last_login_at.map(|at| at.with_timezone(&Utc)),
+        }
+    }
+
+    async fn find_model(&self, tenant_id: &Uuid, user_id: &Uuid) -> AppResult<user::Model> {
+        let query = UserEntity::find_by_id(*user_id)
+            .filter(user::Column::TenantId.eq(*tenant_id))
+            .one(&*self.db);
+        self.slow_queries
+            .observe("user.find_by_id", query)
+            .await
+            .map_err(|e| database_error("Failed to find user", e))?
+            .ok_or_else(|| AppError::not_found("User not found"))
+    }
+}
+
+fn database_error(message: &str, error: DbErr) -> AppError {
+    error!("{}: {}", message, error);
+    AppError::database(error.to_string())
+        .with_context(ErrorContext::new().with_message(message.to_string()))
+}
+
+fn role_to_domain(role: user::Role) -> UserRole {
+    match role {
+        user::Role::TenantAdmin => UserRole::TenantAdmin,
+        user::Role::Manager => UserRole::Manager,
+        user::Role::User => UserRole::User,
+        user::Role::ReadOnly => UserRole::ReadOnly,
+    }
+}
+
+fn role_to_db(role: UserRole) -> user::Role {
+    match role {
+        UserRole::TenantAdmin => user::Role::TenantAdmin,
+        UserRole::Manager => user::Role::Manager,
+        UserRole::User => user::Role::User,
+        UserRole::ReadOnly => user::Role::ReadOnly,
+    }
+}
+
+#[async_trait]
+impl UserService for UserServiceImpl {
+    #[instrument(skip(self))]
+    async fn find_by_id(&self, tenant_id: &Uuid, user_id: &Uuid) -> AppResult<User> {
+        let model = self.find_model(tenant_id, user_id).await?;
+        Ok(self.map_to_domain(model))
+    }
+
+    #[instrument(skip(self))]
+    async fn find_by_subject(&self, subject: &str) -> AppResult<User> {
+        // A subject that isn't a UUID can't have been provisioned
+        let id = Uuid::parse_str(subject).map_err(|_| AppError::not_found("User not found"))?;
+
+        let query = UserEntity::find_by_id(id).one(&*self.db);
+        let model = self
+            .slow_queries
+            .observe("user.find_by_subject", query)
+            .await
+            .map_err(|e| database_error("Failed to find user", e))?
+            .ok_or_else(|| AppError::not_found("User not found"))?;
+
+        Ok(self.map_to_domain(model))
+    }
+
+    #[instrument(skip(self))]
+    async fn find_by_email(&self, tenant_id: &Uuid, email: &str) -> AppResult<User> {
+        let query = UserEntity::find()
+            .filter(user::Column::TenantId.eq(*tenant_id))
+            .filter(user::Column::Email.eq(email))
+            .one(&*self.db);
+        let model = self
+            .slow_queries
+            .observe("user.find_by_email", query)
+            .await
+            .map_err(|e| database_error("Failed to find user by email", e))?
+            .ok_or_else(|| AppError::not_found("User not found"))?;
+
+        Ok(self.map_to_domain(model))
+    }
+
+    #[instrument(skip(self))]
+    async fn list_by_tenant(&self, tenant_id: &Uuid) -> AppResult<Vec<User>> {
+        let query = UserEntity::find()
+            .filter(user::Column::TenantId.eq(*tenant_id))
+            .order_by_asc(user::Column::CreatedAt)
+            .all(&*self.db);
+        let models = self
+            .slow_queries
+            .observe("user.list_by_tenant", query)
+            .await
+            .map_err(|e| database_error("Failed to list users", e))?;
+
+        Ok(models.into_iter().map(|m| self.map_to_domain(m)).collect())
+    }
+
+    #[instrument(skip(self))]
+    async fn list(
+        &self,
+        tenant_id: &Uuid,
+        pagination: Pagination,
+        sort: UserSort,
+    ) -> AppResult<Vec<User>> {
+        let column = match sort.field {
+            UserSortField::Username => user::Column::Username,
+            UserSortField::Email => user::Column::Email,
+            UserSortField::FullName => user::Column::FullName,
+            UserSortField::CreatedAt => user::Column::CreatedAt,
+            UserSortField::UpdatedAt => user::Column::UpdatedAt,
+        };
+        let order = if sort.descending {
+            Order::Desc
+        } else {
+            Order::Asc
+        };
+        // The id breaks ties, so pages don't overlap
+        let query = UserEntity::find()
+            .filter(user::Column::TenantId.eq(*tenant_id))
+            .order_by(column, order.clone())
+            .order_by(user::Column::Id, order)
+            .offset(pagination.offset())
+            .limit(pagination.page_size)
+            .all(&*self.db);
+        let models = self
+            .slow_queries
+            .observe("user.list", query)
+            .await
+            .map_err(|e| database_error("Failed to list users", e))?;
+
+        Ok(models.into_iter().map(|m| self.map_to_domain(m)).collect())
+    }
+
+    #[instrument(skip(self, user))]
+    async fn create(&self, tenant_id: &Uuid, user: CreateUserDto) -> AppResult<User> {
+        let now = Utc::now();
+        let mut new_user = User {
+            id: Uuid::new_v4(),
+            tenant_id: *tenant_id,
+            email: user.email,
+            username: user.username,
+            full_name: user.full_name,
+            is_active: true,
+            role: user.role,
+            settings: user
+                .settings
+                .unwrap_or_else(|| self.default_settings.clone()),
+            created_at: now,
+            updated_at: now,
+            last_login_at: None,
+        };
+        new_user.normalize();
+        new_user.validate()?;
+
+        let model = user::ActiveModel {
+            id: Set(new_user.id),
+            tenant_id: Set(new_user.tenant_id),
+            email: Set(new_user.email.clone()),
+            username: Set(new_user.username.clone()),
+            full_name: Set(new_user.full_name.clone()),
+            is_active: Set(new_user.is_active),
+            role: Set(role_to_db(new_user.role.clone())),
+            settings: Set(serde_json::to_value(&new_user.settings)?),
+            created_at: Set(now.into()),
+            updated_at: Set(now.into()),
+            last_login_at: Set(None),
+        };
+
+        let result = self
+            .slow_queries
+            .observe("user.create", model.insert(&*self.db))
+            .await
+            .map_err(|e| database_error("Failed to create user", e))?;
+
+        Ok(self.map_to_domain(result))
+    }
+
+    #[instrument(skip(self, update))]
+    async fn update(
+        &self,
+        tenant_id: &Uuid,
+        user_id: &Uuid,
+        update: UpdateUserDto,
+    ) -> AppResult<User> {
+        let mut existing = self.find_by_id(tenant_id, user_id).await?;
+
+        if let Some(email) = update.email {
+            existing.email = email;
+        }
+        if let Some(username) = update.username {
+            existing.username = username;
+        }
+        if let Some(full_name) = update.full_name {
+            existing.full_name = full_name;
+        }
+        if let Some(role) = update.role {
+            existing.role = role;
+        }
+        if let Some(settings) = update.settings {
+            existing.settings = settings;
+        }
+        existing.normalize();
+        existing.validate()?;
+
+        let model = user::ActiveModel {
+            id: Set(existing.id),
+            email: Set(existing.email),
+            username: Set(existing.username),
+            full_name: Set(existing.full_name),
+            role: Set(role_to_db(existing.role)),
+            settings: Set(serde_json::to_value(&existing.settings)?),
+            updated_at: Set(Utc::now().into()),
+            ..Default::default()
+        };
+
+        let result = self
+            .slow_queries
+            .observe("user.update", model.update(&*self.db))
+            .await
+            .map_err(|e| database_error("Failed to update user", e))?;
+
+        Ok(self.map_to_domain(result))
+    }
+
+    #[instrument(skip(self, patch))]
+    async fn update_settings(
+        &self,
+        tenant_id: &Uuid,
+        user_id: &Uuid,
+        patch: UserSettingsPatch,
+    ) -> AppResult<User> {
+        let mut existing = self.find_by_id(tenant_id, user_id).await?;
+        existing.settings.merge(patch);
+        existing.validate()?;
+
+        let model = user::ActiveModel {
+            id: Set(existing.id),
+            settings: Set(serde_json::to_value(&existing.settings)?),
+            updated_at: Set(Utc::now().into()),
+            ..Default::default()
+        };
+
+        let result = self
+            .slow_queries
+            .observe("user.update_settings", model.update(&*self.db))
+            .await
+            .map_err(|e| database_error("Failed to update user settings", e))?;
+
+        Ok(self.map_to_domain(result))
+    }
+
+    #[instrument(skip(self))]
+    async fn deactivate(&self, tenant_id: &Uuid, user_id: &Uuid) -> AppResult<()> {
+        let model = self.find_model(tenant_id, user_id).await?;
+
+        let mut model: user::ActiveModel = model.into();
+        model.is_active = Set(false);
+        model.updated_at = Set(Utc::now().into());
+        self.slow_queries
+            .observe("user.deactivate", model.update(&*self.db))
+            .await
+            .map_err(|e| database_error("Failed to deactivate user", e))?;
+
+        info!("Deactivated user with ID: {}", user_id);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        common::{config::DefaultUserSettings, error::ErrorKind},
+        domain::user::{UiPreferences, UiPreferencesPatch},
+    };
+    use sea_orm::{ActiveEnum, DatabaseBackend, Iterable, MockDatabase};
+
+    fn user_model(id: Uuid, tenant_id: Uuid, settings: &UserSettings) -> user::Model {
+        user::Model {
+            id,
+            tenant_id,
+            email: "jane@example.com".to_string(),
+            username: "jane".to_string(),
+            full_name: "Jane Doe".to_string(),
+            is_active: true,
+            role: user::Role::Manager,
+            settings: serde_json::to_value(settings).expect("serializable settings"),
+            created_at: Utc::now().into(),
+            updated_at: Utc::now().into(),
+            last_login_at: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_find_by_subject() {
+        let id = Uuid::new_v4();
+        let tenant_id = Uuid::new_v4();
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![user_model(
+                id,
+                tenant_id,
+                &UserSettings::default(),
+            )]])
+            .into_connection();
+
+        let service = UserServiceImpl::new(Arc::new(db));
+        let user = service
+            .find_by_subject(&id.to_string())
+            .await
+            .expect("user found");
+
+        assert_eq!(user.id, id);
+        assert_eq!(user.tenant_id, tenant_id);
+        assert_eq!(user.role, UserRole::Manager);
+    }
+
+    #[tokio::test]
+    async fn test_slow_queries_are_counted_by_operation() {
+        let recorder = metrics_exporter_prometheus::PrometheusBuilder::new().build_recorder();
+        let metrics = recorder.handle();
+        let _guard = metrics::set_default_local_recorder(&recorder);
+        let id = Uuid::new_v4();
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![user_model(
+                id,
+                Uuid::new_v4(),
+                &UserSettings::default(),
+            )]])
+            .into_connection();
+
+        // Every call reaches a zero threshold
+        let service = UserServiceImpl::new(Arc::new(db)).with_slow_query_threshold(Duration::ZERO);
+        service
+            .find_by_subject(&id.to_string())
+            .await
+            .expect("user found");
+
+        assert!(metrics
+            .render()
+            .contains(r#"db_slow_queries_total{operation="user.find_by_subject"} 1"#));
+    }
+
+    #[test]
+    fn test_roles_match_the_database_enum_values() {
+        for db_role in user::Role::iter() {
+            let role = role_to_domain(db_role.clone());
+            assert_eq!(
+                serde_json::to_value(&role).expect("serializable role"),
+                serde_json::Value::from(db_role.to_value())
+            );
+            assert_eq!(role_to_db(role), db_role);
+        }
+
+        let role: UserRole = serde_json::from_str(r#""tenant_admin""#).expect("API role");
+        let db_role = role_to_db(role);
+        assert_eq!(db_role, user::Role::TenantAdmin);
+        assert_eq!(
+            user::Role::try_from_value(&db_role.to_value()).expect("database value"),
+            user::Role::TenantAdmin
+        );
+    }
+
+    #[tokio::test]
+    async fn test_find_by_subject_not_a_uuid() {
+        // No query is expected for a subject that can't be a user id
+        let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+
+        let service = UserServiceImpl::new(Arc::new(db));
+        let error = service
+            .find_by_subject("service-account-backend")
+            .await
+            .expect_err("no user for a non-UUID subject");
+
+        assert!(matches!(*error.kind, ErrorKind::NotFoundError(_)));
+    }
+
+    fn stored_settings() -> UserSettings {
+        UserSettings {
+            language: "en".to_string(),
+            timezone: "UTC".to_string(),
+            ui_preferences: UiPreferences {
+                theme: "light".to_string(),
+                sidebar_collapsed: true,
+                items_per_page: 20,
+            },
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_update_settings_persists_merged_settings() {
+        let id = Uuid::new_v4();
+        let tenant_id = Uuid::new_v4();
+        let mut merged = stored_settings();
+        merged.ui_preferences.theme = "dark".to_string();
+        let db = Arc::new(
+            MockDatabase::new(DatabaseBackend::Postgres)
+                .append_query_results(vec![vec![user_model(id, tenant_id, &stored_settings())]])
+                .append_query_results(vec![vec![user_model(id, tenant_id, &merged)]])
+                .into_connection(),
+        );
+
+        let service = UserServiceImpl::new(Arc::clone(&db));
+        let patch = UserSettingsPatch {
+            ui_preferences: Some(UiPreferencesPatch {
+                theme: Some("dark".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let user = service
+            .update_settings(&tenant_id, &id, patch)
+            .await
+            .expect("settings updated");
+        assert_eq!(user.settings.ui_preferences.theme, "dark");
+
+        drop(service);
+        let log = Arc::try_unwrap(db)
+            .expect("service dropped")
+            .into_transaction_log();
+        let update = &log[1].statements()[0];
+        assert!(update
+            .sql
+            .starts_with(r#"UPDATE "users" SET "settings" = $1"#));
+        let persisted = format!("{:?}", update.values);
+        assert!(persisted.contains(r#""theme": String("dark")"#));
+        assert!(persisted.contains(r#""sidebar_collapsed": Bool(true)"#));
+        assert!(persisted.contains(r#""language": String("en")"#));
+    }
+
+    #[tokio::test]
+    async fn test_update_settings_rejects_invalid_result() {
+        let id = Uuid::new_v4();
+        let tenant_id = Uuid::new_v4();
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![user_model(id, tenant_id, &stored_settings())]])
+            .into_connection();
+
+        let service = UserServiceImpl::new(Arc::new(db));
+        let patch = UserSettingsPatch {
+            ui_preferences: Some(UiPreferencesPatch {
+                items_per_page: Some(0),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let error = service
+            .update_settings(&tenant_id, &id, patch)
+            .await
+            .expect_err("items_per_page out of range");
+
+        assert!(matches!(*error.kind, ErrorKind::ValidationError(_)));
+    }
+
+    #[tokio::test]
+    async fn test_create_without_settings_applies_configured_defaults() {
+        let tenant_id = Uuid::new_v4();
+        let defaults =
+            UserSettings::try_from(&DefaultUserSettings::default()).expect("defaults are valid");
+        let db = Arc::new(
+            MockDatabase::new(DatabaseBackend::Postgres)
+                .append_query_results(vec![vec![user_model(Uuid::new_v4(), tenant_id, &defaults)]])
+                .into_connection(),
+        );
+
+        let service = UserServiceImpl::new(Arc::clone(&db)).with_default_settings(defaults);
+        let user = service
+            .create(
+                &tenant_id,
+                CreateUserDto {
+                    email: "jane@example.com".to_string(),
+                    username: "jane".to_string(),
+                    full_name: "Jane Doe".to_string(),
+                    role: UserRole::User,
+                    settings: None,
+                },
+            )
+            .await
+            .expect("defaults pass validation");
+
+        assert_eq!(user.settings.timezone, "UTC");
+        assert_eq!(user.settings.ui_preferences.items_per_page, 25);
+
+        drop(service);
+        let log = Arc::try_unwrap(db)
+            .expect("sole owner of the mock connection")
+            .into_transaction_log();
+        let inserted = format!("{:?}", log[0]);
+        assert!(inserted.contains(r#""timezone": String("UTC")"#));
+        assert!(inserted.contains(r#""language": String("en")"#));
+    }
+
+    #[tokio::test]
+    async fn test_list_orders_by_the_sort_field_then_id() {
+        let tenant_id = Uuid::new_v4();
+        let db = Arc::new(
+            MockDatabase::new(DatabaseBackend::Postgres)
+                .append_query_results(vec![vec![user_model(
+                    Uuid::new_v4(),
+                    tenant_id,
+                    &UserSettings::default(),
+                )]])
+                .into_connection(),
+        );
+
+        let service = UserServiceImpl::new(Arc::clone(&db));
+        let users = service
+            .list(
+                &tenant_id,
+                Pagination {
+                    page: 2,
+                    page_size: 10,
+                },
+                UserSort::parse("-email").expect("sortable"),
+            )
+            .await
+            .expect("users listed");
+        assert_eq!(users.len(), 1);
+
+        drop(service);
+        let log = Arc::try_unwrap(db)
+            .expect("service dropped")
+            .into_transaction_log();
+        let select = &log[0].statements()[0];
+        assert!(select.sql.contains(r#"WHERE "users"."tenant_id" = $1"#));
+        assert!(select
+            .sql
+            .contains(r#"ORDER BY "users"."email" DESC, "users"."id" DESC LIMIT $2 OFFSET $3"#));
+    }
+}
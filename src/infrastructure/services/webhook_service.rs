@@ -0,0 +1,139 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, Set,
+};
+use tracing::{error, info, instrument};
+use uuid::Uuid;
+
+use crate::{
+    common::error::{AppError, AppResult, ErrorContext},
+    domain::webhook::{Webhook, WebhookService},
+    infrastructure::database::entities::{webhook, webhook::Entity as WebhookEntity},
+};
+
+#[derive(Clone)]
+pub struct WebhookServiceImpl {
+    db: Arc<DatabaseConnection>,
+}
+
+impl WebhookServiceImpl {
+    pub fn new(db: Arc<DatabaseConnection>) -> Self {
+        Self { db }
+    }
+}
+
+fn database_error(message: &str, error: DbErr) -> AppError {
+    error!("{}: {}", message, error);
+    AppError::database(error.to_string())
+        .with_context(ErrorContext::new().with_message(message.to_string()))
+}
+
+fn map_to_domain(model: webhook::Model) -> AppResult<Webhook> {
+    let event_types = serde_json::from_value(model.event_types).map_err(|e| {
+        error!("Invalid event types for webhook {}: {}", model.id, e);
+        AppError::serialization(format!("Invalid event types for webhook {}", model.id))
+    })?;
+
+    Ok(Webhook {
+        id: model.id,
+        tenant_id: model.tenant_id,
+        url: model.url,
+        event_types,
+        secret: model.secret,
+        created_at: model.created_at.with_timezone(&Utc),
+    })
+}
+
+/// 256 bits of randomness from two v4 UUIDs
+fn generate_secret() -> String {
+    format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
+#[async_trait]
+impl WebhookService for WebhookServiceImpl {
+    #[instrument(skip(self, event_types))]
+    async fn create(
+        &self,
+        tenant_id: &Uuid,
+        url: String,
+        event_types: Vec<String>,
+    ) -> AppResult<Webhook> {
+        let model = webhook::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            tenant_id: Set(*tenant_id),
+            url: Set(url),
+            event_types: Set(serde_json::to_value(&event_types)?),
+            secret: Set(generate_secret()),
+            created_at: Set(Utc::now().into()),
+        };
+
+        let result = model
+            .insert(&*self.db)
+            .await
+            .map_err(|e| database_error("Failed to create webhook", e))?;
+
+        info!("Created webhook {} for tenant {}", result.id, tenant_id);
+        map_to_domain(result)
+    }
+
+    #[instrument(skip(self))]
+    async fn list_for_tenant(&self, tenant_id: &Uuid) -> AppResult<Vec<Webhook>> {
+        WebhookEntity::find()
+            .filter(webhook::Column::TenantId.eq(*tenant_id))
+            .all(&*self.db)
+            .await
+            .map_err(|e| database_error("Failed to list webhooks", e))?
+            .into_iter()
+            .map(map_to_domain)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::error::ErrorKind;
+    use sea_orm::{DatabaseBackend, MockDatabase};
+
+    fn stored_webhook(event_types: serde_json::Value) -> webhook::Model {
+        webhook::Model {
+            id: Uuid::new_v4(),
+            tenant_id: Uuid::new_v4(),
+            url: "https://example.com/hooks".to_string(),
+            event_types,
+            secret: "secret".to_string(),
+            created_at: Utc::now().into(),
+        }
+    }
+
+    async fn list(model: webhook::Model) -> AppResult<Vec<Webhook>> {
+        let tenant_id = model.tenant_id;
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![model]])
+            .into_connection();
+        WebhookServiceImpl::new(Arc::new(db))
+            .list_for_tenant(&tenant_id)
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_lists_webhooks_with_their_event_types() {
+        let model =
+            stored_webhook(serde_json::from_str(r#"["TenantUpdated"]"#).expect("event types"));
+
+        let webhooks = list(model).await.expect("webhooks");
+        assert_eq!(webhooks.len(), 1);
+        assert_eq!(webhooks[0].event_types, vec!["TenantUpdated"]);
+    }
+
+    #[tokio::test]
+    async fn test_malformed_event_types_are_reported() {
+        let error = list(stored_webhook(serde_json::Value::Null))
+            .await
+            .expect_err("malformed event types");
+        assert!(matches!(*error.kind, ErrorKind::SerializationError(_)));
+    }
+}
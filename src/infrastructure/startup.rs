@@ -0,0 +1,94 @@
+use std::future::Future;
+use std::time::Duration;
+
+use tracing::{info, warn};
+
+use crate::common::config::HealthSettings;
+
+/// Runs `connect` until it succeeds or the configured attempts run out.
+///
+/// Used for optional dependencies at startup: a dependency that stays
+/// unreachable is logged and yields `None` instead of aborting startup, and
+/// readiness reports the service as degraded while it is missing.
+pub async fn connect_optional<T, F, Fut>(
+    name: &str,
+    settings: &HealthSettings,
+    mut connect: F,
+) -> Option<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = anyhow::Result<T>>,
+{
+    let attempts = settings.startup_check_attempts.max(1);
+    let delay = Duration::from_millis(settings.startup_retry_delay_ms);
+
+    for attempt in 1..=attempts {
+        match connect().await {
+            Ok(value) => {
+                info!("{} reachable after {} attempt(s)", name, attempt);
+                return Some(value);
+            },
+            Err(e) if attempt < attempts => {
+                warn!(
+                    "{} unreachable (attempt {}/{}): {}",
+                    name, attempt, attempts, e
+                );
+                tokio::time::sleep(delay).await;
+            },
+            Err(e) => {
+                warn!(
+                    "{} unreachable after {} attempts, starting degraded: {}",
+                    name, attempts, e
+                );
+            },
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn settings() -> HealthSettings {
+        HealthSettings {
+            startup_check_attempts: 3,
+            startup_retry_delay_ms: 1,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failing_dependency_is_retried_then_skipped() {
+        let calls = AtomicU32::new(0);
+
+        let result: Option<()> = connect_optional("RabbitMQ", &settings(), || {
+            calls.fetch_add(1, Ordering::SeqCst);
+            async { Err(anyhow::anyhow!("connection refused")) }
+        })
+        .await;
+
+        assert!(result.is_none());
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_dependency_recovering_within_attempts_is_used() {
+        let calls = AtomicU32::new(0);
+
+        let result = connect_optional("Redis", &settings(), || {
+            let attempt = calls.fetch_add(1, Ordering::SeqCst);
+            async move {
+                if attempt == 0 {
+                    anyhow::bail!("connection refused");
+                }
+                Ok(attempt)
+            }
+        })
+        .await;
+
+        assert_eq!(result, Some(1));
+    }
+}
@@ -2,38 +2,120 @@ use std::sync::Arc;
 
 use metrics_exporter_prometheus::PrometheusHandle;
 
+use crate::api::health::HealthCache;
+use crate::common::config::AppConfig;
 use crate::common::i18n::I18nManager;
+use crate::common::middleware::maintenance::MaintenanceMode;
+use crate::common::middleware::rate_limit::TenantRateLimiter;
+use crate::domain::audit::AuditLogService;
 use crate::domain::tenant::TenantService;
+use crate::domain::user::UserService;
+use crate::domain::webhook::WebhookService;
+use crate::infrastructure::cache::CacheConnection;
 use crate::infrastructure::event_store::EventStoreClient;
+use crate::infrastructure::live_events::LiveEvents;
 use crate::infrastructure::message_broker::MessageBroker;
 use crate::infrastructure::redis::RedisClient;
+use crate::infrastructure::subscriptions::SubscriptionRegistry;
+use crate::infrastructure::system_monitor::SystemMonitor;
 
 #[derive(Clone)]
 pub struct AppState {
+    pub config: Arc<AppConfig>,
     pub tenant_service: Arc<dyn TenantService>,
+    pub user_service: Arc<dyn UserService>,
+    pub audit_log: Arc<dyn AuditLogService>,
+    pub webhooks: Arc<dyn WebhookService>,
     pub i18n: Arc<I18nManager>,
     pub metrics_handle: PrometheusHandle,
     pub redis: Option<Arc<RedisClient>>,
     pub event_store: Option<Arc<EventStoreClient>>,
     pub message_broker: Option<Arc<MessageBroker>>,
+    pub system: SystemMonitor,
+    pub subscriptions: SubscriptionRegistry,
+    pub health_cache: HealthCache,
+    pub live_events: LiveEvents,
+    /// Request windows of every tenant, kept in the swept in-memory cache
+    /// and shared by the rate limit middleware and the usage report
+    pub rate_limiter: TenantRateLimiter,
+    pub maintenance: MaintenanceMode,
+    /// Pooled client for outbound calls such as the Keycloak health probe
+    pub http_client: reqwest::Client,
 }
 
 impl AppState {
+    // One argument per shared dependency, all built in `main`
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
+        config: Arc<AppConfig>,
         tenant_service: Arc<dyn TenantService>,
+        user_service: Arc<dyn UserService>,
+        audit_log: Arc<dyn AuditLogService>,
+        webhooks: Arc<dyn WebhookService>,
         i18n: Arc<I18nManager>,
         metrics_handle: PrometheusHandle,
-        redis: Arc<RedisClient>,
-        event_store: Arc<EventStoreClient>,
-        message_broker: Arc<MessageBroker>,
+        redis: Option<Arc<RedisClient>>,
+        event_store: Option<Arc<EventStoreClient>>,
+        message_broker: Option<Arc<MessageBroker>>,
+        system: SystemMonitor,
+        http_client: reqwest::Client,
+        cache: CacheConnection,
     ) -> Self {
+        let health_cache = HealthCache::new(config.health.cache_ttl());
+        let live_events = LiveEvents::new(&config.live_events);
+        let maintenance = MaintenanceMode::new(&config.maintenance);
         Self {
+            config,
             tenant_service,
+            user_service,
+            audit_log,
+            webhooks,
             i18n,
             metrics_handle,
-            redis: Some(redis),
-            event_store: Some(event_store),
-            message_broker: Some(message_broker),
+            redis,
+            event_store,
+            message_broker,
+            system,
+            subscriptions: SubscriptionRegistry::default(),
+            health_cache,
+            live_events,
+            rate_limiter: TenantRateLimiter::new(cache),
+            maintenance,
+            http_client,
         }
     }
 }
+
+#[cfg(test)]
+impl AppState {
+    /// State for handler tests: default config, no Redis, EventStore or
+    /// RabbitMQ, user, audit log and webhook services on empty mock
+    /// databases and a metrics handle that isn't installed as the global
+    /// recorder
+    pub fn for_test(tenant_service: Arc<dyn TenantService>, i18n: Arc<I18nManager>) -> Self {
+        use crate::infrastructure::services::{
+            audit_log_service::AuditLogServiceImpl, user_service::UserServiceImpl,
+            webhook_service::WebhookServiceImpl,
+        };
+        use sea_orm::{DatabaseBackend, MockDatabase};
+
+        let db = || Arc::new(MockDatabase::new(DatabaseBackend::Postgres).into_connection());
+        Self::new(
+            Arc::new(AppConfig::default()),
+            tenant_service,
+            Arc::new(UserServiceImpl::new(db())),
+            Arc::new(AuditLogServiceImpl::new(db())),
+            Arc::new(WebhookServiceImpl::new(db())),
+            i18n,
+            metrics_exporter_prometheus::PrometheusBuilder::new()
+                .build_recorder()
+                .handle(),
+            None,
+            None,
+            None,
+            SystemMonitor::new(),
+            reqwest::Client::new(),
+            CacheConnection::default(),
+        )
+    }
+}
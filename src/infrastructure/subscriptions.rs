@@ -0,0 +1,547 @@
+use std::collections::{BTreeSet, HashMap};
+use std::future::Future;
+use std::pin::pin;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use event_store::{RecordedEvent, StreamName};
+use futures::stream::FuturesUnordered;
+use futures::StreamExt;
+use metrics::gauge;
+use sea_orm::sea_query::OnConflict;
+use sea_orm::{DatabaseConnection, EntityTrait, Set};
+use serde::Serialize;
+use tokio::task::JoinHandle;
+use tracing::{error, warn};
+use uuid::Uuid;
+
+use crate::common::error::{AppError, AppResult};
+use crate::infrastructure::database::entities::subscription_checkpoint;
+use crate::infrastructure::event_store::EventStoreClient;
+
+/// Events between a subscription's checkpoint and the head of its stream,
+/// labelled by subscription name. Names come from the code registering the
+/// subscriptions, which keeps the label bounded.
+const SUBSCRIPTION_LAG_GAUGE: &str = "eventstore_subscription_lag";
+/// Wait before following `$all` again after the subscription failed
+const RESUBSCRIBE_DELAY: Duration = Duration::from_secs(5);
+
+/// Progress of a running EventStore subscription as reported by its task
+#[derive(Debug, Clone, Default)]
+struct SubscriptionProgress {
+    position: u64,
+    head_position: u64,
+    last_error: Option<String>,
+    /// Whether the task currently holds a working EventStore subscription
+    connected: bool,
+}
+
+impl SubscriptionProgress {
+    fn lag(&self) -> u64 {
+        self.head_position.saturating_sub(self.position)
+    }
+}
+
+/// Point-in-time view of a subscription, returned by `/admin/subscriptions`
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct SubscriptionStatus {
+    pub name: String,
+    pub position: u64,
+    pub lag: u64,
+    pub last_error: Option<String>,
+    pub connected: bool,
+}
+
+/// Registry of the subscriptions currently running in this process.
+///
+/// Each subscription task registers itself and reports its checkpoint through
+/// the returned `SubscriptionHandle`; dropping the handle when the task ends
+/// removes the entry, so the registry only lists active subscriptions.
+#[derive(Clone, Default)]
+pub struct SubscriptionRegistry {
+    inner: Arc<RwLock<HashMap<String, SubscriptionProgress>>>,
+}
+
+impl SubscriptionRegistry {
+    #[allow(dead_code)]
+    pub fn register(&self, name: impl Into<String>) -> SubscriptionHandle {
+        let name = name.into();
+        self.inner
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(name.clone(), SubscriptionProgress::default());
+
+        SubscriptionHandle {
+            name,
+            registry: self.clone(),
+        }
+    }
+
+    /// Active subscriptions ordered by name
+    pub fn snapshot(&self) -> Vec<SubscriptionStatus> {
+        let inner = self.inner.read().unwrap_or_else(|e| e.into_inner());
+        let mut statuses: Vec<_> = inner
+            .iter()
+            .map(|(name, progress)| SubscriptionStatus {
+                name: name.clone(),
+                position: progress.position,
+                lag: progress.lag(),
+                last_error: progress.last_error.clone(),
+                connected: progress.connected,
+            })
+            .collect();
+        statuses.sort_by(|a, b| a.name.cmp(&b.name));
+        statuses
+    }
+
+    /// Applies `f` to the subscription's progress and publishes the
+    /// resulting lag
+    fn update(&self, name: &str, f: impl FnOnce(&mut SubscriptionProgress)) {
+        let mut inner = self.inner.write().unwrap_or_else(|e| e.into_inner());
+        if let Some(progress) = inner.get_mut(name) {
+            f(progress);
+            gauge!(SUBSCRIPTION_LAG_GAUGE, "subscription" => name.to_string())
+                .set(progress.lag() as f64);
+        }
+    }
+}
+
+/// Reporting handle owned by a subscription task
+#[allow(dead_code)]
+pub struct SubscriptionHandle {
+    name: String,
+    registry: SubscriptionRegistry,
+}
+
+#[allow(dead_code)]
+impl SubscriptionHandle {
+    /// Records the position of the last processed event and clears the last
+    /// error. Processing an event also shows the subscription is connected.
+    pub fn checkpoint(&self, position: u64) {
+        self.registry.update(&self.name, |progress| {
+            progress.position = position;
+            progress.head_position = progress.head_position.max(position);
+            progress.last_error = None;
+            progress.connected = true;
+        });
+    }
+
+    /// Marks the subscription as established. A newly registered
+    /// subscription counts as disconnected until then.
+    pub fn connected(&self) {
+        self.registry.update(&self.name, |progress| {
+            progress.connected = true;
+        });
+    }
+
+    /// Records that the subscription lost EventStore and why
+    pub fn disconnected(&self, message: impl Into<String>) {
+        let message = message.into();
+        self.registry.update(&self.name, |progress| {
+            progress.connected = false;
+            progress.last_error = Some(message);
+        });
+    }
+
+    /// Records the position of the newest event in the subscribed stream
+    pub fn head(&self, position: u64) {
+        self.registry.update(&self.name, |progress| {
+            progress.head_position = position;
+        });
+    }
+
+    pub fn error(&self, message: impl Into<String>) {
+        let message = message.into();
+        self.registry.update(&self.name, |progress| {
+            progress.last_error = Some(message);
+        });
+    }
+}
+
+impl Drop for SubscriptionHandle {
+    fn drop(&mut self) {
+        // A stopped subscription is no longer behind anything
+        gauge!(SUBSCRIPTION_LAG_GAUGE, "subscription" => self.name.clone()).set(0.0);
+        self.registry
+            .inner
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&self.name);
+    }
+}
+
+/// Follows `$all` from its current end as subscription `name`, handing every
+/// new event to `on_event` and subscribing again from the last position after
+/// a failure
+pub fn follow_all(
+    client: Arc<EventStoreClient>,
+    registry: SubscriptionRegistry,
+    name: &'static str,
+    mut on_event: impl FnMut(RecordedEvent) + Send + 'static,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let handle = registry.register(name);
+        let mut position = loop {
+            match client.head_position(StreamName::all_stream()).await {
+                Ok(position) => break position,
+                Err(e) => {
+                    handle.disconnected(e.to_string());
+                    tokio::time::sleep(RESUBSCRIBE_DELAY).await;
+                },
+            }
+        };
+
+        loop {
+            handle.checkpoint(position);
+            let mut events = pin!(client.subscribe(StreamName::all_stream(), position));
+            while let Some(event) = events.next().await {
+                match event {
+                    Ok(event) => {
+                        position += 1;
+                        handle.checkpoint(position);
+                        on_event(event);
+                    },
+                    Err(e) => {
+                        warn!("Subscription {} to $all failed: {}", name, e);
+                        handle.disconnected(e.to_string());
+                    },
+                }
+            }
+            tokio::time::sleep(RESUBSCRIBE_DELAY).await;
+        }
+    })
+}
+
+/// Persisted positions of subscriptions, so they resume where they stopped
+/// after a restart instead of at the end of the stream
+#[async_trait]
+pub trait CheckpointStore: Send + Sync + 'static {
+    /// Position of the first event the subscription hasn't handled, if it
+    /// ran before
+    async fn load(&self, name: &str) -> AppResult<Option<u64>>;
+    async fn save(&self, name: &str, position: u64) -> AppResult<()>;
+}
+
+/// Checkpoints in the `subscription_checkpoints` table
+pub struct DbCheckpointStore {
+    db: Arc<DatabaseConnection>,
+}
+
+impl DbCheckpointStore {
+    pub fn new(db: Arc<DatabaseConnection>) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl CheckpointStore for DbCheckpointStore {
+    async fn load(&self, name: &str) -> AppResult<Option<u64>> {
+        let checkpoint = subscription_checkpoint::Entity::find_by_id(name.to_string())
+            .one(&*self.db)
+            .await
+            .map_err(|e| AppError::database(e.to_string()))?;
+        Ok(checkpoint.map(|checkpoint| checkpoint.position.max(0) as u64))
+    }
+
+    async fn save(&self, name: &str, position: u64) -> AppResult<()> {
+        let checkpoint = subscription_checkpoint::ActiveModel {
+            name: Set(name.to_string()),
+            position: Set(i64::try_from(position).unwrap_or(i64::MAX)),
+            updated_at: Set(Utc::now().into()),
+        };
+        subscription_checkpoint::Entity::insert(checkpoint)
+            .on_conflict(
+                OnConflict::column(subscription_checkpoint::Column::Name)
+                    .update_columns([
+                        subscription_checkpoint::Column::Position,
+                        subscription_checkpoint::Column::UpdatedAt,
+                    ])
+                    .to_owned(),
+            )
+            .exec(&*self.db)
+            .await
+            .map_err(|e| AppError::database(e.to_string()))?;
+        Ok(())
+    }
+}
+
+/// Positions of the events being handled. Handling completes out of order,
+/// so the checkpoint is the oldest event still in flight: every event
+/// before it has been handled.
+#[derive(Debug)]
+struct InFlight {
+    pending: BTreeSet<u64>,
+    /// Position of the next event of the subscription
+    next: u64,
+}
+
+impl InFlight {
+    fn new(position: u64) -> Self {
+        Self {
+            pending: BTreeSet::new(),
+            next: position,
+        }
+    }
+
+    /// Takes the position of the next event
+    fn start(&mut self) -> u64 {
+        let position = self.next;
+        self.pending.insert(position);
+        self.next += 1;
+        position
+    }
+
+    fn finish(&mut self, position: u64) {
+        self.pending.remove(&position);
+    }
+
+    fn checkpoint(&self) -> u64 {
+        self.pending.first().copied().unwrap_or(self.next)
+    }
+}
+
+/// Like `follow_all`, but starting from the checkpoint saved in
+/// `checkpoints`, or the current end of `$all` on the first run. Up to
+/// `concurrency` events are handled at once; once that many are in flight
+/// the subscription waits for one to finish. The checkpoint only moves past
+/// an event once it has been handled, so events in flight during a restart
+/// are handled again.
+pub fn follow_all_from_checkpoint<F, Fut>(
+    client: Arc<EventStoreClient>,
+    registry: SubscriptionRegistry,
+    checkpoints: Arc<dyn CheckpointStore>,
+    name: &'static str,
+    concurrency: usize,
+    mut on_event: F,
+) -> JoinHandle<()>
+where
+    F: FnMut(RecordedEvent) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let concurrency = concurrency.max(1);
+    tokio::spawn(async move {
+        let handle = registry.register(name);
+        let (start, mut saved) = loop {
+            let start = match checkpoints.load(name).await {
+                Ok(Some(position)) => Ok((position, Some(position))),
+                Ok(None) => client
+                    .head_position(StreamName::all_stream())
+                    .await
+                    .map(|position| (position, None))
+                    .map_err(|e| e.to_string()),
+                Err(e) => Err(e.to_string()),
+            };
+            match start {
+                Ok(start) => break start,
+                Err(e) => {
+                    handle.disconnected(e);
+                    tokio::time::sleep(RESUBSCRIBE_DELAY).await;
+                },
+            }
+        };
+
+        let mut in_flight = InFlight::new(start);
+        let mut handling = FuturesUnordered::new();
+        loop {
+            save_checkpoint(&*checkpoints, name, in_flight.checkpoint(), &mut saved).await;
+            handle.checkpoint(in_flight.checkpoint());
+            let mut events = pin!(client.subscribe(StreamName::all_stream(), in_flight.next));
+            loop {
+                tokio::select! {
+                    Some(position) = handling.next(), if !handling.is_empty() => {
+                        in_flight.finish(position);
+                        handle.checkpoint(in_flight.checkpoint());
+                        save_checkpoint(&*checkpoints, name, in_flight.checkpoint(), &mut saved)
+                            .await;
+                    },
+                    event = events.next(), if handling.len() < concurrency => match event {
+                        Some(Ok(event)) => {
+                            let position = in_flight.start();
+                            let handled = on_event(event);
+                            handling.push(async move {
+                                handled.await;
+                                position
+                            });
+                        },
+                        Some(Err(e)) => {
+                            warn!("Subscription {} to $all failed: {}", name, e);
+                            handle.disconnected(e.to_string());
+                            break;
+                        },
+                        None => break,
+                    },
+                }
+            }
+
+            // Finish what is in flight so the checkpoint covers it before
+            // subscribing again
+            while let Some(position) = handling.next().await {
+                in_flight.finish(position);
+            }
+            tokio::time::sleep(RESUBSCRIBE_DELAY).await;
+        }
+    })
+}
+
+/// Saves the checkpoint unless it is the one saved last. A failed save is
+/// only logged; the next handled event tries again.
+async fn save_checkpoint(
+    checkpoints: &dyn CheckpointStore,
+    name: &str,
+    position: u64,
+    saved: &mut Option<u64>,
+) {
+    if *saved == Some(position) {
+        return;
+    }
+    match checkpoints.save(name, position).await {
+        Ok(()) => *saved = Some(position),
+        Err(e) => error!("Failed to save checkpoint of subscription {}: {}", name, e),
+    }
+}
+
+/// Tenant an event belongs to, from the `tenant_id` in its metadata
+pub fn event_tenant_id(event: &RecordedEvent) -> Option<Uuid> {
+    event
+        .metadata
+        .get("tenant_id")
+        .and_then(|tenant_id| tenant_id.as_str())
+        .and_then(|tenant_id| Uuid::parse_str(tenant_id).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult};
+    use tokio::sync::oneshot;
+
+    #[test]
+    fn test_checkpoint_waits_for_the_oldest_event_in_flight() {
+        let mut in_flight = InFlight::new(10);
+        assert_eq!(in_flight.checkpoint(), 10);
+
+        let first = in_flight.start();
+        let second = in_flight.start();
+        let third = in_flight.start();
+        assert_eq!((first, second, third), (10, 11, 12));
+
+        in_flight.finish(second);
+        in_flight.finish(third);
+        assert_eq!(in_flight.checkpoint(), 10);
+
+        in_flight.finish(first);
+        assert_eq!(in_flight.checkpoint(), 13);
+    }
+
+    #[tokio::test]
+    async fn test_checkpoints_are_loaded_and_saved() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![
+                vec![subscription_checkpoint::Model {
+                    name: "webhooks".to_string(),
+                    position: 42,
+                    updated_at: Utc::now().into(),
+                }],
+                vec![],
+            ])
+            .append_exec_results(vec![MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 1,
+            }])
+            .into_connection();
+        let checkpoints = DbCheckpointStore::new(Arc::new(db));
+
+        assert_eq!(checkpoints.load("webhooks").await.expect("load"), Some(42));
+        assert_eq!(checkpoints.load("other").await.expect("load"), None);
+        checkpoints.save("webhooks", 43).await.expect("save");
+    }
+
+    #[tokio::test]
+    async fn test_running_subscription_is_listed_with_position() {
+        let registry = SubscriptionRegistry::default();
+        let (checkpointed_tx, checkpointed_rx) = oneshot::channel();
+        let (stop_tx, stop_rx) = oneshot::channel::<()>();
+
+        let task = tokio::spawn({
+            let registry = registry.clone();
+            async move {
+                let handle = registry.register("tenant-projection");
+                handle.head(42);
+                handle.error("connection reset");
+                handle.checkpoint(40);
+                let _ = checkpointed_tx.send(());
+                let _ = stop_rx.await;
+            }
+        });
+
+        checkpointed_rx
+            .await
+            .expect("subscription task ended early");
+        assert_eq!(
+            registry.snapshot(),
+            vec![SubscriptionStatus {
+                name: "tenant-projection".to_string(),
+                position: 40,
+                lag: 2,
+                last_error: None,
+                connected: true,
+            }]
+        );
+
+        let _ = stop_tx.send(());
+        task.await.expect("subscription task panicked");
+        assert!(registry.snapshot().is_empty());
+    }
+
+    #[test]
+    fn test_lag_gauge_follows_head_and_checkpoint() {
+        let recorder = metrics_exporter_prometheus::PrometheusBuilder::new().build_recorder();
+        let metrics = recorder.handle();
+
+        metrics::with_local_recorder(&recorder, || {
+            let registry = SubscriptionRegistry::default();
+            let handle = registry.register("tenant-projection");
+            handle.checkpoint(10);
+            handle.head(25);
+            assert!(metrics
+                .render()
+                .contains(r#"eventstore_subscription_lag{subscription="tenant-projection"} 15"#));
+
+            handle.checkpoint(25);
+            assert!(metrics
+                .render()
+                .contains(r#"eventstore_subscription_lag{subscription="tenant-projection"} 0"#));
+        });
+    }
+
+    #[test]
+    fn test_last_error_is_reported() {
+        let registry = SubscriptionRegistry::default();
+        let handle = registry.register("audit-log");
+        handle.checkpoint(7);
+        handle.error("deserialization failed");
+
+        let status = &registry.snapshot()[0];
+        assert_eq!(status.position, 7);
+        assert_eq!(status.last_error.as_deref(), Some("deserialization failed"));
+        // A failed event doesn't mean the connection is gone
+        assert!(status.connected);
+    }
+
+    #[test]
+    fn test_connectivity_is_reported() {
+        let registry = SubscriptionRegistry::default();
+        let handle = registry.register("tenant-projection");
+        assert!(!registry.snapshot()[0].connected);
+
+        handle.connected();
+        assert!(registry.snapshot()[0].connected);
+
+        handle.disconnected("connection refused");
+        let status = &registry.snapshot()[0];
+        assert!(!status.connected);
+        assert_eq!(status.last_error.as_deref(), Some("connection refused"));
+    }
+}
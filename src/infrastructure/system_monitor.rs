@@ -0,0 +1,136 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use sysinfo::System;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+
+/// Long-lived `System` shared by the health handlers.
+///
+/// sysinfo computes CPU usage as the delta between two refreshes, so keeping a
+/// single instance refreshed in the background gives every request a real CPU
+/// reading without sampling twice per request.
+#[derive(Clone)]
+pub struct SystemMonitor {
+    system: Arc<Mutex<System>>,
+}
+
+impl SystemMonitor {
+    pub fn new() -> Self {
+        let mut system = System::new();
+        system.refresh_cpu_usage();
+        system.refresh_memory();
+        Self {
+            system: Arc::new(Mutex::new(system)),
+        }
+    }
+
+    /// Runs `f` against the latest snapshot. The lock is held only for the
+    /// duration of the call, so `f` must not block.
+    pub fn with_snapshot<R>(&self, f: impl FnOnce(&System) -> R) -> R {
+        let system = self.system.lock().unwrap_or_else(|e| e.into_inner());
+        f(&system)
+    }
+
+    fn refresh(&self) {
+        let mut system = self.system.lock().unwrap_or_else(|e| e.into_inner());
+        system.refresh_cpu_usage();
+        system.refresh_memory();
+    }
+
+    /// Starts a background task that refreshes the snapshot every `interval`
+    /// until the returned handle is shut down or dropped. The interval is
+    /// raised to `sysinfo::MINIMUM_CPU_UPDATE_INTERVAL` if shorter.
+    pub fn spawn_refresher(&self, interval: Duration) -> SystemRefresher {
+        let monitor = self.clone();
+        let interval = interval.max(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
+        let (shutdown_tx, mut shutdown_rx) = oneshot::channel();
+
+        let handle = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.tick().await; // the first tick completes immediately
+
+            loop {
+                tokio::select! {
+                    _ = &mut shutdown_rx => break,
+                    _ = ticker.tick() => monitor.refresh(),
+                }
+            }
+        });
+
+        SystemRefresher {
+            shutdown: Some(shutdown_tx),
+            handle: Some(handle),
+        }
+    }
+}
+
+impl Default for SystemMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Handle to the background task started by `SystemMonitor::spawn_refresher`
+pub struct SystemRefresher {
+    shutdown: Option<oneshot::Sender<()>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl SystemRefresher {
+    /// Stops the refresher and waits for the task to finish
+    #[allow(dead_code)]
+    pub async fn shutdown(mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.await;
+        }
+    }
+}
+
+impl Drop for SystemRefresher {
+    fn drop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            handle.abort();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    #[tokio::test]
+    async fn test_refresher_updates_shared_snapshot() {
+        // Start from a never-refreshed System so every reading below comes
+        // from the background task
+        let monitor = SystemMonitor {
+            system: Arc::new(Mutex::new(System::new())),
+        };
+        assert_eq!(monitor.with_snapshot(|sys| sys.total_memory()), 0);
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let worker = {
+            let stop = stop.clone();
+            std::thread::spawn(move || {
+                let mut x: u64 = 0;
+                while !stop.load(Ordering::Relaxed) {
+                    x = std::hint::black_box(x.wrapping_add(1));
+                }
+            })
+        };
+
+        let refresher = monitor.spawn_refresher(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
+        tokio::time::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL * 3).await;
+        refresher.shutdown().await;
+
+        stop.store(true, Ordering::Relaxed);
+        worker.join().expect("load thread panicked");
+
+        assert!(monitor.with_snapshot(|sys| sys.global_cpu_usage()) > 0.0);
+        assert!(monitor.with_snapshot(|sys| sys.total_memory()) > 0);
+    }
+}
@@ -0,0 +1,505 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use event_store::{Event, RecordedEvent};
+use futures::future::join_all;
+use metrics::counter;
+use reqwest::header::CONTENT_TYPE;
+use serde::Serialize;
+use tokio::task::JoinHandle;
+use tracing::{error, warn};
+use uuid::Uuid;
+
+use crate::common::config::WebhookSettings;
+use crate::domain::webhook::{
+    ensure_public_target, sign, Webhook, WebhookDeliveryFailed, WebhookService,
+    DELIVERY_FAILED_EVENT_TYPE, SIGNATURE_HEADER,
+};
+use crate::infrastructure::event_publisher::EventPublisher;
+use crate::infrastructure::event_store::EventStoreClient;
+use crate::infrastructure::subscriptions::{
+    event_tenant_id, follow_all_from_checkpoint, CheckpointStore, SubscriptionRegistry,
+};
+
+/// Name the `$all` subscription feeding the webhooks is registered under
+const WEBHOOK_SUBSCRIPTION: &str = "webhooks";
+/// Header with the id of the delivered event, so receivers can recognize a
+/// retried delivery they already handled
+const EVENT_ID_HEADER: &str = "X-Webhook-Event-Id";
+/// Deliveries by outcome, `delivered` or `dead_lettered`
+const DELIVERIES_COUNTER: &str = "webhook_deliveries_total";
+
+/// Body POSTed to the webhooks
+#[derive(Serialize)]
+struct WebhookPayload<'a> {
+    event_id: Uuid,
+    event_type: &'a str,
+    tenant_id: Uuid,
+    created: DateTime<Utc>,
+    data: &'a serde_json::Value,
+}
+
+/// POSTs the events of each tenant to the webhooks it registered for their
+/// types.
+///
+/// Bodies are signed with the webhook's secret in `X-Webhook-Signature`.
+/// The client should come from `webhook_client`, which refuses internal
+/// targets the URL only reaches through DNS or redirects. Failed deliveries
+/// are retried with a doubling delay; after `max_attempts` they are appended
+/// to the dead-letter stream. Up to `max_concurrent_dispatches` events are
+/// delivered at once, so a slow receiver neither holds up the subscription
+/// nor the other webhooks, and receivers may see events out of order. The
+/// subscription resumes from its saved checkpoint after a restart, so an
+/// event may be delivered twice but isn't lost.
+#[derive(Clone)]
+pub struct WebhookDispatcher {
+    webhooks: Arc<dyn WebhookService>,
+    client: reqwest::Client,
+    max_attempts: u32,
+    retry_delay: Duration,
+    dead_letter_stream: String,
+    max_concurrent_dispatches: usize,
+    allow_private_targets: bool,
+    events: Option<EventPublisher>,
+}
+
+impl WebhookDispatcher {
+    pub fn new(
+        webhooks: Arc<dyn WebhookService>,
+        client: reqwest::Client,
+        settings: &WebhookSettings,
+    ) -> Self {
+        Self {
+            webhooks,
+            client,
+            max_attempts: settings.max_attempts.max(1),
+            retry_delay: settings.retry_delay(),
+            dead_letter_stream: settings.dead_letter_stream.clone(),
+            max_concurrent_dispatches: settings.max_concurrent_dispatches,
+            allow_private_targets: settings.allow_private_targets,
+            events: None,
+        }
+    }
+
+    /// Appends `WebhookDeliveryFailed` events for dead-lettered deliveries;
+    /// without a publisher they are only logged and counted
+    pub fn with_event_publisher(mut self, events: EventPublisher) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Delivers the event to every webhook of its tenant subscribed to its
+    /// type. Events without a tenant in their metadata are not delivered,
+    /// and neither are dead letters, so a dead receiver subscribed to them
+    /// can't keep feeding itself failed deliveries.
+    pub async fn dispatch(&self, event: RecordedEvent) {
+        if event.event_type == DELIVERY_FAILED_EVENT_TYPE {
+            return;
+        }
+        let Some(tenant_id) = event_tenant_id(&event) else {
+            return;
+        };
+        let webhooks = match self.webhooks.list_for_tenant(&tenant_id).await {
+            Ok(webhooks) => webhooks,
+            Err(e) => {
+                error!("Failed to load webhooks of tenant {}: {}", tenant_id, e);
+                return;
+            },
+        };
+
+        let payload = WebhookPayload {
+            event_id: event.event_id,
+            event_type: &event.event_type,
+            tenant_id,
+            created: event.created,
+            data: &event.data,
+        };
+        let payload = match serde_json::to_value(payload) {
+            Ok(payload) => payload,
+            Err(e) => {
+                error!("Failed to serialize event {}: {}", event.event_id, e);
+                return;
+            },
+        };
+        join_all(
+            webhooks
+                .iter()
+                .filter(|webhook| webhook.matches(&event.event_type))
+                .map(|webhook| self.deliver(webhook, &event, &payload)),
+        )
+        .await;
+    }
+
+    /// POSTs the payload until the receiver answers with a success status
+    /// or the attempts are used up, returning whether it was delivered.
+    /// Webhooks pointing to non-public addresses are dead-lettered without
+    /// being called.
+    async fn deliver(
+        &self,
+        webhook: &Webhook,
+        event: &RecordedEvent,
+        payload: &serde_json::Value,
+    ) -> bool {
+        if !self.allow_private_targets {
+            if let Err(e) = ensure_public_target(&webhook.url).await {
+                self.dead_letter(webhook, event, payload, 0, e.to_string());
+                return false;
+            }
+        }
+
+        let body = payload.to_string();
+        let signature = sign(&webhook.secret, body.as_bytes());
+        let mut delay = self.retry_delay;
+        let mut attempt = 1;
+
+        loop {
+            let result = self
+                .client
+                .post(&webhook.url)
+                .header(CONTENT_TYPE, "application/json")
+                .header(SIGNATURE_HEADER, &signature)
+                .header(EVENT_ID_HEADER, event.event_id.to_string())
+                .body(body.clone())
+                .send()
+                .await;
+            let error = match result {
+                Ok(response) if response.status().is_success() => {
+                    counter!(DELIVERIES_COUNTER, "outcome" => "delivered").increment(1);
+                    return true;
+                },
+                Ok(response) => format!("receiver answered {}", response.status()),
+                Err(e) => e.to_string(),
+            };
+
+            if attempt >= self.max_attempts {
+                self.dead_letter(webhook, event, payload, attempt, error);
+                return false;
+            }
+            warn!(
+                "Delivery of event {} to webhook {} failed (attempt {} of {}): {}",
+                event.event_id, webhook.id, attempt, self.max_attempts, error
+            );
+            tokio::time::sleep(delay).await;
+            delay = delay.saturating_mul(2);
+            attempt += 1;
+        }
+    }
+
+    fn dead_letter(
+        &self,
+        webhook: &Webhook,
+        event: &RecordedEvent,
+        payload: &serde_json::Value,
+        attempts: u32,
+        last_error: String,
+    ) {
+        counter!(DELIVERIES_COUNTER, "outcome" => "dead_lettered").increment(1);
+        error!(
+            "Giving up delivering event {} to webhook {} after {} attempts: {}",
+            event.event_id, webhook.id, attempts, last_error
+        );
+
+        if let Some(events) = &self.events {
+            let failed = WebhookDeliveryFailed {
+                webhook_id: webhook.id,
+                tenant_id: webhook.tenant_id,
+                url: webhook.url.clone(),
+                event_id: event.event_id,
+                event_type: event.event_type.clone(),
+                payload: payload.clone(),
+                attempts,
+                last_error,
+            };
+            events.publish_for_tenant(
+                webhook.tenant_id,
+                self.dead_letter_stream.clone(),
+                vec![Event::new(failed, 1, None, Some(event.event_id), None)],
+            );
+        }
+    }
+
+    /// Follows `$all` from the saved checkpoint, or its current end on the
+    /// first run, and dispatches every event
+    pub fn spawn(
+        &self,
+        client: Arc<EventStoreClient>,
+        registry: SubscriptionRegistry,
+        checkpoints: Arc<dyn CheckpointStore>,
+    ) -> JoinHandle<()> {
+        let dispatcher = self.clone();
+        follow_all_from_checkpoint(
+            client,
+            registry,
+            checkpoints,
+            WEBHOOK_SUBSCRIPTION,
+            self.max_concurrent_dispatches,
+            move |event| {
+                let dispatcher = dispatcher.clone();
+                async move { dispatcher.dispatch(event).await }
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    use async_trait::async_trait;
+    use event_store::EventData;
+    use sea_orm::{DatabaseBackend, MockDatabase};
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, Request, ResponseTemplate};
+
+    use crate::infrastructure::database::entities::webhook;
+    use crate::infrastructure::event_publisher::EventSink;
+    use crate::infrastructure::services::webhook_service::WebhookServiceImpl;
+
+    const SECRET: &str = "webhook-secret";
+
+    /// Records the events appended to each stream
+    #[derive(Default)]
+    struct RecordingSink {
+        written: Mutex<Vec<(String, EventData)>>,
+    }
+
+    #[async_trait]
+    impl EventSink for RecordingSink {
+        async fn publish(&self, stream_name: &str, events: Vec<EventData>) -> anyhow::Result<()> {
+            let mut written = self.written.lock().expect("sink lock");
+            for event in events {
+                written.push((stream_name.to_string(), event));
+            }
+            Ok(())
+        }
+    }
+
+    fn registered(tenant_id: Uuid, url: String, event_type: &str) -> webhook::Model {
+        webhook::Model {
+            id: Uuid::new_v4(),
+            tenant_id,
+            url,
+            event_types: serde_json::Value::from(vec![event_type]),
+            secret: SECRET.to_string(),
+            created_at: Utc::now().into(),
+        }
+    }
+
+    fn dispatcher(webhooks: Vec<webhook::Model>, max_attempts: u32) -> WebhookDispatcher {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![webhooks])
+            .into_connection();
+        WebhookDispatcher::new(
+            Arc::new(WebhookServiceImpl::new(Arc::new(db))),
+            reqwest::Client::new(),
+            &WebhookSettings {
+                max_attempts,
+                retry_delay_ms: 1,
+                // The mock receivers listen on loopback
+                allow_private_targets: true,
+                ..Default::default()
+            },
+        )
+    }
+
+    fn event_of(tenant_id: Uuid, event_type: &str) -> RecordedEvent {
+        let mut metadata = serde_json::Map::new();
+        metadata.insert("tenant_id".to_string(), tenant_id.to_string().into());
+        RecordedEvent {
+            event_id: Uuid::new_v4(),
+            event_type: event_type.to_string(),
+            data: serde_json::Value::from("payload"),
+            metadata: metadata.into(),
+            created: Utc::now(),
+        }
+    }
+
+    async fn requests_to(server: &MockServer, route: &str) -> Vec<Request> {
+        server
+            .received_requests()
+            .await
+            .expect("request recording enabled")
+            .into_iter()
+            .filter(|request| request.url.path() == route)
+            .collect()
+    }
+
+    fn signature_of(request: &Request) -> Option<String> {
+        request
+            .headers
+            .iter()
+            .find(|(name, _)| name.as_str().eq_ignore_ascii_case(SIGNATURE_HEADER))
+            .map(|(_, values)| values.last().as_str().to_string())
+    }
+
+    #[tokio::test]
+    async fn test_matching_webhooks_receive_signed_event() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(204))
+            .mount(&server)
+            .await;
+        let tenant_id = Uuid::new_v4();
+        let dispatcher = dispatcher(
+            vec![
+                registered(
+                    tenant_id,
+                    format!("{}/updates", server.uri()),
+                    "TenantUpdated",
+                ),
+                registered(
+                    tenant_id,
+                    format!("{}/deletes", server.uri()),
+                    "TenantDeleted",
+                ),
+            ],
+            1,
+        );
+
+        let event = event_of(tenant_id, "TenantUpdated");
+        dispatcher.dispatch(event.clone()).await;
+
+        let delivered = requests_to(&server, "/updates").await;
+        assert_eq!(delivered.len(), 1);
+        assert_eq!(
+            signature_of(&delivered[0]),
+            Some(sign(SECRET, &delivered[0].body))
+        );
+        let payload: serde_json::Value =
+            serde_json::from_slice(&delivered[0].body).expect("JSON payload");
+        assert_eq!(payload["event_id"], event.event_id.to_string());
+        assert_eq!(payload["data"], "payload");
+        assert!(requests_to(&server, "/deletes").await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_failed_delivery_is_retried() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/hooks"))
+            .respond_with(ResponseTemplate::new(500))
+            .up_to_n_times(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/hooks"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+        let tenant_id = Uuid::new_v4();
+        let webhook = registered(
+            tenant_id,
+            format!("{}/hooks", server.uri()),
+            "TenantUpdated",
+        );
+        let sink = Arc::new(RecordingSink::default());
+        let (publisher, worker) = EventPublisher::spawn(sink.clone(), 8);
+        let dispatcher = dispatcher(vec![webhook], 5).with_event_publisher(publisher);
+
+        dispatcher
+            .dispatch(event_of(tenant_id, "TenantUpdated"))
+            .await;
+        worker.shutdown().await;
+
+        assert_eq!(requests_to(&server, "/hooks").await.len(), 3);
+        assert!(sink.written.lock().expect("sink lock").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_internal_targets_are_dead_lettered_without_a_call() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(204))
+            .mount(&server)
+            .await;
+        let tenant_id = Uuid::new_v4();
+        let webhook = registered(
+            tenant_id,
+            format!("{}/hooks", server.uri()),
+            "TenantUpdated",
+        );
+        let sink = Arc::new(RecordingSink::default());
+        let (publisher, worker) = EventPublisher::spawn(sink.clone(), 8);
+        let mut dispatcher = dispatcher(vec![webhook], 3).with_event_publisher(publisher);
+        dispatcher.allow_private_targets = false;
+
+        dispatcher
+            .dispatch(event_of(tenant_id, "TenantUpdated"))
+            .await;
+        worker.shutdown().await;
+
+        assert!(requests_to(&server, "/hooks").await.is_empty());
+        let written = sink.written.lock().expect("sink lock");
+        assert_eq!(written.len(), 1);
+        let failed: WebhookDeliveryFailed =
+            serde_json::from_value(written[0].1.data.clone()).expect("dead letter");
+        assert_eq!(failed.attempts, 0);
+        assert!(failed.last_error.contains("non-public"));
+    }
+
+    #[tokio::test]
+    async fn test_dead_letters_are_not_dispatched() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(503))
+            .mount(&server)
+            .await;
+        let tenant_id = Uuid::new_v4();
+        let webhook = registered(
+            tenant_id,
+            format!("{}/hooks", server.uri()),
+            DELIVERY_FAILED_EVENT_TYPE,
+        );
+        let sink = Arc::new(RecordingSink::default());
+        let (publisher, worker) = EventPublisher::spawn(sink.clone(), 8);
+        let dispatcher = dispatcher(vec![webhook], 1).with_event_publisher(publisher);
+
+        dispatcher
+            .dispatch(event_of(tenant_id, DELIVERY_FAILED_EVENT_TYPE))
+            .await;
+        worker.shutdown().await;
+
+        assert!(requests_to(&server, "/hooks").await.is_empty());
+        assert!(sink.written.lock().expect("sink lock").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_delivery_is_dead_lettered_after_max_attempts() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/hooks"))
+            .respond_with(ResponseTemplate::new(503))
+            .mount(&server)
+            .await;
+        let tenant_id = Uuid::new_v4();
+        let webhook = registered(
+            tenant_id,
+            format!("{}/hooks", server.uri()),
+            "TenantUpdated",
+        );
+        let webhook_id = webhook.id;
+        let sink = Arc::new(RecordingSink::default());
+        let (publisher, worker) = EventPublisher::spawn(sink.clone(), 8);
+        let dispatcher = dispatcher(vec![webhook], 3).with_event_publisher(publisher);
+
+        let event = event_of(tenant_id, "TenantUpdated");
+        dispatcher.dispatch(event.clone()).await;
+        worker.shutdown().await;
+
+        assert_eq!(requests_to(&server, "/hooks").await.len(), 3);
+        let written = sink.written.lock().expect("sink lock");
+        assert_eq!(written.len(), 1);
+        let (stream_name, dead_letter) = &written[0];
+        assert_eq!(stream_name, "webhook-dead-letters");
+        assert_eq!(dead_letter.event_type, "WebhookDeliveryFailed");
+        assert_eq!(dead_letter.metadata["tenant_id"], tenant_id.to_string());
+        let failed: WebhookDeliveryFailed =
+            serde_json::from_value(dead_letter.data.clone()).expect("dead letter");
+        assert_eq!(failed.webhook_id, webhook_id);
+        assert_eq!(failed.event_id, event.event_id);
+        assert_eq!(failed.attempts, 3);
+        assert!(failed.last_error.contains("503"));
+    }
+}
@@ -1,19 +1,44 @@
 use std::net::SocketAddr;
 use std::sync::Arc;
 
-use axum::Router;
+use axum::{extract::Request, Router, ServiceExt};
 use tower_http::{compression::CompressionLayer, cors::CorsLayer, trace::TraceLayer};
 
 use crate::common::error::AppError;
 use crate::common::i18n::{FileResourceProvider, I18nManager, SupportedLanguage};
 use crate::common::metrics;
+use crate::common::middleware::access_log::{access_log, AccessLog};
+use crate::common::middleware::api_key::{api_key_middleware, ApiKeyState};
+use crate::common::middleware::auth::{auth_middleware, AuthState};
+use crate::common::middleware::content_type::require_json;
+use crate::common::middleware::envelope::{response_envelope, ResponseEnvelope};
+use crate::common::middleware::load_shed::{shed_load, ConcurrencyLimit};
+use crate::common::middleware::maintenance::maintenance_mode;
+use crate::common::middleware::rate_limit::rate_limit_middleware;
+use crate::common::middleware::tenant::{tenant_middleware, TenantState};
+use crate::common::middleware::timeout::{request_timeout, RequestTimeouts};
+use crate::domain::tenant::TenantSort;
+use crate::domain::user::{UserSettings, UserSort};
+use crate::domain::webhook::WebhookService;
+use crate::infrastructure::cache::CacheConnection;
 use crate::infrastructure::config::Config;
 use crate::infrastructure::database::connection::establish_connection;
+use crate::infrastructure::event_publisher::EventPublisher;
 use crate::infrastructure::event_store::EventStoreClient;
+use crate::infrastructure::http_client::{outbound_client, webhook_client};
 use crate::infrastructure::message_broker::MessageBroker;
 use crate::infrastructure::redis::RedisClient;
+use crate::infrastructure::seed::seed_defaults;
+use crate::infrastructure::services::api_key_service::ApiKeyServiceImpl;
+use crate::infrastructure::services::audit_log_service::AuditLogServiceImpl;
 use crate::infrastructure::services::tenant_service::TenantServiceImpl;
+use crate::infrastructure::services::user_service::UserServiceImpl;
+use crate::infrastructure::services::webhook_service::WebhookServiceImpl;
+use crate::infrastructure::startup::connect_optional;
 use crate::infrastructure::state::AppState;
+use crate::infrastructure::subscriptions::DbCheckpointStore;
+use crate::infrastructure::system_monitor::SystemMonitor;
+use crate::infrastructure::webhooks::WebhookDispatcher;
 
 mod api;
 mod common;
@@ -21,8 +46,20 @@ mod domain;
 mod infrastructure;
 mod router;
 
-#[tokio::main]
-async fn main() -> Result<(), AppError> {
+fn main() -> Result<(), AppError> {
+    let app_config = Arc::new(common::config::get_app_config());
+
+    // Build the Tokio runtime from config
+    let runtime = app_config
+        .runtime
+        .to_runtime_builder()
+        .build()
+        .map_err(|e| AppError::configuration(format!("Failed to build runtime: {}", e)))?;
+
+    runtime.block_on(run(app_config))
+}
+
+async fn run(app_config: Arc<common::config::AppConfig>) -> Result<(), AppError> {
     // Initialize logging
     common::setup_logging()?;
 
@@ -30,42 +67,218 @@ async fn main() -> Result<(), AppError> {
     let config = Config::load()?;
 
     // Initialize i18n
-    let i18n_manager =
-        Arc::new(I18nManager::new(SupportedLanguage::En, Arc::new(FileResourceProvider)).await?);
+    let i18n_manager = Arc::new(
+        I18nManager::new(
+            SupportedLanguage::En,
+            Arc::new(FileResourceProvider::default()),
+        )
+        .await?,
+    );
+
+    // The default order is parsed per request like `?sort=`, so catch a bad
+    // one before serving any
+    TenantSort::parse(&app_config.pagination.default_tenant_sort).map_err(|e| {
+        AppError::configuration(format!(
+            "Invalid pagination.default_tenant_sort: {}",
+            e.kind
+        ))
+    })?;
+    UserSort::parse(&app_config.pagination.default_user_sort).map_err(|e| {
+        AppError::configuration(format!("Invalid pagination.default_user_sort: {}", e.kind))
+    })?;
 
     // Initialize database
     let db = Arc::new(establish_connection().await?);
 
-    // Initialize tenant service
-    let tenant_service = Arc::new(TenantServiceImpl::new(Arc::clone(&db)));
+    // Initialize user service; defaults that every new user would fail on
+    // are a configuration error
+    let default_user_settings = UserSettings::try_from(&app_config.default_user_settings)?;
+    default_user_settings
+        .validate()
+        .map_err(|e| AppError::configuration(format!("Invalid default_user_settings: {}", e)))?;
+    let user_service = Arc::new(
+        UserServiceImpl::new(Arc::clone(&db))
+            .with_default_settings(default_user_settings)
+            .with_slow_query_threshold(app_config.database.slow_query_threshold()),
+    );
+
+    // Initialize audit log
+    let audit_log = Arc::new(AuditLogServiceImpl::new(Arc::clone(&db)));
+
+    // Tenant-scoped API keys as an alternative to Keycloak tokens
+    let api_keys = ApiKeyState::new(Arc::new(ApiKeyServiceImpl::new(Arc::clone(&db))));
+
+    // In-process cache holding the rate-limit windows, with a sweeper
+    // evicting the ended ones
+    let cache = CacheConnection::new().await?;
+    let cache_sweeper = cache.spawn_sweeper(app_config.cache.sweep_interval());
 
     // Initialize metrics
     let metrics_handle = metrics::init_metrics()?;
 
+    // Optional dependencies: verify connectivity up front, but start degraded
+    // rather than crash when one stays unreachable
+    let health = &app_config.health;
+
     // Initialize Redis
     let redis = Arc::new(RedisClient::new(&config.redis)?);
+    // The client reconnects lazily, so keep it even when the check fails
+    connect_optional("Redis", health, || redis.ping()).await;
+
+    // Keycloak login flow, caching the realm's signing keys in Redis
+    let auth = AuthState::new(app_config.clone(), Arc::new(redis.client().clone())).await?;
 
     // Initialize EventStore
-    let event_store = Arc::new(EventStoreClient::new(config.event_store)?);
+    let event_store = Arc::new(EventStoreClient::new(
+        config.event_store,
+        &app_config.eventstore,
+        (&app_config.http_client).into(),
+    )?);
+    connect_optional("EventStore", health, || event_store.check_connection()).await;
+
+    // Publish domain events in the background so requests don't wait on EventStore
+    let (event_publisher, publisher_worker) = EventPublisher::spawn(
+        event_store.clone(),
+        app_config.event_publisher.queue_capacity,
+    );
 
-    // Initialize MessageBroker
-    let message_broker = Arc::new(MessageBroker::new(&config.rabbitmq)?);
+    // Initialize tenant service
+    let tenant_service = Arc::new(
+        TenantServiceImpl::new(Arc::clone(&db))
+            .with_event_publisher(event_publisher.clone())
+            .with_slow_query_threshold(app_config.database.slow_query_threshold()),
+    );
+
+    // Bootstrap data for a fresh database; prod only when explicitly enabled
+    if app_config.seed.should_run(&common::config::get_run_mode()) {
+        if let Some(seeded) =
+            seed_defaults(&db, &*tenant_service, &*user_service, &app_config.seed).await?
+        {
+            tracing::info!(
+                "Seeded admin user {} ({}) of tenant {} ({})",
+                seeded.admin.username,
+                seeded.admin.id,
+                seeded.tenant.domain,
+                seeded.tenant.id
+            );
+        }
+    }
+
+    // Initialize MessageBroker; without a connection it stays unavailable
+    let message_broker = connect_optional("RabbitMQ", health, || async {
+        MessageBroker::connect(&config.rabbitmq).await.map(Arc::new)
+    })
+    .await;
+
+    // Keep a shared system snapshot fresh for the health checks
+    let system = SystemMonitor::new();
+    let _system_refresher = system.spawn_refresher(app_config.health.refresh_interval());
+
+    // Shared across every route except the health probes
+    let concurrency_limit = ConcurrencyLimit::new(&app_config.concurrency);
+    let request_logging = app_config
+        .logging
+        .request_logging
+        .then(|| AccessLog::new(&app_config.logging));
+    let envelope = ResponseEnvelope::new(&app_config.response_envelope);
+    let timeouts = RequestTimeouts::new(&app_config.request_timeouts);
+    let http_client = outbound_client(&app_config.http_client)?;
+    let webhook_service: Arc<dyn WebhookService> =
+        Arc::new(WebhookServiceImpl::new(Arc::clone(&db)));
+    let webhooks = WebhookDispatcher::new(
+        webhook_service.clone(),
+        webhook_client(
+            &app_config.http_client,
+            app_config.webhooks.allow_private_targets,
+        )?,
+        &app_config.webhooks,
+    )
+    .with_event_publisher(event_publisher);
 
     // Create app state
     let state = AppState::new(
+        app_config,
         tenant_service,
+        user_service,
+        audit_log,
+        webhook_service,
         i18n_manager,
         metrics_handle,
-        redis,
-        event_store,
+        Some(redis),
+        Some(event_store.clone()),
         message_broker,
+        system,
+        http_client,
+        cache,
+    );
+
+    // One `$all` subscription shared by every live event socket
+    let _live_events = state
+        .live_events
+        .spawn(event_store.clone(), state.subscriptions.clone());
+    // Tenant events to the webhooks registered for them
+    let _webhooks = webhooks.spawn(
+        event_store,
+        state.subscriptions.clone(),
+        Arc::new(DbCheckpointStore::new(Arc::clone(&db))),
     );
 
     // Build application
-    let app = Router::new()
+    let shed_routes = api::service_routes(&state.config.routes)
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            require_json,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            concurrency_limit,
+            shed_load,
+        ))
+        // Per-tenant limits, counted in the limiter the usage report reads
+        .layer(axum::middleware::from_fn_with_state(
+            state.rate_limiter.clone(),
+            rate_limit_middleware,
+        ))
+        // Inactive, read-only and login method rules of the caller's tenant;
+        // inside the API key layer, which sets the caller
+        .layer(axum::middleware::from_fn_with_state(
+            TenantState::new(state.tenant_service.clone()).optional(),
+            tenant_middleware,
+        ));
+    let mut routes = Router::new()
         .merge(api::health::health_routes())
-        .merge(api::tenant::tenant_routes())
-        .merge(api::metrics::metrics_routes())
+        .merge(shed_routes)
+        .merge(api::auth::auth_routes(auth.clone()))
+        // Lets the health, metrics and admin routes through by path
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            maintenance_mode,
+        ))
+        // Looked up per route, so the health probes and bulk routes can have
+        // their own limits
+        .layer(axum::middleware::from_fn_with_state(
+            timeouts,
+            request_timeout,
+        ));
+    if let Some(log) = request_logging {
+        routes = routes.layer(axum::middleware::from_fn_with_state(log, access_log));
+    }
+    // Keycloak bearer tokens; anonymous requests and those already
+    // authenticated by an API key pass through
+    let routes = routes.layer(axum::middleware::from_fn_with_state(
+        auth.optional(),
+        auth_middleware,
+    ));
+    // Outside the access log, so it sees who the key belongs to
+    let routes = routes.layer(axum::middleware::from_fn_with_state(
+        api_keys,
+        api_key_middleware,
+    ));
+    // Outermost, so errors of the layers inside are enveloped as well
+    let routes = routes.layer(axum::middleware::from_fn_with_state(
+        envelope,
+        response_envelope,
+    ));
+    let app = routes
         .with_state(state)
         .layer(TraceLayer::new_for_http())
         .layer(CompressionLayer::new())
@@ -81,7 +294,46 @@ async fn main() -> Result<(), AppError> {
         .await
         .map_err(|e| AppError::configuration(format!("Failed to bind to address: {}", e)))?;
 
-    axum::serve(listener, app)
+    // Trailing slashes are trimmed before routing, so it wraps the whole app
+    let app = router::normalize_trailing_slash(app);
+    let served = axum::serve(listener, ServiceExt::<Request>::into_make_service(app))
+        .with_graceful_shutdown(shutdown_signal())
         .await
-        .map_err(|e| AppError::configuration(format!("Server error: {}", e)))
+        .map_err(|e| AppError::configuration(format!("Server error: {}", e)));
+
+    // Write the events still queued before exiting
+    publisher_worker.shutdown().await;
+    cache_sweeper.shutdown().await;
+    served
+}
+
+/// Resolves on Ctrl+C or SIGTERM
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!("Failed to listen for Ctrl+C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            },
+            Err(e) => {
+                tracing::error!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            },
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+    tracing::info!("Shutting down");
 }
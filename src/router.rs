@@ -1,14 +1,245 @@
 use axum::Router;
+use tower::Layer;
+use tower_http::normalize_path::{NormalizePath, NormalizePathLayer};
 
 use crate::{
     api::{api_routes, not_found::not_found},
+    common::middleware::{content_type::require_json, maintenance::maintenance_mode},
     infrastructure::state::AppState,
 };
 
 #[allow(dead_code)]
 pub fn create_router(state: AppState) -> Router {
     Router::new()
-        .merge(api_routes())
+        .merge(api_routes(&state.config.routes))
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            require_json,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            maintenance_mode,
+        ))
         .fallback(not_found)
         .with_state(state)
 }
+
+/// Makes trailing slashes insignificant, so `/tenants/` is served by the
+/// `/tenants` route.
+///
+/// Routes are declared without a trailing slash, which is the canonical form;
+/// a trailing slash is trimmed from the request path before routing (`/`
+/// itself stays as is). This has to wrap the whole router: added with
+/// `Router::layer` it would only run after a route was already matched.
+pub fn normalize_trailing_slash(app: Router) -> NormalizePath<Router> {
+    NormalizePathLayer::trim_trailing_slash().layer(app)
+}
+
+/// The API router over a tenant service on `db`, with metrics and tracing
+/// set up like in `main`. Can be called any number of times per process.
+#[cfg(test)]
+pub async fn build_test_app(db: sea_orm::MockDatabase) -> Router {
+    use crate::common::i18n::{I18nManager, SupportedLanguage, TestResourceProvider};
+    use crate::common::{logging, metrics};
+    use crate::infrastructure::services::tenant_service::TenantServiceImpl;
+    use std::sync::Arc;
+
+    logging::init_for_tests();
+    let i18n = I18nManager::new(SupportedLanguage::En, Arc::new(TestResourceProvider::new()))
+        .await
+        .expect("i18n manager");
+    let mut state = AppState::for_test(
+        Arc::new(TenantServiceImpl::new(Arc::new(db.into_connection()))),
+        Arc::new(i18n),
+    );
+    state.metrics_handle = metrics::init_for_tests();
+    create_router(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        body::Body,
+        extract::Path,
+        http::{header, Request, StatusCode},
+        routing::get,
+    };
+    use chrono::Utc;
+    use sea_orm::{DatabaseBackend, MockDatabase};
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    use crate::domain::tenant::TenantSettings;
+    use crate::infrastructure::database::entities::tenant;
+
+    fn app() -> NormalizePath<Router> {
+        normalize_trailing_slash(
+            Router::new()
+                .route("/tenants", get(|| async { "all tenants" }))
+                .route(
+                    "/tenants/{id}",
+                    get(|Path(id): Path<String>| async move { format!("tenant {}", id) }),
+                ),
+        )
+    }
+
+    async fn get_path(path: &str) -> (StatusCode, String) {
+        let request = Request::builder()
+            .uri(path)
+            .body(Body::empty())
+            .expect("valid request");
+        let response = app().oneshot(request).await.expect("response");
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("response body");
+        (
+            status,
+            String::from_utf8(body.to_vec()).expect("utf-8 body"),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_slashed_and_unslashed_paths_match_the_same_route() {
+        for (canonical, slashed) in [
+            ("/tenants", "/tenants/"),
+            ("/tenants/42", "/tenants/42/"),
+            ("/tenants?page=2", "/tenants/?page=2"),
+        ] {
+            let expected = get_path(canonical).await;
+            assert_eq!(expected.0, StatusCode::OK, "{}", canonical);
+            assert_eq!(get_path(slashed).await, expected, "{}", slashed);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_path_params_keep_their_value() {
+        // Only the trailing slash is removed, never part of the id
+        let (status, body) = get_path("/tenants/a-b//").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, "tenant a-b");
+    }
+
+    #[tokio::test]
+    async fn test_list_tenants_through_router() {
+        let model = tenant::Model {
+            id: Uuid::new_v4(),
+            name: "Acme".to_string(),
+            domain: "acme.example.com".to_string(),
+            is_active: true,
+            settings: serde_json::to_value(TenantSettings::default())
+                .expect("serializable settings"),
+            created_at: Utc::now().naive_utc(),
+            updated_at: Utc::now().naive_utc(),
+            deleted_at: None,
+        };
+        let app = build_test_app(
+            MockDatabase::new(DatabaseBackend::Postgres)
+                .append_query_results(vec![vec![model.clone()]]),
+        )
+        .await;
+
+        let request = Request::builder()
+            .uri("/tenants")
+            .body(Body::empty())
+            .expect("valid request");
+        let response = app.oneshot(request).await.expect("response");
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("response body");
+        let tenants: Vec<serde_json::Value> = serde_json::from_slice(&body).expect("tenant list");
+        assert_eq!(tenants.len(), 1);
+        assert_eq!(tenants[0]["id"], model.id.to_string());
+        assert_eq!(tenants[0]["domain"], "acme.example.com");
+    }
+
+    #[tokio::test]
+    async fn test_app_can_be_built_repeatedly() {
+        for _ in 0..2 {
+            let app = build_test_app(MockDatabase::new(DatabaseBackend::Postgres)).await;
+            let request = Request::builder()
+                .uri("/metrics")
+                .body(Body::empty())
+                .expect("valid request");
+            let response = app.oneshot(request).await.expect("response");
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_non_json_body_is_rejected_with_415() {
+        let app = build_test_app(MockDatabase::new(DatabaseBackend::Postgres)).await;
+        let request = Request::builder()
+            .method("POST")
+            .uri("/tenants")
+            .header(header::CONTENT_TYPE, "text/plain")
+            .header(header::CONTENT_LENGTH, "4")
+            .body(Body::from("Acme"))
+            .expect("valid request");
+        let response = app.oneshot(request).await.expect("response");
+        assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("response body");
+        let error: serde_json::Value = serde_json::from_slice(&body).expect("JSON error");
+        assert_eq!(error["code"], "UNSUPPORTED_MEDIA_TYPE");
+        assert_eq!(
+            error["message"],
+            "Request body must be sent as application/json"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_disabled_tenant_writes_are_not_mounted() {
+        use crate::common::config::{AppConfig, RouteSettings};
+        use crate::common::i18n::{I18nManager, SupportedLanguage, TestResourceProvider};
+        use crate::infrastructure::services::tenant_service::TenantServiceImpl;
+        use std::sync::Arc;
+
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![Vec::<tenant::Model>::new()]);
+        let i18n = I18nManager::new(SupportedLanguage::En, Arc::new(TestResourceProvider::new()))
+            .await
+            .expect("i18n manager");
+        let mut state = AppState::for_test(
+            Arc::new(TenantServiceImpl::new(Arc::new(db.into_connection()))),
+            Arc::new(i18n),
+        );
+        state.config = Arc::new(AppConfig {
+            routes: RouteSettings {
+                tenant_write: false,
+                ..RouteSettings::default()
+            },
+            ..AppConfig::default()
+        });
+        let app = create_router(state);
+
+        let request = Request::builder()
+            .uri("/tenants")
+            .body(Body::empty())
+            .expect("valid request");
+        let response = app.clone().oneshot(request).await.expect("response");
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let request = Request::builder()
+            .method("POST")
+            .uri("/tenants")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(r#"{"name":"Acme","domain":"acme.example.com"}"#))
+            .expect("valid request");
+        let response = app.clone().oneshot(request).await.expect("response");
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let request = Request::builder()
+            .method("DELETE")
+            .uri(format!("/tenants/{}", Uuid::new_v4()))
+            .body(Body::empty())
+            .expect("valid request");
+        let response = app.oneshot(request).await.expect("response");
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}